@@ -0,0 +1,12 @@
+// Generates the gRPC server and client of `inbound::grpc` from the protobuf definitions, with
+// the vendored `protoc` unless `PROTOC` names another one.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = tonic_prost_build::Config::new();
+    if std::env::var_os("PROTOC").is_none() {
+        config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+    }
+    println!("cargo:rerun-if-changed=proto");
+    tonic_prost_build::configure().compile_with_config(config, &["proto/petstore/v1/pets.proto"], &["proto"])?;
+    Ok(())
+}
@@ -0,0 +1,16 @@
+DROP INDEX IF EXISTS pets_search_vector_idx;
+DROP INDEX IF EXISTS pets_search_text_trgm_idx;
+
+DROP TRIGGER IF EXISTS pet_tags_search_refresh ON pet_tags;
+DROP FUNCTION IF EXISTS pet_tags_search_refresh();
+DROP TRIGGER IF EXISTS tags_search_refresh ON tags;
+DROP FUNCTION IF EXISTS tags_search_refresh();
+DROP TRIGGER IF EXISTS categories_search_refresh ON categories;
+DROP FUNCTION IF EXISTS categories_search_refresh();
+DROP TRIGGER IF EXISTS pets_search_document ON pets;
+DROP FUNCTION IF EXISTS pets_search_document();
+
+ALTER TABLE pets DROP COLUMN IF EXISTS search_vector;
+ALTER TABLE pets DROP COLUMN IF EXISTS search_text;
+
+DROP EXTENSION IF EXISTS pg_trgm;
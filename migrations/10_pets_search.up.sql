@@ -0,0 +1,77 @@
+-- Full-text and substring search over pet names, category names and tag names
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- The searchable names of each pet, denormalized onto the pet and kept current by triggers:
+-- lowercase text for trigram substring matching, and a vector weighting the name above the
+-- category above the tags for ranking.
+ALTER TABLE pets ADD COLUMN IF NOT EXISTS search_text TEXT NOT NULL DEFAULT '';
+ALTER TABLE pets ADD COLUMN IF NOT EXISTS search_vector TSVECTOR NOT NULL DEFAULT ''::TSVECTOR;
+
+CREATE OR REPLACE FUNCTION pets_search_document() RETURNS TRIGGER AS $$
+DECLARE
+    category_name TEXT;
+    tag_names TEXT;
+BEGIN
+    SELECT name INTO category_name FROM categories WHERE id = NEW.category_id;
+    SELECT string_agg(t.name, ' ' ORDER BY t.id) INTO tag_names
+    FROM pet_tags pt
+    JOIN tags t ON t.id = pt.tag_id
+    WHERE pt.pet_id = NEW.id;
+
+    NEW.search_text := lower(concat_ws(' ', NEW.name, category_name, tag_names));
+    NEW.search_vector := setweight(to_tsvector('simple', NEW.name), 'A')
+        || setweight(to_tsvector('simple', coalesce(category_name, '')), 'B')
+        || setweight(to_tsvector('simple', coalesce(tag_names, '')), 'C');
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS pets_search_document ON pets;
+CREATE TRIGGER pets_search_document BEFORE INSERT OR UPDATE ON pets
+    FOR EACH ROW EXECUTE FUNCTION pets_search_document();
+
+-- Renamed categories and tags, and retagged pets, rebuild the documents of the affected pets
+CREATE OR REPLACE FUNCTION categories_search_refresh() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE pets SET search_text = search_text WHERE category_id = NEW.id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS categories_search_refresh ON categories;
+CREATE TRIGGER categories_search_refresh AFTER UPDATE OF name ON categories
+    FOR EACH ROW EXECUTE FUNCTION categories_search_refresh();
+
+CREATE OR REPLACE FUNCTION tags_search_refresh() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE pets SET search_text = search_text
+    WHERE id IN (SELECT pet_id FROM pet_tags WHERE tag_id = NEW.id);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS tags_search_refresh ON tags;
+CREATE TRIGGER tags_search_refresh AFTER UPDATE OF name ON tags
+    FOR EACH ROW EXECUTE FUNCTION tags_search_refresh();
+
+CREATE OR REPLACE FUNCTION pet_tags_search_refresh() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        UPDATE pets SET search_text = search_text WHERE id = NEW.pet_id;
+    END IF;
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        UPDATE pets SET search_text = search_text WHERE id = OLD.pet_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS pet_tags_search_refresh ON pet_tags;
+CREATE TRIGGER pet_tags_search_refresh AFTER INSERT OR UPDATE OR DELETE ON pet_tags
+    FOR EACH ROW EXECUTE FUNCTION pet_tags_search_refresh();
+
+-- Build the documents of the existing pets
+UPDATE pets SET search_text = search_text;
+
+CREATE INDEX IF NOT EXISTS pets_search_text_trgm_idx ON pets USING GIN (search_text gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS pets_search_vector_idx ON pets USING GIN (search_vector);
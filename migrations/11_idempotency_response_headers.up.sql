@@ -0,0 +1,3 @@
+-- The headers of stored responses worth replaying, such as Content-Type and ETag, as
+-- `name: value` lines
+ALTER TABLE idempotency_keys ADD COLUMN IF NOT EXISTS response_headers TEXT[] NOT NULL DEFAULT '{}';
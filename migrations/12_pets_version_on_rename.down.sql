@@ -0,0 +1,14 @@
+CREATE OR REPLACE FUNCTION tags_search_refresh() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE pets SET search_text = search_text
+    WHERE id IN (SELECT pet_id FROM pet_tags WHERE tag_id = NEW.id);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION categories_search_refresh() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE pets SET search_text = search_text WHERE category_id = NEW.id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,20 @@
+-- Renaming a category or tag changes the representation of every pet that carries it, so the
+-- refresh of their search documents bumps their version too and their ETags stop matching
+CREATE OR REPLACE FUNCTION categories_search_refresh() RETURNS TRIGGER AS $$
+BEGIN
+    IF OLD.name IS DISTINCT FROM NEW.name THEN
+        UPDATE pets SET version = version + 1 WHERE category_id = NEW.id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION tags_search_refresh() RETURNS TRIGGER AS $$
+BEGIN
+    IF OLD.name IS DISTINCT FROM NEW.name THEN
+        UPDATE pets SET version = version + 1
+        WHERE id IN (SELECT pet_id FROM pet_tags WHERE tag_id = NEW.id);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
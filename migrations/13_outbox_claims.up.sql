@@ -0,0 +1,3 @@
+-- Pending events are claimed by one relay at a time, until they are dispatched or the claim
+-- runs out
+ALTER TABLE outbox_events ADD COLUMN IF NOT EXISTS claimed_until TIMESTAMPTZ;
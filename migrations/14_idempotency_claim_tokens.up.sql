@@ -0,0 +1,3 @@
+-- Each claim of a key is told apart from a later claim of the same key by the same request,
+-- so that only the claim that is still current completes or releases it
+ALTER TABLE idempotency_keys ADD COLUMN IF NOT EXISTS claim_token VARCHAR(64);
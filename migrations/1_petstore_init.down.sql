@@ -0,0 +1,5 @@
+DROP TABLE IF EXISTS pet_tags;
+DROP TABLE IF EXISTS pet_photos;
+DROP TABLE IF EXISTS tags;
+DROP TABLE IF EXISTS pets;
+DROP TABLE IF EXISTS categories;
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS pet_photos_pet_id_idx;
+DROP INDEX IF EXISTS pets_category_id_idx;
+DROP INDEX IF EXISTS pets_status_idx;
+DROP INDEX IF EXISTS pets_name_id_idx;
@@ -0,0 +1,5 @@
+-- Indexes backing keyset pagination and filtering of the pet listing
+CREATE INDEX IF NOT EXISTS pets_name_id_idx ON pets (name, id);
+CREATE INDEX IF NOT EXISTS pets_status_idx ON pets (status);
+CREATE INDEX IF NOT EXISTS pets_category_id_idx ON pets (category_id);
+CREATE INDEX IF NOT EXISTS pet_photos_pet_id_idx ON pet_photos (pet_id);
@@ -0,0 +1,3 @@
+-- Categories must be created with an id again
+ALTER TABLE categories ALTER COLUMN id DROP DEFAULT;
+DROP SEQUENCE IF EXISTS categories_id_seq;
@@ -0,0 +1,4 @@
+-- Let the database assign ids to categories created without one
+CREATE SEQUENCE IF NOT EXISTS categories_id_seq OWNED BY categories.id;
+SELECT setval('categories_id_seq', COALESCE((SELECT MAX(id) FROM categories), 0) + 1, false);
+ALTER TABLE categories ALTER COLUMN id SET DEFAULT nextval('categories_id_seq');
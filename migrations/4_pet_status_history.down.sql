@@ -0,0 +1,2 @@
+-- The history of every pet is lost
+DROP TABLE IF EXISTS pet_status_history;
@@ -0,0 +1,17 @@
+-- Lifecycle audit trail: one row per status a pet has been in
+CREATE TABLE IF NOT EXISTS pet_status_history (
+    id BIGSERIAL PRIMARY KEY,
+    pet_id BIGINT NOT NULL REFERENCES pets(id) ON DELETE CASCADE,
+    from_status VARCHAR(50),
+    to_status VARCHAR(50) NOT NULL,
+    actor VARCHAR(255),
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS pet_status_history_pet_id_idx ON pet_status_history (pet_id, id);
+
+-- Pets created before the history existed start with their current status
+INSERT INTO pet_status_history (pet_id, to_status)
+SELECT p.id, p.status
+FROM pets p
+WHERE NOT EXISTS (SELECT 1 FROM pet_status_history h WHERE h.pet_id = p.id);
@@ -0,0 +1,2 @@
+-- Optimistic concurrency control: bumped on every change to a pet
+ALTER TABLE pets ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 1;
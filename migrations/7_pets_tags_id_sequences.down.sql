@@ -0,0 +1,6 @@
+-- Pets and tags must be created with an id again
+ALTER TABLE tags ALTER COLUMN id DROP DEFAULT;
+DROP SEQUENCE IF EXISTS tags_id_seq;
+
+ALTER TABLE pets ALTER COLUMN id DROP DEFAULT;
+DROP SEQUENCE IF EXISTS pets_id_seq;
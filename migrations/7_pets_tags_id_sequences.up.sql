@@ -0,0 +1,8 @@
+-- Let the database assign ids to pets and tags created without one
+CREATE SEQUENCE IF NOT EXISTS pets_id_seq OWNED BY pets.id;
+SELECT setval('pets_id_seq', COALESCE((SELECT MAX(id) FROM pets), 0) + 1, false);
+ALTER TABLE pets ALTER COLUMN id SET DEFAULT nextval('pets_id_seq');
+
+CREATE SEQUENCE IF NOT EXISTS tags_id_seq OWNED BY tags.id;
+SELECT setval('tags_id_seq', COALESCE((SELECT MAX(id) FROM tags), 0) + 1, false);
+ALTER TABLE tags ALTER COLUMN id SET DEFAULT nextval('tags_id_seq');
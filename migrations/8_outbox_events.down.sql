@@ -0,0 +1,2 @@
+-- Events not yet relayed are lost
+DROP TABLE IF EXISTS outbox_events;
@@ -0,0 +1,482 @@
+/*!
+    `petstore-admin` runs operational tasks against the pet store database through the same
+    domain [Service] as the HTTP server, so that pets created or imported here are validated,
+    get ids and emit events exactly like pets created through the API.
+
+    Pets are printed one JSON record per line, in the layout `export --format ndjson` writes.
+*/
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use anyhow::Context;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use futures::StreamExt;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use petstore_hexarch_rust::domain::petstore::ids::StrategyIdGenerator;
+use petstore_hexarch_rust::domain::petstore::models::id::IdStrategy;
+use petstore_hexarch_rust::domain::petstore::models::pet::{
+    ListPetsRequest, PetCursor, PetSortField, SortDirection, Status,
+};
+use petstore_hexarch_rust::domain::petstore::models::seed::{SeedPlan, SeedProfile};
+use petstore_hexarch_rust::domain::petstore::models::transfer::{ImportReport, PetRecord};
+use petstore_hexarch_rust::domain::petstore::ports::{PetService, PetTransferService};
+use petstore_hexarch_rust::domain::petstore::seed::Seeder;
+use petstore_hexarch_rust::domain::petstore::service::Service;
+use petstore_hexarch_rust::inbound::transfer::{
+    csv_header, csv_line, csv_rows, json_line, json_lines_rows, TransferFormat,
+};
+use petstore_hexarch_rust::outbound::connect::PostgresClient;
+use petstore_hexarch_rust::outbound::params::ConnectionParams;
+
+/// Administer the pet store database.
+#[derive(Debug, Parser)]
+#[command(name = "petstore-admin", version)]
+struct Cli {
+    /// How ids are assigned to created pets: sequence, snowflake or client.
+    #[arg(long, env = "ID_STRATEGY", default_value = "sequence", global = true)]
+    id_strategy: IdStrategy,
+
+    /// Tells apart the snowflake ids of instances running side by side.
+    #[arg(long, env = "NODE_ID", default_value_t = 0, global = true)]
+    node_id: u16,
+
+    #[command(flatten)]
+    database: DatabaseArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// Where the database is, with the server's defaults.
+#[derive(Debug, Args)]
+#[command(next_help_heading = "Database")]
+struct DatabaseArgs {
+    #[arg(long, env = "PGHOST", default_value = "localhost", global = true)]
+    db_host: String,
+    #[arg(long, env = "PGPORT", default_value_t = 5432, global = true)]
+    db_port: u16,
+    #[arg(long, env = "PGDATABASE", default_value = "postgres", global = true)]
+    db_name: String,
+    #[arg(long, env = "PGUSER", default_value = "postgres", global = true)]
+    db_user: String,
+    #[arg(long, env = "PGPASSWORD", default_value = "postgres", global = true, hide_env_values = true)]
+    db_password: String,
+    /// How many seconds to wait for a connection.
+    #[arg(long, default_value_t = 10, global = true)]
+    connect_timeout: u64,
+}
+
+impl From<DatabaseArgs> for ConnectionParams {
+    fn from(args: DatabaseArgs) -> Self {
+        Self {
+            host: args.db_host,
+            port: args.db_port,
+            dbname: args.db_name,
+            user: args.db_user,
+            password: args.db_password,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Check that the database can be reached and answers queries.
+    Check,
+    /// Apply, revert or report the database migrations; applies the pending ones by default.
+    Migrate {
+        #[command(subcommand)]
+        action: Option<MigrateAction>,
+    },
+    /// Create, find and list pets.
+    #[command(subcommand)]
+    Pet(PetCommand),
+    /// Import pets from a CSV or JSON Lines file, `-` for standard input.
+    ///
+    /// Every valid row is imported and the others are reported by line; the command fails if
+    /// any row did.
+    Import {
+        file: PathBuf,
+        /// The file format, by default told by the file extension.
+        #[arg(long)]
+        format: Option<Format>,
+        /// Report the rows that would fail without importing anything.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Load sample data; categories and pets whose name is taken are left as they are.
+    Seed {
+        /// minimal, sample or synthetic, optionally with a pet count, e.g. synthetic:5000.
+        #[arg(long, default_value = "minimal")]
+        profile: SeedProfile,
+        /// The seed of synthetic data: the same seed always generates the same pets.
+        #[arg(long, default_value_t = 42)]
+        seed: u64,
+    },
+    /// Export every pet as a CSV or JSON Lines file.
+    Export {
+        /// The file to write, standard output by default.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// The file format, by default told by the output file extension, else CSV.
+        #[arg(long)]
+        format: Option<Format>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum MigrateAction {
+    /// Apply the pending migrations.
+    Up,
+    /// Revert the applied migrations above a version, latest first.
+    Down {
+        /// The version to revert to; 0 reverts every migration, dropping all data.
+        #[arg(long)]
+        to: i64,
+    },
+    /// List every migration and whether it is applied; fails unless the schema is current.
+    Status,
+}
+
+#[derive(Debug, Subcommand)]
+enum PetCommand {
+    /// Create a pet.
+    Create {
+        #[arg(long)]
+        name: String,
+        /// The pet id, if the id strategy lets clients choose.
+        #[arg(long)]
+        id: Option<i64>,
+        #[arg(long)]
+        category_id: Option<i64>,
+        #[arg(long, requires = "category_id")]
+        category_name: Option<String>,
+        /// available, pending or sold; available by default.
+        #[arg(long)]
+        status: Option<String>,
+        /// A tag name; repeat for several tags.
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// A photo URL; repeat for several photos.
+        #[arg(long = "photo-url")]
+        photo_urls: Vec<String>,
+    },
+    /// Print the pet with the given id.
+    Find { id: i64 },
+    /// List a page of pets; the cursor of the next page, if any, is printed on standard error.
+    List {
+        #[arg(long)]
+        status: Option<String>,
+        #[arg(long)]
+        category_id: Option<i64>,
+        #[arg(long, default_value_t = ListPetsRequest::DEFAULT_LIMIT)]
+        limit: u32,
+        #[arg(long, value_enum, default_value_t = SortArg::Id)]
+        sort: SortArg,
+        /// Sort in descending order.
+        #[arg(long)]
+        desc: bool,
+        /// Continue from the cursor printed with the previous page.
+        #[arg(long)]
+        cursor: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Csv,
+    #[value(alias = "jsonl")]
+    Ndjson,
+}
+
+impl From<Format> for TransferFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Csv => Self::Csv,
+            Format::Ndjson => Self::JsonLines,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SortArg {
+    Id,
+    Name,
+}
+
+type AdminService = Service<PostgresClient, StrategyIdGenerator>;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(tracing::Level::WARN)
+        .init();
+
+    match run(Cli::parse()).await {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> anyhow::Result<ExitCode> {
+    let connect_timeout = Duration::from_secs(cli.database.connect_timeout);
+    let params = ConnectionParams::from(cli.database);
+    let client = tokio::time::timeout(connect_timeout, PostgresClient::new(&params))
+        .await
+        .map_err(|_| anyhow::anyhow!("timed out after {:?}", connect_timeout))
+        .and_then(|connected| connected)
+        .with_context(|| format!("failed to connect to {}:{}/{}", params.host, params.port, params.dbname))?
+        .with_id_strategy(cli.id_strategy);
+
+    match cli.command {
+        Command::Check => {
+            client.ping().await.context("database does not answer queries")?;
+            println!("ok: connected to {}:{}/{}", params.host, params.port, params.dbname);
+        }
+        Command::Migrate { action } => return migrate(&client, action.unwrap_or(MigrateAction::Up)).await,
+        Command::Seed { profile, seed } => {
+            let report = Seeder::new(client).seed(&SeedPlan::new(profile, seed)).await?;
+            println!(
+                "ok: {} categories and {} pets created, {} categories and {} pets already there",
+                report.categories_created, report.pets_created, report.categories_existing, report.pets_existing
+            );
+        }
+        command => {
+            let ids = StrategyIdGenerator::new(cli.id_strategy, cli.node_id)?;
+            let service = Service::with_id_generator(client, ids);
+            return match command {
+                Command::Pet(command) => run_pet_command(&service, command).await,
+                Command::Import { file, format, dry_run } => import(&service, file, format, dry_run).await,
+                Command::Export { output, format } => export(&service, output, format).await,
+                Command::Check | Command::Migrate { .. } | Command::Seed { .. } => unreachable!("handled above"),
+            };
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn migrate(client: &PostgresClient, action: MigrateAction) -> anyhow::Result<ExitCode> {
+    match action {
+        MigrateAction::Up => {
+            client.migrate().await?;
+            println!("ok: migrations applied");
+        }
+        MigrateAction::Down { to } => {
+            client.revert_migrations(to).await?;
+            println!("ok: migrations above version {} reverted", to);
+        }
+        MigrateAction::Status => {
+            let status = client.migration_status().await?;
+            for migration in &status.migrations {
+                let installed_on = migration.installed_on.map(|at| at.to_rfc3339()).unwrap_or_default();
+                println!("{:>6}  {:<9} {:<32} {}", migration.version, migration.state, migration.description, installed_on);
+            }
+            if !status.is_current() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn run_pet_command(service: &AdminService, command: PetCommand) -> anyhow::Result<ExitCode> {
+    match command {
+        PetCommand::Create { name, id, category_id, category_name, status, tags, photo_urls } => {
+            let record = PetRecord { id, name, category_id, category_name, status, tags, photo_urls };
+            let req = record.try_into_request().map_err(|errors| anyhow::anyhow!("invalid pet: {}", errors))?;
+            let pet = service.add_pet(&req).await?;
+            print_record(&PetRecord::from(&pet))?;
+        }
+        PetCommand::Find { id } => match service.find_pet_by_id(id).await? {
+            Some(pet) => print_record(&PetRecord::from(&pet))?,
+            None => {
+                eprintln!("pet with id {} not found", id);
+                return Ok(ExitCode::FAILURE);
+            }
+        },
+        PetCommand::List { status, category_id, limit, sort, desc, cursor } => {
+            let status = status.map(|status| Status::try_from(Some(status))).transpose()?;
+            let cursor = cursor.as_deref().map(PetCursor::decode).transpose()?;
+            let sort = match sort {
+                SortArg::Id => PetSortField::Id,
+                SortArg::Name => PetSortField::Name,
+            };
+            let direction = if desc { SortDirection::Desc } else { SortDirection::Asc };
+            let req = ListPetsRequest::new(sort, direction, status, category_id, limit, cursor);
+            let page = service.list_pets(&req).await?;
+            for pet in &page.pets {
+                print_record(&PetRecord::from(pet))?;
+            }
+            if let Some(cursor) = page.next_cursor {
+                eprintln!("next cursor: {}", cursor.encode());
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_record(record: &PetRecord) -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string(record)?);
+    Ok(())
+}
+
+async fn import(
+    service: &AdminService,
+    file: PathBuf,
+    format: Option<Format>,
+    dry_run: bool,
+) -> anyhow::Result<ExitCode> {
+    let stdin = file.as_os_str() == "-";
+    let format = match format {
+        Some(format) => format.into(),
+        None if stdin => TransferFormat::Csv,
+        None => TransferFormat::from_path(&file)
+            .with_context(|| format!("cannot tell the format of {}, use --format", file.display()))?,
+    };
+    let report = if stdin {
+        import_from(service, tokio::io::stdin(), format, dry_run).await?
+    } else {
+        let reader = tokio::fs::File::open(&file)
+            .await
+            .with_context(|| format!("failed to open {}", file.display()))?;
+        import_from(service, reader, format, dry_run).await?
+    };
+
+    for failure in &report.failures {
+        let name = failure.name.as_deref().map(|name| format!(" ({})", name)).unwrap_or_default();
+        for error in &failure.errors {
+            eprintln!("line {}{}: {}: {}", failure.line, name, error.field, error.message);
+        }
+    }
+    if report.failed > report.failures.len() as u64 {
+        eprintln!("only the first {} failed rows are listed", report.failures.len());
+    }
+    let verb = if report.dry_run { "would be imported" } else { "imported" };
+    println!("{} rows read, {} {}, {} failed", report.rows, report.imported, verb, report.failed);
+    Ok(if report.failed == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
+
+async fn import_from<R>(
+    service: &AdminService,
+    reader: R,
+    format: TransferFormat,
+    dry_run: bool,
+) -> anyhow::Result<ImportReport>
+where
+    R: tokio::io::AsyncRead + Unpin + Send + 'static,
+{
+    let report = match format {
+        TransferFormat::Csv => service.import_pets(csv_rows(reader), dry_run).await,
+        TransferFormat::JsonLines => service.import_pets(json_lines_rows(reader), dry_run).await,
+    }?;
+    Ok(report)
+}
+
+async fn export(
+    service: &AdminService,
+    output: Option<PathBuf>,
+    format: Option<Format>,
+) -> anyhow::Result<ExitCode> {
+    let format = match (format, &output) {
+        (Some(format), _) => format.into(),
+        (None, Some(path)) => TransferFormat::from_path(path).unwrap_or(TransferFormat::Csv),
+        (None, None) => TransferFormat::Csv,
+    };
+    let count = match &output {
+        Some(path) => {
+            let file = tokio::fs::File::create(path)
+                .await
+                .with_context(|| format!("failed to create {}", path.display()))?;
+            export_to(service, tokio::io::BufWriter::new(file), format).await?
+        }
+        None => export_to(service, tokio::io::BufWriter::new(tokio::io::stdout()), format).await?,
+    };
+    eprintln!("{} pets exported", count);
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn export_to<W>(service: &AdminService, mut writer: W, format: TransferFormat) -> anyhow::Result<u64>
+where
+    W: AsyncWrite + Unpin,
+{
+    if format == TransferFormat::Csv {
+        writer.write_all(&csv_header()).await?;
+    }
+    let mut records = std::pin::pin!(service.export_pets());
+    let mut count = 0;
+    while let Some(record) = records.next().await {
+        let record = record?;
+        let line = match format {
+            TransferFormat::Csv => csv_line(&record)?,
+            TransferFormat::JsonLines => json_line(&record)?,
+        };
+        writer.write_all(&line).await?;
+        count += 1;
+    }
+    writer.flush().await?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse_pet_create() {
+        let cli = Cli::try_parse_from([
+            "petstore-admin", "pet", "create", "--name", "Rex", "--tag", "friendly", "--tag", "vaccinated",
+            "--db-host", "db",
+        ])
+        .unwrap();
+
+        assert_eq!(cli.database.db_host, "db");
+        let Command::Pet(PetCommand::Create { name, tags, id, .. }) = cli.command else {
+            panic!("expected pet create, got {:?}", cli.command);
+        };
+        assert_eq!((name.as_str(), tags, id), ("Rex", vec!["friendly".to_string(), "vaccinated".to_string()], None));
+    }
+
+    #[test]
+    fn test_parse_migrate() {
+        let cli = Cli::try_parse_from(["petstore-admin", "migrate"]).unwrap();
+        assert!(matches!(cli.command, Command::Migrate { action: None }));
+
+        let cli = Cli::try_parse_from(["petstore-admin", "migrate", "down", "--to", "9"]).unwrap();
+        assert!(matches!(cli.command, Command::Migrate { action: Some(MigrateAction::Down { to: 9 }) }));
+        assert!(Cli::try_parse_from(["petstore-admin", "migrate", "down"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_seed() {
+        let cli = Cli::try_parse_from(["petstore-admin", "seed", "--profile", "synthetic:250", "--seed", "7"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::Seed { profile: SeedProfile::Synthetic { pets: 250 }, seed: 7 }
+        ));
+        assert!(Cli::try_parse_from(["petstore-admin", "seed", "--profile", "everything"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_import() {
+        let cli = Cli::try_parse_from(["petstore-admin", "import", "pets.jsonl", "--dry-run", "--format", "jsonl"]).unwrap();
+
+        let Command::Import { file, format, dry_run } = cli.command else {
+            panic!("expected import, got {:?}", cli.command);
+        };
+        assert_eq!((file, format, dry_run), (PathBuf::from("pets.jsonl"), Some(Format::Ndjson), true));
+        assert!(Cli::try_parse_from(["petstore-admin", "pet", "create", "--name", "Rex", "--category-name", "Dogs"]).is_err());
+    }
+}
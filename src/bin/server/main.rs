@@ -1,14 +1,40 @@
-use petstore_hexarch_rust::inbound::http::{HttpServer, HttpServerConfig};
+use petstore_hexarch_rust::inbound::grpc::{GrpcServer, GrpcServerConfig};
+use petstore_hexarch_rust::inbound::http::{
+    CorsConfig, HttpServer, HttpServerConfig, RateLimitConfig, RequestLimits, RouteLimit,
+    SecurityHeadersConfig, DEFAULT_CACHE_CONTROL, DEFAULT_IDEMPOTENCY_LEASE, DEFAULT_IDEMPOTENCY_WINDOW,
+};
+use petstore_hexarch_rust::domain::petstore::ids::StrategyIdGenerator;
+use petstore_hexarch_rust::domain::petstore::models::id::IdStrategy;
+use petstore_hexarch_rust::domain::petstore::ports::IdempotencyService;
+use petstore_hexarch_rust::domain::petstore::relay::OutboxRelay;
 use petstore_hexarch_rust::domain::petstore::service::Service;
+use petstore_hexarch_rust::domain::petstore::webhooks::{WebhookDispatcher, WebhookPublisher};
 use petstore_hexarch_rust::outbound::connect::PostgresClient;
+use petstore_hexarch_rust::outbound::migrations::MigrationMode;
+use petstore_hexarch_rust::outbound::pet_cache::{CachedPetRepository, PetCacheConfig};
+use petstore_hexarch_rust::outbound::webhook_sender::HttpWebhookSender;
 use petstore_hexarch_rust::outbound::params::ConnectionParams;
+use petstore_hexarch_rust::telemetry::{init_telemetry, LogFormat};
+
+/// How often the outbox is polled for events to relay.
+const OUTBOX_RELAY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+/// How often due webhook deliveries are attempted.
+const WEBHOOK_DISPATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+/// How often the statistics of the pet cache are logged.
+const PET_CACHE_STATS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+/// How often expired idempotency keys are purged.
+const IDEMPOTENCY_PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-
-
-    // A minimal tracing middleware for request logging.
-    tracing_subscriber::fmt::init();
+    // LOG_FORMAT is text (the default) for people or json for log collectors; RUST_LOG
+    // selects the levels logged. Built with the otel feature, traces are exported to
+    // OTEL_EXPORTER_OTLP_ENDPOINT when it is set
+    let log_format: LogFormat = match std::env::var("LOG_FORMAT") {
+        Ok(format) => format.parse()?,
+        Err(_) => LogFormat::default(),
+    };
+    let _telemetry = init_telemetry(log_format)?;
 
     let params = ConnectionParams {
         host: "localhost".to_string(),
@@ -24,7 +50,7 @@ async fn main() -> anyhow::Result<()> {
         match PostgresClient::new(&params).await {
             Ok(client) => break client,
             Err(e) if retries > 0 => {
-                eprintln!("Failed to connect to database, retrying... ({})", e);
+                tracing::warn!("failed to connect to database, retrying... ({})", e);
                 retries -= 1;
                 tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
             }
@@ -32,16 +58,181 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
-    sqlx::migrate!("./migrations")
-        .run(client.pool())
-        .await
-        .expect("Failed to run migrations");
-    
-    let pet_service = Service::new(client);
+    // MIGRATION_MODE is auto (the default) to apply pending migrations at startup, or verify to
+    // refuse to start until they have been applied, e.g. by `petstore-admin migrate`
+    let migration_mode: MigrationMode = match std::env::var("MIGRATION_MODE") {
+        Ok(mode) => mode.parse()?,
+        Err(_) => MigrationMode::default(),
+    };
+    match migration_mode {
+        MigrationMode::Auto => client.migrate().await?,
+        MigrationMode::Verify => client.migration_status().await?.ensure_current()?,
+    }
+
+    // ID_STRATEGY is one of sequence (the default), snowflake or client; NODE_ID tells apart
+    // the snowflake ids of instances running side by side
+    let id_strategy: IdStrategy = match std::env::var("ID_STRATEGY") {
+        Ok(strategy) => strategy.parse()?,
+        Err(_) => IdStrategy::default(),
+    };
+    let node_id: u16 = match std::env::var("NODE_ID") {
+        Ok(node_id) => node_id.parse()?,
+        Err(_) => 0,
+    };
+    let ids = StrategyIdGenerator::new(id_strategy, node_id)?;
+    let client = client.with_id_strategy(id_strategy);
+
+    // Deliver the pet events recorded in the outbox to the registered webhooks in the
+    // background
+    let relay = OutboxRelay::new(client.clone(), WebhookPublisher::new(client.clone()));
+    tokio::spawn(relay.run(OUTBOX_RELAY_INTERVAL));
+    let dispatcher = WebhookDispatcher::new(client.clone(), HttpWebhookSender::new(HttpWebhookSender::DEFAULT_TIMEOUT)?);
+    tokio::spawn(dispatcher.run(WEBHOOK_DISPATCH_INTERVAL));
+
+    // PET_CACHE_CAPACITY and PET_CACHE_TTL (in seconds) bound the pets cached by id; unknown
+    // ids are cached for PET_CACHE_NEGATIVE_TTL
+    let mut cache_config = PetCacheConfig::default();
+    if let Ok(capacity) = std::env::var("PET_CACHE_CAPACITY") {
+        cache_config.capacity = capacity.parse()?;
+    }
+    if let Ok(ttl) = std::env::var("PET_CACHE_TTL") {
+        cache_config.ttl = std::time::Duration::from_secs(ttl.parse()?);
+    }
+    if let Ok(ttl) = std::env::var("PET_CACHE_NEGATIVE_TTL") {
+        cache_config.negative_ttl = std::time::Duration::from_secs(ttl.parse()?);
+    }
+    let repo = CachedPetRepository::new(client, cache_config);
+    let cache = repo.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PET_CACHE_STATS_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let stats = cache.stats();
+            tracing::info!(
+                hits = stats.hits,
+                negative_hits = stats.negative_hits,
+                misses = stats.misses,
+                evictions = stats.evictions,
+                expirations = stats.expirations,
+                invalidations = stats.invalidations,
+                entries = stats.entries,
+                hit_ratio = stats.hit_ratio(),
+                "pet cache statistics"
+            );
+        }
+    });
+
+    let pet_service = Service::with_id_generator(repo, ids);
+
+    // Forget the idempotency keys whose responses are no longer replayed
+    let idempotency = pet_service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(IDEMPOTENCY_PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            match idempotency.purge_expired_idempotency_keys().await {
+                Ok(purged) => tracing::debug!(purged, "purged expired idempotency keys"),
+                Err(e) => tracing::warn!("failed to purge expired idempotency keys: {}", e),
+            }
+        }
+    });
+
+    let cache_control = std::env::var("CACHE_CONTROL").unwrap_or_else(|_| DEFAULT_CACHE_CONTROL.to_string());
+    // RATE_LIMIT (e.g. 100/s) limits the requests of each client, RATE_LIMIT_ROUTES (e.g.
+    // POST /api/pet/import=5/m,GET /api/pet/export=2/m) some routes on their own and
+    // DAILY_QUOTA the requests of each client per day; none of them admits every request
+    let mut rate_limit = RateLimitConfig::default();
+    if let Ok(limit) = std::env::var("RATE_LIMIT") {
+        rate_limit.default = Some(limit.parse()?);
+    }
+    if let Ok(routes) = std::env::var("RATE_LIMIT_ROUTES") {
+        rate_limit.routes = routes.split(',').map(str::parse).collect::<Result<Vec<RouteLimit>, _>>()?;
+    }
+    if let Ok(quota) = std::env::var("DAILY_QUOTA") {
+        rate_limit.daily_quota = Some(quota.parse()?);
+    }
+    if let Ok(trust) = std::env::var("RATE_LIMIT_TRUST_FORWARDED_FOR") {
+        rate_limit.trust_forwarded_for = trust.parse()?;
+    }
+    // API_KEYS (comma-separated) are the X-API-Key values identifying clients of their own;
+    // other clients are identified by their address
+    if let Ok(keys) = std::env::var("API_KEYS") {
+        rate_limit.api_keys = keys.split(',').map(str::trim).filter(|key| !key.is_empty()).map(String::from).collect();
+    }
+    let rate_limit = (rate_limit != RateLimitConfig::default()).then_some(rate_limit);
+
+    // CORS_ALLOWED_ORIGINS (e.g. https://shop.example.com,http://localhost:3000, or *) lets
+    // browser front-ends on those origins call the API, with cookies if CORS_ALLOW_CREDENTIALS
+    // is true; CORS_MAX_AGE is how long browsers cache preflight answers, in seconds
+    let mut cors = None;
+    if let Ok(origins) = std::env::var("CORS_ALLOWED_ORIGINS") {
+        let mut config = CorsConfig::new(origins.parse()?);
+        if let Ok(credentials) = std::env::var("CORS_ALLOW_CREDENTIALS") {
+            config.allow_credentials = credentials.parse()?;
+        }
+        if let Ok(max_age) = std::env::var("CORS_MAX_AGE") {
+            config.max_age = std::time::Duration::from_secs(max_age.parse()?);
+        }
+        cors = Some(config);
+    }
+    // HSTS_MAX_AGE, in seconds, is only to be set behind a proxy serving HTTPS
+    let mut security_headers = SecurityHeadersConfig::default();
+    if let Ok(max_age) = std::env::var("HSTS_MAX_AGE") {
+        security_headers.hsts_max_age = Some(std::time::Duration::from_secs(max_age.parse()?));
+    }
+    // MAX_BODY_BYTES and MAX_IMPORT_BODY_BYTES limit request bodies, REQUEST_TIMEOUT and
+    // IMPORT_TIMEOUT (in seconds) the time taken by requests and imports; COMPRESSION=false
+    // sends responses uncompressed
+    let mut limits = RequestLimits::default();
+    if let Ok(max) = std::env::var("MAX_BODY_BYTES") {
+        limits.max_body_bytes = max.parse()?;
+    }
+    if let Ok(max) = std::env::var("MAX_IMPORT_BODY_BYTES") {
+        limits.max_import_body_bytes = max.parse()?;
+    }
+    if let Ok(timeout) = std::env::var("REQUEST_TIMEOUT") {
+        limits.timeout = std::time::Duration::from_secs(timeout.parse()?);
+    }
+    if let Ok(timeout) = std::env::var("IMPORT_TIMEOUT") {
+        limits.import_timeout = std::time::Duration::from_secs(timeout.parse()?);
+    }
+    let compression = match std::env::var("COMPRESSION") {
+        Ok(compression) => compression.parse()?,
+        Err(_) => true,
+    };
+
+    // IDEMPOTENCY_WINDOW is how long, in seconds, the response to a request with an
+    // Idempotency-Key is replayed; IDEMPOTENCY_LEASE how long the key stays claimed while the
+    // request is processed, which should exceed REQUEST_TIMEOUT
+    let mut idempotency_window = DEFAULT_IDEMPOTENCY_WINDOW;
+    if let Ok(window) = std::env::var("IDEMPOTENCY_WINDOW") {
+        idempotency_window = std::time::Duration::from_secs(window.parse()?);
+    }
+    let mut idempotency_lease = DEFAULT_IDEMPOTENCY_LEASE;
+    if let Ok(lease) = std::env::var("IDEMPOTENCY_LEASE") {
+        idempotency_lease = std::time::Duration::from_secs(lease.parse()?);
+    }
 
     let server_config = HttpServerConfig {
         port: "8080",
+        cache_control: &cache_control,
+        idempotency_window,
+        idempotency_lease,
+        rate_limit,
+        cors,
+        security_headers,
+        limits,
+        compression,
+    };
+    // GRPC_PORT (e.g. 50051) also serves the pet operations over gRPC, beside the HTTP API
+    let grpc_server = match std::env::var("GRPC_PORT") {
+        Ok(port) => Some(GrpcServer::new(pet_service.clone(), GrpcServerConfig { port: &port }).await?),
+        Err(_) => None,
     };
     let http_server = HttpServer::new(pet_service, server_config).await?;
-    http_server.run().await
+    match grpc_server {
+        Some(grpc_server) => tokio::try_join!(http_server.run(), grpc_server.run()).map(|_| ()),
+        None => http_server.run().await,
+    }
 }
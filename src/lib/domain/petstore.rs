@@ -1,3 +1,7 @@
+pub mod ids;
 pub mod models;
 pub mod ports;
+pub mod relay;
+pub mod seed;
 pub mod service;
+pub mod webhooks;
\ No newline at end of file
@@ -0,0 +1,147 @@
+/*!
+   Module `ids` provides the canonical implementation of the [IdGenerator] port, choosing ids
+   according to the configured [IdStrategy].
+*/
+
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::domain::petstore::models::id::{GenerateIdError, IdKind, IdStrategy, IdStrategyError};
+use crate::domain::petstore::ports::IdGenerator;
+
+/// Generates ids according to an [IdStrategy].
+#[derive(Debug, Clone, Default)]
+pub struct StrategyIdGenerator {
+    strategy: IdStrategy,
+    snowflake: Option<Arc<Snowflake>>,
+}
+
+impl StrategyIdGenerator {
+    /// Returns a generator following `strategy`. `node_id` distinguishes the snowflake ids of
+    /// concurrently running instances, and is ignored by the other strategies.
+    ///
+    /// # Errors:
+    ///
+    /// - [IdStrategyError::NodeIdOutOfRange] if `node_id` does not fit a snowflake id.
+    pub fn new(strategy: IdStrategy, node_id: u16) -> Result<Self, IdStrategyError> {
+        let snowflake = match strategy {
+            IdStrategy::Snowflake => Some(Arc::new(Snowflake::new(node_id)?)),
+            IdStrategy::Sequence | IdStrategy::ClientSupplied => None,
+        };
+        Ok(Self { strategy, snowflake })
+    }
+
+    pub fn strategy(&self) -> IdStrategy {
+        self.strategy
+    }
+}
+
+impl IdGenerator for StrategyIdGenerator {
+    async fn next_id(&self, kind: IdKind) -> Result<Option<i64>, GenerateIdError> {
+        match (&self.strategy, &self.snowflake) {
+            (IdStrategy::Sequence, _) => Ok(None),
+            (IdStrategy::Snowflake, Some(snowflake)) => Ok(Some(snowflake.next_id())),
+            (IdStrategy::Snowflake, None) => Err(GenerateIdError::Unknown(anyhow::anyhow!(
+                "snowflake generator is not initialized"
+            ))),
+            (IdStrategy::ClientSupplied, _) => Err(GenerateIdError::Required { kind }),
+        }
+    }
+}
+
+/// A Twitter-style snowflake: 41 bits of milliseconds since [Snowflake::EPOCH_MS], 10 bits of
+/// node id and a 12-bit sequence within the millisecond.
+///
+/// Ids are strictly increasing per node. When the sequence of a millisecond is exhausted or the
+/// clock moves backwards, the generator borrows from the following milliseconds instead of
+/// waiting.
+#[derive(Debug)]
+struct Snowflake {
+    node_id: i64,
+    /// The millisecond and sequence of the last id.
+    last: Mutex<(i64, i64)>,
+}
+
+impl Snowflake {
+    /// 2024-01-01T00:00:00Z.
+    const EPOCH_MS: i64 = 1_704_067_200_000;
+    const NODE_BITS: u32 = 10;
+    const SEQUENCE_BITS: u32 = 12;
+    const MAX_NODE_ID: u16 = (1 << Self::NODE_BITS) - 1;
+    const MAX_SEQUENCE: i64 = (1 << Self::SEQUENCE_BITS) - 1;
+
+    fn new(node_id: u16) -> Result<Self, IdStrategyError> {
+        if node_id > Self::MAX_NODE_ID {
+            return Err(IdStrategyError::NodeIdOutOfRange {
+                actual: node_id,
+                max: Self::MAX_NODE_ID,
+            });
+        }
+        Ok(Self {
+            node_id: i64::from(node_id),
+            last: Mutex::new((-1, Self::MAX_SEQUENCE)),
+        })
+    }
+
+    fn next_id(&self) -> i64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or_default()
+            - Self::EPOCH_MS;
+        let mut last = self.last.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let (last_ms, last_sequence) = *last;
+        let next = if now > last_ms {
+            (now, 0)
+        } else if last_sequence < Self::MAX_SEQUENCE {
+            (last_ms, last_sequence + 1)
+        } else {
+            (last_ms + 1, 0)
+        };
+        *last = next;
+        (next.0 << (Self::NODE_BITS + Self::SEQUENCE_BITS)) | (self.node_id << Self::SEQUENCE_BITS) | next.1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sequence_leaves_ids_to_the_store() {
+        let ids = StrategyIdGenerator::new(IdStrategy::Sequence, 0).unwrap();
+
+        assert_eq!(ids.next_id(IdKind::Pet).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_client_supplied_requires_ids() {
+        let ids = StrategyIdGenerator::new(IdStrategy::ClientSupplied, 0).unwrap();
+
+        assert!(matches!(
+            ids.next_id(IdKind::Tag).await,
+            Err(GenerateIdError::Required { kind: IdKind::Tag })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_snowflake_ids_increase_and_carry_the_node_id() {
+        let ids = StrategyIdGenerator::new(IdStrategy::Snowflake, 7).unwrap();
+
+        let mut previous = 0;
+        for _ in 0..10_000 {
+            let id = ids.next_id(IdKind::Pet).await.unwrap().unwrap();
+            assert!(id > previous, "{} is not greater than {}", id, previous);
+            assert_eq!((id >> Snowflake::SEQUENCE_BITS) & i64::from(Snowflake::MAX_NODE_ID), 7);
+            previous = id;
+        }
+    }
+
+    #[test]
+    fn test_snowflake_node_id_range() {
+        assert!(matches!(
+            StrategyIdGenerator::new(IdStrategy::Snowflake, 1024),
+            Err(IdStrategyError::NodeIdOutOfRange { actual: 1024, max: 1023 })
+        ));
+    }
+}
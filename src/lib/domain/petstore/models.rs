@@ -1,4 +1,12 @@
 pub mod category;
+pub mod event;
+pub mod id;
+pub mod idempotency;
 pub mod pet;
+pub mod search;
+pub mod seed;
 pub mod tag;
-pub mod value_objects;
\ No newline at end of file
+pub mod transfer;
+pub mod validation;
+pub mod value_objects;
+pub mod webhook;
\ No newline at end of file
@@ -1,4 +1,7 @@
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::id::IdKind;
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Category {
@@ -35,6 +38,70 @@ impl std::fmt::Display for Category {
     }
 }
 
+/// A request to create a new [Category]. When `id` is `None` the repository assigns one.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CreateCategoryRequest {
+    pub id: Option<i64>,
+    pub name: String,
+}
+
+impl CreateCategoryRequest {
+    pub fn new(id: Option<i64>, name: String) -> Self {
+        Self { id, name }
+    }
+}
+
+/// A request to give an existing [Category] a new name.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RenameCategoryRequest {
+    pub id: i64,
+    pub name: String,
+}
+
+impl RenameCategoryRequest {
+    pub fn new(id: i64, name: String) -> Self {
+        Self { id, name }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum CreateCategoryError {
+    #[error("category with name {name} already exists")]
+    Duplicate { name: String },
+    #[error("category with id {id} already exists")]
+    DuplicateId { id: i64 },
+    #[error("a {kind} id is required")]
+    IdRequired { kind: IdKind },
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum FindCategoryError {
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum RenameCategoryError {
+    #[error("category with id {id} not found")]
+    NotFound { id: i64 },
+    #[error("category with name {name} already exists")]
+    Duplicate { name: String },
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum DeleteCategoryError {
+    #[error("category with id {id} not found")]
+    NotFound { id: i64 },
+    #[error("category with id {id} is still assigned to pets")]
+    InUse { id: i64 },
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -84,4 +151,20 @@ mod tests {
         category.name = Some(String::from("Birds"));
         assert_eq!(category.name, Some(String::from("Birds")));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_category_errors_display() {
+        assert_eq!(
+            CreateCategoryError::Duplicate { name: String::from("Dogs") }.to_string(),
+            "category with name Dogs already exists"
+        );
+        assert_eq!(
+            RenameCategoryError::NotFound { id: 7 }.to_string(),
+            "category with id 7 not found"
+        );
+        assert_eq!(
+            DeleteCategoryError::InUse { id: 7 }.to_string(),
+            "category with id 7 is still assigned to pets"
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::pet::{Pet, Status};
+
+/// A change to a [Pet] that other services may react to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PetEvent {
+    PetCreated {
+        pet_id: i64,
+        name: String,
+        category_id: Option<i64>,
+        status: Status,
+        version: i64,
+    },
+    PetStatusChanged {
+        pet_id: i64,
+        from: Status,
+        to: Status,
+        actor: Option<String>,
+        version: i64,
+    },
+    PetDeleted {
+        pet_id: i64,
+    },
+}
+
+impl PetEvent {
+    /// The names of all event types, as returned by [PetEvent::event_type].
+    pub const EVENT_TYPES: [&'static str; 3] = ["PetCreated", "PetStatusChanged", "PetDeleted"];
+
+    /// The event announcing a newly persisted `pet`, which must have an id.
+    pub fn created(pet: &Pet) -> Self {
+        Self::PetCreated {
+            pet_id: pet.id.unwrap_or_default(),
+            name: pet.name.clone(),
+            category_id: pet.category.as_ref().and_then(|c| c.id),
+            status: pet.status.clone().unwrap_or_default(),
+            version: pet.version,
+        }
+    }
+
+    /// The name of the event type, e.g. `PetCreated`.
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            Self::PetCreated { .. } => "PetCreated",
+            Self::PetStatusChanged { .. } => "PetStatusChanged",
+            Self::PetDeleted { .. } => "PetDeleted",
+        }
+    }
+
+    /// The id of the [Pet] the event is about.
+    pub fn pet_id(&self) -> i64 {
+        match self {
+            Self::PetCreated { pet_id, .. }
+            | Self::PetStatusChanged { pet_id, .. }
+            | Self::PetDeleted { pet_id } => *pet_id,
+        }
+    }
+}
+
+/// A [PetEvent] recorded in the outbox. Events are relayed in the order of their `id`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutboxEvent {
+    pub id: i64,
+    pub occurred_at: DateTime<Utc>,
+    pub event: PetEvent,
+}
+
+/// A [PetEvent] together with the state of the [Pet] after it, as streamed to live
+/// subscribers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PetChange {
+    pub event: PetEvent,
+    pub pet: Pet,
+}
+
+/// Selects the [PetChange]s a subscriber receives, by the state of the pet after the change.
+/// Unset criteria match every pet.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PetChangeFilter {
+    pub status: Option<Status>,
+    pub category_id: Option<i64>,
+}
+
+impl PetChangeFilter {
+    pub fn matches(&self, change: &PetChange) -> bool {
+        let pet = &change.pet;
+        self.status.as_ref().is_none_or(|status| pet.status.as_ref() == Some(status))
+            && self
+                .category_id
+                .is_none_or(|id| pet.category.as_ref().and_then(|c| c.id) == Some(id))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum OutboxError {
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum PublishEventError {
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum RelayError {
+    #[error("failed to publish event {event_id}: {source}")]
+    Publish {
+        event_id: i64,
+        source: PublishEventError,
+    },
+    #[error(transparent)]
+    Outbox(#[from] OutboxError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::petstore::models::category::Category;
+
+    #[test]
+    fn test_pet_event_serialization() {
+        let event = PetEvent::PetStatusChanged {
+            pet_id: 1,
+            from: Status::Available,
+            to: Status::Pending,
+            actor: Some("alice".to_string()),
+            version: 2,
+        };
+
+        let json = serde_json::to_value(&event).unwrap();
+
+        assert_eq!(json["PetStatusChanged"]["to"], "pending");
+        assert_eq!(serde_json::from_value::<PetEvent>(json).unwrap(), event);
+        assert_eq!(event.event_type(), "PetStatusChanged");
+        assert_eq!(event.pet_id(), 1);
+    }
+
+    #[test]
+    fn test_pet_deleted_serialization() {
+        let event = PetEvent::PetDeleted { pet_id: 7 };
+
+        let json = serde_json::to_value(&event).unwrap();
+
+        assert_eq!(json["PetDeleted"]["pet_id"], 7);
+        assert_eq!(serde_json::from_value::<PetEvent>(json).unwrap(), event);
+        assert_eq!(event.event_type(), "PetDeleted");
+        assert_eq!(event.pet_id(), 7);
+    }
+
+    #[test]
+    fn test_pet_created() {
+        let mut pet = Pet::with_id(7, "Rex".to_string());
+        pet.set_status(Status::Pending);
+
+        assert_eq!(
+            PetEvent::created(&pet),
+            PetEvent::PetCreated {
+                pet_id: 7,
+                name: "Rex".to_string(),
+                category_id: None,
+                status: Status::Pending,
+                version: Pet::INITIAL_VERSION,
+            }
+        );
+    }
+
+    #[test]
+    fn test_pet_change_filter() {
+        let mut pet = Pet::with_id(7, "Rex".to_string());
+        pet.set_status(Status::Sold);
+        pet.set_category(Category::with_values(3, "Dogs".to_string()));
+        let change = PetChange {
+            event: PetEvent::created(&pet),
+            pet,
+        };
+        let filter = |status: Option<Status>, category_id: Option<i64>| PetChangeFilter { status, category_id };
+
+        assert!(filter(None, None).matches(&change));
+        assert!(filter(Some(Status::Sold), Some(3)).matches(&change));
+        assert!(!filter(Some(Status::Available), None).matches(&change));
+        assert!(!filter(None, Some(4)).matches(&change));
+    }
+}
@@ -0,0 +1,76 @@
+use std::str::FromStr;
+
+use thiserror::Error;
+
+/// The kinds of entity that are assigned ids when created.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IdKind {
+    Pet,
+    Category,
+    Tag,
+}
+
+impl std::fmt::Display for IdKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IdKind::Pet => write!(f, "pet"),
+            IdKind::Category => write!(f, "category"),
+            IdKind::Tag => write!(f, "tag"),
+        }
+    }
+}
+
+/// How ids are chosen for entities created without one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum IdStrategy {
+    /// The store assigns the next value of its per-table sequence.
+    #[default]
+    Sequence,
+    /// Time-ordered 63-bit ids generated in the application, unique across nodes with
+    /// distinct node ids.
+    Snowflake,
+    /// Clients must supply every id; creating an entity without one is rejected.
+    ClientSupplied,
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum IdStrategyError {
+    #[error("id strategy {0} is not one of sequence, snowflake, client")]
+    Unknown(String),
+    #[error("snowflake node id {actual} is greater than {max}")]
+    NodeIdOutOfRange { actual: u16, max: u16 },
+}
+
+impl FromStr for IdStrategy {
+    type Err = IdStrategyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "sequence" => Ok(IdStrategy::Sequence),
+            "snowflake" => Ok(IdStrategy::Snowflake),
+            "client" => Ok(IdStrategy::ClientSupplied),
+            other => Err(IdStrategyError::Unknown(other.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum GenerateIdError {
+    #[error("a {kind} id is required")]
+    Required { kind: IdKind },
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_strategy_from_str() {
+        assert_eq!("sequence".parse::<IdStrategy>().unwrap(), IdStrategy::Sequence);
+        assert_eq!(" Snowflake ".parse::<IdStrategy>().unwrap(), IdStrategy::Snowflake);
+        assert_eq!("client".parse::<IdStrategy>().unwrap(), IdStrategy::ClientSupplied);
+        assert!(matches!("uuid".parse::<IdStrategy>(), Err(IdStrategyError::Unknown(s)) if s == "uuid"));
+    }
+}
@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use thiserror::Error;
+
+/// A client-chosen key identifying one logical request across its retries.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IdempotencyKey(String);
+
+#[derive(Debug, Clone, Error)]
+pub enum IdempotencyKeyError {
+    #[error("idempotency key cannot be empty")]
+    Empty,
+    #[error("idempotency key cannot be longer than {max} characters")]
+    TooLong { max: usize },
+}
+
+impl IdempotencyKey {
+    pub const MAX_LEN: usize = 255;
+
+    pub fn new(key: &str) -> Result<Self, IdempotencyKeyError> {
+        let trimmed = key.trim();
+        if trimmed.is_empty() {
+            return Err(IdempotencyKeyError::Empty);
+        }
+        if trimmed.chars().count() > Self::MAX_LEN {
+            return Err(IdempotencyKeyError::TooLong { max: Self::MAX_LEN });
+        }
+        Ok(Self(trimmed.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for IdempotencyKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A request to claim `key` for a request identified by `fingerprint`. The claim lasts for
+/// `lease` from now, long enough to process the request, after which an abandoned claim is
+/// free again; completing the key keeps it for the replay window instead.
+///
+/// The fingerprint is opaque to the domain; retries of the same request must produce the same
+/// fingerprint, and different requests different ones.
+///
+/// The `token` is unique to this claim, telling it apart from a retry that took the key over
+/// after the lease expired: only the claim still holding the key may complete or release it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ClaimIdempotencyKeyRequest {
+    pub key: IdempotencyKey,
+    pub fingerprint: String,
+    pub lease: Duration,
+    pub token: String,
+}
+
+impl ClaimIdempotencyKeyRequest {
+    pub fn new(key: IdempotencyKey, fingerprint: String, lease: Duration) -> Self {
+        Self {
+            key,
+            fingerprint,
+            lease,
+            token: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+}
+
+/// The response first returned for an idempotency key, replayed to its retries.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IdempotentResponse {
+    pub status_code: u16,
+    /// The headers worth replaying, such as the content type, as names and values.
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+/// The outcome of claiming an idempotency key.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum IdempotencyClaim {
+    /// The key was free or expired: the caller now owns it and must process the request, then
+    /// complete or release the key.
+    Claimed,
+    /// The key was used for the same request, which completed with this response.
+    Completed(IdempotentResponse),
+    /// The key is owned by the same request, which is still being processed.
+    InProgress,
+    /// The key was used for a different request.
+    Mismatch,
+}
+
+#[derive(Debug, Error)]
+pub enum IdempotencyError {
+    /// The claim expired and the key was claimed again, or completed, by another request or a
+    /// retry of the same request.
+    #[error("idempotency key {key} is no longer claimed by this request")]
+    ClaimLost { key: IdempotencyKey },
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idempotency_key() {
+        assert_eq!(IdempotencyKey::new(" abc-123 ").unwrap().as_str(), "abc-123");
+        assert!(matches!(IdempotencyKey::new("  "), Err(IdempotencyKeyError::Empty)));
+        assert!(matches!(
+            IdempotencyKey::new(&"k".repeat(IdempotencyKey::MAX_LEN + 1)),
+            Err(IdempotencyKeyError::TooLong { max: IdempotencyKey::MAX_LEN })
+        ));
+    }
+}
@@ -1,9 +1,16 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
 use derive_more::From;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 
 use super::category::Category;
+use super::id::IdKind;
 use super::tag::Tag;
+use super::validation::{ValidationErrors, Violation};
+use super::value_objects::{CategoryName, PetCategory, PetName, PhotoUrl, PhotoUrls, TagName, Tags, TagsError};
 
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -14,10 +21,14 @@ pub struct Pet {
     pub photo_urls: Vec<String>,
     pub tags: Vec<Tag>,
     pub status: Option<Status>,
+    /// Incremented on every change, for optimistic concurrency control.
+    pub version: i64,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Status {
+    #[default]
     Available,
     Pending,
     Sold,
@@ -31,6 +42,19 @@ impl Status {
             Status::Sold => "sold",
         }
     }
+
+    /// Whether a pet in this status may move to `next`.
+    ///
+    /// A pet is put on hold (available → pending), then either sold (pending → sold) or
+    /// released again when the sale is cancelled (pending → available). Sold is final.
+    pub fn can_transition_to(&self, next: &Status) -> bool {
+        matches!(
+            (self, next),
+            (Status::Available, Status::Pending)
+                | (Status::Pending, Status::Sold)
+                | (Status::Pending, Status::Available)
+        )
+    }
 }
 
 impl std::fmt::Display for Status {
@@ -39,13 +63,10 @@ impl std::fmt::Display for Status {
     }
 }
 
-impl Default for Status {
-    fn default() -> Status {
-        Self::Available
-    }
-}
-
 impl Pet {
+    /// The version of a newly created [Pet].
+    pub const INITIAL_VERSION: i64 = 1;
+
     pub fn new(name: String) -> Self {
         Pet {
             id: None,
@@ -54,6 +75,7 @@ impl Pet {
             photo_urls: Vec::new(),
             tags: Vec::new(),
             status: Some(Status::default()),
+            version: Self::INITIAL_VERSION,
         }
     }
 
@@ -65,6 +87,7 @@ impl Pet {
             photo_urls: Vec::new(),
             tags: Vec::new(),
             status: Some(Status::default()),
+            version: Self::INITIAL_VERSION,
         }
     }
 
@@ -80,10 +103,30 @@ impl Pet {
         self.tags.push(tag);
     }
 
+    /// Sets the status unconditionally. Only meant for building a [Pet] from its initial or
+    /// persisted state; status changes of an existing pet go through [Pet::transition_to].
     pub fn set_status(&mut self, status: Status) {
         self.status = Some(status);
     }
 
+    /// Moves the pet to the `next` status if [Status::can_transition_to] allows it.
+    ///
+    /// # Errors:
+    ///
+    /// - [InvalidTransition] if the move is not allowed. The pet is left unchanged.
+    pub fn transition_to(&mut self, next: Status) -> Result<(), InvalidTransition> {
+        let current = self.status.clone().unwrap_or_default();
+        if !current.can_transition_to(&next) {
+            return Err(InvalidTransition { from: current, to: next });
+        }
+        self.status = Some(next);
+        Ok(())
+    }
+
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, From)]
@@ -131,17 +174,347 @@ impl CreatePetRequest {
     pub fn status(&self) -> &Option<Status> {
         &self.status
     }
+
+    /// Checks every field of the request, collecting every invalid one, and returns the request
+    /// with its tag names trimmed. Violations of nested fields are reported at the paths in
+    /// `fields`, which depend on the shape the request was read from.
+    pub fn validate(self, fields: &CreatePetFields) -> Result<Self, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let name = errors.check("name", PetName::new(&self.name));
+        let category = errors.check(fields.category_id, PetCategory::new(&self.category));
+        if let Some(category_name) = self.category.as_ref().and_then(|c| c.name.as_deref()) {
+            errors.check(fields.category_name, CategoryName::new(category_name));
+        }
+        let photo_urls = errors.check("photo_urls", PhotoUrls::new(&self.photo_urls));
+        for (i, url) in self.photo_urls.iter().enumerate() {
+            errors.check(format!("photo_urls[{}]", i), PhotoUrl::new(url));
+        }
+        let tags = (!self.tags.is_empty()).then_some(self.tags);
+        let tags = errors
+            .check("tags", Tags::new(&tags))
+            .map(|tags| check_tag_names(tags.into_inner(), fields, &mut errors));
+
+        match (name, category, photo_urls, tags) {
+            (Some(name), Some(category), Some(photo_urls), Some(tags)) if errors.is_empty() => Ok(Self {
+                id: self.id,
+                name: name.into_inner(),
+                category: category.into_inner(),
+                photo_urls: photo_urls.into_inner(),
+                tags,
+                status: self.status,
+            }),
+            _ => Err(errors),
+        }
+    }
+}
+
+/// The paths at which [CreatePetRequest::validate] reports violations of nested fields.
+#[derive(Clone, Copy, Debug)]
+pub struct CreatePetFields {
+    pub category_id: &'static str,
+    pub category_name: &'static str,
+    /// The path of the name of the tag at an index.
+    pub tag_name: fn(usize) -> String,
+}
+
+impl CreatePetFields {
+    /// Paths into a pet with a nested category and tags, as in the HTTP and gRPC APIs.
+    pub const NESTED: Self = Self {
+        category_id: "category.id",
+        category_name: "category.name",
+        tag_name: |i| format!("tags[{}].name", i),
+    };
+
+    /// Paths into a flat [PetRecord](super::transfer::PetRecord) of bulk import files.
+    pub const FLAT: Self = Self {
+        category_id: "category_id",
+        category_name: "category_name",
+        tag_name: |i| format!("tags[{}]", i),
+    };
+}
+
+/// Checks that every tag has a valid name, listed once, and returns the tags with their names
+/// trimmed.
+fn check_tag_names(tags: Vec<Tag>, fields: &CreatePetFields, errors: &mut ValidationErrors) -> Vec<Tag> {
+    let mut names = HashSet::new();
+    let mut checked = Vec::with_capacity(tags.len());
+    for (i, tag) in tags.into_iter().enumerate() {
+        let field = (fields.tag_name)(i);
+        let Some(name) = errors.check(field.clone(), TagName::new(tag.name.as_deref().unwrap_or_default())) else {
+            continue;
+        };
+        let name = name.into_inner();
+        if !names.insert(name.clone()) {
+            errors.add(field, &TagsError::Duplicate { name });
+            continue;
+        }
+        checked.push(Tag { id: tag.id, name: Some(name) });
+    }
+    checked
 }
 
 #[derive(Debug, Error)]
 pub enum CreatePetError {
     #[error("pet with name {name} already exists")]
     Duplicate { name: String },
+    #[error("pet with id {id} already exists")]
+    DuplicateId { id: i64 },
+    #[error("tag with id {id} already exists under another name")]
+    DuplicateTagId { id: i64 },
+    #[error("category with id {id} does not exist")]
+    UnknownCategory { id: i64 },
+    #[error("category id is required")]
+    CategoryIdRequired,
+    #[error("a {kind} id is required")]
+    IdRequired { kind: IdKind },
     #[error(transparent)]
     Unknown(#[from] anyhow::Error),
     // to be extended as new error scenarios are introduced
 }
 
+impl Violation for CreatePetError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::Duplicate { .. } | Self::DuplicateId { .. } | Self::DuplicateTagId { .. } => "duplicate",
+            Self::UnknownCategory { .. } => "unknown-category",
+            Self::CategoryIdRequired | Self::IdRequired { .. } => "required",
+            Self::Unknown(_) => "unknown",
+        }
+    }
+}
+
+/// A status change that the pet lifecycle does not allow.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Error)]
+#[error("cannot change pet status from {from} to {to}")]
+pub struct InvalidTransition {
+    pub from: Status,
+    pub to: Status,
+}
+
+/// A request to move the [Pet] with id `pet_id` to a new status, on behalf of `actor`.
+///
+/// If `expected_version` is set, the change is only applied to that version of the pet.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ChangePetStatusRequest {
+    pub pet_id: i64,
+    pub status: Status,
+    pub actor: Option<String>,
+    pub expected_version: Option<i64>,
+}
+
+impl ChangePetStatusRequest {
+    pub fn new(pet_id: i64, status: Status) -> Self {
+        Self {
+            pet_id,
+            status,
+            actor: None,
+            expected_version: None,
+        }
+    }
+
+    pub fn with_expected_version(mut self, version: Option<i64>) -> Self {
+        self.expected_version = version;
+        self
+    }
+
+    pub fn with_actor(mut self, actor: Option<String>) -> Self {
+        self.actor = actor;
+        self
+    }
+}
+
+/// One entry of a pet's lifecycle audit trail. `from` is `None` for the status the pet was
+/// created with.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StatusChange {
+    pub pet_id: i64,
+    pub from: Option<Status>,
+    pub to: Status,
+    pub actor: Option<String>,
+    pub changed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Error)]
+pub enum PetHistoryError {
+    #[error("pet with id {id} not found")]
+    NotFound { id: i64 },
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum DeletePetError {
+    #[error("pet with id {id} not found")]
+    NotFound { id: i64 },
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum ChangePetStatusError {
+    #[error("pet with id {id} not found")]
+    NotFound { id: i64 },
+    #[error(transparent)]
+    InvalidTransition(#[from] InvalidTransition),
+    #[error("pet with id {id} was modified concurrently: expected version {expected}, found {actual}")]
+    Conflict { id: i64, expected: i64, actual: i64 },
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+/// The field by which a page of [Pet]s is ordered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PetSortField {
+    #[default]
+    Id,
+    Name,
+}
+
+/// The direction in which a page of [Pet]s is ordered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Position of the last [Pet] of a page, from which the next page continues.
+///
+/// The cursor carries both the id and the name of the pet so that it remains valid whichever
+/// [PetSortField] is requested. Its string form is opaque to clients.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PetCursor {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum PetCursorError {
+    #[error("cursor {0} is malformed")]
+    Malformed(String),
+}
+
+impl Violation for PetCursorError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::Malformed(_) => "malformed",
+        }
+    }
+}
+
+impl PetCursor {
+    pub fn new(id: i64, name: String) -> Self {
+        Self { id, name }
+    }
+
+    /// Returns the cursor positioned at `pet`, or `None` if the pet has not been persisted.
+    pub fn from_pet(pet: &Pet) -> Option<Self> {
+        pet.id.map(|id| Self::new(id, pet.name.clone()))
+    }
+
+    /// Encodes the cursor as an opaque, URL-safe string.
+    pub fn encode(&self) -> String {
+        format!("{}:{}", self.id, self.name)
+            .bytes()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// Decodes a cursor previously produced by [PetCursor::encode].
+    pub fn decode(encoded: &str) -> Result<Self, PetCursorError> {
+        let malformed = || PetCursorError::Malformed(encoded.to_string());
+        let bytes = encoded
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| match pair {
+                [hi, lo] => std::str::from_utf8(&[*hi, *lo])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(malformed),
+                _ => Err(malformed()),
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+        let decoded = String::from_utf8(bytes).map_err(|_| malformed())?;
+        let (id, name) = decoded.split_once(':').ok_or_else(malformed)?;
+        let id = id.parse::<i64>().map_err(|_| malformed())?;
+        Ok(Self::new(id, name.to_string()))
+    }
+}
+
+
+/// Parameters for listing [Pet]s a page at a time using keyset pagination.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ListPetsRequest {
+    pub sort: PetSortField,
+    pub direction: SortDirection,
+    pub status: Option<Status>,
+    pub category_id: Option<i64>,
+    pub limit: u32,
+    pub cursor: Option<PetCursor>,
+}
+
+impl ListPetsRequest {
+    pub const DEFAULT_LIMIT: u32 = 20;
+
+    pub fn new(
+        sort: PetSortField,
+        direction: SortDirection,
+        status: Option<Status>,
+        category_id: Option<i64>,
+        limit: u32,
+        cursor: Option<PetCursor>,
+    ) -> Self {
+        Self {
+            sort,
+            direction,
+            status,
+            category_id,
+            limit,
+            cursor,
+        }
+    }
+}
+
+impl Default for ListPetsRequest {
+    fn default() -> Self {
+        Self::new(
+            PetSortField::default(),
+            SortDirection::default(),
+            None,
+            None,
+            Self::DEFAULT_LIMIT,
+            None,
+        )
+    }
+}
+
+/// A page of [Pet]s and the cursor from which the following page starts, if any.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PetPage {
+    pub pets: Vec<Pet>,
+    pub next_cursor: Option<PetCursor>,
+}
+
+impl PetPage {
+    /// Builds a page from up to `limit + 1` pets fetched in order. The extra pet, if present,
+    /// only signals that another page exists and is dropped from the result.
+    pub fn from_overfetched(mut pets: Vec<Pet>, limit: u32) -> Self {
+        let limit = limit as usize;
+        if pets.len() <= limit {
+            return Self { pets, next_cursor: None };
+        }
+        pets.truncate(limit);
+        let next_cursor = pets.last().and_then(PetCursor::from_pet);
+        Self { pets, next_cursor }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ListPetsError {
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -225,6 +598,37 @@ mod tests {
         assert!(matches!(pet.status, Some(Status::Sold)));
     }
 
+    #[test]
+    fn test_status_transitions() {
+        use Status::*;
+
+        let allowed = [(Available, Pending), (Pending, Sold), (Pending, Available)];
+        for from in [Available, Pending, Sold] {
+            for to in [Available, Pending, Sold] {
+                assert_eq!(
+                    from.can_transition_to(&to),
+                    allowed.contains(&(from.clone(), to.clone())),
+                    "{} -> {}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_pet_transition_to() {
+        let mut pet = Pet::new(String::from("Luna"));
+
+        pet.transition_to(Status::Pending).unwrap();
+        pet.transition_to(Status::Sold).unwrap();
+
+        let error = pet.transition_to(Status::Available).unwrap_err();
+        assert_eq!(error, InvalidTransition { from: Status::Sold, to: Status::Available });
+        assert_eq!(error.to_string(), "cannot change pet status from sold to available");
+        assert_eq!(pet.status, Some(Status::Sold));
+    }
+
     #[test]
     fn test_pet_clone() {
         let mut pet = Pet::new(String::from("Bella"));
@@ -254,7 +658,7 @@ mod tests {
         let status = Some(Status::Available);
 
         let request = CreatePetRequest::new(
-            id.clone(),
+            id,
             name.clone(),
             category.clone(),
             photo_urls.clone(),
@@ -310,6 +714,52 @@ mod tests {
         assert_eq!(request.status(), &Some(Status::Pending));
     }
 
+    #[test]
+    fn test_create_pet_request_validate() {
+        let request = CreatePetRequest::new(
+            None,
+            String::from("Buddy"),
+            Some(Category::with_values(1, String::from("Dogs"))),
+            vec![String::from("http://example.com/buddy.jpg")],
+            vec![Tag { id: None, name: Some(String::from(" friendly ")) }],
+            Some(Status::Available),
+        );
+
+        let validated = request.validate(&CreatePetFields::NESTED).unwrap();
+
+        assert_eq!(validated.tags()[0].name.as_deref(), Some("friendly"));
+    }
+
+    #[test]
+    fn test_create_pet_request_validate_collects_every_invalid_field() {
+        let request = CreatePetRequest::new(
+            None,
+            String::new(),
+            Some(Category { id: None, name: Some(String::from(" ")) }),
+            vec![String::from("buddy.jpg")],
+            vec![
+                Tag { id: None, name: Some(String::from("friendly")) },
+                Tag { id: None, name: Some(String::from("friendly ")) },
+                Tag { id: None, name: None },
+            ],
+            None,
+        );
+
+        let errors = request.validate(&CreatePetFields::NESTED).unwrap_err();
+
+        assert_eq!(
+            errors.violations().iter().map(|v| (v.field.as_str(), v.code)).collect::<Vec<_>>(),
+            vec![
+                ("name", "empty"),
+                ("category.id", "required"),
+                ("category.name", "empty"),
+                ("photo_urls[0]", "invalid-url"),
+                ("tags[1].name", "duplicate"),
+                ("tags[2].name", "empty"),
+            ]
+        );
+    }
+
     #[test]
     fn test_create_pet_error_duplicate() {
         let name = String::from("Rex");
@@ -340,5 +790,34 @@ mod tests {
         assert!(format!("{:?}", error).contains("Duplicate"));
         assert!(format!("{:?}", error).contains("Max"));
     }
-}
 
+    #[test]
+    fn test_pet_cursor_round_trip() {
+        let cursor = PetCursor::new(42, String::from("Mr: Whiskers"));
+
+        let encoded = cursor.encode();
+        assert!(encoded.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(PetCursor::decode(&encoded).unwrap(), cursor);
+    }
+
+    #[test]
+    fn test_pet_cursor_decode_malformed() {
+        assert!(PetCursor::decode("abc").is_err());
+        assert!(PetCursor::decode("zz").is_err());
+        // "nope" has no id separator
+        assert!(PetCursor::decode("6e6f7065").is_err());
+    }
+
+    #[test]
+    fn test_pet_page_from_overfetched() {
+        let pets: Vec<Pet> = (1..=3).map(|id| Pet::with_id(id, format!("pet{}", id))).collect();
+
+        let page = PetPage::from_overfetched(pets.clone(), 2);
+        assert_eq!(page.pets, pets[..2].to_vec());
+        assert_eq!(page.next_cursor, Some(PetCursor::new(2, String::from("pet2"))));
+
+        let last_page = PetPage::from_overfetched(pets.clone(), 3);
+        assert_eq!(last_page.pets, pets);
+        assert!(last_page.next_cursor.is_none());
+    }
+}
@@ -0,0 +1,332 @@
+use std::cmp::Ordering;
+
+use serde::Serialize;
+use thiserror::Error;
+
+use super::pet::{ListPetsRequest, Pet};
+use super::validation::Violation;
+
+/// What staff typed to find pets: lowercase terms, each of which must occur in the name, the
+/// category name or a tag name of a matching pet.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SearchQuery {
+    text: String,
+    terms: Vec<String>,
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum SearchQueryError {
+    #[error("search query cannot be empty")]
+    Empty,
+    #[error("search query cannot be longer than {max} characters")]
+    TooLong { max: usize },
+    #[error("search query cannot have more than {max} terms")]
+    TooManyTerms { max: usize },
+}
+
+impl Violation for SearchQueryError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::TooLong { .. } => "too-long",
+            Self::TooManyTerms { .. } => "too-many-terms",
+        }
+    }
+}
+
+impl SearchQuery {
+    pub const MAX_LEN: usize = 100;
+    pub const MAX_TERMS: usize = 8;
+
+    pub fn new(text: &str) -> Result<Self, SearchQueryError> {
+        if text.chars().count() > Self::MAX_LEN {
+            return Err(SearchQueryError::TooLong { max: Self::MAX_LEN });
+        }
+        let terms: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+        if terms.is_empty() {
+            return Err(SearchQueryError::Empty);
+        }
+        if terms.len() > Self::MAX_TERMS {
+            return Err(SearchQueryError::TooManyTerms { max: Self::MAX_TERMS });
+        }
+        Ok(Self { text: terms.join(" "), terms })
+    }
+
+    /// The normalized query: its terms joined by single spaces.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn terms(&self) -> &[String] {
+        &self.terms
+    }
+}
+
+/// Parameters for searching [Pet]s, best matches first.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SearchPetsRequest {
+    pub query: SearchQuery,
+    pub limit: u32,
+}
+
+impl SearchPetsRequest {
+    pub const DEFAULT_LIMIT: u32 = ListPetsRequest::DEFAULT_LIMIT;
+
+    pub fn new(query: SearchQuery, limit: u32) -> Self {
+        Self { query, limit }
+    }
+}
+
+/// The searchable fields of a [Pet].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchField {
+    Name,
+    Category,
+    Tag,
+}
+
+impl SearchField {
+    /// How much a match in the field counts towards the rank; the name counts most.
+    pub fn weight(&self) -> f32 {
+        match self {
+            Self::Name => 1.0,
+            Self::Category => 0.4,
+            Self::Tag => 0.2,
+        }
+    }
+}
+
+/// A [Pet] matching a search, and how well it matches: the higher the rank, the better.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PetMatch {
+    pub pet: Pet,
+    pub rank: f32,
+}
+
+/// The span of a field value that matches a search term, in characters, `end` excluded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+pub struct MatchSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// The value of a field of a found [Pet], and the spans of it matching the search.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Highlight {
+    pub field: SearchField,
+    pub value: String,
+    pub spans: Vec<MatchSpan>,
+}
+
+/// A found [Pet] with its rank and the highlighted matches.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PetSearchHit {
+    pub pet: Pet,
+    pub rank: f32,
+    pub highlights: Vec<Highlight>,
+}
+
+impl PetSearchHit {
+    /// Highlights the fields of the matched pet that match `query`.
+    pub fn new(found: PetMatch, query: &SearchQuery) -> Self {
+        let highlights = searchable_fields(&found.pet)
+            .filter_map(|(field, value)| {
+                let spans = match_spans(value, query.terms());
+                (!spans.is_empty()).then(|| Highlight { field, value: value.to_string(), spans })
+            })
+            .collect();
+        Self { pet: found.pet, rank: found.rank, highlights }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SearchPetsError {
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+/// The name, category name and tag names of `pet`.
+fn searchable_fields(pet: &Pet) -> impl Iterator<Item = (SearchField, &str)> {
+    let name = std::iter::once((SearchField::Name, pet.name.as_str()));
+    let category = pet
+        .category
+        .iter()
+        .filter_map(|c| c.name.as_deref())
+        .map(|name| (SearchField::Category, name));
+    let tags = pet
+        .tags
+        .iter()
+        .filter_map(|t| t.name.as_deref())
+        .map(|name| (SearchField::Tag, name));
+    name.chain(category).chain(tags)
+}
+
+/// The case-insensitive occurrences of `terms` in `value`, merged where they overlap.
+fn match_spans(value: &str, terms: &[String]) -> Vec<MatchSpan> {
+    let haystack: Vec<char> = value.chars().collect();
+    let mut spans: Vec<MatchSpan> = Vec::new();
+    for term in terms {
+        let needle: Vec<char> = term.chars().collect();
+        if needle.is_empty() || needle.len() > haystack.len() {
+            continue;
+        }
+        for start in 0..=haystack.len() - needle.len() {
+            let window = &haystack[start..start + needle.len()];
+            if window.iter().zip(&needle).all(|(h, n)| h.to_lowercase().eq(n.to_lowercase())) {
+                spans.push(MatchSpan { start, end: start + needle.len() });
+            }
+        }
+    }
+    spans.sort_by_key(|span| span.start);
+    let mut merged: Vec<MatchSpan> = Vec::with_capacity(spans.len());
+    for span in spans {
+        match merged.last_mut() {
+            Some(last) if span.start <= last.end => last.end = last.end.max(span.end),
+            _ => merged.push(span),
+        }
+    }
+    merged
+}
+
+/// Ranks `pet` against `query` by plain substring matching, for stores without a text index.
+///
+/// The pet matches if every term occurs in one of its fields. Each term adds the weight of the
+/// best field it occurs in, doubled when it starts a word there; the sum is divided by the
+/// number of terms.
+pub fn substring_rank(pet: &Pet, query: &SearchQuery) -> Option<f32> {
+    let mut rank = 0.0;
+    for term in query.terms() {
+        let best = searchable_fields(pet)
+            .filter_map(|(field, value)| {
+                let spans = match_spans(value, std::slice::from_ref(term));
+                if spans.is_empty() {
+                    return None;
+                }
+                let prefix = spans.iter().any(|span| starts_word(value, span.start));
+                Some(field.weight() * if prefix { 2.0 } else { 1.0 })
+            })
+            .reduce(f32::max)?;
+        rank += best;
+    }
+    Some(rank / query.terms().len() as f32)
+}
+
+fn starts_word(value: &str, start: usize) -> bool {
+    start == 0 || value.chars().nth(start - 1).is_some_and(|c| !c.is_alphanumeric())
+}
+
+/// Searches `pets` by [substring_rank], best matches first and ties by id, up to `req.limit`.
+pub fn search_by_substring<'a>(pets: impl IntoIterator<Item = &'a Pet>, req: &SearchPetsRequest) -> Vec<PetMatch> {
+    let mut found: Vec<PetMatch> = pets
+        .into_iter()
+        .filter_map(|pet| {
+            substring_rank(pet, &req.query).map(|rank| PetMatch { pet: pet.clone(), rank })
+        })
+        .collect();
+    found.sort_by(|a, b| {
+        b.rank
+            .partial_cmp(&a.rank)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.pet.id.cmp(&b.pet.id))
+    });
+    found.truncate(req.limit as usize);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::petstore::models::category::Category;
+    use crate::domain::petstore::models::tag::Tag;
+
+    fn pet(id: i64, name: &str, category: &str, tags: &[&str]) -> Pet {
+        let mut pet = Pet::with_id(id, name.to_string());
+        pet.set_category(Category::with_values(1, category.to_string()));
+        for (tag_id, tag) in tags.iter().enumerate() {
+            pet.add_tag(Tag::with_values(tag_id as i64, tag.to_string()));
+        }
+        pet
+    }
+
+    #[test]
+    fn test_search_query() {
+        let query = SearchQuery::new("  Golden   RETRIEVER ").unwrap();
+        assert_eq!(query.text(), "golden retriever");
+        assert_eq!(query.terms(), ["golden", "retriever"]);
+
+        assert!(matches!(SearchQuery::new(" \t"), Err(SearchQueryError::Empty)));
+        assert!(matches!(
+            SearchQuery::new(&"x".repeat(SearchQuery::MAX_LEN + 1)),
+            Err(SearchQueryError::TooLong { .. })
+        ));
+        assert!(matches!(
+            SearchQuery::new("a b c d e f g h i"),
+            Err(SearchQueryError::TooManyTerms { max: 8 })
+        ));
+    }
+
+    #[test]
+    fn test_highlights() {
+        let query = SearchQuery::new("bud dd dog").unwrap();
+        let found = PetMatch { pet: pet(1, "Buddy", "Dogs", &["good boy", "cat-friendly"]), rank: 1.0 };
+
+        let hit = PetSearchHit::new(found, &query);
+
+        assert_eq!(
+            hit.highlights,
+            vec![
+                Highlight {
+                    field: SearchField::Name,
+                    value: "Buddy".to_string(),
+                    // "bud" and "dd" overlap
+                    spans: vec![MatchSpan { start: 0, end: 4 }],
+                },
+                Highlight {
+                    field: SearchField::Category,
+                    value: "Dogs".to_string(),
+                    spans: vec![MatchSpan { start: 0, end: 3 }],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_highlight_spans_count_characters() {
+        let spans = match_spans("Zoë & Zoé", &["zo".to_string(), "é".to_string()]);
+        assert_eq!(
+            spans,
+            // "zo" and "é" are adjacent in the second name, so one span covers both
+            vec![MatchSpan { start: 0, end: 2 }, MatchSpan { start: 6, end: 9 }]
+        );
+    }
+
+    #[test]
+    fn test_substring_rank() {
+        let buddy = pet(1, "Buddy", "Dogs", &["good boy"]);
+
+        // Every term must match some field
+        assert!(substring_rank(&buddy, &SearchQuery::new("bud cat").unwrap()).is_none());
+        // A name prefix ranks above a tag prefix, which ranks above the inside of a tag
+        let name = substring_rank(&buddy, &SearchQuery::new("bud").unwrap()).unwrap();
+        let tag = substring_rank(&buddy, &SearchQuery::new("boy").unwrap()).unwrap();
+        let inside = substring_rank(&buddy, &SearchQuery::new("oy").unwrap()).unwrap();
+        assert!(name > tag && tag > inside, "{} {} {}", name, tag, inside);
+    }
+
+    #[test]
+    fn test_search_by_substring() {
+        let pets = [
+            pet(1, "Rex", "Dogs", &["rescue"]),
+            pet(2, "Daisy", "Cats", &["dog-friendly"]),
+            pet(3, "Doggo", "Dogs", &[]),
+            pet(4, "Tom", "Cats", &[]),
+        ];
+        let req = SearchPetsRequest::new(SearchQuery::new("dog").unwrap(), 2);
+
+        let found = search_by_substring(&pets, &req);
+
+        let ids: Vec<Option<i64>> = found.iter().map(|m| m.pet.id).collect();
+        assert_eq!(ids, vec![Some(3), Some(1)]);
+    }
+}
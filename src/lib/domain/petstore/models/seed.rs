@@ -0,0 +1,322 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use super::category::Category;
+use super::pet::{CreatePetRequest, Status};
+use super::tag::Tag;
+
+/// A set of sample data to load into an empty store.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SeedProfile {
+    /// Two categories and three pets, enough to click through every endpoint.
+    Minimal,
+    /// A hand-written pet store: a few categories, and pets with tags, photos and every status.
+    Sample,
+    /// `pets` generated pets with realistic names, categories, tags and photos.
+    Synthetic { pets: u32 },
+}
+
+impl SeedProfile {
+    pub const DEFAULT_SYNTHETIC_PETS: u32 = 1000;
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum SeedProfileError {
+    #[error("seed profile {0} is not one of minimal, sample, synthetic[:<pets>]")]
+    Unknown(String),
+    #[error("synthetic pet count {0} is not a number")]
+    InvalidPetCount(String),
+}
+
+impl FromStr for SeedProfile {
+    type Err = SeedProfileError;
+
+    /// Parses `minimal`, `sample` or `synthetic`, optionally followed by `:<pets>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        let (name, pets) = match s.split_once(':') {
+            Some((name, pets)) => (name, Some(pets)),
+            None => (s.as_str(), None),
+        };
+        match (name, pets) {
+            ("minimal", None) => Ok(Self::Minimal),
+            ("sample", None) => Ok(Self::Sample),
+            ("synthetic", None) => Ok(Self::Synthetic { pets: Self::DEFAULT_SYNTHETIC_PETS }),
+            ("synthetic", Some(pets)) => pets
+                .parse()
+                .map(|pets| Self::Synthetic { pets })
+                .map_err(|_| SeedProfileError::InvalidPetCount(pets.to_string())),
+            _ => Err(SeedProfileError::Unknown(s.clone())),
+        }
+    }
+}
+
+/// A pet to seed. Its category is an index into [SeedPlan::categories].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SeedPet {
+    pub name: String,
+    pub category: Option<usize>,
+    pub tags: Vec<String>,
+    pub photo_urls: Vec<String>,
+    pub status: Status,
+}
+
+impl SeedPet {
+    /// The request creating the pet in `category`, leaving its id to the store.
+    pub fn to_request(&self, category: Option<Category>) -> CreatePetRequest {
+        let tags = self
+            .tags
+            .iter()
+            .map(|name| Tag { id: None, name: Some(name.clone()) })
+            .collect();
+        CreatePetRequest::new(None, self.name.clone(), category, self.photo_urls.clone(), tags, Some(self.status.clone()))
+    }
+}
+
+/// The categories and pets of a [SeedProfile]. The same profile and seed always plan the same
+/// data, in the same order.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SeedPlan {
+    pub categories: Vec<String>,
+    pub pets: Vec<SeedPet>,
+}
+
+impl SeedPlan {
+    /// Plans the data of `profile`. Only synthetic data depends on `seed`.
+    pub fn new(profile: SeedProfile, seed: u64) -> Self {
+        match profile {
+            SeedProfile::Minimal => Self::fixed(&["Dogs", "Cats"], MINIMAL_PETS),
+            SeedProfile::Sample => Self::fixed(&SAMPLE_CATEGORIES, SAMPLE_PETS),
+            SeedProfile::Synthetic { pets } => Self::synthetic(pets, seed),
+        }
+    }
+
+    fn fixed(categories: &[&str], pets: &[FixedPet]) -> Self {
+        let categories: Vec<String> = categories.iter().map(|c| c.to_string()).collect();
+        let pets = pets
+            .iter()
+            .map(|(name, category, tags, photos, status)| SeedPet {
+                name: name.to_string(),
+                category: category.and_then(|c| categories.iter().position(|known| known == c)),
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                photo_urls: photos
+                    .iter()
+                    .map(|photo| format!("{}/{}", PHOTO_BASE_URL, photo))
+                    .collect(),
+                status: status.clone(),
+            })
+            .collect();
+        Self { categories, pets }
+    }
+
+    fn synthetic(count: u32, seed: u64) -> Self {
+        let mut rng = SeedRng::new(seed);
+        let categories = SYNTHETIC_CATEGORIES.iter().map(|(name, _, _)| name.to_string()).collect();
+        let mut names = HashSet::new();
+        let pets = (0..count)
+            .map(|_| {
+                let category = rng.below(SYNTHETIC_CATEGORIES.len());
+                let (_, slug, breeds) = SYNTHETIC_CATEGORIES[category];
+                let name = unique_name(format!("{} the {}", rng.pick(GIVEN_NAMES), rng.pick(breeds)), &mut names);
+                let tag_count = rng.below(4);
+                let tags = rng.sample(TAGS, tag_count);
+                let photo_count = 1 + rng.below(3);
+                let photo_urls = (0..photo_count)
+                    .map(|_| format!("{}/{}/{:016x}.jpg", PHOTO_BASE_URL, slug, rng.next_u64()))
+                    .collect();
+                let status = match rng.below(10) {
+                    0..=6 => Status::Available,
+                    7..=8 => Status::Pending,
+                    _ => Status::Sold,
+                };
+                SeedPet { name, category: Some(category), tags, photo_urls, status }
+            })
+            .collect();
+        Self { categories, pets }
+    }
+}
+
+/// `name`, or `name` numbered from 2 if taken.
+fn unique_name(name: String, taken: &mut HashSet<String>) -> String {
+    let mut candidate = name.clone();
+    let mut n = 2;
+    while !taken.insert(candidate.clone()) {
+        candidate = format!("{} {}", name, n);
+        n += 1;
+    }
+    candidate
+}
+
+/// How many categories and pets a seeding created, and how many were already there.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SeedReport {
+    pub categories_created: u64,
+    pub categories_existing: u64,
+    pub pets_created: u64,
+    pub pets_existing: u64,
+}
+
+#[derive(Debug, Error)]
+pub enum SeedError {
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+/// A SplitMix64 generator: tiny, and stable across releases, unlike general purpose generators
+/// whose output may change with their version.
+struct SeedRng(u64);
+
+impl SeedRng {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+
+    /// `count` distinct items, in the order they were drawn.
+    fn sample(&mut self, items: &[&str], count: usize) -> Vec<String> {
+        let mut pool: Vec<&str> = items.to_vec();
+        (0..count.min(pool.len()))
+            .map(|_| pool.swap_remove(self.below(pool.len())).to_string())
+            .collect()
+    }
+}
+
+const PHOTO_BASE_URL: &str = "https://images.example.com/pets";
+
+/// A hand-written pet: name, category, tags, photo paths and status.
+type FixedPet = (&'static str, Option<&'static str>, &'static [&'static str], &'static [&'static str], Status);
+
+const MINIMAL_PETS: &[FixedPet] = &[
+    ("Rex", Some("Dogs"), &["friendly"], &["dogs/rex.jpg"], Status::Available),
+    ("Whiskers", Some("Cats"), &[], &["cats/whiskers.jpg"], Status::Pending),
+    ("Nemo", None, &[], &["nemo.jpg"], Status::Sold),
+];
+
+const SAMPLE_CATEGORIES: [&str; 5] = ["Dogs", "Cats", "Birds", "Fish", "Rabbits"];
+
+const SAMPLE_PETS: &[FixedPet] = &[
+    ("Buddy", Some("Dogs"), &["friendly", "vaccinated", "house-trained"], &["dogs/buddy.jpg"], Status::Available),
+    ("Max", Some("Dogs"), &["energetic", "good with kids"], &["dogs/max.jpg", "dogs/max-park.jpg"], Status::Available),
+    ("Daisy", Some("Dogs"), &["senior", "calm"], &["dogs/daisy.jpg"], Status::Pending),
+    ("Rocky", Some("Dogs"), &["vaccinated"], &["dogs/rocky.jpg"], Status::Sold),
+    ("Luna", Some("Cats"), &["indoor", "neutered"], &["cats/luna.jpg"], Status::Available),
+    ("Oliver", Some("Cats"), &["friendly", "playful"], &["cats/oliver.jpg"], Status::Available),
+    ("Cleo", Some("Cats"), &["hypoallergenic"], &["cats/cleo.jpg"], Status::Pending),
+    ("Kiwi", Some("Birds"), &["talks"], &["birds/kiwi.jpg"], Status::Available),
+    ("Sunny", Some("Birds"), &["sings", "hand-raised"], &["birds/sunny.jpg"], Status::Sold),
+    ("Bubbles", Some("Fish"), &["beginner friendly"], &["fish/bubbles.jpg"], Status::Available),
+    ("Finn", Some("Fish"), &[], &["fish/finn.jpg"], Status::Available),
+    ("Thumper", Some("Rabbits"), &["litter-trained", "friendly"], &["rabbits/thumper.jpg"], Status::Pending),
+    ("Clover", Some("Rabbits"), &["playful"], &["rabbits/clover.jpg"], Status::Available),
+];
+
+/// The synthetic categories, the path of their photos and their breeds.
+const SYNTHETIC_CATEGORIES: &[(&str, &str, &[&str])] = &[
+    ("Dogs", "dogs", &["Beagle", "Labrador", "Poodle", "Terrier", "Collie", "Boxer", "Dachshund"]),
+    ("Cats", "cats", &["Siamese", "Persian", "Maine Coon", "Bengal", "Ragdoll", "Sphynx"]),
+    ("Birds", "birds", &["Parakeet", "Cockatiel", "Canary", "Lovebird", "Finch"]),
+    ("Fish", "fish", &["Goldfish", "Betta", "Guppy", "Angelfish", "Tetra"]),
+    ("Rabbits", "rabbits", &["Lop", "Lionhead", "Dutch", "Angora"]),
+    ("Reptiles", "reptiles", &["Gecko", "Iguana", "Corn Snake", "Tortoise", "Bearded Dragon"]),
+];
+
+const GIVEN_NAMES: &[&str] = &[
+    "Bella", "Charlie", "Luna", "Max", "Daisy", "Milo", "Coco", "Rocky", "Lola", "Oscar",
+    "Ruby", "Teddy", "Nala", "Leo", "Rosie", "Toby", "Pepper", "Jack", "Willow", "Ziggy",
+    "Maple", "Biscuit", "Olive", "Finn", "Hazel", "Loki", "Pickles", "Sage", "Juno", "Mochi",
+];
+
+const TAGS: &[&str] = &[
+    "friendly", "vaccinated", "house-trained", "neutered", "senior", "young", "playful", "calm",
+    "good with kids", "good with cats", "indoor", "hypoallergenic", "special needs", "energetic",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::petstore::models::transfer::PetRecord;
+
+    /// Checks every planned pet with the value objects pets are created with.
+    fn assert_valid(plan: &SeedPlan) {
+        for pet in &plan.pets {
+            let record = PetRecord {
+                name: pet.name.clone(),
+                category_id: pet.category.map(|i| i as i64 + 1),
+                status: Some(pet.status.to_string()),
+                tags: pet.tags.clone(),
+                photo_urls: pet.photo_urls.clone(),
+                ..Default::default()
+            };
+            if let Err(errors) = record.try_into_request() {
+                panic!("invalid seed pet {:?}: {}", pet, errors);
+            }
+        }
+        let names: HashSet<&str> = plan.pets.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names.len(), plan.pets.len(), "pet names must be unique");
+    }
+
+    #[test]
+    fn test_seed_profile() {
+        assert_eq!("Minimal".parse::<SeedProfile>().unwrap(), SeedProfile::Minimal);
+        assert_eq!("synthetic".parse::<SeedProfile>().unwrap(), SeedProfile::Synthetic { pets: 1000 });
+        assert_eq!("synthetic:25".parse::<SeedProfile>().unwrap(), SeedProfile::Synthetic { pets: 25 });
+        assert!(matches!("synthetic:many".parse::<SeedProfile>(), Err(SeedProfileError::InvalidPetCount(_))));
+        assert!(matches!("huge".parse::<SeedProfile>(), Err(SeedProfileError::Unknown(_))));
+    }
+
+    #[test]
+    fn test_fixed_plans() {
+        let minimal = SeedPlan::new(SeedProfile::Minimal, 0);
+        assert_eq!(minimal.categories, vec!["Dogs", "Cats"]);
+        assert_eq!(minimal.pets[1].category, Some(1));
+        assert_eq!(minimal.pets[2].category, None);
+        assert_eq!(SeedPlan::new(SeedProfile::Minimal, 99), minimal);
+        assert_valid(&minimal);
+
+        let sample = SeedPlan::new(SeedProfile::Sample, 0);
+        assert!(sample.pets.iter().all(|p| p.category.is_some()));
+        assert_valid(&sample);
+    }
+
+    #[test]
+    fn test_synthetic_plan_is_deterministic() {
+        let plan = SeedPlan::new(SeedProfile::Synthetic { pets: 2000 }, 42);
+
+        assert_eq!(plan.pets.len(), 2000);
+        assert_eq!(plan, SeedPlan::new(SeedProfile::Synthetic { pets: 2000 }, 42));
+        assert_ne!(plan, SeedPlan::new(SeedProfile::Synthetic { pets: 2000 }, 43));
+        // A smaller plan with the same seed is a prefix of the larger one
+        assert_eq!(SeedPlan::new(SeedProfile::Synthetic { pets: 10 }, 42).pets[..], plan.pets[..10]);
+        assert_valid(&plan);
+
+        let sold = plan.pets.iter().filter(|p| p.status == Status::Sold).count();
+        assert!((100..=300).contains(&sold), "{} of 2000 pets sold", sold);
+        assert!(plan.pets.iter().any(|p| p.tags.len() == 3 && p.photo_urls.len() == 3));
+    }
+
+    #[test]
+    fn test_unique_name() {
+        let mut taken = HashSet::new();
+        assert_eq!(unique_name("Bella the Beagle".to_string(), &mut taken), "Bella the Beagle");
+        assert_eq!(unique_name("Bella the Beagle".to_string(), &mut taken), "Bella the Beagle 2");
+        assert_eq!(unique_name("Bella the Beagle".to_string(), &mut taken), "Bella the Beagle 3");
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Tag {
@@ -34,6 +35,82 @@ impl std::fmt::Display for Tag {
     }
 }
 
+/// A [Tag] and the number of pets it is attached to.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TagUsage {
+    pub tag: Tag,
+    pub pet_count: i64,
+}
+
+impl TagUsage {
+    pub fn new(tag: Tag, pet_count: i64) -> Self {
+        Self { tag, pet_count }
+    }
+}
+
+/// A request to give an existing [Tag] a new name.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RenameTagRequest {
+    pub id: i64,
+    pub name: String,
+}
+
+impl RenameTagRequest {
+    pub fn new(id: i64, name: String) -> Self {
+        Self { id, name }
+    }
+}
+
+/// A request to fold the `source` [Tag] into the `target` one: every pet tagged with the
+/// source ends up tagged with the target, and the source is removed.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MergeTagsRequest {
+    pub source_id: i64,
+    pub target_id: i64,
+}
+
+impl MergeTagsRequest {
+    pub fn new(source_id: i64, target_id: i64) -> Self {
+        Self { source_id, target_id }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ListTagsError {
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum RenameTagError {
+    #[error("tag with id {id} not found")]
+    NotFound { id: i64 },
+    #[error("tag with name {name} already exists")]
+    Duplicate { name: String },
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum MergeTagsError {
+    #[error("tag with id {id} not found")]
+    NotFound { id: i64 },
+    #[error("cannot merge tag {id} into itself")]
+    SameTag { id: i64 },
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum DeleteTagError {
+    #[error("tag with id {id} not found")]
+    NotFound { id: i64 },
+    #[error("tag with id {id} is still attached to {pet_count} pets")]
+    InUse { id: i64, pet_count: i64 },
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -83,4 +160,18 @@ mod tests {
         tag.name = Some(String::from("Quiet"));
         assert_eq!(tag.name, Some(String::from("Quiet")));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_tag_usage_ordering() {
+        let used = TagUsage::new(Tag::with_values(1, String::from("Friendly")), 3);
+        let unused = TagUsage::new(Tag::with_values(2, String::from("Active")), 0);
+
+        assert!(used < unused); // Orders by tag first
+    }
+
+    #[test]
+    fn test_delete_tag_error_display() {
+        let error = DeleteTagError::InUse { id: 4, pet_count: 2 };
+        assert_eq!(error.to_string(), "tag with id 4 is still attached to 2 pets");
+    }
+}
@@ -0,0 +1,242 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::category::Category;
+use super::pet::{CreatePetFields, CreatePetRequest, Pet, Status};
+use super::tag::Tag;
+use super::validation::{FieldViolation, ValidationErrors, Violation};
+
+/// A [Pet] as exchanged in bulk import and export files, whatever their format.
+///
+/// Pets reference existing categories by `category_id`; `category_name` is informative.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PetRecord {
+    #[serde(default)]
+    pub id: Option<i64>,
+    pub name: String,
+    #[serde(default)]
+    pub category_id: Option<i64>,
+    #[serde(default)]
+    pub category_name: Option<String>,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub photo_urls: Vec<String>,
+}
+
+impl From<&Pet> for PetRecord {
+    fn from(pet: &Pet) -> Self {
+        Self {
+            id: pet.id,
+            name: pet.name.clone(),
+            category_id: pet.category.as_ref().and_then(|c| c.id),
+            category_name: pet.category.as_ref().and_then(|c| c.name.clone()),
+            status: pet.status.as_ref().map(|s| s.to_string()),
+            tags: pet.tags.iter().filter_map(|t| t.name.clone()).collect(),
+            photo_urls: pet.photo_urls.clone(),
+        }
+    }
+}
+
+impl PetRecord {
+    /// Converts the record into a domain request, collecting every invalid field.
+    pub fn try_into_request(self) -> Result<CreatePetRequest, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let status = errors.check("status", Status::try_from(self.status));
+        let category = match (self.category_id, self.category_name) {
+            (None, None) => None,
+            (id, name) => Some(Category { id, name }),
+        };
+        let tags = self.tags.into_iter().map(|name| Tag { id: None, name: Some(name) }).collect();
+        let request = CreatePetRequest::new(self.id, self.name, category, self.photo_urls, tags, status);
+
+        match request.validate(&CreatePetFields::FLAT) {
+            Ok(request) if errors.is_empty() => Ok(request),
+            Ok(_) => Err(errors),
+            Err(mut violations) => {
+                violations.extend(errors);
+                Err(violations)
+            }
+        }
+    }
+}
+
+/// A row of an import file, numbered by the line it starts on, and the record read from it
+/// or why it could not be read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportRow {
+    pub line: u64,
+    pub record: Result<PetRecord, MalformedRecord>,
+}
+
+/// A row that is not a valid record of the file format, e.g. a CSV row with too many fields or
+/// a line that is not JSON.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[error("{0}")]
+pub struct MalformedRecord(pub String);
+
+impl Violation for MalformedRecord {
+    fn code(&self) -> &'static str {
+        "malformed"
+    }
+}
+
+/// A row that was not imported, or would not be in a dry run, and why.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportFailure {
+    pub line: u64,
+    pub name: Option<String>,
+    pub errors: Vec<FieldViolation>,
+}
+
+/// The outcome of an import: how many rows were read, imported and failed, and the first
+/// [ImportReport::MAX_FAILURES] rows that failed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub rows: u64,
+    pub imported: u64,
+    pub failed: u64,
+    pub failures: Vec<ImportFailure>,
+}
+
+impl ImportReport {
+    /// How many failed rows are listed at most, so that the report of a large file of bad rows
+    /// stays small.
+    pub const MAX_FAILURES: usize = 1000;
+
+    pub fn new(dry_run: bool) -> Self {
+        Self { dry_run, ..Default::default() }
+    }
+
+    /// Counts a row, failed if `errors` has any violation.
+    pub fn record(&mut self, line: u64, name: Option<String>, errors: ValidationErrors) {
+        self.rows += 1;
+        if errors.is_empty() {
+            self.imported += 1;
+            return;
+        }
+        self.failed += 1;
+        if self.failures.len() < Self::MAX_FAILURES {
+            self.failures.push(ImportFailure { line, name, errors: errors.violations().to_vec() });
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ImportPetsError {
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> PetRecord {
+        PetRecord {
+            id: Some(7),
+            name: "Rex".to_string(),
+            category_id: Some(1),
+            category_name: Some("Dogs".to_string()),
+            status: Some("pending".to_string()),
+            tags: vec!["friendly ".to_string(), "vaccinated".to_string()],
+            photo_urls: vec!["https://example.com/rex.jpg".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_record_into_request() {
+        let req = record().try_into_request().unwrap();
+
+        assert_eq!(req.id, Some(7));
+        assert_eq!(req.category, Some(Category::with_values(1, "Dogs".to_string())));
+        assert_eq!(
+            req.tags.iter().map(|t| t.name.as_deref().unwrap()).collect::<Vec<_>>(),
+            vec!["friendly", "vaccinated"]
+        );
+        assert_eq!(req.status, Some(Status::Pending));
+    }
+
+    #[test]
+    fn test_record_collects_every_invalid_field() {
+        let invalid = PetRecord {
+            name: String::new(),
+            status: Some("lost".to_string()),
+            tags: vec!["a".to_string(), " a".to_string(), " ".to_string()],
+            photo_urls: vec!["rex.jpg".to_string()],
+            ..record()
+        };
+
+        let errors = invalid.try_into_request().unwrap_err();
+
+        assert_eq!(
+            errors.violations().iter().map(|v| (v.field.as_str(), v.code)).collect::<Vec<_>>(),
+            vec![
+                ("name", "empty"),
+                ("photo_urls[0]", "invalid-url"),
+                ("tags[1]", "duplicate"),
+                ("tags[2]", "empty"),
+                ("status", "unknown-status"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_record_category_name_requires_an_id() {
+        let invalid = PetRecord { category_id: None, ..record() };
+
+        let errors = invalid.try_into_request().unwrap_err();
+
+        assert_eq!(
+            errors.violations().iter().map(|v| (v.field.as_str(), v.code)).collect::<Vec<_>>(),
+            vec![("category_id", "required")]
+        );
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let mut pet = Pet::with_id(7, "Rex".to_string());
+        pet.set_category(Category::with_values(1, "Dogs".to_string()));
+        pet.add_tag(Tag::with_values(3, "friendly".to_string()));
+        pet.add_photo("https://example.com/rex.jpg".to_string());
+
+        let req = PetRecord::from(&pet).try_into_request().unwrap();
+
+        assert_eq!(req.name, pet.name);
+        assert_eq!(req.category.as_ref(), pet.category.as_deref());
+        assert_eq!(req.photo_urls, pet.photo_urls);
+        assert_eq!(req.status, pet.status);
+    }
+
+    #[test]
+    fn test_import_report() {
+        let mut report = ImportReport::new(true);
+        let mut errors = ValidationErrors::new();
+        errors.add("record", &MalformedRecord("expected 7 fields, found 2".to_string()));
+
+        report.record(2, Some("Rex".to_string()), ValidationErrors::new());
+        report.record(3, None, errors);
+
+        assert_eq!((report.rows, report.imported, report.failed), (2, 1, 1));
+        assert_eq!(report.failures[0].line, 3);
+        assert_eq!(report.failures[0].errors[0].code, "malformed");
+    }
+
+    #[test]
+    fn test_import_report_lists_the_first_failures() {
+        let mut report = ImportReport::new(false);
+        let total = ImportReport::MAX_FAILURES as u64 + 10;
+        for line in 2..total + 2 {
+            let mut errors = ValidationErrors::new();
+            errors.add("record", &MalformedRecord("not JSON".to_string()));
+            report.record(line, None, errors);
+        }
+
+        assert_eq!(report.failed, total);
+        assert_eq!(report.failures.len(), ImportReport::MAX_FAILURES);
+        assert_eq!(report.failures.last().unwrap().line, ImportReport::MAX_FAILURES as u64 + 1);
+    }
+}
@@ -0,0 +1,89 @@
+use thiserror::Error;
+
+/// A violated constraint on a single value, identified by a stable machine-readable code such
+/// as `too-long`. Its [Display](std::fmt::Display) is the human-readable reason.
+pub trait Violation: std::fmt::Display {
+    fn code(&self) -> &'static str;
+}
+
+/// A [Violation] of the value at `field`, a path into the request such as `photo_urls[1]` or
+/// `tags[0].name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldViolation {
+    pub field: String,
+    pub code: &'static str,
+    pub message: String,
+}
+
+/// Every [FieldViolation] found in a request, collected rather than stopping at the first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Error)]
+#[error("{}", .0.iter().map(|v| format!("{}: {}", v.field, v.message)).collect::<Vec<_>>().join("; "))]
+pub struct ValidationErrors(Vec<FieldViolation>);
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a violation of the value at `field`.
+    pub fn add(&mut self, field: impl Into<String>, violation: &impl Violation) {
+        self.0.push(FieldViolation {
+            field: field.into(),
+            code: violation.code(),
+            message: violation.to_string(),
+        });
+    }
+
+    /// Returns the valid value, or records the violation at `field` and returns `None`.
+    pub fn check<T, V: Violation>(&mut self, field: impl Into<String>, result: Result<T, V>) -> Option<T> {
+        result.map_err(|violation| self.add(field, &violation)).ok()
+    }
+
+    /// Appends every violation of `other`.
+    pub fn extend(&mut self, other: ValidationErrors) {
+        self.0.extend(other.0);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn violations(&self) -> &[FieldViolation] {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct TooLong;
+
+    impl std::fmt::Display for TooLong {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "value is too long")
+        }
+    }
+
+    impl Violation for TooLong {
+        fn code(&self) -> &'static str {
+            "too-long"
+        }
+    }
+
+    #[test]
+    fn test_validation_errors_collects_every_violation() {
+        let mut errors = ValidationErrors::new();
+
+        assert_eq!(errors.check("name", Ok::<_, TooLong>(1)), Some(1));
+        assert_eq!(errors.check("name", Err::<i32, _>(TooLong)), None);
+        errors.add("tags[0].name", &TooLong);
+
+        assert_eq!(
+            errors.violations().iter().map(|v| (v.field.as_str(), v.code)).collect::<Vec<_>>(),
+            vec![("name", "too-long"), ("tags[0].name", "too-long")]
+        );
+        assert_eq!(errors.to_string(), "name: value is too long; tags[0].name: value is too long");
+    }
+}
@@ -1,7 +1,9 @@
 use crate::domain::petstore::models::category::Category;
 use crate::domain::petstore::models::tag::Tag;
-use crate::domain::petstore::models::pet::Status;
+use crate::domain::petstore::models::pet::{ListPetsRequest, Status};
+use crate::domain::petstore::models::validation::Violation;
 use thiserror::Error;
+use url::Url;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PetName(String);
@@ -10,13 +12,35 @@ pub struct PetName(String);
 pub enum PetNameError {
     #[error("pet name cannot be empty")]
     Empty,
+    #[error("pet name cannot be longer than {max} characters")]
+    TooLong { max: usize },
+    #[error("pet name cannot contain control characters")]
+    InvalidCharacters,
+}
+
+impl Violation for PetNameError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::TooLong { .. } => "too-long",
+            Self::InvalidCharacters => "invalid-characters",
+        }
+    }
 }
 
 impl PetName {
+    pub const MAX_LEN: usize = 255;
+
     pub fn new(name: &str) -> Result<Self, PetNameError> {
         if name.trim().is_empty() {
             return Err(PetNameError::Empty);
         }
+        if name.chars().count() > Self::MAX_LEN {
+            return Err(PetNameError::TooLong { max: Self::MAX_LEN });
+        }
+        if name.chars().any(char::is_control) {
+            return Err(PetNameError::InvalidCharacters);
+        }
         Ok(Self(name.to_string()))
     }
 
@@ -38,13 +62,30 @@ pub struct PhotoUrls(Vec<String>);
 pub enum PhotoUrlsError {
     #[error("photo urls cannot be empty")]
     Empty,
+    #[error("a pet cannot have more than {max} photo urls")]
+    TooMany { max: usize },
+}
+
+impl Violation for PhotoUrlsError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::TooMany { .. } => "too-many",
+        }
+    }
 }
 
 impl PhotoUrls {
+    pub const MAX_COUNT: usize = 20;
+
+    /// Checks the number of urls; each url is checked by [PhotoUrl].
     pub fn new(urls: &[String]) -> Result<Self, PhotoUrlsError> {
         if urls.is_empty() {
             return Err(PhotoUrlsError::Empty);
         }
+        if urls.len() > Self::MAX_COUNT {
+            return Err(PhotoUrlsError::TooMany { max: Self::MAX_COUNT });
+        }
         Ok(Self(urls.to_vec()))
     }
 
@@ -59,6 +100,54 @@ impl std::fmt::Display for PhotoUrls {
     }
 }
 
+/// An absolute `http` or `https` url of a photo of a pet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhotoUrl(String);
+
+#[derive(Debug, Clone, Error)]
+pub enum PhotoUrlError {
+    #[error("photo url {url} is not a valid absolute url: {reason}")]
+    Invalid { url: String, reason: String },
+    #[error("photo url scheme {scheme} is not one of http, https")]
+    UnsupportedScheme { scheme: String },
+    #[error("photo url cannot be longer than {max} characters")]
+    TooLong { max: usize },
+}
+
+impl Violation for PhotoUrlError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::Invalid { .. } => "invalid-url",
+            Self::UnsupportedScheme { .. } => "unsupported-scheme",
+            Self::TooLong { .. } => "too-long",
+        }
+    }
+}
+
+impl PhotoUrl {
+    pub const MAX_LEN: usize = 255;
+
+    pub fn new(url: &str) -> Result<Self, PhotoUrlError> {
+        if url.chars().count() > Self::MAX_LEN {
+            return Err(PhotoUrlError::TooLong { max: Self::MAX_LEN });
+        }
+        let parsed = Url::parse(url).map_err(|e| PhotoUrlError::Invalid {
+            url: url.to_string(),
+            reason: e.to_string(),
+        })?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(PhotoUrlError::UnsupportedScheme {
+                scheme: parsed.scheme().to_string(),
+            });
+        }
+        Ok(Self(url.to_string()))
+    }
+
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Tags(Vec<Tag>);
 
@@ -66,12 +155,30 @@ pub struct Tags(Vec<Tag>);
 pub enum TagsError {
     #[error("tags cannot be empty")]
     Empty,
+    #[error("a pet cannot have more than {max} tags")]
+    TooMany { max: usize },
+    #[error("tag {name} is listed more than once")]
+    Duplicate { name: String },
+}
+
+impl Violation for TagsError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::TooMany { .. } => "too-many",
+            Self::Duplicate { .. } => "duplicate",
+        }
+    }
 }
 
 impl Tags {
+    pub const MAX_COUNT: usize = 20;
+
+    /// Checks the number of tags; each tag name is checked by [TagName].
     pub fn new(tags: &Option<Vec<Tag>>) -> Result<Self, TagsError> {
         match tags {
             Some(t) if t.is_empty() => Err(TagsError::Empty),
+            Some(t) if t.len() > Self::MAX_COUNT => Err(TagsError::TooMany { max: Self::MAX_COUNT }),
             Some(t) => Ok(Self(t.clone())),
             None => Ok(Self(vec![])),
         }
@@ -94,6 +201,14 @@ pub enum StatusError {
     InvalidStatus { invalid_status: String },
 }
 
+impl Violation for StatusError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidStatus { .. } => "unknown-status",
+        }
+    }
+}
+
 impl TryFrom<Option<String>> for Status {
     type Error = StatusError;
 
@@ -110,17 +225,194 @@ impl TryFrom<Option<String>> for Status {
 
 #[derive(Debug, Clone, Error)]
 pub enum CategoryError {
-    #[error("invalid category")]
-    InvalidCategory,
+    #[error("category id is required")]
+    MissingId,
+}
+
+impl Violation for CategoryError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::MissingId => "required",
+        }
+    }
+}
+
+/// The category a pet is assigned to, if any. Pets reference existing categories by id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PetCategory(Option<Category>);
+
+impl PetCategory {
+    pub fn new(category: &Option<Category>) -> Result<Self, CategoryError> {
+        match category {
+            Some(c) if c.id.is_none() => Err(CategoryError::MissingId),
+            Some(c) => Ok(Self(Some(c.clone()))),
+            None => Ok(Self(None)),
+        }
+    }
+
+    pub fn into_inner(self) -> Option<Category> {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategoryName(String);
+
+#[derive(Debug, Clone, Error)]
+pub enum CategoryNameError {
+    #[error("category name cannot be empty")]
+    Empty,
+    #[error("category name cannot be longer than {max} characters")]
+    TooLong { max: usize },
+}
+
+impl Violation for CategoryNameError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::TooLong { .. } => "too-long",
+        }
+    }
+}
+
+impl CategoryName {
+    pub const MAX_LEN: usize = 255;
+
+    pub fn new(name: &str) -> Result<Self, CategoryNameError> {
+        let trimmed = name.trim();
+        if trimmed.is_empty() {
+            return Err(CategoryNameError::Empty);
+        }
+        if trimmed.chars().count() > Self::MAX_LEN {
+            return Err(CategoryNameError::TooLong { max: Self::MAX_LEN });
+        }
+        Ok(Self(trimmed.to_string()))
+    }
+
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl std::fmt::Display for CategoryName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagName(String);
+
+#[derive(Debug, Clone, Error)]
+pub enum TagNameError {
+    #[error("tag name cannot be empty")]
+    Empty,
+    #[error("tag name cannot be longer than {max} characters")]
+    TooLong { max: usize },
+}
+
+impl Violation for TagNameError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::TooLong { .. } => "too-long",
+        }
+    }
+}
+
+impl TagName {
+    pub const MAX_LEN: usize = 255;
+
+    pub fn new(name: &str) -> Result<Self, TagNameError> {
+        let trimmed = name.trim();
+        if trimmed.is_empty() {
+            return Err(TagNameError::Empty);
+        }
+        if trimmed.chars().count() > Self::MAX_LEN {
+            return Err(TagNameError::TooLong { max: Self::MAX_LEN });
+        }
+        Ok(Self(trimmed.to_string()))
+    }
+
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl std::fmt::Display for TagName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
 }
 
-impl TryFrom<Option<Category>> for Category {
-    type Error = CategoryError;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageSize(u32);
+
+#[derive(Debug, Clone, Error)]
+pub enum PageSizeError {
+    #[error("page size must be between 1 and {max}, got {actual}")]
+    OutOfRange { actual: u32, max: u32 },
+}
 
-    fn try_from(value: Option<Category>) -> Result<Self, Self::Error> {
-        match value {
-            Some(c) => Ok(c),
-            None => Ok(Category::new()),
+impl Violation for PageSizeError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::OutOfRange { .. } => "out-of-range",
         }
     }
-} 
\ No newline at end of file
+}
+
+impl PageSize {
+    pub const MAX: u32 = 100;
+
+    pub fn new(size: Option<u32>) -> Result<Self, PageSizeError> {
+        match size {
+            None => Ok(Self(ListPetsRequest::DEFAULT_LIMIT)),
+            Some(s) if s == 0 || s > Self::MAX => Err(PageSizeError::OutOfRange {
+                actual: s,
+                max: Self::MAX,
+            }),
+            Some(s) => Ok(Self(s)),
+        }
+    }
+
+    pub fn into_inner(self) -> u32 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pet_name() {
+        assert_eq!(PetName::new("Rex").unwrap().into_inner(), "Rex");
+        assert!(matches!(PetName::new(" "), Err(PetNameError::Empty)));
+        assert!(matches!(
+            PetName::new(&"x".repeat(PetName::MAX_LEN + 1)),
+            Err(PetNameError::TooLong { max: PetName::MAX_LEN })
+        ));
+        assert!(matches!(PetName::new("Rex\n"), Err(PetNameError::InvalidCharacters)));
+    }
+
+    #[test]
+    fn test_photo_url() {
+        assert!(PhotoUrl::new("https://example.com/rex.jpg").is_ok());
+        assert!(matches!(PhotoUrl::new("rex.jpg"), Err(PhotoUrlError::Invalid { .. })));
+        assert!(matches!(
+            PhotoUrl::new("ftp://example.com/rex.jpg"),
+            Err(PhotoUrlError::UnsupportedScheme { scheme }) if scheme == "ftp"
+        ));
+        let long = format!("https://example.com/{}", "x".repeat(PhotoUrl::MAX_LEN));
+        assert!(matches!(PhotoUrl::new(&long), Err(PhotoUrlError::TooLong { .. })));
+    }
+
+    #[test]
+    fn test_counts() {
+        let urls = vec!["https://example.com/rex.jpg".to_string(); PhotoUrls::MAX_COUNT + 1];
+        assert!(matches!(PhotoUrls::new(&urls), Err(PhotoUrlsError::TooMany { .. })));
+        let tags = Some(vec![Tag::new(); Tags::MAX_COUNT + 1]);
+        assert!(matches!(Tags::new(&tags), Err(TagsError::TooMany { .. })));
+    }
+}
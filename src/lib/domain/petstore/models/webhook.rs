@@ -0,0 +1,364 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use thiserror::Error;
+use url::Url;
+
+use super::event::{OutboxEvent, PetEvent};
+use super::validation::Violation;
+
+/// A partner's subscription to [PetEvent]s, delivered as signed `POST` requests to `url`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Webhook {
+    pub id: i64,
+    pub url: String,
+    /// The event types delivered to the webhook; all of them when empty.
+    pub event_types: Vec<String>,
+    /// The key the deliveries are signed with. Never exposed after registration.
+    pub secret: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    /// Whether events of `event_type` are delivered to the webhook.
+    pub fn accepts(&self, event_type: &str) -> bool {
+        self.event_types.is_empty() || self.event_types.iter().any(|t| t == event_type)
+    }
+}
+
+/// A request to register a new [Webhook].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub secret: String,
+}
+
+impl CreateWebhookRequest {
+    pub fn new(url: WebhookUrl, event_types: EventTypes, secret: WebhookSecret) -> Self {
+        Self {
+            url: url.into_inner(),
+            event_types: event_types.into_inner(),
+            secret: secret.into_inner(),
+        }
+    }
+}
+
+/// An absolute `http` or `https` url receiving webhook deliveries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookUrl(String);
+
+#[derive(Debug, Clone, Error)]
+pub enum WebhookUrlError {
+    #[error("webhook url {url} is not a valid absolute url: {reason}")]
+    Invalid { url: String, reason: String },
+    #[error("webhook url scheme {scheme} is not one of http, https")]
+    UnsupportedScheme { scheme: String },
+    #[error("webhook url cannot be longer than {max} characters")]
+    TooLong { max: usize },
+}
+
+impl Violation for WebhookUrlError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::Invalid { .. } => "invalid-url",
+            Self::UnsupportedScheme { .. } => "unsupported-scheme",
+            Self::TooLong { .. } => "too-long",
+        }
+    }
+}
+
+impl WebhookUrl {
+    pub const MAX_LEN: usize = 2048;
+
+    pub fn new(url: &str) -> Result<Self, WebhookUrlError> {
+        let url = url.trim();
+        if url.chars().count() > Self::MAX_LEN {
+            return Err(WebhookUrlError::TooLong { max: Self::MAX_LEN });
+        }
+        let parsed = Url::parse(url).map_err(|e| WebhookUrlError::Invalid {
+            url: url.to_string(),
+            reason: e.to_string(),
+        })?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(WebhookUrlError::UnsupportedScheme {
+                scheme: parsed.scheme().to_string(),
+            });
+        }
+        Ok(Self(url.to_string()))
+    }
+
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+/// The [PetEvent] types a [Webhook] subscribes to, each at most once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventTypes(Vec<String>);
+
+#[derive(Debug, Clone, Error)]
+pub enum EventTypesError {
+    #[error("event type {event_type} is not one of {}", PetEvent::EVENT_TYPES.join(", "))]
+    Unknown { event_type: String },
+    #[error("event type {event_type} is listed more than once")]
+    Duplicate { event_type: String },
+}
+
+impl Violation for EventTypesError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::Unknown { .. } => "unknown-event-type",
+            Self::Duplicate { .. } => "duplicate",
+        }
+    }
+}
+
+impl EventTypes {
+    /// Checks that every event type is known and listed once. No event types subscribes to all
+    /// of them.
+    pub fn new(event_types: &[String]) -> Result<Self, EventTypesError> {
+        for (i, event_type) in event_types.iter().enumerate() {
+            if !PetEvent::EVENT_TYPES.contains(&event_type.as_str()) {
+                return Err(EventTypesError::Unknown { event_type: event_type.clone() });
+            }
+            if event_types[..i].contains(event_type) {
+                return Err(EventTypesError::Duplicate { event_type: event_type.clone() });
+            }
+        }
+        Ok(Self(event_types.to_vec()))
+    }
+
+    pub fn into_inner(self) -> Vec<String> {
+        self.0
+    }
+}
+
+/// The shared secret a [Webhook]'s deliveries are signed with.
+#[derive(Clone, PartialEq, Eq)]
+pub struct WebhookSecret(String);
+
+impl std::fmt::Debug for WebhookSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "WebhookSecret(..)")
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum WebhookSecretError {
+    #[error("webhook secret must be at least {min} characters long")]
+    TooShort { min: usize },
+    #[error("webhook secret cannot be longer than {max} characters")]
+    TooLong { max: usize },
+}
+
+impl Violation for WebhookSecretError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::TooShort { .. } => "too-short",
+            Self::TooLong { .. } => "too-long",
+        }
+    }
+}
+
+impl WebhookSecret {
+    pub const MIN_LEN: usize = 16;
+    pub const MAX_LEN: usize = 255;
+
+    pub fn new(secret: &str) -> Result<Self, WebhookSecretError> {
+        let len = secret.chars().count();
+        if len < Self::MIN_LEN {
+            return Err(WebhookSecretError::TooShort { min: Self::MIN_LEN });
+        }
+        if len > Self::MAX_LEN {
+            return Err(WebhookSecretError::TooLong { max: Self::MAX_LEN });
+        }
+        Ok(Self(secret.to_string()))
+    }
+
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+/// The JSON body of a webhook delivery.
+#[derive(Debug, Serialize)]
+pub struct WebhookPayload<'a> {
+    /// The id of the event, identical across redeliveries.
+    pub id: i64,
+    #[serde(rename = "type")]
+    pub event_type: &'static str,
+    pub occurred_at: DateTime<Utc>,
+    pub data: &'a PetEvent,
+}
+
+impl<'a> From<&'a OutboxEvent> for WebhookPayload<'a> {
+    fn from(event: &'a OutboxEvent) -> Self {
+        Self {
+            id: event.id,
+            event_type: event.event.event_type(),
+            occurred_at: event.occurred_at,
+            data: &event.event,
+        }
+    }
+}
+
+/// Where a [WebhookDelivery] stands.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Awaiting its first or next attempt.
+    #[default]
+    Pending,
+    Delivered,
+    /// Given up on after exhausting its attempts.
+    DeadLetter,
+}
+
+impl DeliveryStatus {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::DeadLetter => "dead_letter",
+        }
+    }
+}
+
+impl std::str::FromStr for DeliveryStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "delivered" => Ok(Self::Delivered),
+            "dead_letter" => Ok(Self::DeadLetter),
+            _ => Err(anyhow::anyhow!("unknown delivery status {}", s)),
+        }
+    }
+}
+
+/// The delivery of one event to one [Webhook], and the outcome of its latest attempt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub event_id: i64,
+    pub event_type: String,
+    /// The JSON [WebhookPayload] sent with every attempt.
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_response_status: Option<u16>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// The outcome of an attempt to deliver a [WebhookDelivery].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeliveryOutcome {
+    Delivered {
+        response_status: u16,
+    },
+    /// The attempt failed and the delivery is retried at `next_attempt_at`.
+    Retry {
+        next_attempt_at: DateTime<Utc>,
+        response_status: Option<u16>,
+        error: String,
+    },
+    /// The attempt failed and was the last one.
+    DeadLetter {
+        response_status: Option<u16>,
+        error: String,
+    },
+}
+
+/// A signed request delivering a [WebhookPayload].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WebhookMessage {
+    pub url: String,
+    pub delivery_id: i64,
+    pub event_type: String,
+    /// Seconds since the Unix epoch at which the message was signed.
+    pub timestamp: i64,
+    /// `sha256=` followed by the hex-encoded HMAC-SHA256 of `{timestamp}.{body}`.
+    pub signature: String,
+    pub body: String,
+}
+
+#[derive(Debug, Error)]
+pub enum CreateWebhookError {
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum FindWebhookError {
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum DeleteWebhookError {
+    #[error("webhook with id {id} not found")]
+    NotFound { id: i64 },
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum WebhookDeliveryError {
+    #[error("webhook with id {id} not found")]
+    NotFound { id: i64 },
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+/// The message could not be sent, e.g. the receiver was unreachable or timed out.
+#[derive(Debug, Error)]
+pub enum SendWebhookError {
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webhook_url() {
+        assert!(WebhookUrl::new("https://partner.example/hooks").is_ok());
+        assert_eq!(WebhookUrl::new("ftp://partner.example").unwrap_err().code(), "unsupported-scheme");
+        assert_eq!(WebhookUrl::new("partner.example").unwrap_err().code(), "invalid-url");
+    }
+
+    #[test]
+    fn test_event_types() {
+        let types = |types: &[&str]| types.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+
+        assert!(EventTypes::new(&[]).is_ok());
+        assert!(EventTypes::new(&types(&["PetCreated", "PetStatusChanged"])).is_ok());
+        assert_eq!(EventTypes::new(&types(&["PetSold"])).unwrap_err().code(), "unknown-event-type");
+        assert_eq!(
+            EventTypes::new(&types(&["PetCreated", "PetCreated"])).unwrap_err().code(),
+            "duplicate"
+        );
+    }
+
+    #[test]
+    fn test_webhook_accepts() {
+        let mut webhook = Webhook {
+            id: 1,
+            url: "https://partner.example".to_string(),
+            event_types: Vec::new(),
+            secret: "0123456789abcdef".to_string(),
+            created_at: Utc::now(),
+        };
+        assert!(webhook.accepts("PetStatusChanged"));
+
+        webhook.event_types = vec!["PetCreated".to_string()];
+        assert!(webhook.accepts("PetCreated"));
+        assert!(!webhook.accepts("PetStatusChanged"));
+    }
+}
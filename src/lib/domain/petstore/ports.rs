@@ -8,8 +8,40 @@
    since the application is expected to always run in a multithreaded environment.
 */
 
+use futures::Stream;
 use std::future::Future;
-use crate::domain::petstore::models::pet::{Pet, CreatePetRequest, CreatePetError};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use crate::domain::petstore::models::category::{
+    Category, CreateCategoryError, CreateCategoryRequest, DeleteCategoryError, FindCategoryError,
+    RenameCategoryError, RenameCategoryRequest,
+};
+use crate::domain::petstore::models::event::{
+    OutboxError, OutboxEvent, PetChange, PetEvent, PublishEventError,
+};
+use crate::domain::petstore::models::id::{GenerateIdError, IdKind};
+use crate::domain::petstore::models::idempotency::{
+    ClaimIdempotencyKeyRequest, IdempotencyClaim, IdempotencyError, IdempotentResponse,
+};
+use crate::domain::petstore::models::pet::{
+    ChangePetStatusError, ChangePetStatusRequest, CreatePetError, CreatePetRequest, DeletePetError,
+    ListPetsError, ListPetsRequest, Pet, PetHistoryError, PetPage, Status, StatusChange,
+};
+use crate::domain::petstore::models::search::{
+    PetMatch, PetSearchHit, SearchPetsError, SearchPetsRequest,
+};
+use crate::domain::petstore::models::tag::{
+    DeleteTagError, ListTagsError, MergeTagsError, MergeTagsRequest, RenameTagError,
+    RenameTagRequest, Tag, TagUsage,
+};
+use crate::domain::petstore::models::transfer::{
+    ImportPetsError, ImportReport, ImportRow, PetRecord,
+};
+use crate::domain::petstore::models::webhook::{
+    CreateWebhookError, CreateWebhookRequest, DeleteWebhookError, DeliveryOutcome,
+    FindWebhookError, SendWebhookError, Webhook, WebhookDelivery, WebhookDeliveryError,
+    WebhookMessage,
+};
 
 /// `PetService` is the public API for the pet domain.
 ///
@@ -21,6 +53,7 @@ pub trait PetService: Clone + Send + Sync + 'static {
     /// # Errors:
     ///
     /// - [CreateAuthorError::Duplicate] if an [Pet] with the same [name] already exists.
+    /// - [CreatePetError::UnknownCategory] if the referenced [Category] does not exist.
     fn add_pet(
         &self,
         req: &CreatePetRequest,
@@ -35,6 +68,96 @@ pub trait PetService: Clone + Send + Sync + 'static {
         &self,
         pet_id: i64,
     ) -> impl Future<Output = Result<Option<Pet>, CreatePetError>> + Send;
+
+    /// Find the current version of a pet, without loading the pet itself. Unlike
+    /// [PetService::find_pet_by_id], never answered from a cache.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [CreatePetError] returned by the [PetRepository].
+    fn find_pet_version(
+        &self,
+        pet_id: i64,
+    ) -> impl Future<Output = Result<Option<i64>, CreatePetError>> + Send;
+
+    /// List a page of pets matching the filters in `req`, in the requested order.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [ListPetsError] returned by the [PetRepository].
+    fn list_pets(
+        &self,
+        req: &ListPetsRequest,
+    ) -> impl Future<Output = Result<PetPage, ListPetsError>> + Send;
+
+    /// Search pets by partial name, category name or tag name, best matches first, with the
+    /// matching parts of each found pet highlighted.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [SearchPetsError] returned by the [PetRepository].
+    fn search_pets(
+        &self,
+        req: &SearchPetsRequest,
+    ) -> impl Future<Output = Result<Vec<PetSearchHit>, SearchPetsError>> + Send;
+
+    /// Move a pet to a new status, enforcing the pet lifecycle.
+    ///
+    /// # Errors:
+    ///
+    /// - [ChangePetStatusError::NotFound] if no [Pet] has the requested id.
+    /// - [ChangePetStatusError::InvalidTransition] if the lifecycle forbids the change.
+    /// - [ChangePetStatusError::Conflict] if the pet is no longer at the expected version.
+    fn change_pet_status(
+        &self,
+        req: &ChangePetStatusRequest,
+    ) -> impl Future<Output = Result<Pet, ChangePetStatusError>> + Send;
+
+    /// Delete a pet, together with its photos, tags and status history.
+    ///
+    /// # Errors:
+    ///
+    /// - [DeletePetError::NotFound] if no [Pet] has the requested id.
+    fn delete_pet(&self, pet_id: i64) -> impl Future<Output = Result<(), DeletePetError>> + Send;
+
+    /// The status changes of a pet, oldest first, starting with the status it was created with.
+    ///
+    /// # Errors:
+    ///
+    /// - [PetHistoryError::NotFound] if no [Pet] has the requested id.
+    fn pet_status_history(
+        &self,
+        pet_id: i64,
+    ) -> impl Future<Output = Result<Vec<StatusChange>, PetHistoryError>> + Send;
+}
+
+/// `PetChangeService` streams the changes made through the [PetService] as they happen.
+pub trait PetChangeService: Clone + Send + Sync + 'static {
+    /// A receiver of every [PetChange] successfully written from now on. A receiver falling
+    /// too far behind misses the oldest changes, and is told how many it missed.
+    fn subscribe_to_pet_changes(&self) -> broadcast::Receiver<PetChange>;
+}
+
+/// `PetTransferService` imports and exports pets in bulk, whatever the file format.
+pub trait PetTransferService: Clone + Send + Sync + 'static {
+    /// Create a pet for every valid row of `rows`, in order, and report the rows that failed;
+    /// a failing row does not stop the import. In a `dry_run`, nothing is created and the
+    /// report tells which rows would fail.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [ImportPetsError] yielded by `rows`, e.g. when the upload is
+    ///   interrupted. The rows before it stay imported.
+    fn import_pets<S>(
+        &self,
+        rows: S,
+        dry_run: bool,
+    ) -> impl Future<Output = Result<ImportReport, ImportPetsError>> + Send
+    where
+        S: Stream<Item = Result<ImportRow, ImportPetsError>> + Send;
+
+    /// Every pet as a [PetRecord], ordered by id and read from the store a page at a time.
+    fn export_pets(&self) -> impl Stream<Item = Result<PetRecord, ListPetsError>> + Send + 'static;
 }
 
 /// `PetRepository` represents a store of pet data.
@@ -44,13 +167,30 @@ pub trait PetService: Clone + Send + Sync + 'static {
 pub trait PetRepository: Send + Sync + Clone + 'static {
     /// Asynchronously persist a new [Author].
     ///
+    /// The returned [Pet] and its tags MUST have ids: those in the request, or ones assigned by
+    /// the store where the request has none.
+    ///
+    /// The [PetEvent]s returned by `events` for the persisted pet MUST be appended to the
+    /// outbox atomically with the pet.
+    ///
     /// # Errors:
     ///
     /// - MUST return [CreateAuthorError::Duplicate] if an [Pet]] with the same [name]]
     ///   already exists.
+    /// - MUST return [CreatePetError::DuplicateId] if a [Pet] with the requested id already
+    ///   exists.
+    /// - MUST return [CreatePetError::DuplicateTagId] if a new tag has the id of an existing tag
+    ///   of another name.
+    /// - MUST return [CreatePetError::IdRequired] if a new tag has no id and ids must be
+    ///   supplied by clients. Existing tags MUST be found by name regardless.
+    /// - MUST return [CreatePetError::UnknownCategory] if the request references a [Category]
+    ///   that does not exist. Existing categories MUST NOT be created or renamed as a side effect.
+    /// - MUST return [CreatePetError::CategoryIdRequired] if the request references a
+    ///   [Category] without an id.
     fn add_pet(
         &self,
         req: &CreatePetRequest,
+        events: impl FnOnce(&Pet) -> Vec<PetEvent> + Send,
     ) -> impl Future<Output = Result<Pet, CreatePetError>> + Send;
 
     /// Find a pet by its ID.
@@ -62,11 +202,521 @@ pub trait PetRepository: Send + Sync + Clone + 'static {
         &self,
         pet_id: i64,
     ) -> impl Future<Output = Result<Option<Pet>, CreatePetError>> + Send;
+
+    /// Find the current version of a pet, or `None` if no [Pet] has the requested id.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [CreatePetError] returned by the database.
+    fn find_pet_version(
+        &self,
+        pet_id: i64,
+    ) -> impl Future<Output = Result<Option<i64>, CreatePetError>> + Send;
+
+    /// Find the id of the pet named `name`, or `None` if no [Pet] has that name.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [CreatePetError] returned by the database.
+    fn find_pet_id_by_name(
+        &self,
+        name: &str,
+    ) -> impl Future<Output = Result<Option<i64>, CreatePetError>> + Send;
+
+    /// List a page of pets matching the filters in `req`.
+    ///
+    /// Implementations MUST apply the cursor as a keyset on the requested sort order, so that
+    /// paging stays stable while pets are being added, and MUST set [PetPage::next_cursor] only
+    /// when further pets exist.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [ListPetsError] returned by the database.
+    fn list_pets(
+        &self,
+        req: &ListPetsRequest,
+    ) -> impl Future<Output = Result<PetPage, ListPetsError>> + Send;
+
+    /// Find up to `req.limit` pets matching `req.query`, best matches first and ties by id.
+    ///
+    /// A pet MUST match if every term of the query occurs, ignoring case, in its name, its
+    /// category name or one of its tag names. Stores without a text index may rank with
+    /// [substring_rank](crate::domain::petstore::models::search::substring_rank).
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [SearchPetsError] returned by the database.
+    fn search_pets(
+        &self,
+        req: &SearchPetsRequest,
+    ) -> impl Future<Output = Result<Vec<PetMatch>, SearchPetsError>> + Send;
+
+    /// Persist the status change in `req` for a pet currently in status `from`.
+    ///
+    /// The change MUST only be applied if the stored status still equals `from` and, when
+    /// `req.expected_version` is set, the stored version still equals it, so that a concurrent
+    /// change cannot be overwritten by a transition validated against stale data. The stored
+    /// version MUST be incremented, the change recorded as a [StatusChange] and `events`
+    /// appended to the outbox, atomically with the update.
+    ///
+    /// # Errors:
+    ///
+    /// - MUST return [ChangePetStatusError::NotFound] if no [Pet] has the requested id.
+    /// - MUST return [ChangePetStatusError::Conflict] if the stored version differs from
+    ///   `req.expected_version`.
+    /// - MUST return [ChangePetStatusError::InvalidTransition] from the stored status if it no
+    ///   longer equals `from`.
+    fn update_pet_status(
+        &self,
+        req: &ChangePetStatusRequest,
+        from: &Status,
+        events: &[PetEvent],
+    ) -> impl Future<Output = Result<Pet, ChangePetStatusError>> + Send;
+
+    /// Delete a pet, together with its photos, tags and status history, and append `events`
+    /// to the outbox atomically with the deletion.
+    ///
+    /// # Errors:
+    ///
+    /// - MUST return [DeletePetError::NotFound] if no [Pet] has the requested id.
+    fn delete_pet(
+        &self,
+        pet_id: i64,
+        events: &[PetEvent],
+    ) -> impl Future<Output = Result<(), DeletePetError>> + Send;
+
+    /// The recorded status changes of a pet, oldest first. [PetRepository::add_pet] MUST record
+    /// the initial status as a [StatusChange] without a `from` status.
+    ///
+    /// # Errors:
+    ///
+    /// - MUST return [PetHistoryError::NotFound] if no [Pet] has the requested id.
+    fn pet_status_history(
+        &self,
+        pet_id: i64,
+    ) -> impl Future<Output = Result<Vec<StatusChange>, PetHistoryError>> + Send;
+}
+
+/// `CategoryService` is the public API for managing pet categories.
+pub trait CategoryService: Clone + Send + Sync + 'static {
+    /// Asynchronously create a new [Category].
+    ///
+    /// # Errors:
+    ///
+    /// - [CreateCategoryError::Duplicate] if a [Category] with the same name already exists.
+    fn create_category(
+        &self,
+        req: &CreateCategoryRequest,
+    ) -> impl Future<Output = Result<Category, CreateCategoryError>> + Send;
+
+    /// List all categories, ordered by id.
+    fn list_categories(&self) -> impl Future<Output = Result<Vec<Category>, FindCategoryError>> + Send;
+
+    /// Find a category by its ID.
+    fn find_category_by_id(
+        &self,
+        category_id: i64,
+    ) -> impl Future<Output = Result<Option<Category>, FindCategoryError>> + Send;
+
+    /// Give an existing [Category] a new name.
+    ///
+    /// # Errors:
+    ///
+    /// - [RenameCategoryError::NotFound] if no [Category] has the requested id.
+    /// - [RenameCategoryError::Duplicate] if another [Category] already has the new name.
+    fn rename_category(
+        &self,
+        req: &RenameCategoryRequest,
+    ) -> impl Future<Output = Result<Category, RenameCategoryError>> + Send;
+
+    /// Delete a [Category].
+    ///
+    /// # Errors:
+    ///
+    /// - [DeleteCategoryError::NotFound] if no [Category] has the requested id.
+    /// - [DeleteCategoryError::InUse] if pets are still assigned to the [Category].
+    fn delete_category(
+        &self,
+        category_id: i64,
+    ) -> impl Future<Output = Result<(), DeleteCategoryError>> + Send;
+}
+
+/// `CategoryRepository` represents a store of category data.
+pub trait CategoryRepository: Send + Sync + Clone + 'static {
+    /// Asynchronously persist a new [Category], assigning an id if the request has none.
+    ///
+    /// # Errors:
+    ///
+    /// - MUST return [CreateCategoryError::Duplicate] if a [Category] with the same name
+    ///   already exists.
+    /// - MUST return [CreateCategoryError::DuplicateId] if a [Category] with the requested id
+    ///   already exists.
+    fn create_category(
+        &self,
+        req: &CreateCategoryRequest,
+    ) -> impl Future<Output = Result<Category, CreateCategoryError>> + Send;
+
+    /// List all categories, ordered by id.
+    fn list_categories(&self) -> impl Future<Output = Result<Vec<Category>, FindCategoryError>> + Send;
+
+    /// Find a category by its ID.
+    fn find_category_by_id(
+        &self,
+        category_id: i64,
+    ) -> impl Future<Output = Result<Option<Category>, FindCategoryError>> + Send;
+
+    /// Persist a new name for an existing [Category].
+    ///
+    /// # Errors:
+    ///
+    /// - MUST return [RenameCategoryError::NotFound] if no [Category] has the requested id.
+    /// - MUST return [RenameCategoryError::Duplicate] if another [Category] has the new name.
+    fn rename_category(
+        &self,
+        req: &RenameCategoryRequest,
+    ) -> impl Future<Output = Result<Category, RenameCategoryError>> + Send;
+
+    /// Remove a [Category].
+    ///
+    /// # Errors:
+    ///
+    /// - MUST return [DeleteCategoryError::NotFound] if no [Category] has the requested id.
+    /// - MUST return [DeleteCategoryError::InUse] if any pet references the [Category].
+    fn delete_category(
+        &self,
+        category_id: i64,
+    ) -> impl Future<Output = Result<(), DeleteCategoryError>> + Send;
+}
+
+/// `TagService` is the public API for administering the tags attached to pets.
+pub trait TagService: Clone + Send + Sync + 'static {
+    /// List all tags with the number of pets each is attached to, ordered by id.
+    fn list_tags(&self) -> impl Future<Output = Result<Vec<TagUsage>, ListTagsError>> + Send;
+
+    /// Give an existing [Tag] a new name.
+    ///
+    /// # Errors:
+    ///
+    /// - [RenameTagError::NotFound] if no [Tag] has the requested id.
+    /// - [RenameTagError::Duplicate] if another [Tag] already has the new name.
+    fn rename_tag(
+        &self,
+        req: &RenameTagRequest,
+    ) -> impl Future<Output = Result<Tag, RenameTagError>> + Send;
+
+    /// Merge one [Tag] into another, re-pointing every pet tagged with the source.
+    ///
+    /// # Errors:
+    ///
+    /// - [MergeTagsError::SameTag] if the source and target are the same [Tag].
+    /// - [MergeTagsError::NotFound] if either [Tag] does not exist.
+    fn merge_tags(
+        &self,
+        req: &MergeTagsRequest,
+    ) -> impl Future<Output = Result<TagUsage, MergeTagsError>> + Send;
+
+    /// Delete a [Tag] that is not attached to any pet.
+    ///
+    /// # Errors:
+    ///
+    /// - [DeleteTagError::NotFound] if no [Tag] has the requested id.
+    /// - [DeleteTagError::InUse] if pets are still tagged with it.
+    fn delete_tag(&self, tag_id: i64) -> impl Future<Output = Result<(), DeleteTagError>> + Send;
+
+    /// Delete every [Tag] that is not attached to any pet, returning the deleted tags.
+    fn delete_unused_tags(&self) -> impl Future<Output = Result<Vec<Tag>, DeleteTagError>> + Send;
+}
+
+/// `TagRepository` represents a store of tag data.
+pub trait TagRepository: Send + Sync + Clone + 'static {
+    /// List all tags with the number of pets each is attached to, ordered by id.
+    fn list_tags(&self) -> impl Future<Output = Result<Vec<TagUsage>, ListTagsError>> + Send;
+
+    /// Persist a new name for an existing [Tag].
+    ///
+    /// # Errors:
+    ///
+    /// - MUST return [RenameTagError::NotFound] if no [Tag] has the requested id.
+    /// - MUST return [RenameTagError::Duplicate] if another [Tag] has the new name.
+    fn rename_tag(
+        &self,
+        req: &RenameTagRequest,
+    ) -> impl Future<Output = Result<Tag, RenameTagError>> + Send;
+
+    /// Atomically re-point the associations of the source [Tag] to the target and remove the
+    /// source. Pets tagged with both end up tagged with the target once.
+    ///
+    /// # Errors:
+    ///
+    /// - MUST return [MergeTagsError::NotFound] if either [Tag] does not exist.
+    fn merge_tags(
+        &self,
+        req: &MergeTagsRequest,
+    ) -> impl Future<Output = Result<TagUsage, MergeTagsError>> + Send;
+
+    /// Remove a [Tag].
+    ///
+    /// # Errors:
+    ///
+    /// - MUST return [DeleteTagError::NotFound] if no [Tag] has the requested id.
+    /// - MUST return [DeleteTagError::InUse] if any pet is tagged with it.
+    fn delete_tag(&self, tag_id: i64) -> impl Future<Output = Result<(), DeleteTagError>> + Send;
+
+    /// Remove every [Tag] that no pet is tagged with, returning the removed tags.
+    fn delete_unused_tags(&self) -> impl Future<Output = Result<Vec<Tag>, DeleteTagError>> + Send;
+}
+
+/// `IdempotencyService` lets callers make a request safe to retry: the first request under an
+/// [IdempotencyKey](crate::domain::petstore::models::idempotency::IdempotencyKey) is processed and its response stored, retries get the stored response.
+pub trait IdempotencyService: Clone + Send + Sync + 'static {
+    /// Claim `req.key` for the request identified by `req.fingerprint`.
+    ///
+    /// On [IdempotencyClaim::Claimed], the caller must process the request and then either
+    /// [complete](IdempotencyService::complete_idempotency_key) the key with the response or
+    /// [release](IdempotencyService::release_idempotency_key) it if the request may be retried.
+    fn claim_idempotency_key(
+        &self,
+        req: &ClaimIdempotencyKeyRequest,
+    ) -> impl Future<Output = Result<IdempotencyClaim, IdempotencyError>> + Send;
+
+    /// Store the response of the request that made `claim`, for replay to its retries for
+    /// `window` from now.
+    ///
+    /// # Errors:
+    ///
+    /// - [IdempotencyError::ClaimLost] if the claim expired and the key was claimed or
+    ///   completed by another request since. The response is then not stored.
+    fn complete_idempotency_key(
+        &self,
+        claim: &ClaimIdempotencyKeyRequest,
+        response: &IdempotentResponse,
+        window: Duration,
+    ) -> impl Future<Output = Result<(), IdempotencyError>> + Send;
+
+    /// Give up `claim` without a response, so that a retry is processed afresh. Nothing is
+    /// released if the claim expired and the key was claimed or completed by another request.
+    fn release_idempotency_key(
+        &self,
+        claim: &ClaimIdempotencyKeyRequest,
+    ) -> impl Future<Output = Result<(), IdempotencyError>> + Send;
+
+    /// Forget the expired claims and their responses, returning how many were forgotten.
+    fn purge_expired_idempotency_keys(&self) -> impl Future<Output = Result<u64, IdempotencyError>> + Send;
+}
+
+/// `IdempotencyRepository` represents a store of idempotency keys and their responses.
+///
+/// External modules must conform to this contract – the domain is not concerned with the
+/// implementation details or underlying technology of any external code.
+pub trait IdempotencyRepository: Send + Sync + Clone + 'static {
+    /// Atomically claim `req.key` unless an unexpired claim exists, expiring the new claim
+    /// `req.lease` from now.
+    ///
+    /// An existing unexpired claim MUST be reported as [IdempotencyClaim::Mismatch] if its
+    /// fingerprint differs from `req.fingerprint`, otherwise as [IdempotencyClaim::Completed]
+    /// or [IdempotencyClaim::InProgress] depending on whether a response was stored.
+    fn claim_idempotency_key(
+        &self,
+        req: &ClaimIdempotencyKeyRequest,
+    ) -> impl Future<Output = Result<IdempotencyClaim, IdempotencyError>> + Send;
+
+    /// Store the response of the request that made `claim`, expiring the claim `window` from
+    /// now.
+    ///
+    /// # Errors:
+    ///
+    /// - MUST return [IdempotencyError::ClaimLost] without storing the response if the key is
+    ///   no longer held by `claim`, as told by its token, or already completed.
+    fn complete_idempotency_key(
+        &self,
+        claim: &ClaimIdempotencyKeyRequest,
+        response: &IdempotentResponse,
+        window: Duration,
+    ) -> impl Future<Output = Result<(), IdempotencyError>> + Send;
+
+    /// Delete `claim`, unless the key is no longer held by it, as told by its token, or was
+    /// completed.
+    fn release_idempotency_key(
+        &self,
+        claim: &ClaimIdempotencyKeyRequest,
+    ) -> impl Future<Output = Result<(), IdempotencyError>> + Send;
+
+    /// Delete the expired claims, returning how many were deleted.
+    fn purge_expired_idempotency_keys(&self) -> impl Future<Output = Result<u64, IdempotencyError>> + Send;
+}
+
+/// `IdGenerator` chooses the ids of entities created without one.
+///
+/// External modules must conform to this contract – the domain is not concerned with the
+/// implementation details or underlying technology of any external code.
+pub trait IdGenerator: Send + Sync + Clone + 'static {
+    /// The id for a new entity of `kind`, or `None` to let the store assign one from its
+    /// sequence.
+    ///
+    /// # Errors:
+    ///
+    /// - [GenerateIdError::Required] if ids of this kind must be supplied by clients.
+    fn next_id(&self, kind: IdKind) -> impl Future<Output = Result<Option<i64>, GenerateIdError>> + Send;
+}
+
+/// `OutboxRepository` represents the store of [PetEvent]s recorded with the changes they
+/// describe, awaiting delivery to other services.
+///
+/// External modules must conform to this contract – the domain is not concerned with the
+/// implementation details or underlying technology of any external code.
+pub trait OutboxRepository: Send + Sync + Clone + 'static {
+    /// Claim up to `limit` events not yet dispatched, in the order they were recorded, for
+    /// `lease`.
+    ///
+    /// MUST claim nothing while events claimed by another caller are pending and leased, so
+    /// that events are relayed by one caller at a time and never out of order. Events not
+    /// marked as dispatched before their lease ends may be claimed again.
+    ///
+    /// The order of events MUST be the order in which they were committed: an event MUST NOT be
+    /// claimed while an event recorded before it may still be committed.
+    fn claim_pending_events(
+        &self,
+        limit: usize,
+        lease: Duration,
+    ) -> impl Future<Output = Result<Vec<OutboxEvent>, OutboxError>> + Send;
+
+    /// Mark the event with the given id as dispatched, so that it is not relayed again.
+    fn mark_dispatched(&self, event_id: i64) -> impl Future<Output = Result<(), OutboxError>> + Send;
+
+    /// End the lease of the events with the given ids that are not dispatched yet, so that they
+    /// can be claimed again right away.
+    fn release_claims(&self, event_ids: &[i64]) -> impl Future<Output = Result<(), OutboxError>> + Send;
+}
+
+/// `EventPublisher` delivers [PetEvent]s to other services.
+///
+/// Delivery is at-least-once: an event whose publication succeeded may be published again if
+/// it could not be marked as dispatched, so consumers must tolerate duplicates, e.g. by the
+/// event id.
+pub trait EventPublisher: Send + Sync + Clone + 'static {
+    fn publish(&self, event: &OutboxEvent) -> impl Future<Output = Result<(), PublishEventError>> + Send;
+}
+
+/// `WebhookService` is the public API for managing the webhooks partners receive pet events
+/// through.
+pub trait WebhookService: Clone + Send + Sync + 'static {
+    /// Register a new [Webhook].
+    fn create_webhook(
+        &self,
+        req: &CreateWebhookRequest,
+    ) -> impl Future<Output = Result<Webhook, CreateWebhookError>> + Send;
+
+    /// List all webhooks, ordered by id.
+    fn list_webhooks(&self) -> impl Future<Output = Result<Vec<Webhook>, FindWebhookError>> + Send;
+
+    /// Find a webhook by its ID.
+    fn find_webhook(
+        &self,
+        webhook_id: i64,
+    ) -> impl Future<Output = Result<Option<Webhook>, FindWebhookError>> + Send;
+
+    /// Delete a [Webhook] and its deliveries.
+    ///
+    /// # Errors:
+    ///
+    /// - [DeleteWebhookError::NotFound] if no [Webhook] has the requested id.
+    fn delete_webhook(&self, webhook_id: i64) -> impl Future<Output = Result<(), DeleteWebhookError>> + Send;
+
+    /// The delivery log of a [Webhook], most recent first.
+    ///
+    /// # Errors:
+    ///
+    /// - [WebhookDeliveryError::NotFound] if no [Webhook] has the requested id.
+    fn webhook_deliveries(
+        &self,
+        webhook_id: i64,
+    ) -> impl Future<Output = Result<Vec<WebhookDelivery>, WebhookDeliveryError>> + Send;
+}
+
+/// `WebhookRepository` represents a store of webhooks and of their deliveries.
+///
+/// External modules must conform to this contract – the domain is not concerned with the
+/// implementation details or underlying technology of any external code.
+pub trait WebhookRepository: Send + Sync + Clone + 'static {
+    /// Persist a new [Webhook], assigning its id.
+    fn create_webhook(
+        &self,
+        req: &CreateWebhookRequest,
+    ) -> impl Future<Output = Result<Webhook, CreateWebhookError>> + Send;
+
+    /// List all webhooks, ordered by id.
+    fn list_webhooks(&self) -> impl Future<Output = Result<Vec<Webhook>, FindWebhookError>> + Send;
+
+    /// Find a webhook by its ID.
+    fn find_webhook(
+        &self,
+        webhook_id: i64,
+    ) -> impl Future<Output = Result<Option<Webhook>, FindWebhookError>> + Send;
+
+    /// Remove a [Webhook] together with its deliveries.
+    ///
+    /// # Errors:
+    ///
+    /// - MUST return [DeleteWebhookError::NotFound] if no [Webhook] has the requested id.
+    fn delete_webhook(&self, webhook_id: i64) -> impl Future<Output = Result<(), DeleteWebhookError>> + Send;
+
+    /// Record a pending delivery of `payload` to every webhook accepting `event_type`, due
+    /// immediately, and return how many were recorded.
+    ///
+    /// MUST record at most one delivery per webhook and event, so that enqueueing an event
+    /// again has no effect.
+    fn enqueue_deliveries(
+        &self,
+        event_id: i64,
+        event_type: &str,
+        payload: &str,
+    ) -> impl Future<Output = Result<usize, WebhookDeliveryError>> + Send;
+
+    /// Claim up to `limit` pending deliveries whose next attempt is due, oldest first, with
+    /// their webhooks, by postponing their next attempt by `lease`.
+    ///
+    /// MUST claim atomically, so that concurrent callers never receive the same delivery while
+    /// its lease runs. A delivery whose attempt is not recorded before its lease ends is due
+    /// again, so deliveries are at least once.
+    fn claim_due_deliveries(
+        &self,
+        limit: usize,
+        lease: Duration,
+    ) -> impl Future<Output = Result<Vec<(Webhook, WebhookDelivery)>, WebhookDeliveryError>> + Send;
+
+    /// Record the outcome of an attempt of a delivery, counting the attempt.
+    fn record_delivery_attempt(
+        &self,
+        delivery_id: i64,
+        outcome: &DeliveryOutcome,
+    ) -> impl Future<Output = Result<(), WebhookDeliveryError>> + Send;
+
+    /// The deliveries of a [Webhook], most recent first.
+    ///
+    /// # Errors:
+    ///
+    /// - MUST return [WebhookDeliveryError::NotFound] if no [Webhook] has the requested id.
+    fn list_deliveries(
+        &self,
+        webhook_id: i64,
+    ) -> impl Future<Output = Result<Vec<WebhookDelivery>, WebhookDeliveryError>> + Send;
+}
+
+/// `WebhookSender` sends [WebhookMessage]s to their receivers.
+pub trait WebhookSender: Send + Sync + Clone + 'static {
+    /// Send `message` and return the HTTP status code of the response, whether successful or
+    /// not.
+    ///
+    /// # Errors:
+    ///
+    /// - [SendWebhookError] if no response was received.
+    fn send(&self, message: &WebhookMessage) -> impl Future<Output = Result<u16, SendWebhookError>> + Send;
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::petstore::models::search::search_by_substring;
     use std::collections::HashMap;
     use std::sync::{Arc, Mutex};
 
@@ -88,6 +738,7 @@ mod tests {
         fn add_pet(
             &self,
             req: &CreatePetRequest,
+            _: impl FnOnce(&Pet) -> Vec<PetEvent> + Send,
         ) -> impl Future<Output = Result<Pet, CreatePetError>> + Send {
             let pets = self.pets.clone();
             let req = req.clone();
@@ -121,6 +772,113 @@ mod tests {
                 Ok(pet)
             }
         }
+
+        fn find_pet_version(
+            &self,
+            pet_id: i64,
+        ) -> impl Future<Output = Result<Option<i64>, CreatePetError>> + Send {
+            let pets = self.pets.clone();
+
+            async move {
+                let pets = pets.lock().unwrap();
+                Ok(pets.values().find(|p| p.id == Some(pet_id)).map(|p| p.version))
+            }
+        }
+
+        fn find_pet_id_by_name(
+            &self,
+            name: &str,
+        ) -> impl Future<Output = Result<Option<i64>, CreatePetError>> + Send {
+            let pets = self.pets.clone();
+            let name = name.to_string();
+
+            async move {
+                let pets = pets.lock().unwrap();
+                Ok(pets.get(&name).and_then(|p| p.id))
+            }
+        }
+
+        fn list_pets(
+            &self,
+            req: &ListPetsRequest,
+        ) -> impl Future<Output = Result<PetPage, ListPetsError>> + Send {
+            let pets = self.pets.clone();
+            let limit = req.limit;
+
+            async move {
+                let pets = pets.lock().unwrap();
+                let mut pets: Vec<Pet> = pets.values().cloned().collect();
+                pets.sort_by(|a, b| a.name.cmp(&b.name));
+                Ok(PetPage::from_overfetched(pets, limit))
+            }
+        }
+
+        fn search_pets(
+            &self,
+            req: &SearchPetsRequest,
+        ) -> impl Future<Output = Result<Vec<PetMatch>, SearchPetsError>> + Send {
+            let pets = self.pets.clone();
+            let req = req.clone();
+
+            async move {
+                let pets = pets.lock().unwrap();
+                Ok(search_by_substring(pets.values(), &req))
+            }
+        }
+
+        fn update_pet_status(
+            &self,
+            req: &ChangePetStatusRequest,
+            _: &Status,
+            _: &[PetEvent],
+        ) -> impl Future<Output = Result<Pet, ChangePetStatusError>> + Send {
+            let pets = self.pets.clone();
+            let req = req.clone();
+
+            async move {
+                let mut pets = pets.lock().unwrap();
+                let pet = pets
+                    .values_mut()
+                    .find(|p| p.id == Some(req.pet_id))
+                    .ok_or(ChangePetStatusError::NotFound { id: req.pet_id })?;
+                pet.set_status(req.status);
+                pet.version += 1;
+                Ok(pet.clone())
+            }
+        }
+
+        fn delete_pet(
+            &self,
+            pet_id: i64,
+            _: &[PetEvent],
+        ) -> impl Future<Output = Result<(), DeletePetError>> + Send {
+            let pets = self.pets.clone();
+
+            async move {
+                let mut pets = pets.lock().unwrap();
+                let before = pets.len();
+                pets.retain(|_, p| p.id != Some(pet_id));
+                if pets.len() == before {
+                    return Err(DeletePetError::NotFound { id: pet_id });
+                }
+                Ok(())
+            }
+        }
+
+        fn pet_status_history(
+            &self,
+            pet_id: i64,
+        ) -> impl Future<Output = Result<Vec<StatusChange>, PetHistoryError>> + Send {
+            let pets = self.pets.clone();
+
+            async move {
+                let pets = pets.lock().unwrap();
+                if !pets.values().any(|p| p.id == Some(pet_id)) {
+                    return Err(PetHistoryError::NotFound { id: pet_id });
+                }
+                Ok(Vec::new())
+            }
+        }
     }
 
     // Mock implementation of PetService for testing
@@ -140,7 +898,7 @@ mod tests {
             &self,
             req: &CreatePetRequest,
         ) -> impl Future<Output = Result<Pet, CreatePetError>> + Send {
-            self.repository.add_pet(req)
+            self.repository.add_pet(req, |_| Vec::new())
         }
 
         fn find_pet_by_id(
@@ -149,6 +907,43 @@ mod tests {
         ) -> impl Future<Output = Result<Option<Pet>, CreatePetError>> + Send {
             self.repository.find_pet_by_id(pet_id)
         }
+
+        fn find_pet_version(
+            &self,
+            pet_id: i64,
+        ) -> impl Future<Output = Result<Option<i64>, CreatePetError>> + Send {
+            self.repository.find_pet_version(pet_id)
+        }
+
+        fn list_pets(
+            &self,
+            req: &ListPetsRequest,
+        ) -> impl Future<Output = Result<PetPage, ListPetsError>> + Send {
+            self.repository.list_pets(req)
+        }
+
+        async fn search_pets(&self, req: &SearchPetsRequest) -> Result<Vec<PetSearchHit>, SearchPetsError> {
+            let found = self.repository.search_pets(req).await?;
+            Ok(found.into_iter().map(|m| PetSearchHit::new(m, &req.query)).collect())
+        }
+
+        fn change_pet_status(
+            &self,
+            req: &ChangePetStatusRequest,
+        ) -> impl Future<Output = Result<Pet, ChangePetStatusError>> + Send {
+            self.repository.update_pet_status(req, &Status::Available, &[])
+        }
+
+        fn delete_pet(&self, pet_id: i64) -> impl Future<Output = Result<(), DeletePetError>> + Send {
+            self.repository.delete_pet(pet_id, &[])
+        }
+
+        fn pet_status_history(
+            &self,
+            pet_id: i64,
+        ) -> impl Future<Output = Result<Vec<StatusChange>, PetHistoryError>> + Send {
+            self.repository.pet_status_history(pet_id)
+        }
     }
 
     #[tokio::test]
@@ -174,7 +969,7 @@ mod tests {
         assert!(pet.category.is_none());
         assert!(pet.photo_urls.is_empty());
         assert!(pet.tags.is_empty());
-        assert!(matches!(pet.status, Some(crate::domain::petstore::models::pet::Status::Available)));
+        assert!(matches!(pet.status, Some(Status::Available)));
     }
 
     #[tokio::test]
@@ -217,14 +1012,33 @@ mod tests {
         );
 
         // Test successful addition
-        let result = repository.add_pet(&request).await;
+        let result = repository.add_pet(&request, |_| Vec::new()).await;
         assert!(result.is_ok());
         
         // Test duplicate error
-        let result = repository.add_pet(&request).await;
+        let result = repository.add_pet(&request, |_| Vec::new()).await;
         assert!(matches!(
             result,
             Err(CreatePetError::Duplicate { name }) if name == "Luna"
         ));
     }
+
+    #[tokio::test]
+    async fn test_list_pets() {
+        let repository = MockPetRepository::new();
+        let service = MockPetService::new(repository);
+
+        for name in ["Rex", "Buddy", "Luna"] {
+            let request = CreatePetRequest::new(None, String::from(name), None, Vec::new(), Vec::new(), None);
+            service.add_pet(&request).await.unwrap();
+        }
+
+        let request = ListPetsRequest {
+            limit: 2,
+            ..Default::default()
+        };
+        let page = service.list_pets(&request).await.unwrap();
+        let names: Vec<&str> = page.pets.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["Buddy", "Luna"]);
+    }
 }
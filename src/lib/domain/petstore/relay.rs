@@ -0,0 +1,269 @@
+/*!
+   Module `relay` delivers the [PetEvent](crate::domain::petstore::models::event::PetEvent)s
+   recorded in the outbox through an [EventPublisher], in the order they were recorded.
+
+   Relays claim the events they publish for a lease, so that several instances of the
+   application can run a relay without publishing an event twice at once or out of order.
+   Delivery is still at least once: an event whose publication succeeded is published again if
+   it could not be marked as dispatched before its lease ended. When a publication fails, the
+   relay releases the events of its batch it has not published, so that the next pass retries
+   them without waiting for the lease to end.
+*/
+
+use std::time::Duration;
+
+use crate::domain::petstore::models::event::{OutboxEvent, RelayError};
+use crate::domain::petstore::ports::{EventPublisher, OutboxRepository};
+
+/// Relays pending outbox events to an [EventPublisher] and marks them dispatched.
+#[derive(Debug, Clone)]
+pub struct OutboxRelay<O, P>
+where
+    O: OutboxRepository,
+    P: EventPublisher,
+{
+    outbox: O,
+    publisher: P,
+    batch_size: usize,
+    lease: Duration,
+}
+
+impl<O, P> OutboxRelay<O, P>
+where
+    O: OutboxRepository,
+    P: EventPublisher,
+{
+    pub const DEFAULT_BATCH_SIZE: usize = 100;
+    pub const DEFAULT_LEASE: Duration = Duration::from_secs(30);
+
+    pub fn new(outbox: O, publisher: P) -> Self {
+        Self {
+            outbox,
+            publisher,
+            batch_size: Self::DEFAULT_BATCH_SIZE,
+            lease: Self::DEFAULT_LEASE,
+        }
+    }
+
+    /// Sets the number of events fetched from the outbox at a time.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Sets how long the events of a batch stay claimed by this relay. It must outlast the
+    /// publication of a whole batch.
+    pub fn with_lease(mut self, lease: Duration) -> Self {
+        self.lease = lease;
+        self
+    }
+
+    /// Publishes the pending events oldest first, marking each dispatched once published, and
+    /// returns how many were relayed. Nothing is relayed while another relay holds a claim.
+    ///
+    /// # Errors:
+    ///
+    /// - [RelayError::Publish] if an event could not be published. The events after it are
+    ///   left pending, so that they are never delivered ahead of it, and their claim is
+    ///   released.
+    /// - [RelayError::Outbox] if the outbox could not be read or updated.
+    pub async fn relay_pending(&self) -> Result<usize, RelayError> {
+        let mut relayed = 0;
+        loop {
+            let events = self.outbox.claim_pending_events(self.batch_size, self.lease).await?;
+            let exhausted = events.len() < self.batch_size;
+            for (i, event) in events.iter().enumerate() {
+                if let Err(source) = self.publisher.publish(event).await {
+                    self.release(&events[i..]).await;
+                    return Err(RelayError::Publish { event_id: event.id, source });
+                }
+                self.outbox.mark_dispatched(event.id).await?;
+                relayed += 1;
+            }
+            if exhausted {
+                return Ok(relayed);
+            }
+        }
+    }
+
+    /// Releases the claim on `events`. A failure is only logged: the claim then lasts until its
+    /// lease ends.
+    async fn release(&self, events: &[OutboxEvent]) {
+        let ids: Vec<i64> = events.iter().map(|e| e.id).collect();
+        if let Err(e) = self.outbox.release_claims(&ids).await {
+            tracing::warn!("failed to release outbox events: {}", e);
+        }
+    }
+
+    /// Relays pending events every `interval`, forever. Failures are logged and retried on the
+    /// next tick.
+    pub async fn run(self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.relay_pending().await {
+                tracing::warn!("failed to relay outbox events: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+
+    use crate::domain::petstore::models::event::{OutboxError, PetEvent, PublishEventError};
+    use crate::domain::petstore::models::pet::Pet;
+
+    #[derive(Debug, Clone, Default)]
+    struct MockOutbox {
+        events: Arc<Mutex<Vec<(OutboxEvent, bool)>>>,
+        claimed_until: Arc<Mutex<HashMap<i64, Instant>>>,
+    }
+
+    impl MockOutbox {
+        fn with_events(count: i64) -> Self {
+            let outbox = Self::default();
+            for id in 1..=count {
+                let event = OutboxEvent {
+                    id,
+                    occurred_at: chrono::Utc::now(),
+                    event: PetEvent::created(&Pet::with_id(id, "Rex".to_string())),
+                };
+                outbox.events.lock().unwrap().push((event, false));
+            }
+            outbox
+        }
+
+        fn dispatched(&self) -> Vec<i64> {
+            self.events.lock().unwrap().iter().filter(|(_, d)| *d).map(|(e, _)| e.id).collect()
+        }
+    }
+
+    impl OutboxRepository for MockOutbox {
+        async fn claim_pending_events(&self, limit: usize, lease: Duration) -> Result<Vec<OutboxEvent>, OutboxError> {
+            let events = self.events.lock().unwrap();
+            let mut claimed_until = self.claimed_until.lock().unwrap();
+            let now = Instant::now();
+            let pending = events.iter().filter(|(_, d)| !d).map(|(e, _)| e);
+            if pending.clone().any(|e| claimed_until.get(&e.id).is_some_and(|until| *until > now)) {
+                return Ok(Vec::new());
+            }
+            Ok(pending
+                .take(limit)
+                .inspect(|e| {
+                    claimed_until.insert(e.id, now + lease);
+                })
+                .cloned()
+                .collect())
+        }
+
+        async fn mark_dispatched(&self, event_id: i64) -> Result<(), OutboxError> {
+            let mut events = self.events.lock().unwrap();
+            if let Some((_, dispatched)) = events.iter_mut().find(|(e, _)| e.id == event_id) {
+                *dispatched = true;
+            }
+            Ok(())
+        }
+
+        async fn release_claims(&self, event_ids: &[i64]) -> Result<(), OutboxError> {
+            let mut claimed_until = self.claimed_until.lock().unwrap();
+            for id in event_ids {
+                claimed_until.remove(id);
+            }
+            Ok(())
+        }
+    }
+
+    /// Records the ids of published events, failing on `fail_on`.
+    #[derive(Debug, Clone, Default)]
+    struct MockPublisher {
+        published: Arc<Mutex<Vec<i64>>>,
+        fail_on: Option<i64>,
+    }
+
+    impl EventPublisher for MockPublisher {
+        async fn publish(&self, event: &OutboxEvent) -> Result<(), PublishEventError> {
+            if self.fail_on == Some(event.id) {
+                return Err(PublishEventError::Unknown(anyhow::anyhow!("broker unavailable")));
+            }
+            self.published.lock().unwrap().push(event.id);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_relay_pending_in_order() {
+        let outbox = MockOutbox::with_events(5);
+        let publisher = MockPublisher::default();
+        let relay = OutboxRelay::new(outbox.clone(), publisher.clone()).with_batch_size(2);
+
+        assert_eq!(relay.relay_pending().await.unwrap(), 5);
+        assert_eq!(*publisher.published.lock().unwrap(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(outbox.dispatched(), vec![1, 2, 3, 4, 5]);
+
+        assert_eq!(relay.relay_pending().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_relay_pet_deleted() {
+        let outbox = MockOutbox::with_events(1);
+        let deleted = OutboxEvent {
+            id: 2,
+            occurred_at: chrono::Utc::now(),
+            event: PetEvent::PetDeleted { pet_id: 1 },
+        };
+        outbox.events.lock().unwrap().push((deleted, false));
+        let publisher = MockPublisher::default();
+        let relay = OutboxRelay::new(outbox.clone(), publisher.clone());
+
+        assert_eq!(relay.relay_pending().await.unwrap(), 2);
+        assert_eq!(*publisher.published.lock().unwrap(), vec![1, 2]);
+        assert_eq!(outbox.dispatched(), vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_relay_stops_at_first_failure() {
+        let outbox = MockOutbox::with_events(4);
+        let publisher = MockPublisher {
+            fail_on: Some(3),
+            ..Default::default()
+        };
+        let relay = OutboxRelay::new(outbox.clone(), publisher.clone());
+
+        let result = relay.relay_pending().await;
+
+        assert!(matches!(result, Err(RelayError::Publish { event_id: 3, .. })));
+        assert_eq!(*publisher.published.lock().unwrap(), vec![1, 2]);
+        assert_eq!(outbox.dispatched(), vec![1, 2]);
+
+        // The events left pending are released, so the next pass retries them right away
+        let retry = MockPublisher::default();
+        let relay = OutboxRelay::new(outbox.clone(), retry.clone());
+        assert_eq!(relay.relay_pending().await.unwrap(), 2);
+        assert_eq!(*retry.published.lock().unwrap(), vec![3, 4]);
+        assert_eq!(outbox.dispatched(), vec![1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn test_relay_skips_claimed_events() {
+        let outbox = MockOutbox::with_events(3);
+        let publisher = MockPublisher::default();
+        let relay = OutboxRelay::new(outbox.clone(), publisher.clone());
+
+        // Another relay claimed the first event and has not dispatched it yet
+        let claimed = outbox.claim_pending_events(1, Duration::from_secs(60)).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(relay.relay_pending().await.unwrap(), 0);
+        assert!(publisher.published.lock().unwrap().is_empty());
+
+        // Once it dispatched the event, the rest are relayed
+        outbox.mark_dispatched(claimed[0].id).await.unwrap();
+        assert_eq!(relay.relay_pending().await.unwrap(), 2);
+        assert_eq!(*publisher.published.lock().unwrap(), vec![2, 3]);
+    }
+}
@@ -0,0 +1,80 @@
+/*!
+   Module `seed` loads the sample data of a [SeedProfile](crate::domain::petstore::models::seed::SeedProfile)
+   into a store through its repository ports.
+*/
+
+use std::collections::HashMap;
+
+use crate::domain::petstore::models::category::{Category, CreateCategoryError, CreateCategoryRequest};
+use crate::domain::petstore::models::pet::CreatePetError;
+use crate::domain::petstore::models::seed::{SeedError, SeedPlan, SeedReport};
+use crate::domain::petstore::ports::{CategoryRepository, PetRepository};
+
+/// Loads [SeedPlan]s into a store.
+#[derive(Debug, Clone)]
+pub struct Seeder<R>
+where
+    R: PetRepository + CategoryRepository,
+{
+    repo: R,
+}
+
+impl<R> Seeder<R>
+where
+    R: PetRepository + CategoryRepository,
+{
+    pub fn new(repo: R) -> Self {
+        Self { repo }
+    }
+
+    /// Creates the categories and pets of `plan`, in order, with ids assigned by the store.
+    ///
+    /// Categories and pets whose name is taken are left as they are, so seeding again is
+    /// harmless. Seeded pets emit no events, so that demo data never reaches webhooks.
+    ///
+    /// # Errors:
+    ///
+    /// - [SeedError::Unknown] if the store fails. The data created before stays.
+    pub async fn seed(&self, plan: &SeedPlan) -> Result<SeedReport, SeedError> {
+        let mut report = SeedReport::default();
+        let existing: HashMap<String, Category> = self
+            .repo
+            .list_categories()
+            .await
+            .map_err(anyhow::Error::new)?
+            .into_iter()
+            .filter_map(|category| Some((category.name.clone()?, category)))
+            .collect();
+
+        let mut categories = Vec::with_capacity(plan.categories.len());
+        for name in &plan.categories {
+            let category = match existing.get(name) {
+                Some(category) => {
+                    report.categories_existing += 1;
+                    category.clone()
+                }
+                None => {
+                    let req = CreateCategoryRequest::new(None, name.clone());
+                    let category = self.repo.create_category(&req).await.map_err(category_error)?;
+                    report.categories_created += 1;
+                    category
+                }
+            };
+            categories.push(category);
+        }
+
+        for pet in &plan.pets {
+            let category = pet.category.map(|i| categories[i].clone());
+            match self.repo.add_pet(&pet.to_request(category), |_| Vec::new()).await {
+                Ok(_) => report.pets_created += 1,
+                Err(CreatePetError::Duplicate { .. }) => report.pets_existing += 1,
+                Err(e) => return Err(anyhow::Error::new(e).context(format!("failed to seed pet {}", pet.name)).into()),
+            }
+        }
+        Ok(report)
+    }
+}
+
+fn category_error(e: CreateCategoryError) -> SeedError {
+    anyhow::Error::new(e).context("failed to seed category").into()
+}
@@ -3,42 +3,129 @@
    blog-domain logic is defined here.
 */
 
-use crate::domain::petstore::models::pet::{Pet, CreatePetRequest, CreatePetError};
-use crate::domain::petstore::ports::{PetRepository, PetService};
+use crate::domain::petstore::models::category::{
+    Category, CreateCategoryError, CreateCategoryRequest, DeleteCategoryError, FindCategoryError,
+    RenameCategoryError, RenameCategoryRequest,
+};
+use crate::domain::petstore::ids::StrategyIdGenerator;
+use crate::domain::petstore::models::event::{PetChange, PetEvent};
+use crate::domain::petstore::models::id::{GenerateIdError, IdKind};
+use crate::domain::petstore::models::idempotency::{
+    ClaimIdempotencyKeyRequest, IdempotencyClaim, IdempotencyError, IdempotentResponse,
+};
+use crate::domain::petstore::models::pet::{
+    ChangePetStatusError, ChangePetStatusRequest, CreatePetError, CreatePetRequest, DeletePetError,
+    ListPetsError, ListPetsRequest, Pet, PetHistoryError, PetPage, StatusChange,
+};
+use crate::domain::petstore::models::search::{
+    PetSearchHit, SearchPetsError, SearchPetsRequest,
+};
+use crate::domain::petstore::models::tag::{
+    DeleteTagError, ListTagsError, MergeTagsError, MergeTagsRequest, RenameTagError,
+    RenameTagRequest, Tag, TagUsage,
+};
+use crate::domain::petstore::models::transfer::{
+    ImportPetsError, ImportReport, ImportRow, PetRecord,
+};
+use crate::domain::petstore::models::validation::ValidationErrors;
+use crate::domain::petstore::models::webhook::{
+    CreateWebhookError, CreateWebhookRequest, DeleteWebhookError, FindWebhookError, Webhook,
+    WebhookDelivery, WebhookDeliveryError,
+};
+use crate::domain::petstore::ports::{
+    CategoryRepository, CategoryService, IdGenerator, IdempotencyRepository, IdempotencyService,
+    PetChangeService, PetRepository, PetService, PetTransferService, TagRepository, TagService,
+    WebhookRepository, WebhookService,
+};
+use futures::{Stream, StreamExt};
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// How many [PetChange]s a live subscriber may fall behind before missing the oldest.
+const PET_CHANGE_CAPACITY: usize = 256;
+
+/// How many pets an export reads from the store at a time.
+const EXPORT_PAGE_SIZE: u32 = 100;
 
 /// Canonical implementation of the [PetService] port, through which the pet domain API is
 /// consumed.
 #[derive(Debug, Clone)]
-pub struct Service<R>
+pub struct Service<R, G = StrategyIdGenerator>
 where
     R: PetRepository,
+    G: IdGenerator,
 {
-    repo: R
+    repo: R,
+    ids: G,
+    changes: broadcast::Sender<PetChange>,
 }
 
 impl<R> Service<R>
 where
     R: PetRepository
 {
+    /// Returns a service whose store assigns the ids of new entities from its sequences.
     pub fn new(repo: R) -> Self {
-        Self {
-            repo
-        }
+        Self::with_id_generator(repo, StrategyIdGenerator::default())
     }
 }
 
-impl<R> PetService for Service<R>
+impl<R, G> Service<R, G>
 where
-    R: PetRepository
+    R: PetRepository,
+    G: IdGenerator,
+{
+    /// Returns a service choosing the ids of new entities created without one with `ids`.
+    pub fn with_id_generator(repo: R, ids: G) -> Self {
+        let (changes, _) = broadcast::channel(PET_CHANGE_CAPACITY);
+        Self { repo, ids, changes }
+    }
+
+    /// Announces a successfully written change to the live subscribers, if any.
+    fn notify(&self, event: PetEvent, pet: &Pet) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.changes.send(PetChange { event, pet: pet.clone() });
+    }
+}
+
+impl<R, G> PetService for Service<R, G>
+where
+    R: PetRepository,
+    G: IdGenerator,
 {
-    /// Create the [Pet] specified in `req` and trigger notifications.
+    /// Create the [Pet] specified in `req`, choosing the ids of the pet and of new tags with
+    /// the [IdGenerator] if they have none, and emit [PetEvent::PetCreated]. If ids must be
+    /// supplied by clients, tags without one are left for the [PetRepository] to look up by
+    /// name.
     ///
     /// # Errors:
     ///
+    /// - [CreatePetError::IdRequired] if the id of the pet is missing and ids must be supplied
+    ///   by clients.
     /// - Propagates any [CreatePetError] returned by the [PetRepository].
     async fn add_pet(&self, req: &CreatePetRequest) -> Result<Pet, CreatePetError> {
-        let result = self.repo.add_pet(req).await;
-        result
+        let mut req = req.clone();
+        if req.id.is_none() {
+            req.id = self.ids.next_id(IdKind::Pet).await?;
+        }
+        for tag in req.tags.iter_mut().filter(|tag| tag.id.is_none()) {
+            tag.id = match self.ids.next_id(IdKind::Tag).await {
+                // Only a tag that does not exist yet needs one
+                Err(GenerateIdError::Required { .. }) => None,
+                id => id?,
+            };
+        }
+
+        let pet = self.repo.add_pet(&req, |pet| vec![PetEvent::created(pet)]).await?;
+        if pet.id.is_none() {
+            return Err(CreatePetError::Unknown(anyhow::anyhow!(
+                "pet {} was persisted without an id",
+                pet.name
+            )));
+        }
+        self.notify(PetEvent::created(&pet), &pet);
+        Ok(pet)
     }
 
     /// Find a pet by its ID.
@@ -49,33 +136,495 @@ where
     async fn find_pet_by_id(&self, pet_id: i64) -> Result<Option<Pet>, CreatePetError> {
         self.repo.find_pet_by_id(pet_id).await
     }
+
+    /// Find the current version of a pet.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [CreatePetError] returned by the [PetRepository].
+    async fn find_pet_version(&self, pet_id: i64) -> Result<Option<i64>, CreatePetError> {
+        self.repo.find_pet_version(pet_id).await
+    }
+
+    /// List a page of pets.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [ListPetsError] returned by the [PetRepository].
+    async fn list_pets(&self, req: &ListPetsRequest) -> Result<PetPage, ListPetsError> {
+        self.repo.list_pets(req).await
+    }
+
+    /// Search pets, highlighting the matches in the fields of each found pet.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [SearchPetsError] returned by the [PetRepository].
+    async fn search_pets(&self, req: &SearchPetsRequest) -> Result<Vec<PetSearchHit>, SearchPetsError> {
+        let found = self.repo.search_pets(req).await?;
+        Ok(found
+            .into_iter()
+            .map(|found| PetSearchHit::new(found, &req.query))
+            .collect())
+    }
+
+    /// Move a pet to the status in `req` if the pet lifecycle allows it, and emit
+    /// [PetEvent::PetStatusChanged].
+    ///
+    /// # Errors:
+    ///
+    /// - [ChangePetStatusError::NotFound] if no [Pet] has the requested id.
+    /// - [ChangePetStatusError::Conflict] if the pet is no longer at the expected version.
+    /// - [ChangePetStatusError::InvalidTransition] if the lifecycle forbids the change.
+    /// - Propagates any other error returned by the [PetRepository].
+    async fn change_pet_status(&self, req: &ChangePetStatusRequest) -> Result<Pet, ChangePetStatusError> {
+        let mut pet = self
+            .repo
+            .find_pet_by_id(req.pet_id)
+            .await
+            .map_err(|e| ChangePetStatusError::Unknown(anyhow::anyhow!(e)))?
+            .ok_or(ChangePetStatusError::NotFound { id: req.pet_id })?;
+
+        if let Some(expected) = req.expected_version {
+            if expected != pet.version {
+                return Err(ChangePetStatusError::Conflict {
+                    id: req.pet_id,
+                    expected,
+                    actual: pet.version,
+                });
+            }
+        }
+
+        let from = pet.status.clone().unwrap_or_default();
+        pet.transition_to(req.status.clone())?;
+        // Pin the update to the version the transition was validated against
+        let req = req.clone().with_expected_version(Some(pet.version));
+        let event = PetEvent::PetStatusChanged {
+            pet_id: req.pet_id,
+            from: from.clone(),
+            to: req.status.clone(),
+            actor: req.actor.clone(),
+            version: pet.version + 1,
+        };
+        let pet = self.repo.update_pet_status(&req, &from, std::slice::from_ref(&event)).await?;
+        self.notify(event, &pet);
+        Ok(pet)
+    }
+
+    /// Delete a pet and emit [PetEvent::PetDeleted]. Live subscribers receive the pet as it
+    /// was before the deletion.
+    ///
+    /// # Errors:
+    ///
+    /// - [DeletePetError::NotFound] if no [Pet] has the requested id.
+    /// - Propagates any other error returned by the [PetRepository].
+    async fn delete_pet(&self, pet_id: i64) -> Result<(), DeletePetError> {
+        let pet = self
+            .repo
+            .find_pet_by_id(pet_id)
+            .await
+            .map_err(|e| DeletePetError::Unknown(anyhow::anyhow!(e)))?
+            .ok_or(DeletePetError::NotFound { id: pet_id })?;
+
+        let event = PetEvent::PetDeleted { pet_id };
+        self.repo.delete_pet(pet_id, std::slice::from_ref(&event)).await?;
+        self.notify(event, &pet);
+        Ok(())
+    }
+
+    /// The lifecycle audit trail of a pet.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [PetHistoryError] returned by the [PetRepository].
+    async fn pet_status_history(&self, pet_id: i64) -> Result<Vec<StatusChange>, PetHistoryError> {
+        self.repo.pet_status_history(pet_id).await
+    }
+}
+
+impl<R, G> PetChangeService for Service<R, G>
+where
+    R: PetRepository,
+    G: IdGenerator,
+{
+    fn subscribe_to_pet_changes(&self) -> broadcast::Receiver<PetChange> {
+        self.changes.subscribe()
+    }
+}
+
+impl<R, G> PetTransferService for Service<R, G>
+where
+    R: PetRepository + CategoryRepository,
+    G: IdGenerator,
+{
+    /// Validate every row with the pet value objects and create the valid pets with
+    /// [PetService::add_pet], emitting their events. A dry run also reports the rows whose name
+    /// or id is taken, by a stored pet or an earlier row, or whose category does not exist.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [ImportPetsError] yielded by `rows`.
+    /// - [ImportPetsError::Unknown] if a dry run cannot look up stored pets or categories, or
+    ///   a pet cannot be stored for a reason other than its row. The rows before it stay
+    ///   imported.
+    async fn import_pets<S>(&self, rows: S, dry_run: bool) -> Result<ImportReport, ImportPetsError>
+    where
+        S: Stream<Item = Result<ImportRow, ImportPetsError>> + Send,
+    {
+        let mut rows = std::pin::pin!(rows);
+        let mut report = ImportReport::new(dry_run);
+        let mut seen = ImportedKeys::default();
+        while let Some(row) = rows.next().await {
+            let ImportRow { line, record } = row?;
+            let name = record.as_ref().ok().map(|record| record.name.clone());
+            let req = record
+                .map_err(|malformed| {
+                    let mut errors = ValidationErrors::new();
+                    errors.add("record", &malformed);
+                    errors
+                })
+                .and_then(PetRecord::try_into_request);
+            let errors = match req {
+                Ok(req) if dry_run => self.check_importable(&req, &mut seen).await?,
+                Ok(req) => match self.add_pet(&req).await {
+                    Ok(_) => ValidationErrors::new(),
+                    // The store is failing, not the row: stop rather than fail every row after it
+                    Err(CreatePetError::Unknown(cause)) => return Err(ImportPetsError::Unknown(cause)),
+                    Err(e) => {
+                        let mut errors = ValidationErrors::new();
+                        errors.add(import_error_field(&e), &e);
+                        errors
+                    }
+                },
+                Err(errors) => errors,
+            };
+            report.record(line, name, errors);
+        }
+        Ok(report)
+    }
+
+    fn export_pets(&self) -> impl Stream<Item = Result<PetRecord, ListPetsError>> + Send + 'static {
+        let repo = self.repo.clone();
+        let first = ListPetsRequest {
+            limit: EXPORT_PAGE_SIZE,
+            ..Default::default()
+        };
+        futures::stream::unfold(Some(first), move |req| {
+            let repo = repo.clone();
+            async move {
+                let mut req = req?;
+                match repo.list_pets(&req).await {
+                    Ok(page) => {
+                        let next = page.next_cursor.map(|cursor| {
+                            req.cursor = Some(cursor);
+                            req
+                        });
+                        let records: Vec<_> = page.pets.iter().map(|pet| Ok(PetRecord::from(pet))).collect();
+                        Some((futures::stream::iter(records), next))
+                    }
+                    Err(e) => Some((futures::stream::iter(vec![Err(e)]), None)),
+                }
+            }
+        })
+        .flatten()
+    }
+}
+
+/// The names and ids taken by the earlier rows of a dry-run import.
+#[derive(Debug, Default)]
+struct ImportedKeys {
+    names: HashSet<String>,
+    ids: HashSet<i64>,
+}
+
+impl<R, G> Service<R, G>
+where
+    R: PetRepository + CategoryRepository,
+    G: IdGenerator,
+{
+    /// The reasons [PetService::add_pet] would fail for the valid `req`, if any, given the
+    /// stored pets and categories and the rows seen so far.
+    async fn check_importable(&self, req: &CreatePetRequest, seen: &mut ImportedKeys) -> Result<ValidationErrors, ImportPetsError> {
+        let mut errors = ValidationErrors::new();
+        let stored_name = self
+            .repo
+            .find_pet_id_by_name(&req.name)
+            .await
+            .map_err(|e| ImportPetsError::Unknown(anyhow::anyhow!(e)))?;
+        if !seen.names.insert(req.name.clone()) || stored_name.is_some() {
+            errors.add("name", &CreatePetError::Duplicate { name: req.name.clone() });
+        }
+        if let Some(id) = req.id {
+            let stored_id = self
+                .repo
+                .find_pet_version(id)
+                .await
+                .map_err(|e| ImportPetsError::Unknown(anyhow::anyhow!(e)))?;
+            if !seen.ids.insert(id) || stored_id.is_some() {
+                errors.add("id", &CreatePetError::DuplicateId { id });
+            }
+        }
+        if let Some(id) = req.category.as_ref().and_then(|c| c.id) {
+            let category = self
+                .repo
+                .find_category_by_id(id)
+                .await
+                .map_err(|e| ImportPetsError::Unknown(anyhow::anyhow!(e)))?;
+            if category.is_none() {
+                errors.add("category_id", &CreatePetError::UnknownCategory { id });
+            }
+        }
+        Ok(errors)
+    }
+}
+
+/// The field of a [PetRecord] that a [CreatePetError] of an import is about.
+fn import_error_field(e: &CreatePetError) -> &'static str {
+    match e {
+        CreatePetError::Duplicate { .. } => "name",
+        CreatePetError::IdRequired { kind: IdKind::Tag } | CreatePetError::DuplicateTagId { .. } => "tags",
+        CreatePetError::DuplicateId { .. } | CreatePetError::IdRequired { .. } => "id",
+        CreatePetError::UnknownCategory { .. } | CreatePetError::CategoryIdRequired => "category_id",
+        // Not reported by row, but ends the import
+        CreatePetError::Unknown(_) => "record",
+    }
+}
+
+impl<R, G> CategoryService for Service<R, G>
+where
+    R: PetRepository + CategoryRepository,
+    G: IdGenerator,
+{
+    /// Create the [Category] specified in `req`, choosing its id with the [IdGenerator] if it
+    /// has none.
+    ///
+    /// # Errors:
+    ///
+    /// - [CreateCategoryError::IdRequired] if the id is missing and ids must be supplied by
+    ///   clients.
+    /// - Propagates any [CreateCategoryError] returned by the [CategoryRepository].
+    async fn create_category(&self, req: &CreateCategoryRequest) -> Result<Category, CreateCategoryError> {
+        let mut req = req.clone();
+        if req.id.is_none() {
+            req.id = self.ids.next_id(IdKind::Category).await?;
+        }
+        self.repo.create_category(&req).await
+    }
+
+    async fn list_categories(&self) -> Result<Vec<Category>, FindCategoryError> {
+        self.repo.list_categories().await
+    }
+
+    async fn find_category_by_id(&self, category_id: i64) -> Result<Option<Category>, FindCategoryError> {
+        self.repo.find_category_by_id(category_id).await
+    }
+
+    /// Rename the [Category] specified in `req`.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [RenameCategoryError] returned by the [CategoryRepository].
+    async fn rename_category(&self, req: &RenameCategoryRequest) -> Result<Category, RenameCategoryError> {
+        self.repo.rename_category(req).await
+    }
+
+    /// Delete the [Category] with the given id.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [DeleteCategoryError] returned by the [CategoryRepository].
+    async fn delete_category(&self, category_id: i64) -> Result<(), DeleteCategoryError> {
+        self.repo.delete_category(category_id).await
+    }
+}
+
+impl<R, G> TagService for Service<R, G>
+where
+    R: PetRepository + TagRepository,
+    G: IdGenerator,
+{
+    async fn list_tags(&self) -> Result<Vec<TagUsage>, ListTagsError> {
+        self.repo.list_tags().await
+    }
+
+    /// Rename the [Tag] specified in `req`.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [RenameTagError] returned by the [TagRepository].
+    async fn rename_tag(&self, req: &RenameTagRequest) -> Result<Tag, RenameTagError> {
+        self.repo.rename_tag(req).await
+    }
+
+    /// Merge the source [Tag] of `req` into its target.
+    ///
+    /// # Errors:
+    ///
+    /// - [MergeTagsError::SameTag] if source and target are the same [Tag].
+    /// - Propagates any [MergeTagsError] returned by the [TagRepository].
+    async fn merge_tags(&self, req: &MergeTagsRequest) -> Result<TagUsage, MergeTagsError> {
+        if req.source_id == req.target_id {
+            return Err(MergeTagsError::SameTag { id: req.source_id });
+        }
+        self.repo.merge_tags(req).await
+    }
+
+    /// Delete the unused [Tag] with the given id.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [DeleteTagError] returned by the [TagRepository].
+    async fn delete_tag(&self, tag_id: i64) -> Result<(), DeleteTagError> {
+        self.repo.delete_tag(tag_id).await
+    }
+
+    async fn delete_unused_tags(&self) -> Result<Vec<Tag>, DeleteTagError> {
+        self.repo.delete_unused_tags().await
+    }
+}
+
+impl<R, G> IdempotencyService for Service<R, G>
+where
+    R: PetRepository + IdempotencyRepository,
+    G: IdGenerator,
+{
+    /// Claim the idempotency key of `req`.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [IdempotencyError] returned by the [IdempotencyRepository].
+    async fn claim_idempotency_key(&self, req: &ClaimIdempotencyKeyRequest) -> Result<IdempotencyClaim, IdempotencyError> {
+        self.repo.claim_idempotency_key(req).await
+    }
+
+    async fn complete_idempotency_key(&self, claim: &ClaimIdempotencyKeyRequest, response: &IdempotentResponse, window: Duration) -> Result<(), IdempotencyError> {
+        self.repo.complete_idempotency_key(claim, response, window).await
+    }
+
+    async fn release_idempotency_key(&self, claim: &ClaimIdempotencyKeyRequest) -> Result<(), IdempotencyError> {
+        self.repo.release_idempotency_key(claim).await
+    }
+
+    async fn purge_expired_idempotency_keys(&self) -> Result<u64, IdempotencyError> {
+        self.repo.purge_expired_idempotency_keys().await
+    }
+}
+
+impl From<GenerateIdError> for CreatePetError {
+    fn from(e: GenerateIdError) -> Self {
+        match e {
+            GenerateIdError::Required { kind } => Self::IdRequired { kind },
+            GenerateIdError::Unknown(cause) => Self::Unknown(cause),
+        }
+    }
+}
+
+impl From<GenerateIdError> for CreateCategoryError {
+    fn from(e: GenerateIdError) -> Self {
+        match e {
+            GenerateIdError::Required { kind } => Self::IdRequired { kind },
+            GenerateIdError::Unknown(cause) => Self::Unknown(cause),
+        }
+    }
+}
+
+impl<R, G> WebhookService for Service<R, G>
+where
+    R: PetRepository + WebhookRepository,
+    G: IdGenerator,
+{
+    async fn create_webhook(&self, req: &CreateWebhookRequest) -> Result<Webhook, CreateWebhookError> {
+        self.repo.create_webhook(req).await
+    }
+
+    async fn list_webhooks(&self) -> Result<Vec<Webhook>, FindWebhookError> {
+        self.repo.list_webhooks().await
+    }
+
+    async fn find_webhook(&self, webhook_id: i64) -> Result<Option<Webhook>, FindWebhookError> {
+        self.repo.find_webhook(webhook_id).await
+    }
+
+    /// Delete the [Webhook] with the given id. Its pending deliveries are dropped.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [DeleteWebhookError] returned by the [WebhookRepository].
+    async fn delete_webhook(&self, webhook_id: i64) -> Result<(), DeleteWebhookError> {
+        self.repo.delete_webhook(webhook_id).await
+    }
+
+    async fn webhook_deliveries(&self, webhook_id: i64) -> Result<Vec<WebhookDelivery>, WebhookDeliveryError> {
+        self.repo.list_deliveries(webhook_id).await
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
+    use std::sync::atomic::{AtomicBool, Ordering};
     use std::sync::{Arc, Mutex};
-    use crate::domain::petstore::models::pet::Status;
-    use crate::domain::petstore::models::category::Category;
+    use crate::domain::petstore::models::pet::{InvalidTransition, PetCursor, PetSortField, SortDirection, Status};
+    use crate::domain::petstore::models::idempotency::IdempotencyKey;
+    use crate::domain::petstore::models::id::IdStrategy;
+    use crate::domain::petstore::models::search::{
+        search_by_substring, MatchSpan, PetMatch, SearchField, SearchQuery,
+    };
+    use crate::domain::petstore::models::transfer::MalformedRecord;
     use crate::domain::petstore::models::tag::Tag;
+    use std::time::Instant;
+
+    /// A claimed idempotency key: the request fingerprint, the claim token, the stored response
+    /// and the expiry.
+    type IdempotencyRecord = (String, String, Option<IdempotentResponse>, Instant);
 
-    // Mock implementation of PetRepository for testing
+    // Mock implementation of PetRepository and CategoryRepository for testing
     #[derive(Debug, Clone)]
     struct MockRepository {
         pets: Arc<Mutex<HashMap<String, Pet>>>,
+        categories: Arc<Mutex<BTreeMap<i64, Category>>>,
+        history: Arc<Mutex<Vec<StatusChange>>>,
+        idempotency_keys: Arc<Mutex<HashMap<IdempotencyKey, IdempotencyRecord>>>,
+        events: Arc<Mutex<Vec<PetEvent>>>,
+        /// Fails the pets added, as an unreachable store would.
+        unavailable: Arc<AtomicBool>,
     }
 
     impl MockRepository {
         fn new() -> Self {
             Self {
                 pets: Arc::new(Mutex::new(HashMap::new())),
+                categories: Arc::new(Mutex::new(BTreeMap::new())),
+                history: Arc::new(Mutex::new(Vec::new())),
+                idempotency_keys: Arc::new(Mutex::new(HashMap::new())),
+                events: Arc::new(Mutex::new(Vec::new())),
+                unavailable: Arc::new(AtomicBool::new(false)),
+            }
+        }
+
+        fn record(&self, pet_id: Option<i64>, from: Option<Status>, to: Status, actor: Option<String>) {
+            if let Some(pet_id) = pet_id {
+                self.history.lock().unwrap().push(StatusChange {
+                    pet_id,
+                    from,
+                    to,
+                    actor,
+                    changed_at: chrono::Utc::now(),
+                });
             }
         }
     }
 
     impl PetRepository for MockRepository {
-        async fn add_pet(&self, req: &CreatePetRequest) -> Result<Pet, CreatePetError> {
+        async fn add_pet(
+            &self,
+            req: &CreatePetRequest,
+            events: impl FnOnce(&Pet) -> Vec<PetEvent> + Send,
+        ) -> Result<Pet, CreatePetError> {
+            if self.unavailable.load(Ordering::SeqCst) {
+                return Err(CreatePetError::Unknown(anyhow::anyhow!("pool timed out: password=hunter2")));
+            }
             let mut pets = self.pets.lock().unwrap();
             
             // Check for duplicate
@@ -84,12 +633,20 @@ mod tests {
                     name: req.name.clone() 
                 });
             }
+            if let Some(id) = req.id.filter(|id| pets.values().any(|p| p.id == Some(*id))) {
+                return Err(CreatePetError::DuplicateId { id });
+            }
+            let stored_tags: Vec<Tag> = pets.values().flat_map(|p| p.tags.iter().cloned()).collect();
+            for tag in &req.tags {
+                if let Some(id) = tag.id.filter(|id| stored_tags.iter().any(|t| t.id == Some(*id) && t.name != tag.name)) {
+                    return Err(CreatePetError::DuplicateTagId { id });
+                }
+            }
 
             // Create new pet
             let mut pet = Pet::new(req.name.clone());
-            if let Some(id) = req.id {
-                pet.id = Some(id);
-            }
+            // Like a sequence, assign the next id if the request has none
+            pet.id = req.id.or_else(|| Some(pets.values().filter_map(|p| p.id).max().unwrap_or(0) + 1));
             if let Some(category) = &req.category {
                 pet.set_category(category.clone());
             }
@@ -97,12 +654,15 @@ mod tests {
                 pet.add_photo(url.clone());
             }
             for tag in &req.tags {
-                pet.add_tag(tag.clone());
+                let stored = stored_tags.iter().find(|t| tag.id.is_none() && t.name == tag.name);
+                pet.add_tag(stored.unwrap_or(tag).clone());
             }
             if let Some(status) = &req.status {
                 pet.set_status(status.clone());
             }
             pets.insert(req.name.clone(), pet.clone());
+            self.record(pet.id, None, pet.status.clone().unwrap_or_default(), None);
+            self.events.lock().unwrap().extend(events(&pet));
             Ok(pet)
         }
 
@@ -111,6 +671,279 @@ mod tests {
             let pet = pets.values().find(|p| p.id == Some(pet_id)).cloned();
             Ok(pet)
         }
+
+        async fn find_pet_version(&self, pet_id: i64) -> Result<Option<i64>, CreatePetError> {
+            let pets = self.pets.lock().unwrap();
+            Ok(pets.values().find(|p| p.id == Some(pet_id)).map(|p| p.version))
+        }
+
+        async fn find_pet_id_by_name(&self, name: &str) -> Result<Option<i64>, CreatePetError> {
+            let pets = self.pets.lock().unwrap();
+            Ok(pets.get(name).and_then(|p| p.id))
+        }
+
+        async fn list_pets(&self, req: &ListPetsRequest) -> Result<PetPage, ListPetsError> {
+            let pets = self.pets.lock().unwrap();
+            let key = |p: &Pet| match req.sort {
+                PetSortField::Id => (String::new(), p.id.unwrap_or_default()),
+                PetSortField::Name => (p.name.clone(), p.id.unwrap_or_default()),
+            };
+
+            let mut matching: Vec<Pet> = pets
+                .values()
+                .filter(|p| req.status.is_none() || p.status == req.status)
+                .filter(|p| {
+                    req.category_id.is_none()
+                        || p.category.as_ref().and_then(|c| c.id) == req.category_id
+                })
+                .filter(|p| {
+                    req.cursor.as_ref().is_none_or(|c| {
+                        let cursor_key = key(&Pet::with_id(c.id, c.name.clone()));
+                        match req.direction {
+                            SortDirection::Asc => key(p) > cursor_key,
+                            SortDirection::Desc => key(p) < cursor_key,
+                        }
+                    })
+                })
+                .cloned()
+                .collect();
+            matching.sort_by_key(key);
+            if req.direction == SortDirection::Desc {
+                matching.reverse();
+            }
+            matching.truncate(req.limit as usize + 1);
+            Ok(PetPage::from_overfetched(matching, req.limit))
+        }
+
+        async fn search_pets(&self, req: &SearchPetsRequest) -> Result<Vec<PetMatch>, SearchPetsError> {
+            let pets = self.pets.lock().unwrap();
+            Ok(search_by_substring(pets.values(), req))
+        }
+
+        async fn update_pet_status(
+            &self,
+            req: &ChangePetStatusRequest,
+            from: &Status,
+            events: &[PetEvent],
+        ) -> Result<Pet, ChangePetStatusError> {
+            let mut pets = self.pets.lock().unwrap();
+            let pet = pets
+                .values_mut()
+                .find(|p| p.id == Some(req.pet_id))
+                .ok_or(ChangePetStatusError::NotFound { id: req.pet_id })?;
+            if let Some(expected) = req.expected_version {
+                if expected != pet.version {
+                    return Err(ChangePetStatusError::Conflict { id: req.pet_id, expected, actual: pet.version });
+                }
+            }
+            let current = pet.status.clone().unwrap_or_default();
+            if &current != from {
+                return Err(InvalidTransition { from: current, to: req.status.clone() }.into());
+            }
+            pet.set_status(req.status.clone());
+            pet.version += 1;
+            self.record(pet.id, Some(current), req.status.clone(), req.actor.clone());
+            self.events.lock().unwrap().extend_from_slice(events);
+            Ok(pet.clone())
+        }
+
+        async fn delete_pet(&self, pet_id: i64, events: &[PetEvent]) -> Result<(), DeletePetError> {
+            let mut pets = self.pets.lock().unwrap();
+            let name = pets
+                .iter()
+                .find(|(_, p)| p.id == Some(pet_id))
+                .map(|(name, _)| name.clone())
+                .ok_or(DeletePetError::NotFound { id: pet_id })?;
+            pets.remove(&name);
+            self.history.lock().unwrap().retain(|c| c.pet_id != pet_id);
+            self.events.lock().unwrap().extend_from_slice(events);
+            Ok(())
+        }
+
+        async fn pet_status_history(&self, pet_id: i64) -> Result<Vec<StatusChange>, PetHistoryError> {
+            let history: Vec<StatusChange> = self
+                .history
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|c| c.pet_id == pet_id)
+                .cloned()
+                .collect();
+            if history.is_empty() {
+                return Err(PetHistoryError::NotFound { id: pet_id });
+            }
+            Ok(history)
+        }
+    }
+
+    impl CategoryRepository for MockRepository {
+        async fn create_category(&self, req: &CreateCategoryRequest) -> Result<Category, CreateCategoryError> {
+            let mut categories = self.categories.lock().unwrap();
+            if categories.values().any(|c| c.name.as_deref() == Some(req.name.as_str())) {
+                return Err(CreateCategoryError::Duplicate { name: req.name.clone() });
+            }
+            let id = req.id.unwrap_or_else(|| categories.keys().last().map_or(1, |id| id + 1));
+            if categories.contains_key(&id) {
+                return Err(CreateCategoryError::DuplicateId { id });
+            }
+            let category = Category::with_values(id, req.name.clone());
+            categories.insert(id, category.clone());
+            Ok(category)
+        }
+
+        async fn list_categories(&self) -> Result<Vec<Category>, FindCategoryError> {
+            Ok(self.categories.lock().unwrap().values().cloned().collect())
+        }
+
+        async fn find_category_by_id(&self, category_id: i64) -> Result<Option<Category>, FindCategoryError> {
+            Ok(self.categories.lock().unwrap().get(&category_id).cloned())
+        }
+
+        async fn rename_category(&self, req: &RenameCategoryRequest) -> Result<Category, RenameCategoryError> {
+            let mut categories = self.categories.lock().unwrap();
+            if categories.values().any(|c| c.id != Some(req.id) && c.name.as_deref() == Some(req.name.as_str())) {
+                return Err(RenameCategoryError::Duplicate { name: req.name.clone() });
+            }
+            let category = categories
+                .get_mut(&req.id)
+                .ok_or(RenameCategoryError::NotFound { id: req.id })?;
+            category.name = Some(req.name.clone());
+            Ok(category.clone())
+        }
+
+        async fn delete_category(&self, category_id: i64) -> Result<(), DeleteCategoryError> {
+            let in_use = self
+                .pets
+                .lock()
+                .unwrap()
+                .values()
+                .any(|p| p.category.as_ref().and_then(|c| c.id) == Some(category_id));
+            if in_use {
+                return Err(DeleteCategoryError::InUse { id: category_id });
+            }
+            self.categories
+                .lock()
+                .unwrap()
+                .remove(&category_id)
+                .map(|_| ())
+                .ok_or(DeleteCategoryError::NotFound { id: category_id })
+        }
+    }
+
+    impl MockRepository {
+        fn tag_usage(&self) -> BTreeMap<i64, TagUsage> {
+            let pets = self.pets.lock().unwrap();
+            let mut usage = BTreeMap::new();
+            for tag in pets.values().flat_map(|p| p.tags.iter()) {
+                if let Some(id) = tag.id {
+                    usage
+                        .entry(id)
+                        .or_insert_with(|| TagUsage::new(tag.clone(), 0))
+                        .pet_count += 1;
+                }
+            }
+            usage
+        }
+
+        fn retag(&self, from: i64, to: Option<&Tag>) {
+            let mut pets = self.pets.lock().unwrap();
+            for pet in pets.values_mut() {
+                let had_source = pet.tags.iter().any(|t| t.id == Some(from));
+                pet.tags.retain(|t| t.id != Some(from));
+                if let Some(to) = to {
+                    if had_source && !pet.tags.iter().any(|t| t.id == to.id) {
+                        pet.tags.push(to.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    impl IdempotencyRepository for MockRepository {
+        async fn claim_idempotency_key(&self, req: &ClaimIdempotencyKeyRequest) -> Result<IdempotencyClaim, IdempotencyError> {
+            let mut keys = self.idempotency_keys.lock().unwrap();
+            let now = Instant::now();
+            match keys.get(&req.key) {
+                Some((fingerprint, _, _, expires_at)) if *expires_at > now && fingerprint != &req.fingerprint => {
+                    Ok(IdempotencyClaim::Mismatch)
+                }
+                Some((_, _, Some(response), expires_at)) if *expires_at > now => {
+                    Ok(IdempotencyClaim::Completed(response.clone()))
+                }
+                Some((_, _, None, expires_at)) if *expires_at > now => Ok(IdempotencyClaim::InProgress),
+                _ => {
+                    let record = (req.fingerprint.clone(), req.token.clone(), None, now + req.lease);
+                    keys.insert(req.key.clone(), record);
+                    Ok(IdempotencyClaim::Claimed)
+                }
+            }
+        }
+
+        async fn complete_idempotency_key(&self, claim: &ClaimIdempotencyKeyRequest, response: &IdempotentResponse, window: Duration) -> Result<(), IdempotencyError> {
+            match self.idempotency_keys.lock().unwrap().get_mut(&claim.key) {
+                Some(record) if record.1 == claim.token && record.2.is_none() => {
+                    record.2 = Some(response.clone());
+                    record.3 = Instant::now() + window;
+                    Ok(())
+                }
+                _ => Err(IdempotencyError::ClaimLost { key: claim.key.clone() }),
+            }
+        }
+
+        async fn release_idempotency_key(&self, claim: &ClaimIdempotencyKeyRequest) -> Result<(), IdempotencyError> {
+            let mut keys = self.idempotency_keys.lock().unwrap();
+            if keys.get(&claim.key).is_some_and(|record| record.1 == claim.token && record.2.is_none()) {
+                keys.remove(&claim.key);
+            }
+            Ok(())
+        }
+
+        async fn purge_expired_idempotency_keys(&self) -> Result<u64, IdempotencyError> {
+            let mut keys = self.idempotency_keys.lock().unwrap();
+            let before = keys.len();
+            let now = Instant::now();
+            keys.retain(|_, (_, _, _, expires_at)| *expires_at > now);
+            Ok((before - keys.len()) as u64)
+        }
+    }
+
+    impl TagRepository for MockRepository {
+        async fn list_tags(&self) -> Result<Vec<TagUsage>, ListTagsError> {
+            Ok(self.tag_usage().into_values().collect())
+        }
+
+        async fn rename_tag(&self, req: &RenameTagRequest) -> Result<Tag, RenameTagError> {
+            let usage = self.tag_usage();
+            if !usage.contains_key(&req.id) {
+                return Err(RenameTagError::NotFound { id: req.id });
+            }
+            let renamed = Tag::with_values(req.id, req.name.clone());
+            self.retag(req.id, Some(&renamed));
+            Ok(renamed)
+        }
+
+        async fn merge_tags(&self, req: &MergeTagsRequest) -> Result<TagUsage, MergeTagsError> {
+            let usage = self.tag_usage();
+            for id in [req.source_id, req.target_id] {
+                if !usage.contains_key(&id) {
+                    return Err(MergeTagsError::NotFound { id });
+                }
+            }
+            self.retag(req.source_id, Some(&usage[&req.target_id].tag));
+            Ok(self.tag_usage().remove(&req.target_id).unwrap())
+        }
+
+        async fn delete_tag(&self, tag_id: i64) -> Result<(), DeleteTagError> {
+            // Tags only exist through the pets they are attached to in this mock
+            match self.tag_usage().get(&tag_id) {
+                Some(usage) => Err(DeleteTagError::InUse { id: tag_id, pet_count: usage.pet_count }),
+                None => Err(DeleteTagError::NotFound { id: tag_id }),
+            }
+        }
+
+        async fn delete_unused_tags(&self) -> Result<Vec<Tag>, DeleteTagError> {
+            Ok(Vec::new())
+        }
     }
 
     #[tokio::test]
@@ -141,13 +974,63 @@ mod tests {
         
         let pet = result.unwrap();
         assert_eq!(pet.name, "Buddy");
-        assert!(pet.id.is_none());
+        // Assigned by the store, as the default strategy leaves ids to its sequence
+        assert_eq!(pet.id, Some(1));
         assert!(pet.category.is_none());
         assert!(pet.photo_urls.is_empty());
         assert!(pet.tags.is_empty());
         assert!(matches!(pet.status, Some(crate::domain::petstore::models::pet::Status::Available)));
     }
 
+    #[tokio::test]
+    async fn test_service_add_pet_generates_ids() {
+        let ids = StrategyIdGenerator::new(IdStrategy::Snowflake, 1).unwrap();
+        let service = Service::with_id_generator(MockRepository::new(), ids);
+        let tags = vec![Tag { id: None, name: Some(String::from("friendly")) }, Tag::with_values(7, String::from("calm"))];
+
+        let pet = service
+            .add_pet(&CreatePetRequest::new(None, String::from("Buddy"), None, Vec::new(), tags, None))
+            .await
+            .unwrap();
+
+        assert!(pet.id.is_some_and(|id| id > 7));
+        assert!(pet.tags[0].id.is_some_and(|id| id > pet.id.unwrap()));
+        assert_eq!(pet.tags[1].id, Some(7));
+    }
+
+    #[tokio::test]
+    async fn test_service_client_supplied_ids() {
+        let ids = StrategyIdGenerator::new(IdStrategy::ClientSupplied, 0).unwrap();
+        let service = Service::with_id_generator(MockRepository::new(), ids);
+
+        let missing = service
+            .add_pet(&CreatePetRequest::new(None, String::from("Buddy"), None, Vec::new(), Vec::new(), None))
+            .await;
+        assert!(matches!(missing, Err(CreatePetError::IdRequired { kind: IdKind::Pet })));
+
+        let supplied = service
+            .add_pet(&CreatePetRequest::new(Some(42), String::from("Buddy"), None, Vec::new(), Vec::new(), None))
+            .await
+            .unwrap();
+        assert_eq!(supplied.id, Some(42));
+
+        let category = service.create_category(&CreateCategoryRequest::new(None, String::from("Dogs"))).await;
+        assert!(matches!(category, Err(CreateCategoryError::IdRequired { kind: IdKind::Category })));
+
+        // Existing tags are found by name, without an id
+        let calm = Tag::with_values(3, String::from("calm"));
+        service
+            .add_pet(&CreatePetRequest::new(Some(43), String::from("Rex"), None, Vec::new(), vec![calm.clone()], None))
+            .await
+            .unwrap();
+        let tags = vec![Tag { id: None, name: Some(String::from("calm")) }];
+        let tagged = service
+            .add_pet(&CreatePetRequest::new(Some(44), String::from("Luna"), None, Vec::new(), tags, None))
+            .await
+            .unwrap();
+        assert_eq!(tagged.tags, vec![calm]);
+    }
+
     #[tokio::test]
     async fn test_service_add_pet_duplicate() {
         let repo = MockRepository::new();
@@ -286,4 +1169,575 @@ mod tests {
         assert_eq!(found.tags, tags);
         assert_eq!(found.status, Some(Status::Available));
     }
-}
\ No newline at end of file
+
+    async fn add_named_pets(service: &Service<MockRepository>, names: &[&str]) {
+        for (i, name) in names.iter().enumerate() {
+            let request = CreatePetRequest::new(
+                Some(i as i64 + 1),
+                name.to_string(),
+                None,
+                Vec::new(),
+                Vec::new(),
+                None,
+            );
+            service.add_pet(&request).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_service_list_pets_paginates_by_id() {
+        let service = Service::new(MockRepository::new());
+        add_named_pets(&service, &["Rex", "Buddy", "Luna"]).await;
+
+        let mut request = ListPetsRequest {
+            limit: 2,
+            ..Default::default()
+        };
+        let first = service.list_pets(&request).await.unwrap();
+        let ids: Vec<Option<i64>> = first.pets.iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![Some(1), Some(2)]);
+        assert_eq!(first.next_cursor, Some(PetCursor::new(2, "Buddy".to_string())));
+
+        request.cursor = first.next_cursor;
+        let second = service.list_pets(&request).await.unwrap();
+        let ids: Vec<Option<i64>> = second.pets.iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![Some(3)]);
+        assert!(second.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_service_list_pets_sorted_by_name_desc() {
+        let service = Service::new(MockRepository::new());
+        add_named_pets(&service, &["Rex", "Buddy", "Luna"]).await;
+
+        let request = ListPetsRequest::new(
+            PetSortField::Name,
+            SortDirection::Desc,
+            None,
+            None,
+            10,
+            None,
+        );
+        let page = service.list_pets(&request).await.unwrap();
+        let names: Vec<&str> = page.pets.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["Rex", "Luna", "Buddy"]);
+        assert!(page.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_service_search_pets() {
+        let service = Service::new(MockRepository::new());
+        add_named_pets(&service, &["Rex", "Buddy", "Budgie", "Luna"]).await;
+
+        let request = SearchPetsRequest::new(SearchQuery::new("BUD").unwrap(), 10);
+        let hits = service.search_pets(&request).await.unwrap();
+
+        let names: Vec<&str> = hits.iter().map(|h| h.pet.name.as_str()).collect();
+        assert_eq!(names, vec!["Buddy", "Budgie"]);
+        assert_eq!(hits[0].highlights[0].field, SearchField::Name);
+        assert_eq!(hits[0].highlights[0].spans, vec![MatchSpan { start: 0, end: 3 }]);
+    }
+
+    fn import_rows() -> Vec<Result<ImportRow, ImportPetsError>> {
+        let record = |name: &str, category_id: i64| PetRecord {
+            name: name.to_string(),
+            category_id: Some(category_id),
+            photo_urls: vec![format!("https://example.com/{}.jpg", name)],
+            ..Default::default()
+        };
+        let rows = [
+            (2, Ok(record("Buddy", 1))),
+            (3, Ok(record("Rex", 1))),
+            (4, Ok(record("Buddy", 1))),
+            (5, Err(MalformedRecord("expected 7 fields, found 2".to_string()))),
+            (6, Ok(PetRecord { photo_urls: Vec::new(), ..record("Luna", 1) })),
+            (7, Ok(record("Milo", 9))),
+        ];
+        rows.into_iter()
+            .map(|(line, record)| Ok(ImportRow { line, record }))
+            .collect()
+    }
+
+    async fn import_fixture() -> Service<MockRepository> {
+        let service = Service::new(MockRepository::new());
+        service
+            .create_category(&CreateCategoryRequest::new(Some(1), "Dogs".to_string()))
+            .await
+            .unwrap();
+        add_named_pets(&service, &["Rex"]).await;
+        service
+    }
+
+    fn failed_fields(report: &ImportReport) -> Vec<(u64, String, &'static str)> {
+        report
+            .failures
+            .iter()
+            .flat_map(|f| f.errors.iter().map(move |e| (f.line, e.field.clone(), e.code)))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_service_import_pets_dry_run() {
+        let service = import_fixture().await;
+
+        let report = service
+            .import_pets(futures::stream::iter(import_rows()), true)
+            .await
+            .unwrap();
+
+        assert!(report.dry_run);
+        assert_eq!((report.rows, report.imported), (6, 1));
+        assert_eq!(
+            failed_fields(&report),
+            vec![
+                (3, "name".to_string(), "duplicate"),
+                (4, "name".to_string(), "duplicate"),
+                (5, "record".to_string(), "malformed"),
+                (6, "photo_urls".to_string(), "empty"),
+                (7, "category_id".to_string(), "unknown-category"),
+            ]
+        );
+        // Nothing was created
+        assert!(!service.repo.pets.lock().unwrap().contains_key("Buddy"));
+    }
+
+    #[tokio::test]
+    async fn test_service_import_pets() {
+        let service = import_fixture().await;
+        let rows: Vec<_> = import_rows().into_iter().take(5).collect();
+
+        let report = service
+            .import_pets(futures::stream::iter(rows), false)
+            .await
+            .unwrap();
+
+        assert!(!report.dry_run);
+        assert_eq!((report.rows, report.imported), (5, 1));
+        assert_eq!(report.failures.iter().map(|f| f.line).collect::<Vec<_>>(), vec![3, 4, 5, 6]);
+        assert!(service.repo.pets.lock().unwrap().contains_key("Buddy"));
+    }
+
+    #[tokio::test]
+    async fn test_service_import_pets_stops_on_read_error() {
+        let service = import_fixture().await;
+        let mut rows = import_rows();
+        rows.insert(1, Err(ImportPetsError::Unknown(anyhow::anyhow!("connection reset"))));
+
+        let result = service.import_pets(futures::stream::iter(rows), false).await;
+
+        assert!(matches!(result, Err(ImportPetsError::Unknown(_))));
+        // The rows before the error stay imported
+        assert!(service.repo.pets.lock().unwrap().contains_key("Buddy"));
+    }
+
+    /// Gives every new tag the id 1.
+    #[derive(Clone)]
+    struct FirstTagId;
+
+    impl IdGenerator for FirstTagId {
+        async fn next_id(&self, kind: IdKind) -> Result<Option<i64>, GenerateIdError> {
+            Ok((kind == IdKind::Tag).then_some(1))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_service_import_pets_reports_taken_tag_ids() {
+        let service = Service::with_id_generator(MockRepository::new(), FirstTagId);
+        let calm = vec![Tag::with_values(1, "calm".to_string())];
+        service
+            .add_pet(&CreatePetRequest::new(None, "Rex".to_string(), None, Vec::new(), calm, None))
+            .await
+            .unwrap();
+        let record = |name: &str, tags: Vec<String>| PetRecord {
+            name: name.to_string(),
+            tags,
+            photo_urls: vec![format!("https://example.com/{}.jpg", name)],
+            ..Default::default()
+        };
+        let rows = [(2, record("Buddy", vec!["friendly".to_string()])), (3, record("Luna", Vec::new()))];
+        let rows = rows.into_iter().map(|(line, record)| Ok(ImportRow { line, record: Ok(record) }));
+
+        let report = service.import_pets(futures::stream::iter(rows), false).await.unwrap();
+
+        // The row is reported and the import goes on
+        assert_eq!((report.rows, report.imported), (2, 1));
+        assert_eq!(failed_fields(&report), vec![(2, "tags".to_string(), "duplicate")]);
+        assert!(service.repo.pets.lock().unwrap().contains_key("Luna"));
+    }
+
+    #[tokio::test]
+    async fn test_service_import_pets_stops_on_store_error() {
+        let service = import_fixture().await;
+        service.repo.unavailable.store(true, Ordering::SeqCst);
+
+        let result = service.import_pets(futures::stream::iter(import_rows()), false).await;
+
+        // Reported as a whole rather than by row, whose failures are sent to clients
+        assert!(matches!(result, Err(ImportPetsError::Unknown(_))));
+    }
+
+    #[tokio::test]
+    async fn test_service_export_pets_pages_through_all_pets() {
+        let service = Service::new(MockRepository::new());
+        let names: Vec<String> = (0..EXPORT_PAGE_SIZE * 2 + 5).map(|i| format!("Pet {}", i)).collect();
+        add_named_pets(&service, &names.iter().map(String::as_str).collect::<Vec<_>>()).await;
+
+        let records: Vec<PetRecord> = service
+            .export_pets()
+            .map(|record| record.unwrap())
+            .collect()
+            .await;
+
+        let ids: Vec<i64> = records.iter().filter_map(|r| r.id).collect();
+        assert_eq!(ids, (1..=i64::from(EXPORT_PAGE_SIZE * 2 + 5)).collect::<Vec<_>>());
+        assert_eq!(records[0].name, "Pet 0");
+    }
+
+    #[tokio::test]
+    async fn test_service_create_and_rename_category() {
+        let service = Service::new(MockRepository::new());
+
+        let dogs = service
+            .create_category(&CreateCategoryRequest::new(None, "Dogs".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(dogs, Category::with_values(1, "Dogs".to_string()));
+
+        let duplicate = service
+            .create_category(&CreateCategoryRequest::new(None, "Dogs".to_string()))
+            .await;
+        assert!(matches!(duplicate, Err(CreateCategoryError::Duplicate { name }) if name == "Dogs"));
+
+        let renamed = service
+            .rename_category(&RenameCategoryRequest::new(1, "Canines".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(renamed.name, Some("Canines".to_string()));
+        assert_eq!(service.find_category_by_id(1).await.unwrap(), Some(renamed));
+
+        let missing = service
+            .rename_category(&RenameCategoryRequest::new(99, "Birds".to_string()))
+            .await;
+        assert!(matches!(missing, Err(RenameCategoryError::NotFound { id: 99 })));
+    }
+
+    #[tokio::test]
+    async fn test_service_delete_category_in_use() {
+        let service = Service::new(MockRepository::new());
+        let cats = service
+            .create_category(&CreateCategoryRequest::new(Some(2), "Cats".to_string()))
+            .await
+            .unwrap();
+        let request = CreatePetRequest::new(
+            Some(1),
+            "Kitty".to_string(),
+            Some(cats),
+            Vec::new(),
+            Vec::new(),
+            None,
+        );
+        service.add_pet(&request).await.unwrap();
+
+        let result = service.delete_category(2).await;
+        assert!(matches!(result, Err(DeleteCategoryError::InUse { id: 2 })));
+
+        let result = service.delete_category(3).await;
+        assert!(matches!(result, Err(DeleteCategoryError::NotFound { id: 3 })));
+        assert_eq!(service.list_categories().await.unwrap().len(), 1);
+    }
+
+    async fn add_tagged_pet(service: &Service<MockRepository>, id: i64, tags: Vec<Tag>) {
+        let request = CreatePetRequest::new(Some(id), format!("pet{}", id), None, Vec::new(), tags, None);
+        service.add_pet(&request).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_service_merge_tags() {
+        let service = Service::new(MockRepository::new());
+        let friendly = Tag::with_values(1, "friendly".to_string());
+        let kind = Tag::with_values(2, "kind".to_string());
+        add_tagged_pet(&service, 1, vec![friendly.clone()]).await;
+        add_tagged_pet(&service, 2, vec![friendly.clone(), kind.clone()]).await;
+        add_tagged_pet(&service, 3, vec![kind.clone()]).await;
+
+        let merged = service.merge_tags(&MergeTagsRequest::new(2, 1)).await.unwrap();
+        assert_eq!(merged, TagUsage::new(friendly.clone(), 3));
+        assert_eq!(service.list_tags().await.unwrap(), vec![TagUsage::new(friendly, 3)]);
+    }
+
+    #[tokio::test]
+    async fn test_service_merge_tag_into_itself() {
+        let service = Service::new(MockRepository::new());
+        add_tagged_pet(&service, 1, vec![Tag::with_values(1, "friendly".to_string())]).await;
+
+        let result = service.merge_tags(&MergeTagsRequest::new(1, 1)).await;
+        assert!(matches!(result, Err(MergeTagsError::SameTag { id: 1 })));
+
+        let result = service.merge_tags(&MergeTagsRequest::new(1, 5)).await;
+        assert!(matches!(result, Err(MergeTagsError::NotFound { id: 5 })));
+    }
+
+    #[tokio::test]
+    async fn test_service_change_pet_status() {
+        let service = Service::new(MockRepository::new());
+        add_named_pets(&service, &["Rex"]).await;
+
+        let pet = service
+            .change_pet_status(&ChangePetStatusRequest::new(1, Status::Pending))
+            .await
+            .unwrap();
+        assert_eq!(pet.status, Some(Status::Pending));
+
+        let pet = service
+            .change_pet_status(&ChangePetStatusRequest::new(1, Status::Sold))
+            .await
+            .unwrap();
+        assert_eq!(pet.status, Some(Status::Sold));
+
+        let result = service
+            .change_pet_status(&ChangePetStatusRequest::new(1, Status::Available))
+            .await;
+        assert!(matches!(
+            result,
+            Err(ChangePetStatusError::InvalidTransition(InvalidTransition { from: Status::Sold, to: Status::Available }))
+        ));
+        let stored = service.find_pet_by_id(1).await.unwrap().unwrap();
+        assert_eq!(stored.status, Some(Status::Sold));
+    }
+
+    #[tokio::test]
+    async fn test_service_change_pet_status_not_found() {
+        let service = Service::new(MockRepository::new());
+
+        let result = service
+            .change_pet_status(&ChangePetStatusRequest::new(7, Status::Pending))
+            .await;
+        assert!(matches!(result, Err(ChangePetStatusError::NotFound { id: 7 })));
+    }
+
+    #[tokio::test]
+    async fn test_service_pet_status_history() {
+        let service = Service::new(MockRepository::new());
+        add_named_pets(&service, &["Rex"]).await;
+        let actor = Some("alice".to_string());
+        for status in [Status::Pending, Status::Sold] {
+            let req = ChangePetStatusRequest::new(1, status).with_actor(actor.clone());
+            service.change_pet_status(&req).await.unwrap();
+        }
+
+        let history = service.pet_status_history(1).await.unwrap();
+        let transitions: Vec<(Option<Status>, Status, Option<String>)> = history
+            .into_iter()
+            .map(|c| (c.from, c.to, c.actor))
+            .collect();
+        assert_eq!(transitions, vec![
+            (None, Status::Available, None),
+            (Some(Status::Available), Status::Pending, actor.clone()),
+            (Some(Status::Pending), Status::Sold, actor),
+        ]);
+
+        let result = service.pet_status_history(2).await;
+        assert!(matches!(result, Err(PetHistoryError::NotFound { id: 2 })));
+    }
+
+    #[tokio::test]
+    async fn test_service_change_pet_status_version_conflict() {
+        let service = Service::new(MockRepository::new());
+        add_named_pets(&service, &["Rex"]).await;
+
+        let req = ChangePetStatusRequest::new(1, Status::Pending).with_expected_version(Some(Pet::INITIAL_VERSION));
+        let pet = service.change_pet_status(&req).await.unwrap();
+        assert_eq!(pet.version, Pet::INITIAL_VERSION + 1);
+
+        // A second writer still holding the initial version is rejected
+        let stale = ChangePetStatusRequest::new(1, Status::Available).with_expected_version(Some(Pet::INITIAL_VERSION));
+        let result = service.change_pet_status(&stale).await;
+        assert!(matches!(
+            result,
+            Err(ChangePetStatusError::Conflict { id: 1, expected: 1, actual: 2 })
+        ));
+
+        let found = service.find_pet_by_id(1).await.unwrap().unwrap();
+        assert_eq!(found.status, Some(Status::Pending));
+        assert_eq!(service.find_pet_version(1).await.unwrap(), Some(found.version));
+        assert_eq!(service.find_pet_version(2).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_service_emits_pet_events() {
+        let repo = MockRepository::new();
+        let service = Service::new(repo.clone());
+        add_named_pets(&service, &["Rex"]).await;
+        let req = ChangePetStatusRequest::new(1, Status::Pending).with_actor(Some("alice".to_string()));
+        service.change_pet_status(&req).await.unwrap();
+        // Rejected changes emit nothing
+        let _ = service.change_pet_status(&ChangePetStatusRequest::new(1, Status::Pending)).await;
+
+        let events = repo.events.lock().unwrap().clone();
+        assert_eq!(events, vec![
+            PetEvent::PetCreated {
+                pet_id: 1,
+                name: "Rex".to_string(),
+                category_id: None,
+                status: Status::Available,
+                version: Pet::INITIAL_VERSION,
+            },
+            PetEvent::PetStatusChanged {
+                pet_id: 1,
+                from: Status::Available,
+                to: Status::Pending,
+                actor: Some("alice".to_string()),
+                version: Pet::INITIAL_VERSION + 1,
+            },
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_service_delete_pet() {
+        let repo = MockRepository::new();
+        let service = Service::new(repo.clone());
+        add_named_pets(&service, &["Rex"]).await;
+        let mut changes = service.subscribe_to_pet_changes();
+
+        service.delete_pet(1).await.unwrap();
+
+        assert_eq!(service.find_pet_by_id(1).await.unwrap(), None);
+        assert_eq!(repo.events.lock().unwrap().last(), Some(&PetEvent::PetDeleted { pet_id: 1 }));
+        let deleted = changes.recv().await.unwrap();
+        assert_eq!(deleted.event, PetEvent::PetDeleted { pet_id: 1 });
+        assert_eq!(deleted.pet.name, "Rex");
+
+        // Deleting it again emits nothing
+        let result = service.delete_pet(1).await;
+        assert!(matches!(result, Err(DeletePetError::NotFound { id: 1 })));
+        assert_eq!(repo.events.lock().unwrap().len(), 2);
+        assert!(changes.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_service_notifies_pet_changes() {
+        let service = Service::new(MockRepository::new());
+        let mut changes = service.subscribe_to_pet_changes();
+
+        add_named_pets(&service, &["Rex"]).await;
+        service.change_pet_status(&ChangePetStatusRequest::new(1, Status::Pending)).await.unwrap();
+        // Failed writes are not announced
+        let _ = service.change_pet_status(&ChangePetStatusRequest::new(1, Status::Pending)).await;
+        let _ = service.add_pet(&CreatePetRequest::new(None, String::from("Rex"), None, Vec::new(), Vec::new(), None)).await;
+
+        let created = changes.recv().await.unwrap();
+        assert_eq!(created.event.event_type(), "PetCreated");
+        assert_eq!(created.pet.status, Some(Status::Available));
+        let changed = changes.recv().await.unwrap();
+        assert_eq!(changed.event.event_type(), "PetStatusChanged");
+        assert_eq!(changed.pet.status, Some(Status::Pending));
+        assert!(changes.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_service_idempotency_key_lifecycle() {
+        let service = Service::new(MockRepository::new());
+        let key = IdempotencyKey::new("retry-me").unwrap();
+        let lease = Duration::from_secs(60);
+        let claim = ClaimIdempotencyKeyRequest::new(key.clone(), "first".to_string(), lease);
+        let response = IdempotentResponse {
+            status_code: 201,
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            body: "{}".to_string(),
+        };
+
+        assert_eq!(service.claim_idempotency_key(&claim).await.unwrap(), IdempotencyClaim::Claimed);
+        assert_eq!(service.claim_idempotency_key(&claim).await.unwrap(), IdempotencyClaim::InProgress);
+
+        service.complete_idempotency_key(&claim, &response, Duration::from_secs(3600)).await.unwrap();
+        assert_eq!(
+            service.claim_idempotency_key(&claim).await.unwrap(),
+            IdempotencyClaim::Completed(response.clone())
+        );
+
+        let other = ClaimIdempotencyKeyRequest::new(key.clone(), "second".to_string(), lease);
+        assert_eq!(service.claim_idempotency_key(&other).await.unwrap(), IdempotencyClaim::Mismatch);
+
+        // Releasing a completed claim keeps its response
+        service.release_idempotency_key(&claim).await.unwrap();
+        assert_eq!(service.claim_idempotency_key(&other).await.unwrap(), IdempotencyClaim::Mismatch);
+
+        // A released key can be claimed again, even by a different request
+        let released = IdempotencyKey::new("released").unwrap();
+        let claim = ClaimIdempotencyKeyRequest::new(released.clone(), "first".to_string(), lease);
+        assert_eq!(service.claim_idempotency_key(&claim).await.unwrap(), IdempotencyClaim::Claimed);
+        service.release_idempotency_key(&claim).await.unwrap();
+        let other = ClaimIdempotencyKeyRequest::new(released, "second".to_string(), lease);
+        assert_eq!(service.claim_idempotency_key(&other).await.unwrap(), IdempotencyClaim::Claimed);
+
+        // So can one whose lease expired before it was completed
+        let expired = ClaimIdempotencyKeyRequest::new(IdempotencyKey::new("expired").unwrap(), "first".to_string(), Duration::ZERO);
+        assert_eq!(service.claim_idempotency_key(&expired).await.unwrap(), IdempotencyClaim::Claimed);
+        assert_eq!(service.claim_idempotency_key(&expired).await.unwrap(), IdempotencyClaim::Claimed);
+
+        // Until purged
+        assert_eq!(service.purge_expired_idempotency_keys().await.unwrap(), 1);
+
+        // But completing a key keeps it for the window rather than the lease
+        let completed = IdempotencyKey::new("completed").unwrap();
+        let short = ClaimIdempotencyKeyRequest::new(completed.clone(), "first".to_string(), Duration::ZERO);
+        assert_eq!(service.claim_idempotency_key(&short).await.unwrap(), IdempotencyClaim::Claimed);
+        service.complete_idempotency_key(&short, &response, Duration::from_secs(3600)).await.unwrap();
+        assert_eq!(
+            service.claim_idempotency_key(&short).await.unwrap(),
+            IdempotencyClaim::Completed(response)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_service_idempotency_key_lost_claim() {
+        let service = Service::new(MockRepository::new());
+        let key = IdempotencyKey::new("slow").unwrap();
+        let response = |body: &str| IdempotentResponse {
+            status_code: 201,
+            headers: Vec::new(),
+            body: body.to_string(),
+        };
+
+        // The first request outlives its lease, and the key is claimed by another one
+        let slow = ClaimIdempotencyKeyRequest::new(key.clone(), "first".to_string(), Duration::ZERO);
+        assert_eq!(service.claim_idempotency_key(&slow).await.unwrap(), IdempotencyClaim::Claimed);
+        let other = ClaimIdempotencyKeyRequest::new(key.clone(), "second".to_string(), Duration::from_secs(60));
+        assert_eq!(service.claim_idempotency_key(&other).await.unwrap(), IdempotencyClaim::Claimed);
+
+        let lost = service.complete_idempotency_key(&slow, &response("slow"), Duration::from_secs(3600)).await;
+        assert!(matches!(lost, Err(IdempotencyError::ClaimLost { .. })));
+
+        // Nor can a response be stored over a completed one
+        service.complete_idempotency_key(&other, &response("other"), Duration::from_secs(3600)).await.unwrap();
+        let lost = service.complete_idempotency_key(&other, &response("again"), Duration::from_secs(3600)).await;
+        assert!(matches!(lost, Err(IdempotencyError::ClaimLost { .. })));
+        assert_eq!(
+            service.claim_idempotency_key(&other).await.unwrap(),
+            IdempotencyClaim::Completed(response("other"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_service_idempotency_key_taken_over_by_retry() {
+        let service = Service::new(MockRepository::new());
+        let key = IdempotencyKey::new("slow").unwrap();
+        let response = IdempotentResponse { status_code: 201, headers: Vec::new(), body: "{}".to_string() };
+
+        // The first request outlives its lease, and a retry of it takes the key over and completes
+        let slow = ClaimIdempotencyKeyRequest::new(key.clone(), "first".to_string(), Duration::ZERO);
+        assert_eq!(service.claim_idempotency_key(&slow).await.unwrap(), IdempotencyClaim::Claimed);
+        let retry = ClaimIdempotencyKeyRequest::new(key.clone(), "first".to_string(), Duration::from_secs(60));
+        assert_eq!(service.claim_idempotency_key(&retry).await.unwrap(), IdempotencyClaim::Claimed);
+
+        // The first request can neither complete nor release the retry's claim
+        let lost = service.complete_idempotency_key(&slow, &response, Duration::from_secs(3600)).await;
+        assert!(matches!(lost, Err(IdempotencyError::ClaimLost { .. })));
+        service.complete_idempotency_key(&retry, &response, Duration::from_secs(3600)).await.unwrap();
+        service.release_idempotency_key(&slow).await.unwrap();
+        assert_eq!(
+            service.claim_idempotency_key(&retry).await.unwrap(),
+            IdempotencyClaim::Completed(response)
+        );
+    }
+}
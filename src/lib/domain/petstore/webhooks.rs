@@ -0,0 +1,503 @@
+/*!
+   Module `webhooks` delivers pet events to the [Webhook]s partners registered: the
+   [WebhookPublisher] queues a delivery per accepting webhook as events leave the outbox, and
+   the [WebhookDispatcher] sends the due deliveries, retrying failures with exponential backoff
+   until they are delivered or dead-lettered.
+*/
+
+use std::time::Duration;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::domain::petstore::models::event::{OutboxEvent, PublishEventError};
+use crate::domain::petstore::models::webhook::{
+    DeliveryOutcome, Webhook, WebhookDelivery, WebhookDeliveryError, WebhookMessage,
+    WebhookPayload,
+};
+use crate::domain::petstore::ports::{EventPublisher, WebhookRepository, WebhookSender};
+
+/// An [EventPublisher] queueing a delivery of each event to every [Webhook] accepting it.
+#[derive(Debug, Clone)]
+pub struct WebhookPublisher<W: WebhookRepository> {
+    webhooks: W,
+}
+
+impl<W: WebhookRepository> WebhookPublisher<W> {
+    pub fn new(webhooks: W) -> Self {
+        Self { webhooks }
+    }
+}
+
+impl<W: WebhookRepository> EventPublisher for WebhookPublisher<W> {
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), PublishEventError> {
+        let payload = serde_json::to_string(&WebhookPayload::from(event))
+            .map_err(|e| PublishEventError::Unknown(anyhow::anyhow!(e)))?;
+        self.webhooks
+            .enqueue_deliveries(event.id, event.event.event_type(), &payload)
+            .await
+            .map_err(|e| PublishEventError::Unknown(anyhow::anyhow!(e)))?;
+        Ok(())
+    }
+}
+
+/// How failed deliveries are retried: after `initial_backoff`, doubling with every attempt up
+/// to `max_backoff`, until `max_attempts` attempts have failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    /// Eight attempts spread over about half an hour.
+    fn default() -> Self {
+        Self {
+            max_attempts: 8,
+            initial_backoff: Duration::from_secs(15),
+            max_backoff: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The delay before the attempt following failed attempt number `attempt`, counting from 1.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// The signature of a delivery of `body` at `timestamp`: `sha256=` followed by the hex-encoded
+/// HMAC-SHA256 of `{timestamp}.{body}` keyed with the webhook secret.
+///
+/// Receivers recompute it to authenticate deliveries, and reject stale timestamps to guard
+/// against replays.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Sends due webhook deliveries and records their outcome.
+#[derive(Debug, Clone)]
+pub struct WebhookDispatcher<W, S>
+where
+    W: WebhookRepository,
+    S: WebhookSender,
+{
+    webhooks: W,
+    sender: S,
+    policy: RetryPolicy,
+    batch_size: usize,
+    lease: Duration,
+}
+
+impl<W, S> WebhookDispatcher<W, S>
+where
+    W: WebhookRepository,
+    S: WebhookSender,
+{
+    pub const DEFAULT_BATCH_SIZE: usize = 50;
+    /// Long enough for a batch of attempts that all wait out the default send timeout.
+    pub const DEFAULT_LEASE: Duration = Duration::from_secs(15 * 60);
+
+    pub fn new(webhooks: W, sender: S) -> Self {
+        Self {
+            webhooks,
+            sender,
+            policy: RetryPolicy::default(),
+            batch_size: Self::DEFAULT_BATCH_SIZE,
+            lease: Self::DEFAULT_LEASE,
+        }
+    }
+
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets the number of due deliveries attempted per call to
+    /// [dispatch_due](WebhookDispatcher::dispatch_due).
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Sets how long the deliveries of a batch stay claimed by this dispatcher. It must outlast
+    /// the attempts of a whole batch, or another dispatcher may attempt them again.
+    pub fn with_lease(mut self, lease: Duration) -> Self {
+        self.lease = lease;
+        self
+    }
+
+    /// Claims the deliveries that are due, attempts them and returns how many were attempted.
+    /// A response with a 2xx status delivers; anything else is retried or dead-lettered per the
+    /// [RetryPolicy]. Deliveries claimed by another dispatcher are left to it.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [WebhookDeliveryError] returned by the [WebhookRepository].
+    pub async fn dispatch_due(&self) -> Result<usize, WebhookDeliveryError> {
+        let due = self.webhooks.claim_due_deliveries(self.batch_size, self.lease).await?;
+        let attempted = due.len();
+        for (webhook, delivery) in due {
+            let outcome = self.attempt(&webhook, &delivery).await;
+            self.webhooks.record_delivery_attempt(delivery.id, &outcome).await?;
+        }
+        Ok(attempted)
+    }
+
+    async fn attempt(&self, webhook: &Webhook, delivery: &WebhookDelivery) -> DeliveryOutcome {
+        let timestamp = Utc::now().timestamp();
+        let message = WebhookMessage {
+            url: webhook.url.clone(),
+            delivery_id: delivery.id,
+            event_type: delivery.event_type.clone(),
+            timestamp,
+            signature: sign(&webhook.secret, timestamp, &delivery.payload),
+            body: delivery.payload.clone(),
+        };
+        let (response_status, error) = match self.sender.send(&message).await {
+            Ok(status) if (200..300).contains(&status) => {
+                return DeliveryOutcome::Delivered { response_status: status };
+            }
+            Ok(status) => (Some(status), format!("receiver responded with status {}", status)),
+            Err(e) => (None, e.to_string()),
+        };
+
+        let attempts = delivery.attempts + 1;
+        if attempts >= self.policy.max_attempts {
+            return DeliveryOutcome::DeadLetter { response_status, error };
+        }
+        let backoff = chrono::Duration::from_std(self.policy.backoff(attempts)).unwrap_or(chrono::Duration::MAX);
+        DeliveryOutcome::Retry {
+            next_attempt_at: Utc::now() + backoff,
+            response_status,
+            error,
+        }
+    }
+
+    /// Dispatches due deliveries every `interval`, forever. Failures are logged and retried on
+    /// the next tick.
+    pub async fn run(self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.dispatch_due().await {
+                tracing::warn!("failed to dispatch webhook deliveries: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    use crate::domain::petstore::models::event::PetEvent;
+    use crate::domain::petstore::models::pet::Status;
+    use crate::domain::petstore::models::webhook::{
+        CreateWebhookError, CreateWebhookRequest, DeleteWebhookError, DeliveryStatus,
+        FindWebhookError, SendWebhookError,
+    };
+
+    #[derive(Debug, Clone, Default)]
+    struct MockWebhookRepository {
+        webhooks: Arc<Mutex<Vec<Webhook>>>,
+        deliveries: Arc<Mutex<Vec<WebhookDelivery>>>,
+    }
+
+    impl WebhookRepository for MockWebhookRepository {
+        async fn create_webhook(&self, req: &CreateWebhookRequest) -> Result<Webhook, CreateWebhookError> {
+            let mut webhooks = self.webhooks.lock().unwrap();
+            let webhook = Webhook {
+                id: webhooks.len() as i64 + 1,
+                url: req.url.clone(),
+                event_types: req.event_types.clone(),
+                secret: req.secret.clone(),
+                created_at: Utc::now(),
+            };
+            webhooks.push(webhook.clone());
+            Ok(webhook)
+        }
+
+        async fn list_webhooks(&self) -> Result<Vec<Webhook>, FindWebhookError> {
+            Ok(self.webhooks.lock().unwrap().clone())
+        }
+
+        async fn find_webhook(&self, webhook_id: i64) -> Result<Option<Webhook>, FindWebhookError> {
+            Ok(self.webhooks.lock().unwrap().iter().find(|w| w.id == webhook_id).cloned())
+        }
+
+        async fn delete_webhook(&self, webhook_id: i64) -> Result<(), DeleteWebhookError> {
+            self.webhooks.lock().unwrap().retain(|w| w.id != webhook_id);
+            Ok(())
+        }
+
+        async fn enqueue_deliveries(&self, event_id: i64, event_type: &str, payload: &str) -> Result<usize, WebhookDeliveryError> {
+            let webhooks = self.webhooks.lock().unwrap();
+            let mut deliveries = self.deliveries.lock().unwrap();
+            let mut enqueued = 0;
+            for webhook in webhooks.iter().filter(|w| w.accepts(event_type)) {
+                if deliveries.iter().any(|d| d.webhook_id == webhook.id && d.event_id == event_id) {
+                    continue;
+                }
+                let id = deliveries.len() as i64 + 1;
+                deliveries.push(WebhookDelivery {
+                    id,
+                    webhook_id: webhook.id,
+                    event_id,
+                    event_type: event_type.to_string(),
+                    payload: payload.to_string(),
+                    status: DeliveryStatus::Pending,
+                    attempts: 0,
+                    next_attempt_at: Utc::now(),
+                    last_response_status: None,
+                    last_error: None,
+                    created_at: Utc::now(),
+                    delivered_at: None,
+                });
+                enqueued += 1;
+            }
+            Ok(enqueued)
+        }
+
+        async fn claim_due_deliveries(&self, limit: usize, lease: Duration) -> Result<Vec<(Webhook, WebhookDelivery)>, WebhookDeliveryError> {
+            let webhooks = self.webhooks.lock().unwrap();
+            let mut deliveries = self.deliveries.lock().unwrap();
+            let now = Utc::now();
+            Ok(deliveries
+                .iter_mut()
+                .filter(|d| d.status == DeliveryStatus::Pending && d.next_attempt_at <= now)
+                .take(limit)
+                .filter_map(|d| {
+                    d.next_attempt_at = now + chrono::Duration::from_std(lease).unwrap();
+                    webhooks.iter().find(|w| w.id == d.webhook_id).map(|w| (w.clone(), d.clone()))
+                })
+                .collect())
+        }
+
+        async fn record_delivery_attempt(&self, delivery_id: i64, outcome: &DeliveryOutcome) -> Result<(), WebhookDeliveryError> {
+            let mut deliveries = self.deliveries.lock().unwrap();
+            let delivery = deliveries.iter_mut().find(|d| d.id == delivery_id).unwrap();
+            delivery.attempts += 1;
+            match outcome {
+                DeliveryOutcome::Delivered { response_status } => {
+                    delivery.status = DeliveryStatus::Delivered;
+                    delivery.last_response_status = Some(*response_status);
+                    delivery.delivered_at = Some(Utc::now());
+                }
+                DeliveryOutcome::Retry { next_attempt_at, response_status, error } => {
+                    delivery.next_attempt_at = *next_attempt_at;
+                    delivery.last_response_status = *response_status;
+                    delivery.last_error = Some(error.clone());
+                }
+                DeliveryOutcome::DeadLetter { response_status, error } => {
+                    delivery.status = DeliveryStatus::DeadLetter;
+                    delivery.last_response_status = *response_status;
+                    delivery.last_error = Some(error.clone());
+                }
+            }
+            Ok(())
+        }
+
+        async fn list_deliveries(&self, webhook_id: i64) -> Result<Vec<WebhookDelivery>, WebhookDeliveryError> {
+            Ok(self.deliveries.lock().unwrap().iter().filter(|d| d.webhook_id == webhook_id).cloned().collect())
+        }
+    }
+
+    /// Answers with the queued statuses, or 200 once they run out, recording every message.
+    #[derive(Debug, Clone, Default)]
+    struct MockSender {
+        statuses: Arc<Mutex<VecDeque<u16>>>,
+        sent: Arc<Mutex<Vec<WebhookMessage>>>,
+    }
+
+    impl WebhookSender for MockSender {
+        async fn send(&self, message: &WebhookMessage) -> Result<u16, SendWebhookError> {
+            self.sent.lock().unwrap().push(message.clone());
+            Ok(self.statuses.lock().unwrap().pop_front().unwrap_or(200))
+        }
+    }
+
+    const SECRET: &str = "0123456789abcdef";
+
+    async fn publish_event(repo: &MockWebhookRepository) {
+        let event = OutboxEvent {
+            id: 1,
+            occurred_at: Utc::now(),
+            event: PetEvent::PetStatusChanged {
+                pet_id: 7,
+                from: Status::Available,
+                to: Status::Sold,
+                actor: None,
+                version: 2,
+            },
+        };
+        WebhookPublisher::new(repo.clone()).publish(&event).await.unwrap();
+    }
+
+    async fn register(repo: &MockWebhookRepository, event_types: &[&str]) -> Webhook {
+        let req = CreateWebhookRequest {
+            url: "http://localhost/hook".to_string(),
+            event_types: event_types.iter().map(|t| t.to_string()).collect(),
+            secret: SECRET.to_string(),
+        };
+        repo.create_webhook(&req).await.unwrap()
+    }
+
+    #[test]
+    fn test_sign() {
+        // HMAC-SHA256("key", "1700000000.{}") computed independently
+        assert_eq!(
+            sign("key", 1_700_000_000, "{}"),
+            "sha256=9d713ed406bb7076d4123f0dc2c39d2df5c654ed4b0cd56b52c8b4c940bd63ae"
+        );
+        assert_ne!(sign("key", 1_700_000_001, "{}"), sign("key", 1_700_000_000, "{}"));
+    }
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(10),
+        };
+
+        let delays: Vec<u64> = (1..=6).map(|attempt| policy.backoff(attempt).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 10, 10]);
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn test_publisher_filters_by_event_type() {
+        let repo = MockWebhookRepository::default();
+        let all = register(&repo, &[]).await;
+        register(&repo, &["PetCreated"]).await;
+
+        publish_event(&repo).await;
+        publish_event(&repo).await;
+
+        let deliveries = repo.deliveries.lock().unwrap().clone();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].webhook_id, all.id);
+        let payload: serde_json::Value = serde_json::from_str(&deliveries[0].payload).unwrap();
+        assert_eq!(payload["id"], 1);
+        assert_eq!(payload["type"], "PetStatusChanged");
+        assert_eq!(payload["data"]["PetStatusChanged"]["pet_id"], 7);
+    }
+
+    #[tokio::test]
+    async fn test_publisher_enqueues_pet_deleted() {
+        let repo = MockWebhookRepository::default();
+        let deleted = register(&repo, &["PetDeleted"]).await;
+        register(&repo, &["PetCreated"]).await;
+        let event = OutboxEvent {
+            id: 2,
+            occurred_at: Utc::now(),
+            event: PetEvent::PetDeleted { pet_id: 7 },
+        };
+
+        WebhookPublisher::new(repo.clone()).publish(&event).await.unwrap();
+
+        let deliveries = repo.deliveries.lock().unwrap().clone();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].webhook_id, deleted.id);
+        assert_eq!(deliveries[0].event_type, "PetDeleted");
+        let payload: serde_json::Value = serde_json::from_str(&deliveries[0].payload).unwrap();
+        assert_eq!(payload["type"], "PetDeleted");
+        assert_eq!(payload["data"]["PetDeleted"]["pet_id"], 7);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_delivers_signed_messages() {
+        let repo = MockWebhookRepository::default();
+        register(&repo, &[]).await;
+        publish_event(&repo).await;
+        let sender = MockSender::default();
+        let dispatcher = WebhookDispatcher::new(repo.clone(), sender.clone());
+
+        assert_eq!(dispatcher.dispatch_due().await.unwrap(), 1);
+        assert_eq!(dispatcher.dispatch_due().await.unwrap(), 0);
+
+        let sent = sender.sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].signature, sign(SECRET, sent[0].timestamp, &sent[0].body));
+        assert_eq!(sent[0].event_type, "PetStatusChanged");
+        let delivery = repo.deliveries.lock().unwrap()[0].clone();
+        assert_eq!(delivery.status, DeliveryStatus::Delivered);
+        assert_eq!(delivery.last_response_status, Some(200));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_retries_then_dead_letters() {
+        let repo = MockWebhookRepository::default();
+        register(&repo, &[]).await;
+        publish_event(&repo).await;
+        let sender = MockSender::default();
+        sender.statuses.lock().unwrap().extend([500, 503, 500]);
+        let dispatcher = WebhookDispatcher::new(repo.clone(), sender.clone()).with_retry_policy(RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+        });
+
+        for attempt in 1..=3 {
+            assert_eq!(dispatcher.dispatch_due().await.unwrap(), 1);
+            let delivery = repo.deliveries.lock().unwrap()[0].clone();
+            assert_eq!(delivery.attempts, attempt);
+            let expected = if attempt < 3 { DeliveryStatus::Pending } else { DeliveryStatus::DeadLetter };
+            assert_eq!(delivery.status, expected);
+        }
+
+        assert_eq!(dispatcher.dispatch_due().await.unwrap(), 0);
+        let delivery = repo.deliveries.lock().unwrap()[0].clone();
+        assert_eq!(delivery.last_response_status, Some(500));
+        assert_eq!(delivery.last_error.as_deref(), Some("receiver responded with status 500"));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_skips_claimed_deliveries() {
+        let repo = MockWebhookRepository::default();
+        register(&repo, &[]).await;
+        publish_event(&repo).await;
+        let sender = MockSender::default();
+
+        // Another dispatcher claimed the delivery and has not recorded its attempt yet
+        let claimed = repo.claim_due_deliveries(10, Duration::from_secs(60)).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        let dispatcher = WebhookDispatcher::new(repo.clone(), sender.clone());
+        assert_eq!(dispatcher.dispatch_due().await.unwrap(), 0);
+        assert!(sender.sent.lock().unwrap().is_empty());
+
+        // Once its lease ends, the delivery is due again
+        repo.deliveries.lock().unwrap()[0].next_attempt_at = Utc::now();
+        assert_eq!(dispatcher.dispatch_due().await.unwrap(), 1);
+        assert_eq!(sender.sent.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_backs_off() {
+        let repo = MockWebhookRepository::default();
+        register(&repo, &[]).await;
+        publish_event(&repo).await;
+        let sender = MockSender::default();
+        sender.statuses.lock().unwrap().push_back(500);
+        let dispatcher = WebhookDispatcher::new(repo.clone(), sender.clone());
+
+        dispatcher.dispatch_due().await.unwrap();
+
+        // The retry is not due before the initial backoff has passed
+        assert_eq!(dispatcher.dispatch_due().await.unwrap(), 0);
+        let delivery = repo.deliveries.lock().unwrap()[0].clone();
+        assert!(delivery.next_attempt_at > Utc::now() + chrono::Duration::seconds(10));
+    }
+}
@@ -1 +1,3 @@
-pub mod http;
\ No newline at end of file
+pub mod grpc;
+pub mod http;
+pub mod transfer;
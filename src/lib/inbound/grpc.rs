@@ -0,0 +1,70 @@
+/*!
+    Module `grpc` exposes the pet operations of the application as the `petstore.v1.PetService`
+    gRPC service, defined in `proto/petstore/v1/pets.proto`, for consumers that do not speak
+    HTTP/JSON. Its implementation is opaque to module consumers, except for the generated
+    messages and client in [proto].
+*/
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use tokio::net;
+use tonic::transport::server::TcpIncoming;
+
+use crate::domain::petstore::ports::PetService;
+
+mod pets;
+pub mod proto;
+mod status;
+
+/// Configuration for the gRPC server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrpcServerConfig<'a> {
+    pub port: &'a str,
+}
+
+/// The application's gRPC server. The underlying gRPC package is opaque to module consumers.
+pub struct GrpcServer {
+    routes: tonic::service::Routes,
+    listener: net::TcpListener,
+}
+
+impl GrpcServer {
+    /// Returns a new gRPC server bound to the port specified in `config`.
+    pub async fn new(service: impl PetService, config: GrpcServerConfig<'_>) -> anyhow::Result<Self> {
+        let pets = pets::PetGrpcService::new(Arc::new(service));
+        let routes = tonic::service::Routes::new(proto::pet_service_server::PetServiceServer::new(pets));
+
+        let listener = net::TcpListener::bind(format!("0.0.0.0:{}", config.port))
+            .await
+            .with_context(|| format!("failed to listen on {}", config.port))?;
+
+        Ok(Self { routes, listener })
+    }
+
+    /// Runs the gRPC server.
+    pub async fn run(self) -> anyhow::Result<()> {
+        tracing::debug!("listening for gRPC on {}", self.listener.local_addr().unwrap());
+        // Every event logged while handling a call carries its method through this span, which
+        // continues the trace of the caller if it sent a traceparent
+        let trace_layer = tower_http::trace::TraceLayer::new_for_grpc().make_span_with(
+            |request: &axum::http::Request<_>| {
+                let span = tracing::info_span!(
+                    "grpc_request",
+                    otel.kind = "server",
+                    rpc.system = "grpc",
+                    rpc.method = request.uri().path(),
+                );
+                crate::telemetry::set_remote_parent(&span, request.headers());
+                span
+            },
+        );
+        tonic::transport::Server::builder()
+            .layer(trace_layer)
+            .add_routes(self.routes)
+            .serve_with_incoming(TcpIncoming::from(self.listener))
+            .await
+            .context("received error from running gRPC server")?;
+        Ok(())
+    }
+}
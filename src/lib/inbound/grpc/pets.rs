@@ -0,0 +1,497 @@
+/*
+   Module `pets` implements the `petstore.v1.PetService` gRPC service on top of the domain
+   [PetService], converting its messages to and from domain requests and models.
+*/
+
+use std::sync::Arc;
+
+use tonic::{Request, Response, Status};
+
+use crate::domain::petstore::models::category::Category;
+use crate::domain::petstore::models::pet::{
+    self, ChangePetStatusRequest, CreatePetFields, CreatePetRequest, ListPetsRequest, Pet, PetCursor, PetSortField,
+    SortDirection, StatusChange,
+};
+use crate::domain::petstore::models::search::{Highlight, PetSearchHit, SearchField, SearchPetsRequest, SearchQuery};
+use crate::domain::petstore::models::tag::Tag;
+use crate::domain::petstore::models::validation::ValidationErrors;
+use crate::domain::petstore::models::value_objects::{PageSize, StatusError};
+use crate::domain::petstore::ports::PetService;
+use crate::inbound::grpc::proto;
+use crate::inbound::grpc::proto::pet_service_server;
+use crate::inbound::grpc::status::{invalid_argument, pet_not_found};
+
+/// The gRPC pet service, delegating to a domain [PetService].
+#[derive(Debug, Clone)]
+pub struct PetGrpcService<BS: PetService> {
+    pet_service: Arc<BS>,
+}
+
+impl<BS: PetService> PetGrpcService<BS> {
+    pub fn new(pet_service: Arc<BS>) -> Self {
+        Self { pet_service }
+    }
+}
+
+#[tonic::async_trait]
+impl<BS: PetService> pet_service_server::PetService for PetGrpcService<BS> {
+    async fn create_pet(&self, request: Request<proto::CreatePetRequest>) -> Result<Response<proto::Pet>, Status> {
+        let domain_req = request
+            .into_inner()
+            .try_into_domain()
+            .map_err(|e| invalid_argument("invalid pet", &e))?;
+        let pet = self.pet_service.add_pet(&domain_req).await?;
+        Ok(Response::new((&pet).into()))
+    }
+
+    async fn get_pet(&self, request: Request<proto::GetPetRequest>) -> Result<Response<proto::Pet>, Status> {
+        let id = request.into_inner().id;
+        match self.pet_service.find_pet_by_id(id).await? {
+            Some(pet) => Ok(Response::new((&pet).into())),
+            None => Err(pet_not_found(id)),
+        }
+    }
+
+    async fn list_pets(
+        &self,
+        request: Request<proto::ListPetsRequest>,
+    ) -> Result<Response<proto::ListPetsResponse>, Status> {
+        let domain_req = request
+            .into_inner()
+            .try_into_domain()
+            .map_err(|e| invalid_argument("invalid pet listing", &e))?;
+        let page = self.pet_service.list_pets(&domain_req).await?;
+        Ok(Response::new(proto::ListPetsResponse {
+            pets: page.pets.iter().map(proto::Pet::from).collect(),
+            next_page_token: page.next_cursor.as_ref().map(PetCursor::encode).unwrap_or_default(),
+        }))
+    }
+
+    async fn search_pets(
+        &self,
+        request: Request<proto::SearchPetsRequest>,
+    ) -> Result<Response<proto::SearchPetsResponse>, Status> {
+        let domain_req = request
+            .into_inner()
+            .try_into_domain()
+            .map_err(|e| invalid_argument("invalid pet search", &e))?;
+        let hits = self.pet_service.search_pets(&domain_req).await?;
+        Ok(Response::new(proto::SearchPetsResponse {
+            hits: hits.iter().map(proto::PetSearchHit::from).collect(),
+        }))
+    }
+
+    async fn change_pet_status(
+        &self,
+        request: Request<proto::ChangePetStatusRequest>,
+    ) -> Result<Response<proto::Pet>, Status> {
+        let domain_req = request
+            .into_inner()
+            .try_into_domain()
+            .map_err(|e| invalid_argument("invalid status change", &e))?;
+        let pet = self.pet_service.change_pet_status(&domain_req).await?;
+        Ok(Response::new((&pet).into()))
+    }
+
+    async fn delete_pet(
+        &self,
+        request: Request<proto::DeletePetRequest>,
+    ) -> Result<Response<proto::DeletePetResponse>, Status> {
+        self.pet_service.delete_pet(request.into_inner().id).await?;
+        Ok(Response::new(proto::DeletePetResponse {}))
+    }
+
+    async fn get_pet_status_history(
+        &self,
+        request: Request<proto::GetPetStatusHistoryRequest>,
+    ) -> Result<Response<proto::GetPetStatusHistoryResponse>, Status> {
+        let changes = self.pet_service.pet_status_history(request.into_inner().id).await?;
+        Ok(Response::new(proto::GetPetStatusHistoryResponse {
+            // `proto::StatusChange::from` is the generated getter of its `from` field
+            changes: changes.iter().map(Into::into).collect(),
+        }))
+    }
+}
+
+/// The domain status of `status`, or `None` if unspecified.
+fn status_from_proto(status: i32) -> Result<Option<pet::Status>, StatusError> {
+    match proto::PetStatus::try_from(status) {
+        Ok(proto::PetStatus::Unspecified) => Ok(None),
+        Ok(proto::PetStatus::Available) => Ok(Some(pet::Status::Available)),
+        Ok(proto::PetStatus::Pending) => Ok(Some(pet::Status::Pending)),
+        Ok(proto::PetStatus::Sold) => Ok(Some(pet::Status::Sold)),
+        Err(_) => Err(StatusError::InvalidStatus { invalid_status: status.to_string() }),
+    }
+}
+
+fn status_to_proto(status: &pet::Status) -> proto::PetStatus {
+    match status {
+        pet::Status::Available => proto::PetStatus::Available,
+        pet::Status::Pending => proto::PetStatus::Pending,
+        pet::Status::Sold => proto::PetStatus::Sold,
+    }
+}
+
+impl From<&Pet> for proto::Pet {
+    fn from(pet: &Pet) -> Self {
+        Self {
+            id: pet.id,
+            name: pet.name.clone(),
+            category: pet.category.as_ref().map(|c| proto::Category { id: c.id, name: c.name.clone() }),
+            photo_urls: pet.photo_urls.clone(),
+            tags: pet.tags.iter().map(|t| proto::Tag { id: t.id, name: t.name.clone() }).collect(),
+            status: pet.status.as_ref().map_or(proto::PetStatus::Unspecified, status_to_proto).into(),
+            version: pet.version,
+        }
+    }
+}
+
+impl From<&PetSearchHit> for proto::PetSearchHit {
+    fn from(hit: &PetSearchHit) -> Self {
+        Self {
+            pet: Some((&hit.pet).into()),
+            rank: hit.rank,
+            highlights: hit.highlights.iter().map(proto::Highlight::from).collect(),
+        }
+    }
+}
+
+impl From<&Highlight> for proto::Highlight {
+    fn from(highlight: &Highlight) -> Self {
+        let field = match highlight.field {
+            SearchField::Name => proto::SearchField::Name,
+            SearchField::Category => proto::SearchField::Category,
+            SearchField::Tag => proto::SearchField::Tag,
+        };
+        Self {
+            field: field.into(),
+            value: highlight.value.clone(),
+            spans: highlight
+                .spans
+                .iter()
+                .map(|span| proto::MatchSpan { start: span.start as u32, end: span.end as u32 })
+                .collect(),
+        }
+    }
+}
+
+impl From<&StatusChange> for proto::StatusChange {
+    fn from(change: &StatusChange) -> Self {
+        Self {
+            from: change.from.as_ref().map_or(proto::PetStatus::Unspecified, status_to_proto).into(),
+            to: status_to_proto(&change.to).into(),
+            actor: change.actor.clone(),
+            changed_at: Some(prost_types::Timestamp {
+                seconds: change.changed_at.timestamp(),
+                nanos: change.changed_at.timestamp_subsec_nanos() as i32,
+            }),
+        }
+    }
+}
+
+impl proto::CreatePetRequest {
+    /// Converts the message into a domain request, collecting every invalid field.
+    fn try_into_domain(self) -> Result<CreatePetRequest, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let status = errors.check("status", status_from_proto(self.status)).map(Option::unwrap_or_default);
+        let category = self.category.map(|c| Category { id: c.id, name: c.name });
+        let tags = self.tags.into_iter().map(|t| Tag { id: t.id, name: t.name }).collect();
+        let request = CreatePetRequest::new(self.id, self.name, category, self.photo_urls, tags, status);
+
+        match request.validate(&CreatePetFields::NESTED) {
+            Ok(request) if errors.is_empty() => Ok(request),
+            Ok(_) => Err(errors),
+            Err(mut violations) => {
+                violations.extend(errors);
+                Err(violations)
+            }
+        }
+    }
+}
+
+impl proto::ListPetsRequest {
+    /// Converts the message into a domain request, collecting every invalid field.
+    fn try_into_domain(self) -> Result<ListPetsRequest, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let sort = match proto::PetSortField::try_from(self.sort) {
+            Ok(proto::PetSortField::Unspecified | proto::PetSortField::Id) => Some(PetSortField::Id),
+            Ok(proto::PetSortField::Name) => Some(PetSortField::Name),
+            Err(e) => errors.check("sort", Err::<PetSortField, _>(InvalidEnumValue(e.0))),
+        };
+        let direction = match proto::SortDirection::try_from(self.direction) {
+            Ok(proto::SortDirection::Unspecified | proto::SortDirection::Asc) => Some(SortDirection::Asc),
+            Ok(proto::SortDirection::Desc) => Some(SortDirection::Desc),
+            Err(e) => errors.check("direction", Err::<SortDirection, _>(InvalidEnumValue(e.0))),
+        };
+        let status = errors.check("status", status_from_proto(self.status));
+        let limit = errors.check("page_size", PageSize::new(self.page_size));
+        let cursor = match self.page_token.as_str() {
+            "" => Some(None),
+            token => errors.check("page_token", PetCursor::decode(token)).map(Some),
+        };
+
+        match (sort, direction, status, limit, cursor) {
+            (Some(sort), Some(direction), Some(status), Some(limit), Some(cursor)) if errors.is_empty() => {
+                Ok(ListPetsRequest::new(sort, direction, status, self.category_id, limit.into_inner(), cursor))
+            }
+            _ => Err(errors),
+        }
+    }
+}
+
+impl proto::SearchPetsRequest {
+    /// Converts the message into a domain request, collecting every invalid field.
+    fn try_into_domain(self) -> Result<SearchPetsRequest, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let query = errors.check("query", SearchQuery::new(&self.query));
+        let limit = errors.check("limit", PageSize::new(self.limit));
+
+        match (query, limit) {
+            (Some(query), Some(limit)) => Ok(SearchPetsRequest::new(query, limit.into_inner())),
+            _ => Err(errors),
+        }
+    }
+}
+
+impl proto::ChangePetStatusRequest {
+    /// Converts the message into a domain request; the status must be specified.
+    fn try_into_domain(self) -> Result<ChangePetStatusRequest, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let status = match status_from_proto(self.status) {
+            Ok(Some(status)) => Some(status),
+            Ok(None) => errors.check("status", Err::<pet::Status, _>(InvalidEnumValue(self.status))),
+            Err(e) => errors.check("status", Err::<pet::Status, _>(e)),
+        };
+
+        match status {
+            Some(status) => Ok(ChangePetStatusRequest::new(self.id, status)
+                .with_expected_version(self.expected_version)
+                .with_actor(self.actor)),
+            None => Err(errors),
+        }
+    }
+}
+
+/// A value that is not one of the allowed values of its enum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("{0} is not an allowed value")]
+struct InvalidEnumValue(i32);
+
+impl crate::domain::petstore::models::validation::Violation for InvalidEnumValue {
+    fn code(&self) -> &'static str {
+        "invalid"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use tonic::Code;
+    use tonic_types::StatusExt;
+
+    use crate::domain::petstore::models::pet::{
+        ChangePetStatusError, CreatePetError, DeletePetError, ListPetsError, PetHistoryError,
+        PetPage,
+    };
+    use crate::domain::petstore::models::search::SearchPetsError;
+    use crate::inbound::grpc::proto::pet_service_server::PetService as _;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct MockPetService {
+        add_pet_result: Arc<Mutex<Option<Result<Pet, CreatePetError>>>>,
+        add_pet_request: Arc<Mutex<Option<CreatePetRequest>>>,
+    }
+
+    impl PetService for MockPetService {
+        async fn add_pet(&self, req: &CreatePetRequest) -> Result<Pet, CreatePetError> {
+            *self.add_pet_request.lock().unwrap() = Some(req.clone());
+            let mut guard = self.add_pet_result.lock().unwrap();
+            guard.take().unwrap_or_else(|| Err(CreatePetError::Unknown(anyhow::anyhow!("Mock add_pet result not set"))))
+        }
+
+        async fn find_pet_by_id(&self, _: i64) -> Result<Option<Pet>, CreatePetError> {
+            Ok(None)
+        }
+
+        async fn find_pet_version(&self, _: i64) -> Result<Option<i64>, CreatePetError> {
+            Err(CreatePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn list_pets(&self, _: &ListPetsRequest) -> Result<PetPage, ListPetsError> {
+            Ok(PetPage::default())
+        }
+
+        async fn search_pets(&self, _: &SearchPetsRequest) -> Result<Vec<PetSearchHit>, SearchPetsError> {
+            Err(SearchPetsError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn change_pet_status(&self, _: &ChangePetStatusRequest) -> Result<Pet, ChangePetStatusError> {
+            Err(ChangePetStatusError::Conflict { id: 1, expected: 1, actual: 2 })
+        }
+
+        async fn delete_pet(&self, pet_id: i64) -> Result<(), DeletePetError> {
+            Err(DeletePetError::NotFound { id: pet_id })
+        }
+
+        async fn pet_status_history(&self, _: i64) -> Result<Vec<StatusChange>, PetHistoryError> {
+            Err(PetHistoryError::NotFound { id: 1 })
+        }
+    }
+
+    fn grpc_service(service: &MockPetService) -> PetGrpcService<MockPetService> {
+        PetGrpcService::new(Arc::new(service.clone()))
+    }
+
+    fn create_request() -> proto::CreatePetRequest {
+        proto::CreatePetRequest {
+            name: "doggie".to_string(),
+            category: Some(proto::Category { id: Some(1), name: None }),
+            photo_urls: vec!["http://example.com/dog.jpg".to_string()],
+            tags: vec![proto::Tag { id: None, name: Some(" friendly ".to_string()) }],
+            status: proto::PetStatus::Pending.into(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_pet_success() {
+        let mut pet = Pet::new("doggie".to_string());
+        pet.id = Some(7);
+        pet.set_status(pet::Status::Pending);
+        let service = MockPetService::default();
+        *service.add_pet_result.lock().unwrap() = Some(Ok(pet));
+
+        let response = grpc_service(&service).create_pet(Request::new(create_request())).await.unwrap();
+
+        let created = response.into_inner();
+        assert_eq!(created.id, Some(7));
+        assert_eq!(created.status(), proto::PetStatus::Pending);
+        let req = service.add_pet_request.lock().unwrap().take().unwrap();
+        assert_eq!(req.tags()[0].name.as_deref(), Some("friendly"));
+        assert_eq!(req.status(), &Some(pet::Status::Pending));
+    }
+
+    #[tokio::test]
+    async fn test_create_pet_duplicate() {
+        let service = MockPetService::default();
+        *service.add_pet_result.lock().unwrap() = Some(Err(CreatePetError::Duplicate { name: "doggie".to_string() }));
+
+        let status = grpc_service(&service).create_pet(Request::new(create_request())).await.unwrap_err();
+
+        assert_eq!(status.code(), Code::AlreadyExists);
+    }
+
+    #[tokio::test]
+    async fn test_create_pet_invalid_fields() {
+        let service = MockPetService::default();
+        let request = proto::CreatePetRequest {
+            name: String::new(),
+            status: 42,
+            ..create_request()
+        };
+
+        let status = grpc_service(&service).create_pet(Request::new(request)).await.unwrap_err();
+
+        assert_eq!(status.code(), Code::InvalidArgument);
+        let fields: Vec<_> = status
+            .get_details_bad_request()
+            .unwrap()
+            .field_violations
+            .into_iter()
+            .map(|v| v.field)
+            .collect();
+        assert_eq!(fields, vec!["name", "status"]);
+        assert!(service.add_pet_request.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_create_pet_invalid_nested_fields() {
+        let service = MockPetService::default();
+        let request = proto::CreatePetRequest {
+            category: Some(proto::Category { id: None, name: Some("Dogs".to_string()) }),
+            tags: vec![
+                proto::Tag { id: None, name: Some("friendly".to_string()) },
+                proto::Tag { id: None, name: Some("friendly ".to_string()) },
+            ],
+            ..create_request()
+        };
+
+        let status = grpc_service(&service).create_pet(Request::new(request)).await.unwrap_err();
+
+        let fields: Vec<_> = status
+            .get_details_bad_request()
+            .unwrap()
+            .field_violations
+            .into_iter()
+            .map(|v| v.field)
+            .collect();
+        assert_eq!(fields, vec!["category.id", "tags[1].name"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_pet_not_found() {
+        let status = grpc_service(&MockPetService::default())
+            .get_pet(Request::new(proto::GetPetRequest { id: 7 }))
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_list_pets_invalid_page() {
+        let request = proto::ListPetsRequest {
+            page_size: Some(0),
+            page_token: "not a cursor".to_string(),
+            ..Default::default()
+        };
+
+        let status = grpc_service(&MockPetService::default())
+            .list_pets(Request::new(request))
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(status.get_details_bad_request().unwrap().field_violations.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_change_pet_status_requires_status() {
+        let status = grpc_service(&MockPetService::default())
+            .change_pet_status(Request::new(proto::ChangePetStatusRequest { id: 1, ..Default::default() }))
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_change_pet_status_conflict() {
+        let request = proto::ChangePetStatusRequest {
+            id: 1,
+            status: proto::PetStatus::Sold.into(),
+            expected_version: Some(1),
+            ..Default::default()
+        };
+
+        let status = grpc_service(&MockPetService::default())
+            .change_pet_status(Request::new(request))
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), Code::Aborted);
+    }
+
+    #[tokio::test]
+    async fn test_delete_pet_not_found() {
+        let status = grpc_service(&MockPetService::default())
+            .delete_pet(Request::new(proto::DeletePetRequest { id: 7 }))
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), Code::NotFound);
+    }
+}
@@ -0,0 +1,5 @@
+//! The messages and services generated from `proto/petstore/v1/pets.proto`.
+
+#![allow(clippy::all)]
+
+tonic::include_proto!("petstore.v1");
@@ -0,0 +1,159 @@
+/*
+   Module `status` maps domain errors onto gRPC status codes, as the `problem` module of the
+   HTTP adapter maps them onto HTTP statuses:
+
+   - invalid requests are INVALID_ARGUMENT, with a `google.rpc.BadRequest` detail listing every
+     invalid field;
+   - missing pets are NOT_FOUND;
+   - duplicates are ALREADY_EXISTS;
+   - references to missing categories and forbidden status transitions are
+     FAILED_PRECONDITION;
+   - concurrent modifications are ABORTED, so that clients retry from a fresh read;
+   - anything else is INTERNAL, with the cause logged but not sent to the client.
+*/
+
+use tonic::{Code, Status};
+use tonic_types::{ErrorDetails, StatusExt};
+
+use crate::domain::petstore::models::pet::{
+    ChangePetStatusError, CreatePetError, DeletePetError, ListPetsError, PetHistoryError,
+};
+use crate::domain::petstore::models::search::SearchPetsError;
+use crate::domain::petstore::models::validation::ValidationErrors;
+
+/// An INVALID_ARGUMENT status listing every invalid field of the request.
+pub(super) fn invalid_argument(message: &str, errors: &ValidationErrors) -> Status {
+    let mut details = ErrorDetails::new();
+    for violation in errors.violations() {
+        details.add_bad_request_violation(violation.field.clone(), violation.message.clone());
+    }
+    Status::with_error_details(Code::InvalidArgument, format!("{}: {}", message, errors), details)
+}
+
+pub(super) fn pet_not_found(id: i64) -> Status {
+    Status::not_found(format!("pet with id {} not found", id))
+}
+
+/// An INTERNAL status for `cause`, which is logged but never sent to the client.
+fn internal(cause: anyhow::Error) -> Status {
+    tracing::error!("{:?}\n{}", cause, cause.backtrace());
+    Status::internal("internal server error")
+}
+
+impl From<CreatePetError> for Status {
+    fn from(e: CreatePetError) -> Self {
+        match e {
+            CreatePetError::Duplicate { .. }
+            | CreatePetError::DuplicateId { .. }
+            | CreatePetError::DuplicateTagId { .. } => Status::already_exists(e.to_string()),
+            CreatePetError::UnknownCategory { .. } => Status::failed_precondition(e.to_string()),
+            CreatePetError::CategoryIdRequired | CreatePetError::IdRequired { .. } => {
+                Status::invalid_argument(e.to_string())
+            }
+            CreatePetError::Unknown(cause) => internal(cause),
+        }
+    }
+}
+
+impl From<ListPetsError> for Status {
+    fn from(e: ListPetsError) -> Self {
+        match e {
+            ListPetsError::Unknown(cause) => internal(cause),
+        }
+    }
+}
+
+impl From<SearchPetsError> for Status {
+    fn from(e: SearchPetsError) -> Self {
+        match e {
+            SearchPetsError::Unknown(cause) => internal(cause),
+        }
+    }
+}
+
+impl From<ChangePetStatusError> for Status {
+    fn from(e: ChangePetStatusError) -> Self {
+        match e {
+            ChangePetStatusError::NotFound { .. } => Status::not_found(e.to_string()),
+            ChangePetStatusError::InvalidTransition(_) => Status::failed_precondition(e.to_string()),
+            ChangePetStatusError::Conflict { .. } => Status::aborted(e.to_string()),
+            ChangePetStatusError::Unknown(cause) => internal(cause),
+        }
+    }
+}
+
+impl From<DeletePetError> for Status {
+    fn from(e: DeletePetError) -> Self {
+        match e {
+            DeletePetError::NotFound { .. } => Status::not_found(e.to_string()),
+            DeletePetError::Unknown(cause) => internal(cause),
+        }
+    }
+}
+
+impl From<PetHistoryError> for Status {
+    fn from(e: PetHistoryError) -> Self {
+        match e {
+            PetHistoryError::NotFound { .. } => Status::not_found(e.to_string()),
+            PetHistoryError::Unknown(cause) => internal(cause),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::petstore::models::id::IdKind;
+    use crate::domain::petstore::models::pet::{InvalidTransition, Status as PetStatus};
+    use crate::domain::petstore::models::value_objects::PetName;
+
+    use super::*;
+
+    #[test]
+    fn test_create_pet_error_codes() {
+        let cases = [
+            (CreatePetError::Duplicate { name: "Rex".to_string() }, Code::AlreadyExists),
+            (CreatePetError::DuplicateId { id: 7 }, Code::AlreadyExists),
+            (CreatePetError::DuplicateTagId { id: 7 }, Code::AlreadyExists),
+            (CreatePetError::UnknownCategory { id: 3 }, Code::FailedPrecondition),
+            (CreatePetError::CategoryIdRequired, Code::InvalidArgument),
+            (CreatePetError::IdRequired { kind: IdKind::Pet }, Code::InvalidArgument),
+            (CreatePetError::Unknown(anyhow::anyhow!("connection reset")), Code::Internal),
+        ];
+        for (error, code) in cases {
+            assert_eq!(Status::from(error).code(), code);
+        }
+    }
+
+    #[test]
+    fn test_change_pet_status_error_codes() {
+        let transition = InvalidTransition { from: PetStatus::Sold, to: PetStatus::Available };
+        let cases = [
+            (ChangePetStatusError::NotFound { id: 7 }, Code::NotFound),
+            (ChangePetStatusError::InvalidTransition(transition), Code::FailedPrecondition),
+            (ChangePetStatusError::Conflict { id: 7, expected: 1, actual: 2 }, Code::Aborted),
+        ];
+        for (error, code) in cases {
+            assert_eq!(Status::from(error).code(), code);
+        }
+    }
+
+    #[test]
+    fn test_internal_errors_hide_their_cause() {
+        let status = Status::from(CreatePetError::Unknown(anyhow::anyhow!("password=hunter2")));
+
+        assert_eq!(status.message(), "internal server error");
+    }
+
+    #[test]
+    fn test_invalid_argument_lists_fields() {
+        let mut errors = ValidationErrors::new();
+        errors.check("name", PetName::new(""));
+
+        let status = invalid_argument("invalid pet", &errors);
+
+        assert_eq!(status.code(), Code::InvalidArgument);
+        let bad_request = status.get_details_bad_request().unwrap();
+        assert_eq!(bad_request.field_violations.len(), 1);
+        assert_eq!(bad_request.field_violations[0].field, "name");
+    }
+}
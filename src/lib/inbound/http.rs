@@ -3,21 +3,62 @@
     implementation is opaque to module consumers.
 */
 
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Context;
+use axum::http::HeaderValue;
 use axum::Router;
-use axum::routing::{post, get};
+use axum::routing::{delete, get, post, put};
 use tokio::net;
 
-use crate::domain::petstore::ports::PetService;
+use crate::domain::petstore::ports::{
+    CategoryService, IdempotencyService, PetChangeService, PetService, PetTransferService,
+    TagService, WebhookService,
+};
 
+mod cache_control;
+mod cors;
 mod handlers;
+mod idempotency;
+mod limits;
+mod rate_limit;
+mod request_id;
+mod security_headers;
+
+pub use cache_control::DEFAULT_CACHE_CONTROL;
+pub use cors::{CorsConfig, CorsConfigError, CorsOrigins, DEFAULT_CORS_MAX_AGE};
+pub use idempotency::{DEFAULT_IDEMPOTENCY_LEASE, DEFAULT_IDEMPOTENCY_WINDOW};
+pub use limits::{
+    RequestLimits, DEFAULT_IMPORT_TIMEOUT, DEFAULT_MAX_BODY_BYTES, DEFAULT_MAX_IMPORT_BODY_BYTES,
+    DEFAULT_REQUEST_TIMEOUT,
+};
+pub use rate_limit::{RateLimit, RateLimitConfig, RateLimitConfigError, RouteLimit};
+pub use request_id::{RequestId, REQUEST_ID_HEADER};
+pub use security_headers::SecurityHeadersConfig;
 
 /// Configuration for the HTTP server.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HttpServerConfig<'a> {
     pub port: &'a str,
+    /// The `Cache-Control` header sent with responses to read requests.
+    pub cache_control: &'a str,
+    /// How long the response to a request with an `Idempotency-Key` is replayed to retries.
+    pub idempotency_window: Duration,
+    /// How long an `Idempotency-Key` stays claimed by a request still being processed, after
+    /// which a retry may take it over. Should exceed the request timeout.
+    pub idempotency_lease: Duration,
+    /// How the requests of each client are limited, or `None` to admit every request.
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Which browser front-ends served from other origins may call the API, or `None` for none.
+    pub cors: Option<CorsConfig>,
+    /// The optional security headers sent with every response.
+    pub security_headers: SecurityHeadersConfig,
+    /// The limits on the size and duration of every request.
+    pub limits: RequestLimits,
+    /// Whether responses are compressed with gzip or brotli for clients accepting either.
+    pub compression: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -26,6 +67,46 @@ struct AppState<BS: PetService> {
     pet_service: Arc<BS>,
 }
 
+#[derive(Debug, Clone)]
+/// The state shared between the handlers streaming pet changes.
+struct PetChangeState<PS: PetChangeService> {
+    change_service: Arc<PS>,
+}
+
+#[derive(Debug, Clone)]
+/// The state shared between the handlers importing and exporting pets in bulk.
+struct PetTransferState<TS: PetTransferService> {
+    transfer_service: Arc<TS>,
+}
+
+#[derive(Debug, Clone)]
+/// The state shared between category management handlers.
+struct CategoryState<CS: CategoryService> {
+    category_service: Arc<CS>,
+}
+
+#[derive(Debug, Clone)]
+/// The state shared between tag administration handlers.
+struct TagState<TS: TagService> {
+    tag_service: Arc<TS>,
+}
+
+#[derive(Debug, Clone)]
+/// The state shared between webhook management handlers.
+struct WebhookState<WS: WebhookService> {
+    webhook_service: Arc<WS>,
+}
+
+#[derive(Debug, Clone)]
+/// The state of the middleware making requests with an `Idempotency-Key` safe to retry.
+struct IdempotencyState<IS: IdempotencyService> {
+    idempotency_service: Arc<IS>,
+    window: Duration,
+    lease: Duration,
+    /// The largest request body fingerprinted, that of [RequestLimits::max_body_bytes].
+    max_body_bytes: usize,
+}
+
 /// The application's HTTP server. The underlying HTTP package is opaque to module consumers.
 pub struct HttpServer {
     router: axum::Router,
@@ -35,25 +116,103 @@ pub struct HttpServer {
 impl HttpServer {
     /// Returns a new HTTP server bound to the port specified in `config`.
     pub async fn new(
-        pet_service: impl PetService,
+        service: impl PetService
+            + PetChangeService
+            + PetTransferService
+            + CategoryService
+            + TagService
+            + IdempotencyService
+            + WebhookService,
         config: HttpServerConfig<'_>,
     ) -> anyhow::Result<Self> {
-        let trace_layer = tower_http::trace::TraceLayer::new_for_http().make_span_with(
-            |request: &axum::extract::Request<_>| {
+        // Every event logged while handling a request carries its id through this span, which
+        // continues the trace of the caller if it sent a traceparent
+        let trace_layer = tower_http::trace::TraceLayer::new_for_http()
+            .make_span_with(|request: &axum::extract::Request<_>| {
                 let uri = request.uri().to_string();
-                tracing::info_span!("http_request", method = ?request.method(), uri)
-            },
-        );
+                let request_id = request.extensions().get::<RequestId>().map(|id| id.to_string());
+                let span = tracing::info_span!(
+                    "http_request",
+                    otel.kind = "server",
+                    method = ?request.method(),
+                    uri,
+                    request_id
+                );
+                crate::telemetry::set_remote_parent(&span, request.headers());
+                span
+            })
+            .on_response(tower_http::trace::DefaultOnResponse::new().level(tracing::Level::INFO));
+
+        let cache_control = HeaderValue::from_str(config.cache_control)
+            .with_context(|| format!("invalid Cache-Control value {}", config.cache_control))?;
 
         // Construct dependencies to inject into handlers.
+        let service = Arc::new(service);
         let state = AppState {
-            pet_service: Arc::new(pet_service),
+            pet_service: service.clone(),
+        };
+        let change_state = PetChangeState {
+            change_service: service.clone(),
+        };
+        let transfer_state = PetTransferState {
+            transfer_service: service.clone(),
+        };
+        let category_state = CategoryState {
+            category_service: service.clone(),
+        };
+        let idempotency_state = IdempotencyState {
+            idempotency_service: service.clone(),
+            window: config.idempotency_window,
+            lease: config.idempotency_lease,
+            max_body_bytes: config.limits.max_body_bytes,
+        };
+        let tag_state = TagState {
+            tag_service: service.clone(),
         };
+        let webhook_state = WebhookState {
+            webhook_service: service,
+        };
+
+        // Imports stream bodies larger than those of any other request, and take longer
+        let limits = config.limits;
+        let api = api_routes(idempotency_state)
+            .with_state(state)
+            .merge(pet_change_routes().with_state(change_state))
+            .merge(category_routes().with_state(category_state))
+            .merge(tag_routes().with_state(tag_state))
+            .merge(webhook_routes().with_state(webhook_state));
+        let mut api = limits
+            .apply(api)
+            .merge(limits.apply_to_imports(pet_transfer_routes().with_state(transfer_state)))
+            .layer(axum::extract::DefaultBodyLimit::disable());
+        if let Some(rate_limit) = config.rate_limit {
+            let limiter = rate_limit::RateLimiter::new(rate_limit);
+            api = api
+                .merge(usage_routes().with_state(limiter.clone()))
+                .route_layer(axum::middleware::from_fn_with_state(limiter, rate_limit::rate_limit));
+        }
 
-        let router = axum::Router::new()
-            .nest("/api", api_routes())
+        let mut router = axum::Router::new()
+            .nest("/api", api)
+            .fallback(handlers::problem::route_not_found)
+            .layer(axum::middleware::from_fn_with_state(
+                cache_control,
+                cache_control::cache_control,
+            ));
+        if config.compression {
+            router = router.layer(tower_http::compression::CompressionLayer::new());
+        }
+        // Outside of the limits, so that browsers may read the problems they report
+        if let Some(cors) = &config.cors {
+            router = router.layer(cors.layer()?);
+        }
+        let router = router
+            .layer(axum::middleware::from_fn_with_state(
+                security_headers::SecurityHeaders::new(&config.security_headers),
+                security_headers::security_headers,
+            ))
             .layer(trace_layer)
-            .with_state(state);
+            .layer(axum::middleware::from_fn(request_id::request_id));
 
         let listener = net::TcpListener::bind(format!("0.0.0.0:{}", config.port))
             .await
@@ -65,18 +224,96 @@ impl HttpServer {
     /// Runs the HTTP server.
     pub async fn run(self) -> anyhow::Result<()> {
         tracing::debug!("listening on {}", self.listener.local_addr().unwrap());
-        axum::serve(self.listener, self.router)
+        // Clients are told apart by their address when rate limited
+        let service = self.router.into_make_service_with_connect_info::<SocketAddr>();
+        axum::serve(self.listener, service)
             .await
             .context("received error from running server")?;
         Ok(())
     }
 }
 
-fn api_routes<BS: PetService>() -> Router<AppState<BS>> {
+fn api_routes<BS: PetService, IS: IdempotencyService>(
+    idempotency_state: IdempotencyState<IS>,
+) -> Router<AppState<BS>> {
     use crate::inbound::http::handlers::add_pet::add_pet;
+    use crate::inbound::http::handlers::change_pet_status::change_pet_status;
+    use crate::inbound::http::handlers::delete_pet::delete_pet;
     use crate::inbound::http::handlers::find_pet_by_id::find_pet_by_id;
+    use crate::inbound::http::handlers::list_pets::list_pets;
+    use crate::inbound::http::handlers::pet_status_history::pet_status_history;
+    use crate::inbound::http::handlers::search_pets::search_pets;
 
     Router::new()
-        .route("/pet", post(add_pet::<BS>))
-        .route("/pet/:petId", get(find_pet_by_id::<BS>))
-}
\ No newline at end of file
+        .route(
+            "/pet",
+            post(add_pet::<BS>)
+                .layer(axum::middleware::from_fn_with_state(
+                    idempotency_state,
+                    idempotency::idempotent::<IS>,
+                ))
+                .get(list_pets::<BS>),
+        )
+        .route("/pet/search", get(search_pets::<BS>))
+        .route("/pet/{petId}", get(find_pet_by_id::<BS>).delete(delete_pet::<BS>))
+        .route("/pet/{petId}/status", post(change_pet_status::<BS>))
+        .route("/pet/{petId}/history", get(pet_status_history::<BS>))
+}
+
+fn pet_change_routes<PS: PetChangeService>() -> Router<PetChangeState<PS>> {
+    use crate::inbound::http::handlers::pet_changes::{pet_change_events, pet_change_socket};
+
+    Router::new()
+        .route("/pet/events", get(pet_change_events::<PS>))
+        .route("/pet/events/ws", get(pet_change_socket::<PS>))
+}
+
+fn pet_transfer_routes<TS: PetTransferService>() -> Router<PetTransferState<TS>> {
+    use crate::inbound::http::handlers::pet_transfer::{export_pets, import_pets};
+
+    Router::new()
+        .route("/pet/import", post(import_pets::<TS>))
+        .route("/pet/export", get(export_pets::<TS>))
+}
+
+fn category_routes<CS: CategoryService>() -> Router<CategoryState<CS>> {
+    use crate::inbound::http::handlers::categories::{
+        create_category, delete_category, find_category_by_id, list_categories, rename_category,
+    };
+
+    Router::new()
+        .route("/category", post(create_category::<CS>).get(list_categories::<CS>))
+        .route(
+            "/category/{categoryId}",
+            get(find_category_by_id::<CS>)
+                .put(rename_category::<CS>)
+                .delete(delete_category::<CS>),
+        )
+}
+
+fn tag_routes<TS: TagService>() -> Router<TagState<TS>> {
+    use crate::inbound::http::handlers::tags::{
+        delete_tag, delete_unused_tags, list_tags, merge_tags, rename_tag,
+    };
+
+    Router::new()
+        .route("/tag", get(list_tags::<TS>))
+        .route("/tag/unused", delete(delete_unused_tags::<TS>))
+        .route("/tag/{tagId}", put(rename_tag::<TS>).delete(delete_tag::<TS>))
+        .route("/tag/{tagId}/merge", post(merge_tags::<TS>))
+}
+
+fn webhook_routes<WS: WebhookService>() -> Router<WebhookState<WS>> {
+    use crate::inbound::http::handlers::webhooks::{
+        create_webhook, delete_webhook, find_webhook, list_webhooks, webhook_deliveries,
+    };
+
+    Router::new()
+        .route("/webhook", post(create_webhook::<WS>).get(list_webhooks::<WS>))
+        .route("/webhook/{webhookId}", get(find_webhook::<WS>).delete(delete_webhook::<WS>))
+        .route("/webhook/{webhookId}/deliveries", get(webhook_deliveries::<WS>))
+}
+
+fn usage_routes() -> Router<rate_limit::RateLimiter> {
+    Router::new().route("/usage", get(rate_limit::client_usage))
+}
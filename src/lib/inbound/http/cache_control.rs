@@ -0,0 +1,76 @@
+/*!
+    Module `cache_control` sets the configured `Cache-Control` header on responses to read
+    requests, so that CDNs and clients know how long they may reuse a response and when they
+    must revalidate it.
+*/
+
+use axum::extract::{Request, State};
+use axum::http::header::CACHE_CONTROL;
+use axum::http::{HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+
+/// The `Cache-Control` value used unless configured otherwise: responses may be stored, but
+/// must be revalidated with their `ETag` before each reuse.
+pub const DEFAULT_CACHE_CONTROL: &str = "no-cache";
+
+/// Middleware adding `value` as `Cache-Control` to successful and `304 Not Modified` responses
+/// to `GET` and `HEAD` requests, unless the handler already set one.
+pub(super) async fn cache_control(
+    State(value): State<HeaderValue>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let is_read = matches!(*request.method(), Method::GET | Method::HEAD);
+    let mut response = next.run(request).await;
+    let is_cacheable =
+        response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED;
+    if is_read && is_cacheable {
+        response.headers_mut().entry(CACHE_CONTROL).or_insert(value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn router() -> Router {
+        Router::new()
+            .route("/ok", get(|| async { "ok" }).post(|| async { "created" }))
+            .route("/missing", get(|| async { StatusCode::NOT_FOUND }))
+            .route(
+                "/custom",
+                get(|| async { ([(CACHE_CONTROL, "no-store")], "ok") }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                HeaderValue::from_static("max-age=60"),
+                cache_control,
+            ))
+    }
+
+    async fn cache_control_of(method: Method, uri: &str) -> Option<HeaderValue> {
+        let request = Request::builder().method(method).uri(uri).body(Body::empty()).unwrap();
+        let response = router().oneshot(request).await.unwrap();
+        response.headers().get(CACHE_CONTROL).cloned()
+    }
+
+    #[tokio::test]
+    async fn test_cache_control() {
+        assert_eq!(
+            cache_control_of(Method::GET, "/ok").await,
+            Some(HeaderValue::from_static("max-age=60"))
+        );
+        assert_eq!(cache_control_of(Method::POST, "/ok").await, None);
+        assert_eq!(cache_control_of(Method::GET, "/missing").await, None);
+        assert_eq!(
+            cache_control_of(Method::GET, "/custom").await,
+            Some(HeaderValue::from_static("no-store"))
+        );
+    }
+}
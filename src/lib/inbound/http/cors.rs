@@ -0,0 +1,272 @@
+/*!
+    Module `cors` lets browser front-ends served from other origins call the API. Preflight
+    requests from an allowed origin are answered with the methods and headers the API accepts,
+    and responses to it expose the headers clients read, such as `ETag` and the rate limits.
+    Requests from other origins are handled as usual, but browsers hide the responses.
+*/
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use axum::http::header::{CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH, LOCATION, RETRY_AFTER};
+use axum::http::{HeaderName, HeaderValue, Method};
+use thiserror::Error;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::inbound::http::handlers::change_pet_status::ACTOR_HEADER;
+use crate::inbound::http::idempotency::{IDEMPOTENCY_KEY_HEADER, IDEMPOTENT_REPLAYED_HEADER};
+use crate::inbound::http::rate_limit::{
+    API_KEY_HEADER, QUOTA_LIMIT_HEADER, QUOTA_REMAINING_HEADER, QUOTA_RESET_HEADER,
+    RATE_LIMIT_LIMIT_HEADER, RATE_LIMIT_REMAINING_HEADER, RATE_LIMIT_RESET_HEADER,
+};
+use crate::inbound::http::request_id::REQUEST_ID_HEADER;
+
+/// How long browsers may cache the answer to a preflight request unless configured otherwise.
+pub const DEFAULT_CORS_MAX_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// The methods of the API.
+const ALLOWED_METHODS: [Method; 5] = [Method::GET, Method::HEAD, Method::POST, Method::PUT, Method::DELETE];
+
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum CorsConfigError {
+    #[error("CORS origin {0} is not of the form <http|https>://<host>[:<port>]")]
+    InvalidOrigin(String),
+    #[error("CORS credentials cannot be allowed from any origin, only from listed ones")]
+    CredentialsFromAnyOrigin,
+}
+
+/// The origins allowed to call the API from a browser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CorsOrigins {
+    Any,
+    /// Origins such as `https://shop.example.com`, compared exactly.
+    Only(Vec<HeaderValue>),
+}
+
+impl FromStr for CorsOrigins {
+    type Err = CorsConfigError;
+
+    /// Parses `*` for any origin, or a comma-separated list of origins, e.g.
+    /// `https://shop.example.com,http://localhost:3000`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim() == "*" {
+            return Ok(Self::Any);
+        }
+        s.split(',').map(parse_origin).collect::<Result<_, _>>().map(Self::Only)
+    }
+}
+
+/// Parses an origin, normalized as browsers send it in the `Origin` header, e.g. without a
+/// trailing slash or a default port.
+fn parse_origin(s: &str) -> Result<HeaderValue, CorsConfigError> {
+    let invalid = || CorsConfigError::InvalidOrigin(s.trim().to_string());
+    let url = url::Url::parse(s.trim()).map_err(|_| invalid())?;
+    let is_bare = url.path() == "/" && url.query().is_none() && url.fragment().is_none();
+    // Wildcards are valid in host names, but would be compared literally
+    let has_host = url.host_str().is_some_and(|host| !host.contains('*'));
+    if !matches!(url.scheme(), "http" | "https") || !is_bare || !has_host || !url.username().is_empty() {
+        return Err(invalid());
+    }
+    HeaderValue::from_str(&url.origin().ascii_serialization()).map_err(|_| invalid())
+}
+
+/// Which cross-origin requests browsers may send.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsConfig {
+    pub origins: CorsOrigins,
+    /// Whether requests may carry cookies or HTTP authentication, only from listed origins.
+    pub allow_credentials: bool,
+    /// How long browsers may cache the answer to a preflight request.
+    pub max_age: Duration,
+}
+
+impl CorsConfig {
+    pub fn new(origins: CorsOrigins) -> Self {
+        Self {
+            origins,
+            allow_credentials: false,
+            max_age: DEFAULT_CORS_MAX_AGE,
+        }
+    }
+
+    /// Returns the layer answering preflight requests and adding CORS headers to responses.
+    ///
+    /// # Errors
+    ///
+    /// - [CorsConfigError::CredentialsFromAnyOrigin] if credentials are allowed from any origin,
+    ///   which browsers refuse.
+    pub(super) fn layer(&self) -> Result<CorsLayer, CorsConfigError> {
+        let origins = match &self.origins {
+            CorsOrigins::Any if self.allow_credentials => {
+                return Err(CorsConfigError::CredentialsFromAnyOrigin)
+            }
+            CorsOrigins::Any => AllowOrigin::any(),
+            CorsOrigins::Only(origins) => AllowOrigin::list(origins.iter().cloned()),
+        };
+        Ok(CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(ALLOWED_METHODS)
+            .allow_headers(allowed_headers())
+            .expose_headers(exposed_headers())
+            .allow_credentials(self.allow_credentials)
+            .max_age(self.max_age))
+    }
+}
+
+/// The request headers the API reads, besides those browsers always allow.
+fn allowed_headers() -> Vec<HeaderName> {
+    vec![
+        CONTENT_TYPE,
+        IF_MATCH,
+        IF_NONE_MATCH,
+        HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+        HeaderName::from_static(API_KEY_HEADER),
+        HeaderName::from_static(ACTOR_HEADER),
+        REQUEST_ID_HEADER,
+        HeaderName::from_static("traceparent"),
+        HeaderName::from_static("tracestate"),
+    ]
+}
+
+/// The response headers scripts may read, besides those browsers always expose.
+fn exposed_headers() -> Vec<HeaderName> {
+    vec![
+        ETAG,
+        LOCATION,
+        RETRY_AFTER,
+        REQUEST_ID_HEADER,
+        HeaderName::from_static(IDEMPOTENT_REPLAYED_HEADER),
+        RATE_LIMIT_LIMIT_HEADER,
+        RATE_LIMIT_REMAINING_HEADER,
+        RATE_LIMIT_RESET_HEADER,
+        QUOTA_LIMIT_HEADER,
+        QUOTA_REMAINING_HEADER,
+        QUOTA_RESET_HEADER,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::extract::Request;
+    use axum::http::header::{
+        ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+        ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_MAX_AGE,
+        ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN,
+    };
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    const SHOP: &str = "https://shop.example.com";
+
+    fn router(config: CorsConfig) -> Router {
+        Router::new()
+            .route("/pet", get(|| async { "pets" }).post(|| async { "created" }))
+            .layer(config.layer().unwrap())
+    }
+
+    async fn send(config: CorsConfig, request: Request) -> (StatusCode, HeaderMap) {
+        let response = router(config).oneshot(request).await.unwrap();
+        (response.status(), response.headers().clone())
+    }
+
+    fn preflight(origin: &str) -> Request {
+        Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/pet")
+            .header(ORIGIN, origin)
+            .header(ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .header(ACCESS_CONTROL_REQUEST_HEADERS, "content-type,idempotency-key")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn get_from(origin: &str) -> Request {
+        Request::builder().uri("/pet").header(ORIGIN, origin).body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_origins() {
+        assert_eq!("*".parse(), Ok(CorsOrigins::Any));
+        assert_eq!(
+            "https://shop.example.com/, http://localhost:3000,https://a.example.com:443".parse(),
+            Ok(CorsOrigins::Only(vec![
+                HeaderValue::from_static(SHOP),
+                HeaderValue::from_static("http://localhost:3000"),
+                HeaderValue::from_static("https://a.example.com"),
+            ]))
+        );
+        let invalid_origins = [
+            "",
+            "shop.example.com",
+            "ftp://shop.example.com",
+            "https://shop.example.com/app",
+            "https://*.example.com",
+        ];
+        for invalid in invalid_origins {
+            assert!(invalid.parse::<CorsOrigins>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_credentials_from_any_origin_are_refused() {
+        let config = CorsConfig {
+            allow_credentials: true,
+            ..CorsConfig::new(CorsOrigins::Any)
+        };
+
+        assert_eq!(config.layer().unwrap_err(), CorsConfigError::CredentialsFromAnyOrigin);
+    }
+
+    #[tokio::test]
+    async fn test_preflight_from_allowed_origin() {
+        let config = CorsConfig {
+            allow_credentials: true,
+            ..CorsConfig::new(SHOP.parse().unwrap())
+        };
+
+        let (status, headers) = send(config, preflight(SHOP)).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_ORIGIN], SHOP);
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+        assert_eq!(headers[ACCESS_CONTROL_MAX_AGE], "600");
+        let methods = headers[ACCESS_CONTROL_ALLOW_METHODS].to_str().unwrap();
+        assert!(methods.contains("POST") && methods.contains("DELETE"), "{}", methods);
+        let allowed = headers[ACCESS_CONTROL_ALLOW_HEADERS].to_str().unwrap();
+        assert!(allowed.contains("content-type") && allowed.contains("idempotency-key"), "{}", allowed);
+    }
+
+    #[tokio::test]
+    async fn test_request_from_allowed_origin() {
+        let (status, headers) = send(CorsConfig::new(SHOP.parse().unwrap()), get_from(SHOP)).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_ORIGIN], SHOP);
+        let exposed = headers[ACCESS_CONTROL_EXPOSE_HEADERS].to_str().unwrap();
+        assert!(exposed.contains("etag") && exposed.contains("x-ratelimit-remaining"), "{}", exposed);
+    }
+
+    #[tokio::test]
+    async fn test_request_from_other_origin() {
+        let config = CorsConfig::new(SHOP.parse().unwrap());
+
+        let (_, headers) = send(config.clone(), preflight("https://evil.example.com")).await;
+        assert!(!headers.contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+
+        let (status, headers) = send(config, get_from("https://evil.example.com")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!headers.contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[tokio::test]
+    async fn test_any_origin() {
+        let (_, headers) = send(CorsConfig::new(CorsOrigins::Any), get_from("https://any.example.com")).await;
+
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+    }
+}
@@ -1,2 +1,15 @@
 pub mod add_pet;
-pub mod find_pet_by_id;
\ No newline at end of file
+pub mod categories;
+pub mod change_pet_status;
+pub mod delete_pet;
+pub mod etag;
+pub mod extract;
+pub mod find_pet_by_id;
+pub mod list_pets;
+pub mod pet_changes;
+pub mod pet_status_history;
+pub mod pet_transfer;
+pub mod problem;
+pub mod search_pets;
+pub mod tags;
+pub mod webhooks;
\ No newline at end of file
@@ -4,147 +4,83 @@
 */
 
 use axum::extract::State;
-use axum::http::StatusCode;
-use axum::Json;
+use axum::http::header::ETAG;
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
 use axum::response::{IntoResponse, Response};
 use serde::{Deserialize, Serialize};
-use thiserror::Error;
 
-use crate::domain::petstore::models::pet::{Pet, CreatePetRequest, CreatePetError, Status};
+use crate::domain::petstore::models::pet::{Pet, CreatePetFields, CreatePetRequest, CreatePetError, Status};
 use crate::domain::petstore::models::category::Category;
 use crate::domain::petstore::models::tag::Tag;      
-use crate::domain::petstore::models::value_objects::{CategoryError, PetName, PetNameError, PhotoUrls, PhotoUrlsError, StatusError, Tags, TagsError};
+use crate::domain::petstore::models::id::IdKind;
+use crate::domain::petstore::models::validation::ValidationErrors;
+use crate::domain::petstore::models::value_objects::TagsError;
 
 use crate::domain::petstore::ports::PetService;
+use crate::inbound::http::handlers::etag::pet_etag;
+use crate::inbound::http::handlers::extract::Json;
+use crate::inbound::http::handlers::problem::{ApiError, FieldError, Problem, ID_REQUIRED};
 use crate::inbound::http::AppState;
 
 #[derive(Debug, Clone)]
-pub struct ApiSuccess<T: Serialize + PartialEq>(StatusCode, Json<ApiResponseBody<T>>);
+pub struct ApiSuccess<T: Serialize + PartialEq>(StatusCode, HeaderMap, axum::Json<ApiResponseBody<T>>);
 
 impl<T> PartialEq for ApiSuccess<T>
 where
     T: Serialize + PartialEq,
 {
     fn eq(&self, other: &Self) -> bool {
-        self.0 == other.0 && self.1 .0 == other.1 .0
+        self.0 == other.0 && self.1 == other.1 && self.2 .0 == other.2 .0
     }
 }
 
 impl<T: Serialize + PartialEq> ApiSuccess<T> {
-    fn new(status: StatusCode, data: T) -> Self {
-        ApiSuccess(status, Json(ApiResponseBody::new(status, data)))
+    pub fn new(status: StatusCode, data: T) -> Self {
+        ApiSuccess(status, HeaderMap::new(), axum::Json(ApiResponseBody::new(status, data)))
     }
-}
 
-impl<T: Serialize + PartialEq> IntoResponse for ApiSuccess<T> {
-    fn into_response(self) -> Response {
-        (self.0, self.1).into_response()
+    /// Adds a response header, replacing any previous value.
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.1.insert(name, value);
+        self
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum ApiError {
-    InternalServerError(String),
-    UnprocessableEntity(String),
-    BadRequest(String),
-}
-
-impl ApiError {
-    // pub fn status_code(&self) -> StatusCode {
-    //     match self {
-    //         ApiError::InternalServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-    //         ApiError::UnprocessableEntity(_) => StatusCode::UNPROCESSABLE_ENTITY,
-    //         ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
-    //     }
-    // }
-}
-
-impl std::fmt::Display for ApiError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ApiError::InternalServerError(msg) => write!(f, "Internal Server Error: {}", msg),
-            ApiError::UnprocessableEntity(msg) => write!(f, "Unprocessable Entity: {}", msg),
-            ApiError::BadRequest(msg) => write!(f, "Bad Request: {}", msg),
-        }
-    }
-}
-
-impl From<anyhow::Error> for ApiError {
-    fn from(e: anyhow::Error) -> Self {
-        Self::InternalServerError(e.to_string())
+impl<T: Serialize + PartialEq> IntoResponse for ApiSuccess<T> {
+    fn into_response(self) -> Response {
+        (self.0, self.1, self.2).into_response()
     }
 }
 
 impl From<CreatePetError> for ApiError {
     fn from(e: CreatePetError) -> Self {
         match e {
-            CreatePetError::Duplicate { name } => {
-                Self::UnprocessableEntity(format!("pet with name {} already exists", name))
-            }
-            CreatePetError::Unknown(cause) => {
-                tracing::error!("{:?}\n{}", cause, cause.backtrace());
-                Self::InternalServerError("Internal server error".to_string())
-            }
-        }
-    }
-}
-
-impl From<ParseCreatePetHttpRequestError> for ApiError {
-    fn from(e: ParseCreatePetHttpRequestError) -> Self {
-        let message = match e {
-            ParseCreatePetHttpRequestError::Name(cause) => {
-                format!("pet name {} is invalid", cause)
+            CreatePetError::Duplicate { name } => Self::UnprocessableEntity(Problem::new(
+                "duplicate-pet",
+                format!("pet with name {} already exists", name),
+            )),
+            CreatePetError::DuplicateId { .. } => {
+                Self::UnprocessableEntity(Problem::new("duplicate-pet", e.to_string()))
             }
-            ParseCreatePetHttpRequestError::Category(cause) => {
-                format!("category {} is invalid", cause)
+            CreatePetError::DuplicateTagId { .. } => {
+                Self::UnprocessableEntity(Problem::new("duplicate-tag", e.to_string()))
             }
-            ParseCreatePetHttpRequestError::PhotoUrls(cause) => {
-                format!("photo urls {} is invalid", cause)
-            }
-            ParseCreatePetHttpRequestError::Tags(cause) => {
-                format!("tags {} is invalid", cause)
-            }
-            ParseCreatePetHttpRequestError::Status(cause) => {
-                format!("status {} is invalid", cause)
-            }
-        };
-
-        Self::BadRequest(message)
-    }
-}
-
-impl IntoResponse for ApiError {
-    fn into_response(self) -> Response {
-        use ApiError::*;
-
-        match self {
-            InternalServerError(e) => {
-                tracing::error!("{}", e);
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ApiResponseBody::new_error(
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        "Internal server error".to_string(),
-                    )),
+            CreatePetError::UnknownCategory { id } => Self::UnprocessableEntity(Problem::new(
+                "unknown-category",
+                format!("category with id {} does not exist", id),
+            )),
+            CreatePetError::CategoryIdRequired => Self::BadRequest(
+                Problem::new(ID_REQUIRED, e.to_string())
+                    .with_errors(vec![FieldError::new("category.id", "required", e.to_string())]),
+            ),
+            CreatePetError::IdRequired { kind } => {
+                let field = if kind == IdKind::Tag { "tags" } else { "id" };
+                Self::BadRequest(
+                    Problem::new(ID_REQUIRED, e.to_string())
+                        .with_errors(vec![FieldError::new(field, "required", e.to_string())]),
                 )
-                    .into_response()
             }
-            UnprocessableEntity(message) => (
-                StatusCode::UNPROCESSABLE_ENTITY,
-                Json(ApiResponseBody::new_error(
-                    StatusCode::UNPROCESSABLE_ENTITY,
-                    message,
-                )),
-            )
-                .into_response(),
-            BadRequest(message) => (
-                StatusCode::BAD_REQUEST,
-                Json(ApiResponseBody::new_error(
-                    StatusCode::BAD_REQUEST,
-                    message,
-                )),
-            )
-                .into_response(),
+            CreatePetError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
         }
     }
 }
@@ -165,32 +101,6 @@ impl<T: Serialize + PartialEq> ApiResponseBody<T> {
     }
 }
 
-impl ApiResponseBody<ApiErrorData> {
-    pub fn new_error(status_code: StatusCode, message: String) -> Self {
-        Self {
-            status_code: status_code.as_u16(),
-            data: ApiErrorData { message },
-        }
-    }
-}
-
-/// The response data format for all error responses.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
-pub struct ApiErrorData {
-    pub message: String,
-}
-
-/// The body of an [Pet] creation request.
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
-pub struct CreatePetRequestBody {
-    pub id: Option<i64>,
-    pub name: String,
-    pub category: Option<Category>,
-    pub photo_urls: Vec<String>,
-    pub tags: Option<Vec<Tag>>,
-    pub status: Option<String>,
-}
-
 /// The response body data field for successful [Pet] creation.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct CreatePetResponseData {
@@ -229,56 +139,64 @@ pub struct CreatePetHttpRequestBody {
     pub status: Option<String>,
 }
 
-#[derive(Debug, Clone, Error)]
-enum ParseCreatePetHttpRequestError {
-    #[error(transparent)]
-    Name(#[from] PetNameError),
-    #[error(transparent)]
-    Category(#[from] CategoryError),
-    #[error(transparent)]
-    PhotoUrls(#[from] PhotoUrlsError),
-    #[error(transparent)]
-    Tags(#[from] TagsError),
-    #[error(transparent)]
-    Status(#[from] StatusError),
-}
-
 impl CreatePetHttpRequestBody {
-    /// Converts the HTTP request body into a domain request.
-    fn try_into_domain(self) -> Result<CreatePetRequest, ParseCreatePetHttpRequestError> {
-        let name = PetName::new(&self.name)?;
-        let category = Category::try_from(self.category)?;
-        let photo_urls = PhotoUrls::new(&self.photo_urls)?;
-        let tags = Tags::new(&self.tags)?;
-        let status = Status::try_from(self.status)?;
-        Ok(CreatePetRequest::new(self.id, name.into_inner(), Some(category), photo_urls.into_inner(), tags.into_inner(), Some(status)))
+    /// Converts the HTTP request body into a domain request, collecting every invalid field.
+    fn try_into_domain(self) -> Result<CreatePetRequest, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if self.tags.as_ref().is_some_and(Vec::is_empty) {
+            errors.add("tags", &TagsError::Empty);
+        }
+        let status = errors.check("status", Status::try_from(self.status));
+        let request = CreatePetRequest::new(self.id, self.name, self.category, self.photo_urls, self.tags.unwrap_or_default(), status);
+
+        match request.validate(&CreatePetFields::NESTED) {
+            Ok(request) if errors.is_empty() => Ok(request),
+            Ok(_) => Err(errors),
+            Err(mut violations) => {
+                violations.extend(errors);
+                Err(violations)
+            }
+        }
     }
 }
 
 /// Create a new [Pet].
 ///
+/// Clients that retry on timeouts should send an `Idempotency-Key` header: retries of the same
+/// request then get the first response replayed instead of a duplicate error.
+///
 /// # Responses
 ///
-/// - 201 Created: the [Pet] was successfully created.
+/// - 201 Created: the [Pet] was successfully created, with its version as `ETag`.
+/// - 400 Bad Request: the body is malformed, or fields are invalid; every invalid field is
+///   listed in `errors`.
+/// - 400 Bad Request: ids must be supplied by clients, and the pet or a tag that does not exist
+///   yet has none. Existing tags may be referenced by name alone.
 /// - 422 Unprocessable entity: An [Pet] with the same name already exists.
+/// - 422 Unprocessable entity: a new tag has the id of an existing tag of another name.
 pub async fn add_pet<BS: PetService>(
     State(state): State<AppState<BS>>,
     Json(body): Json<CreatePetHttpRequestBody>,
 ) -> Result<ApiSuccess<CreatePetResponseData>, ApiError> {
-    let domain_req = body.try_into_domain()?;
+    let domain_req = body
+        .try_into_domain()
+        .map_err(|e| ApiError::invalid("invalid-pet", e))?;
     state
         .pet_service
         .add_pet(&domain_req)
         .await
         .map_err(ApiError::from)
-        .map(|ref pet| ApiSuccess::new(StatusCode::CREATED, pet.into()))
+        .map(|ref pet| {
+            ApiSuccess::new(StatusCode::CREATED, pet.into()).with_header(ETAG, pet_etag(pet.version))
+        })
 }
 
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
     use axum::http::StatusCode;
-    use crate::domain::petstore::models::pet::{Pet, CreatePetRequest, CreatePetError, Status};
+    use crate::domain::petstore::models::pet::{Pet, ChangePetStatusError, ChangePetStatusRequest, CreatePetRequest, CreatePetError, DeletePetError, ListPetsError, ListPetsRequest, PetHistoryError, PetPage, Status, StatusChange};
+    use crate::domain::petstore::models::search::{PetSearchHit, SearchPetsError, SearchPetsRequest};
     use crate::domain::petstore::models::category::Category;
     use crate::domain::petstore::models::tag::Tag;
     use crate::domain::petstore::ports::PetService;
@@ -304,6 +222,36 @@ mod tests {
         ) -> Result<Option<Pet>, CreatePetError> {
             Ok(None)
         }
+
+        async fn find_pet_version(&self, _: i64) -> Result<Option<i64>, CreatePetError> {
+            Err(CreatePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn list_pets(
+            &self,
+            _: &ListPetsRequest,
+        ) -> Result<PetPage, ListPetsError> {
+            Ok(PetPage::default())
+        }
+
+        async fn search_pets(&self, _: &SearchPetsRequest) -> Result<Vec<PetSearchHit>, SearchPetsError> {
+            Err(SearchPetsError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn change_pet_status(
+            &self,
+            _: &ChangePetStatusRequest,
+        ) -> Result<Pet, ChangePetStatusError> {
+            Err(ChangePetStatusError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn delete_pet(&self, _: i64) -> Result<(), DeletePetError> {
+            Err(DeletePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn pet_status_history(&self, _: i64) -> Result<Vec<StatusChange>, PetHistoryError> {
+            Err(PetHistoryError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
     }
 
     fn create_mock_pet() -> Pet {
@@ -327,7 +275,7 @@ mod tests {
             pet_service: Arc::new(service),
         });
 
-        let body = axum::extract::Json(CreatePetHttpRequestBody {
+        let body = Json(CreatePetHttpRequestBody {
             id: None,
             name: "doggie".to_string(),
             category: Some(Category {
@@ -355,7 +303,8 @@ mod tests {
                 tags: Some(pet.tags.clone()),
                 status: pet.status.map(|s| s.to_string()),
             },
-        );
+        )
+        .with_header(ETAG, pet_etag(Pet::INITIAL_VERSION));
 
         // Act
         let actual = add_pet(state, body).await;
@@ -388,7 +337,7 @@ mod tests {
             pet_service: Arc::new(service),
         });
 
-        let body = axum::extract::Json(CreatePetHttpRequestBody {
+        let body = Json(CreatePetHttpRequestBody {
             id: None,
             name: "doggie".to_string(),
             category: None,
@@ -420,7 +369,7 @@ mod tests {
             pet_service: Arc::new(service),
         });
 
-        let body = axum::extract::Json(CreatePetHttpRequestBody {
+        let body = Json(CreatePetHttpRequestBody {
             id: None,
             name: "doggie".to_string(),
             category: None,
@@ -449,7 +398,7 @@ mod tests {
             pet_service: Arc::new(service),
         });
 
-        let body = axum::extract::Json(CreatePetHttpRequestBody {
+        let body = Json(CreatePetHttpRequestBody {
             id: None,
             name: "".to_string(),
             category: None,
@@ -465,5 +414,137 @@ mod tests {
         assert!(result.is_err());
         let error = result.unwrap_err();
         assert!(matches!(error, ApiError::BadRequest(_)));
+        assert_eq!(error.problem().code, "invalid-pet");
+        let fields: Vec<_> = error.problem().errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["name", "photo_urls", "status"]);
+    }
+
+    #[tokio::test]
+    async fn test_add_pet_reports_every_invalid_field() {
+        // Arrange
+        let service = MockPetService {
+            add_pet_result: Arc::new(std::sync::Mutex::new(Some(Ok(create_mock_pet())))),
+        };
+
+        let state = axum::extract::State(AppState {
+            pet_service: Arc::new(service),
+        });
+
+        let body = Json(CreatePetHttpRequestBody {
+            id: None,
+            name: "x".repeat(256),
+            category: Some(Category {
+                id: None,
+                name: Some(" ".to_string()),
+            }),
+            photo_urls: vec![
+                "http://example.com/dog.jpg".to_string(),
+                "ftp://example.com/dog.jpg".to_string(),
+                "dog.jpg".to_string(),
+            ],
+            tags: Some(vec![
+                Tag { id: None, name: Some("friendly".to_string()) },
+                Tag { id: None, name: Some(" friendly ".to_string()) },
+                Tag { id: Some(3), name: None },
+            ]),
+            status: None,
+        });
+
+        // Act
+        let error = add_pet(state, body).await.unwrap_err();
+
+        // Assert
+        let errors: Vec<_> = error
+            .problem()
+            .errors
+            .iter()
+            .map(|e| (e.field.as_str(), e.code.as_str()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                ("name", "too-long"),
+                ("category.id", "required"),
+                ("category.name", "empty"),
+                ("photo_urls[1]", "unsupported-scheme"),
+                ("photo_urls[2]", "invalid-url"),
+                ("tags[1].name", "duplicate"),
+                ("tags[2].name", "empty"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_add_pet_category_without_id() {
+        // Arrange
+        let service = MockPetService {
+            add_pet_result: Arc::new(std::sync::Mutex::new(Some(Ok(create_mock_pet())))),
+        };
+
+        let state = axum::extract::State(AppState {
+            pet_service: Arc::new(service),
+        });
+
+        let body = Json(CreatePetHttpRequestBody {
+            id: None,
+            name: "doggie".to_string(),
+            category: Some(Category {
+                id: None,
+                name: Some("Dogs".to_string()),
+            }),
+            photo_urls: vec!["http://example.com/dog.jpg".to_string()],
+            tags: None,
+            status: None,
+        });
+
+        // Act
+        let result = add_pet(state, body).await;
+
+        // Assert
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_add_pet_category_id_required_error() {
+        // Arrange
+        let error = ApiError::from(CreatePetError::CategoryIdRequired);
+
+        // Assert
+        assert!(matches!(error, ApiError::BadRequest(_)));
+        assert_eq!(error.problem().errors[0].field, "category.id");
+    }
+
+    #[tokio::test]
+    async fn test_add_pet_unknown_category_error() {
+        // Arrange
+        let service = MockPetService {
+            add_pet_result: Arc::new(std::sync::Mutex::new(Some(Err(CreatePetError::UnknownCategory {
+                id: 42,
+            })))),
+        };
+
+        let state = axum::extract::State(AppState {
+            pet_service: Arc::new(service),
+        });
+
+        let body = Json(CreatePetHttpRequestBody {
+            id: None,
+            name: "doggie".to_string(),
+            category: Some(Category {
+                id: Some(42),
+                name: None,
+            }),
+            photo_urls: vec!["http://example.com/dog.jpg".to_string()],
+            tags: None,
+            status: None,
+        });
+
+        // Act
+        let result = add_pet(state, body).await;
+
+        // Assert
+        let error = result.unwrap_err();
+        assert!(matches!(error, ApiError::UnprocessableEntity(_)));
+        assert!(error.to_string().contains("category with id 42 does not exist"));
     }
 }
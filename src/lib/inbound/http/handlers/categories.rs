@@ -0,0 +1,396 @@
+/*
+   Module `categories` specifies the HTTP handlers for managing [Category]s, and the
+   associated data structures.
+*/
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::petstore::models::category::{
+    Category, CreateCategoryError, CreateCategoryRequest, DeleteCategoryError, FindCategoryError,
+    RenameCategoryError, RenameCategoryRequest,
+};
+use crate::domain::petstore::models::value_objects::{CategoryName, CategoryNameError};
+use crate::domain::petstore::models::validation::Violation;
+use crate::domain::petstore::ports::CategoryService;
+use crate::inbound::http::handlers::add_pet::ApiSuccess;
+use crate::inbound::http::handlers::extract::{Json, Path};
+use crate::inbound::http::handlers::problem::{ApiError, FieldError, Problem, ID_REQUIRED};
+use crate::inbound::http::CategoryState;
+
+/// The body of a [Category] creation request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CreateCategoryHttpRequestBody {
+    pub id: Option<i64>,
+    pub name: String,
+}
+
+/// The body of a [Category] rename request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RenameCategoryHttpRequestBody {
+    pub name: String,
+}
+
+/// The response body data field for a single [Category].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CategoryResponseData {
+    pub id: Option<i64>,
+    pub name: Option<String>,
+}
+
+impl From<&Category> for CategoryResponseData {
+    fn from(category: &Category) -> Self {
+        Self {
+            id: category.id,
+            name: category.name.clone(),
+        }
+    }
+}
+
+impl From<CategoryNameError> for ApiError {
+    fn from(e: CategoryNameError) -> Self {
+        let message = format!("category name {} is invalid", e);
+        Self::BadRequest(
+            Problem::new("invalid-category-name", message.clone())
+                .with_errors(vec![FieldError::new("name", e.code(), message)]),
+        )
+    }
+}
+
+impl From<CreateCategoryError> for ApiError {
+    fn from(e: CreateCategoryError) -> Self {
+        match e {
+            CreateCategoryError::Duplicate { .. } | CreateCategoryError::DuplicateId { .. } => {
+                Self::UnprocessableEntity(Problem::new("duplicate-category", e.to_string()))
+            }
+            CreateCategoryError::IdRequired { .. } => Self::BadRequest(
+                Problem::new(ID_REQUIRED, e.to_string())
+                    .with_errors(vec![FieldError::new("id", "required", e.to_string())]),
+            ),
+            CreateCategoryError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+impl From<FindCategoryError> for ApiError {
+    fn from(e: FindCategoryError) -> Self {
+        match e {
+            FindCategoryError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+impl From<RenameCategoryError> for ApiError {
+    fn from(e: RenameCategoryError) -> Self {
+        match e {
+            RenameCategoryError::NotFound { .. } => {
+                Self::NotFound(Problem::new("category-not-found", e.to_string()))
+            },
+            RenameCategoryError::Duplicate { .. } => {
+                Self::UnprocessableEntity(Problem::new("duplicate-category", e.to_string()))
+            },
+            RenameCategoryError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+impl From<DeleteCategoryError> for ApiError {
+    fn from(e: DeleteCategoryError) -> Self {
+        match e {
+            DeleteCategoryError::NotFound { .. } => {
+                Self::NotFound(Problem::new("category-not-found", e.to_string()))
+            },
+            DeleteCategoryError::InUse { .. } => {
+                Self::Conflict(Problem::new("category-in-use", e.to_string()))
+            },
+            DeleteCategoryError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+/// Create a new [Category].
+///
+/// # Responses
+///
+/// - 201 Created: the [Category] was successfully created.
+/// - 400 Bad Request: the name is empty or too long.
+/// - 422 Unprocessable entity: a [Category] with the same name or id already exists.
+pub async fn create_category<CS: CategoryService>(
+    State(state): State<CategoryState<CS>>,
+    Json(body): Json<CreateCategoryHttpRequestBody>,
+) -> Result<ApiSuccess<CategoryResponseData>, ApiError> {
+    let name = CategoryName::new(&body.name)?;
+    let domain_req = CreateCategoryRequest::new(body.id, name.into_inner());
+    state
+        .category_service
+        .create_category(&domain_req)
+        .await
+        .map_err(ApiError::from)
+        .map(|ref category| ApiSuccess::new(StatusCode::CREATED, category.into()))
+}
+
+/// List all [Category]s.
+///
+/// # Responses
+///
+/// - 200 OK: the [Category]s ordered by id.
+pub async fn list_categories<CS: CategoryService>(
+    State(state): State<CategoryState<CS>>,
+) -> Result<ApiSuccess<Vec<CategoryResponseData>>, ApiError> {
+    state
+        .category_service
+        .list_categories()
+        .await
+        .map_err(ApiError::from)
+        .map(|categories| {
+            ApiSuccess::new(
+                StatusCode::OK,
+                categories.iter().map(CategoryResponseData::from).collect(),
+            )
+        })
+}
+
+/// Find a [Category] by its ID.
+///
+/// # Responses
+///
+/// - 200 OK: the [Category] was found.
+/// - 404 Not Found: no [Category] exists with the given ID.
+pub async fn find_category_by_id<CS: CategoryService>(
+    State(state): State<CategoryState<CS>>,
+    Path(category_id): Path<i64>,
+) -> Result<ApiSuccess<CategoryResponseData>, ApiError> {
+    state
+        .category_service
+        .find_category_by_id(category_id)
+        .await?
+        .map(|ref category| ApiSuccess::new(StatusCode::OK, category.into()))
+        .ok_or_else(|| {
+            ApiError::NotFound(Problem::new(
+                "category-not-found",
+                format!("category with id {} not found", category_id),
+            ))
+        })
+}
+
+/// Rename a [Category]. Pets assigned to it see the new name.
+///
+/// # Responses
+///
+/// - 200 OK: the [Category] was renamed.
+/// - 400 Bad Request: the name is empty or too long.
+/// - 404 Not Found: no [Category] exists with the given ID.
+/// - 422 Unprocessable entity: another [Category] already has the name.
+pub async fn rename_category<CS: CategoryService>(
+    State(state): State<CategoryState<CS>>,
+    Path(category_id): Path<i64>,
+    Json(body): Json<RenameCategoryHttpRequestBody>,
+) -> Result<ApiSuccess<CategoryResponseData>, ApiError> {
+    let name = CategoryName::new(&body.name)?;
+    let domain_req = RenameCategoryRequest::new(category_id, name.into_inner());
+    state
+        .category_service
+        .rename_category(&domain_req)
+        .await
+        .map_err(ApiError::from)
+        .map(|ref category| ApiSuccess::new(StatusCode::OK, category.into()))
+}
+
+/// Delete a [Category].
+///
+/// # Responses
+///
+/// - 204 No Content: the [Category] was deleted.
+/// - 404 Not Found: no [Category] exists with the given ID.
+/// - 409 Conflict: pets are still assigned to the [Category].
+pub async fn delete_category<CS: CategoryService>(
+    State(state): State<CategoryState<CS>>,
+    Path(category_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .category_service
+        .delete_category(category_id)
+        .await
+        .map_err(ApiError::from)
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct MockCategoryService {
+        categories: Arc<Mutex<BTreeMap<i64, Category>>>,
+        in_use: Arc<Mutex<Vec<i64>>>,
+    }
+
+    impl CategoryService for MockCategoryService {
+        async fn create_category(&self, req: &CreateCategoryRequest) -> Result<Category, CreateCategoryError> {
+            let mut categories = self.categories.lock().unwrap();
+            if categories.values().any(|c| c.name.as_deref() == Some(req.name.as_str())) {
+                return Err(CreateCategoryError::Duplicate { name: req.name.clone() });
+            }
+            let id = req.id.unwrap_or(categories.len() as i64 + 1);
+            let category = Category::with_values(id, req.name.clone());
+            categories.insert(id, category.clone());
+            Ok(category)
+        }
+
+        async fn list_categories(&self) -> Result<Vec<Category>, FindCategoryError> {
+            Ok(self.categories.lock().unwrap().values().cloned().collect())
+        }
+
+        async fn find_category_by_id(&self, category_id: i64) -> Result<Option<Category>, FindCategoryError> {
+            Ok(self.categories.lock().unwrap().get(&category_id).cloned())
+        }
+
+        async fn rename_category(&self, req: &RenameCategoryRequest) -> Result<Category, RenameCategoryError> {
+            let mut categories = self.categories.lock().unwrap();
+            let category = categories
+                .get_mut(&req.id)
+                .ok_or(RenameCategoryError::NotFound { id: req.id })?;
+            category.name = Some(req.name.clone());
+            Ok(category.clone())
+        }
+
+        async fn delete_category(&self, category_id: i64) -> Result<(), DeleteCategoryError> {
+            if self.in_use.lock().unwrap().contains(&category_id) {
+                return Err(DeleteCategoryError::InUse { id: category_id });
+            }
+            self.categories
+                .lock()
+                .unwrap()
+                .remove(&category_id)
+                .map(|_| ())
+                .ok_or(DeleteCategoryError::NotFound { id: category_id })
+        }
+    }
+
+    fn state(service: &MockCategoryService) -> State<CategoryState<MockCategoryService>> {
+        State(CategoryState {
+            category_service: Arc::new(service.clone()),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_create_category_success() {
+        // Arrange
+        let service = MockCategoryService::default();
+        let body = Json(CreateCategoryHttpRequestBody {
+            id: Some(1),
+            name: "  Dogs ".to_string(),
+        });
+        let expected = ApiSuccess::new(
+            StatusCode::CREATED,
+            CategoryResponseData {
+                id: Some(1),
+                name: Some("Dogs".to_string()),
+            },
+        );
+
+        // Act
+        let actual = create_category(state(&service), body).await;
+
+        // Assert
+        assert_eq!(actual.unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_create_category_invalid_and_duplicate() {
+        let service = MockCategoryService::default();
+
+        let empty = create_category(
+            state(&service),
+            Json(CreateCategoryHttpRequestBody { id: None, name: " ".to_string() }),
+        )
+        .await;
+        assert!(matches!(empty, Err(ApiError::BadRequest(_))));
+
+        let body = CreateCategoryHttpRequestBody { id: None, name: "Cats".to_string() };
+        create_category(state(&service), Json(body.clone())).await.unwrap();
+        let duplicate = create_category(state(&service), Json(body)).await;
+        assert!(matches!(duplicate, Err(ApiError::UnprocessableEntity(_))));
+    }
+
+    #[tokio::test]
+    async fn test_find_and_list_categories() {
+        let service = MockCategoryService::default();
+        service.categories.lock().unwrap().insert(1, Category::with_values(1, "Dogs".to_string()));
+        service.categories.lock().unwrap().insert(2, Category::with_values(2, "Cats".to_string()));
+
+        let found = find_category_by_id(state(&service), Path(2)).await.unwrap();
+        assert_eq!(
+            found,
+            ApiSuccess::new(
+                StatusCode::OK,
+                CategoryResponseData { id: Some(2), name: Some("Cats".to_string()) }
+            )
+        );
+
+        let missing = find_category_by_id(state(&service), Path(3)).await;
+        assert!(matches!(missing, Err(ApiError::NotFound(_))));
+
+        let listed = list_categories(state(&service)).await.unwrap();
+        assert_eq!(
+            listed,
+            ApiSuccess::new(
+                StatusCode::OK,
+                vec![
+                    CategoryResponseData { id: Some(1), name: Some("Dogs".to_string()) },
+                    CategoryResponseData { id: Some(2), name: Some("Cats".to_string()) },
+                ]
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rename_category() {
+        let service = MockCategoryService::default();
+        service.categories.lock().unwrap().insert(1, Category::with_values(1, "Dogs".to_string()));
+
+        let renamed = rename_category(
+            state(&service),
+            Path(1),
+            Json(RenameCategoryHttpRequestBody { name: "Canines".to_string() }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            renamed,
+            ApiSuccess::new(
+                StatusCode::OK,
+                CategoryResponseData { id: Some(1), name: Some("Canines".to_string()) }
+            )
+        );
+
+        let missing = rename_category(
+            state(&service),
+            Path(9),
+            Json(RenameCategoryHttpRequestBody { name: "Birds".to_string() }),
+        )
+        .await;
+        assert!(matches!(missing, Err(ApiError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_delete_category() {
+        let service = MockCategoryService::default();
+        service.categories.lock().unwrap().insert(1, Category::with_values(1, "Dogs".to_string()));
+        service.categories.lock().unwrap().insert(2, Category::with_values(2, "Cats".to_string()));
+        service.in_use.lock().unwrap().push(2);
+
+        assert_eq!(delete_category(state(&service), Path(1)).await.unwrap(), StatusCode::NO_CONTENT);
+        assert!(matches!(
+            delete_category(state(&service), Path(1)).await,
+            Err(ApiError::NotFound(_))
+        ));
+        assert!(matches!(
+            delete_category(state(&service), Path(2)).await,
+            Err(ApiError::Conflict(_))
+        ));
+    }
+}
@@ -0,0 +1,257 @@
+/*
+   Module `change_pet_status` specifies an HTTP handler for moving a [Pet] through its
+   lifecycle, and the associated data structures.
+*/
+
+use axum::extract::State;
+use axum::http::header::ETAG;
+use axum::http::{HeaderMap, StatusCode};
+use serde::Deserialize;
+
+use crate::domain::petstore::models::pet::{ChangePetStatusError, ChangePetStatusRequest, Status};
+use crate::domain::petstore::models::value_objects::StatusError;
+use crate::domain::petstore::models::validation::Violation;
+use crate::domain::petstore::ports::PetService;
+use crate::inbound::http::handlers::add_pet::{ApiSuccess, CreatePetResponseData};
+use crate::inbound::http::handlers::etag::{if_match_version, pet_etag, STALE_VERSION};
+use crate::inbound::http::handlers::extract::{Json, Path};
+use crate::inbound::http::handlers::find_pet_by_id::PET_NOT_FOUND;
+use crate::inbound::http::handlers::problem::{ApiError, FieldError, Problem};
+use crate::inbound::http::AppState;
+
+/// The header naming who requested a status transition, recorded in the [Pet]'s history.
+pub const ACTOR_HEADER: &str = "x-actor";
+
+/// The body of a [Pet] status transition request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ChangePetStatusHttpRequestBody {
+    pub status: String,
+}
+
+impl From<StatusError> for ApiError {
+    fn from(e: StatusError) -> Self {
+        let message = format!("status {} is invalid", e);
+        Self::BadRequest(
+            Problem::new("invalid-status", message.clone())
+                .with_errors(vec![FieldError::new("status", e.code(), message)]),
+        )
+    }
+}
+
+impl From<ChangePetStatusError> for ApiError {
+    fn from(e: ChangePetStatusError) -> Self {
+        match e {
+            ChangePetStatusError::NotFound { .. } => {
+                Self::NotFound(Problem::new(PET_NOT_FOUND, e.to_string()))
+            }
+            ChangePetStatusError::InvalidTransition(_) => {
+                Self::Conflict(Problem::new("invalid-status-transition", e.to_string()))
+            }
+            ChangePetStatusError::Conflict { .. } => {
+                Self::PreconditionFailed(Problem::new(STALE_VERSION, e.to_string()))
+            }
+            ChangePetStatusError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+/// Move a [Pet] to a new status.
+///
+/// The `If-Match` header must carry the `ETag` of the [Pet] the change is based on. The
+/// optional `X-Actor` header is recorded as the author of the change.
+///
+/// # Responses
+///
+/// - 200 OK: the [Pet] with its new status, and its new version as `ETag`.
+/// - 400 Bad Request: the status is not one of available, pending, sold.
+/// - 404 Not Found: no [Pet] exists with the given ID.
+/// - 409 Conflict: the pet lifecycle does not allow the change.
+/// - 412 Precondition Failed: the [Pet] was modified since the `ETag` was issued.
+/// - 428 Precondition Required: the `If-Match` header is missing.
+pub async fn change_pet_status<BS: PetService>(
+    State(state): State<AppState<BS>>,
+    Path(pet_id): Path<i64>,
+    headers: HeaderMap,
+    Json(body): Json<ChangePetStatusHttpRequestBody>,
+) -> Result<ApiSuccess<CreatePetResponseData>, ApiError> {
+    let expected_version = if_match_version(&headers)?;
+    let status = Status::try_from(Some(body.status))?;
+    let actor = headers
+        .get(ACTOR_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|actor| !actor.is_empty())
+        .map(str::to_string);
+    let domain_req = ChangePetStatusRequest::new(pet_id, status)
+        .with_actor(actor)
+        .with_expected_version(expected_version);
+    state
+        .pet_service
+        .change_pet_status(&domain_req)
+        .await
+        .map_err(ApiError::from)
+        .map(|ref pet| ApiSuccess::new(StatusCode::OK, pet.into()).with_header(ETAG, pet_etag(pet.version)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use crate::domain::petstore::models::pet::{
+        CreatePetError, CreatePetRequest, DeletePetError, InvalidTransition, ListPetsError, ListPetsRequest, Pet,
+        PetHistoryError, PetPage, StatusChange,
+    };
+    use crate::domain::petstore::models::search::{PetSearchHit, SearchPetsError, SearchPetsRequest};
+    use axum::http::header::IF_MATCH;
+    use super::*;
+
+    type ChangePetStatusResult = Result<Pet, ChangePetStatusError>;
+
+    #[derive(Clone)]
+    struct MockPetService {
+        change_pet_status_request: Arc<Mutex<Option<ChangePetStatusRequest>>>,
+        change_pet_status_result: Arc<Mutex<Option<ChangePetStatusResult>>>,
+    }
+
+    impl MockPetService {
+        fn new(result: ChangePetStatusResult) -> Self {
+            Self {
+                change_pet_status_request: Arc::new(Mutex::new(None)),
+                change_pet_status_result: Arc::new(Mutex::new(Some(result))),
+            }
+        }
+    }
+
+    impl PetService for MockPetService {
+        async fn add_pet(&self, _: &CreatePetRequest) -> Result<Pet, CreatePetError> {
+            Err(CreatePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn find_pet_by_id(&self, _: i64) -> Result<Option<Pet>, CreatePetError> {
+            Err(CreatePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn find_pet_version(&self, _: i64) -> Result<Option<i64>, CreatePetError> {
+            Err(CreatePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn list_pets(&self, _: &ListPetsRequest) -> Result<PetPage, ListPetsError> {
+            Err(ListPetsError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn search_pets(&self, _: &SearchPetsRequest) -> Result<Vec<PetSearchHit>, SearchPetsError> {
+            Err(SearchPetsError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn change_pet_status(&self, req: &ChangePetStatusRequest) -> Result<Pet, ChangePetStatusError> {
+            *self.change_pet_status_request.lock().unwrap() = Some(req.clone());
+            let mut guard = self.change_pet_status_result.lock().unwrap();
+            guard.take().unwrap_or_else(|| Err(ChangePetStatusError::Unknown(anyhow::anyhow!("Mock change_pet_status result not set"))))
+        }
+
+        async fn delete_pet(&self, _: i64) -> Result<(), DeletePetError> {
+            Err(DeletePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn pet_status_history(&self, _: i64) -> Result<Vec<StatusChange>, PetHistoryError> {
+            Err(PetHistoryError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+    }
+
+    fn state(service: MockPetService) -> State<AppState<MockPetService>> {
+        State(AppState {
+            pet_service: Arc::new(service),
+        })
+    }
+
+    fn if_match(version: i64) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_MATCH, pet_etag(version));
+        headers
+    }
+
+    fn body(status: &str) -> Json<ChangePetStatusHttpRequestBody> {
+        Json(ChangePetStatusHttpRequestBody {
+            status: status.to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_change_pet_status_success() {
+        // Arrange
+        let mut pet = Pet::with_id(10, String::from("doggie"));
+        pet.set_status(Status::Pending);
+        pet.version = 2;
+        let service = MockPetService::new(Ok(pet.clone()));
+        let requests = service.change_pet_status_request.clone();
+        let mut headers = if_match(1);
+        headers.insert(ACTOR_HEADER, "alice".parse().unwrap());
+
+        // Act
+        let actual = change_pet_status(state(service), Path(10), headers, body("pending")).await;
+
+        // Assert
+        assert_eq!(
+            actual.unwrap(),
+            ApiSuccess::new(StatusCode::OK, (&pet).into()).with_header(ETAG, pet_etag(2))
+        );
+        assert_eq!(
+            requests.lock().unwrap().clone().unwrap(),
+            ChangePetStatusRequest::new(10, Status::Pending)
+                .with_actor(Some("alice".to_string()))
+                .with_expected_version(Some(1))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_change_pet_status_invalid_status() {
+        let service = MockPetService::new(Ok(Pet::with_id(10, String::from("doggie"))));
+
+        let actual = change_pet_status(state(service), Path(10), if_match(1), body("adopted")).await;
+
+        assert!(matches!(actual, Err(ApiError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_change_pet_status_invalid_transition() {
+        let service = MockPetService::new(Err(InvalidTransition {
+            from: Status::Sold,
+            to: Status::Available,
+        }
+        .into()));
+
+        let actual = change_pet_status(state(service), Path(10), if_match(1), body("available")).await;
+
+        let error = actual.unwrap_err();
+        assert!(matches!(error, ApiError::Conflict(_)));
+        assert!(error.to_string().contains("from sold to available"));
+    }
+
+    #[tokio::test]
+    async fn test_change_pet_status_not_found() {
+        let service = MockPetService::new(Err(ChangePetStatusError::NotFound { id: 10 }));
+
+        let actual = change_pet_status(state(service), Path(10), if_match(1), body("pending")).await;
+
+        assert!(matches!(actual, Err(ApiError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_change_pet_status_stale_version() {
+        let service = MockPetService::new(Err(ChangePetStatusError::Conflict { id: 10, expected: 1, actual: 2 }));
+
+        let actual = change_pet_status(state(service), Path(10), if_match(1), body("pending")).await;
+
+        assert!(matches!(actual, Err(ApiError::PreconditionFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_change_pet_status_missing_if_match() {
+        let service = MockPetService::new(Ok(Pet::with_id(10, String::from("doggie"))));
+        let requests = service.change_pet_status_request.clone();
+
+        let actual = change_pet_status(state(service), Path(10), HeaderMap::new(), body("pending")).await;
+
+        assert!(matches!(actual, Err(ApiError::PreconditionRequired(_))));
+        assert!(requests.lock().unwrap().is_none());
+    }
+}
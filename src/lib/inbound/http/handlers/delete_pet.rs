@@ -0,0 +1,127 @@
+/*
+   Module `delete_pet` specifies an HTTP handler for deleting a [Pet].
+*/
+
+use axum::extract::State;
+use axum::http::StatusCode;
+
+use crate::domain::petstore::models::pet::DeletePetError;
+use crate::domain::petstore::ports::PetService;
+use crate::inbound::http::handlers::extract::Path;
+use crate::inbound::http::handlers::find_pet_by_id::PET_NOT_FOUND;
+use crate::inbound::http::handlers::problem::{ApiError, Problem};
+use crate::inbound::http::AppState;
+
+impl From<DeletePetError> for ApiError {
+    fn from(e: DeletePetError) -> Self {
+        match e {
+            DeletePetError::NotFound { .. } => {
+                Self::NotFound(Problem::new(PET_NOT_FOUND, e.to_string()))
+            }
+            DeletePetError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+/// Delete a [Pet], together with its photos, tags and status history.
+///
+/// # Responses
+///
+/// - 204 No Content: the [Pet] was deleted.
+/// - 404 Not Found: no [Pet] exists with the given ID.
+pub async fn delete_pet<BS: PetService>(
+    State(state): State<AppState<BS>>,
+    Path(pet_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .pet_service
+        .delete_pet(pet_id)
+        .await
+        .map_err(ApiError::from)
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use crate::domain::petstore::models::pet::{
+        ChangePetStatusError, ChangePetStatusRequest, CreatePetError, CreatePetRequest,
+        ListPetsError, ListPetsRequest, Pet, PetHistoryError, PetPage, StatusChange,
+    };
+    use crate::domain::petstore::models::search::{PetSearchHit, SearchPetsError, SearchPetsRequest};
+    use super::*;
+
+    type DeletePetResult = Result<(), DeletePetError>;
+
+    #[derive(Clone)]
+    struct MockPetService {
+        delete_pet_result: Arc<Mutex<Option<DeletePetResult>>>,
+    }
+
+    impl MockPetService {
+        fn new(result: DeletePetResult) -> Self {
+            Self {
+                delete_pet_result: Arc::new(Mutex::new(Some(result))),
+            }
+        }
+    }
+
+    impl PetService for MockPetService {
+        async fn add_pet(&self, _: &CreatePetRequest) -> Result<Pet, CreatePetError> {
+            Err(CreatePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn find_pet_by_id(&self, _: i64) -> Result<Option<Pet>, CreatePetError> {
+            Err(CreatePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn find_pet_version(&self, _: i64) -> Result<Option<i64>, CreatePetError> {
+            Err(CreatePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn list_pets(&self, _: &ListPetsRequest) -> Result<PetPage, ListPetsError> {
+            Err(ListPetsError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn search_pets(&self, _: &SearchPetsRequest) -> Result<Vec<PetSearchHit>, SearchPetsError> {
+            Err(SearchPetsError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn change_pet_status(&self, _: &ChangePetStatusRequest) -> Result<Pet, ChangePetStatusError> {
+            Err(ChangePetStatusError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn delete_pet(&self, _: i64) -> Result<(), DeletePetError> {
+            let mut guard = self.delete_pet_result.lock().unwrap();
+            guard.take().unwrap_or_else(|| Err(DeletePetError::Unknown(anyhow::anyhow!("Mock delete_pet result not set"))))
+        }
+
+        async fn pet_status_history(&self, _: i64) -> Result<Vec<StatusChange>, PetHistoryError> {
+            Err(PetHistoryError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+    }
+
+    fn state(service: MockPetService) -> State<AppState<MockPetService>> {
+        State(AppState {
+            pet_service: Arc::new(service),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_delete_pet_success() {
+        let service = MockPetService::new(Ok(()));
+
+        let actual = delete_pet(state(service), Path(10)).await;
+
+        assert_eq!(actual.unwrap(), StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn test_delete_pet_not_found() {
+        let service = MockPetService::new(Err(DeletePetError::NotFound { id: 10 }));
+
+        let actual = delete_pet(state(service), Path(10)).await;
+
+        assert!(matches!(actual, Err(ApiError::NotFound(_))));
+    }
+}
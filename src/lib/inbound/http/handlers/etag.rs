@@ -0,0 +1,114 @@
+/*
+   Module `etag` maps [Pet] versions to entity tags: reads return the version as an `ETag`,
+   which clients send back in `If-None-Match` to revalidate a cached pet, and in `If-Match`
+   to guard a mutation against concurrent changes.
+*/
+
+use axum::http::header::{IF_MATCH, IF_NONE_MATCH};
+use axum::http::{HeaderMap, HeaderValue};
+
+use crate::inbound::http::handlers::problem::{ApiError, Problem};
+
+/// The problem code of a mutation based on a version of a [Pet] that is no longer current.
+pub const STALE_VERSION: &str = "stale-version";
+
+/// The strong entity tag of a [Pet] version, e.g. `"3"`.
+pub fn pet_etag(version: i64) -> HeaderValue {
+    HeaderValue::from_str(&format!("\"{}\"", version)).expect("a quoted integer is a valid header value")
+}
+
+/// Reads the [Pet] version a client expects from the `If-Match` header.
+///
+/// Returns `None` for `If-Match: *`, which matches any version of an existing pet.
+///
+/// # Errors:
+///
+/// - [ApiError::PreconditionRequired] if the header is missing.
+/// - [ApiError::PreconditionFailed] if it is not a single strong entity tag issued by this API,
+///   as no version of the pet can match it.
+pub fn if_match_version(headers: &HeaderMap) -> Result<Option<i64>, ApiError> {
+    let value = headers.get(IF_MATCH).ok_or_else(|| {
+        ApiError::PreconditionRequired(Problem::new(
+            "if-match-required",
+            "If-Match header with the pet ETag is required",
+        ))
+    })?;
+    let value = value.to_str().map(str::trim).unwrap_or_default();
+    if value == "*" {
+        return Ok(None);
+    }
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .and_then(|v| v.parse::<i64>().ok())
+        .map(Some)
+        .ok_or_else(|| {
+            ApiError::PreconditionFailed(Problem::new(
+                STALE_VERSION,
+                format!("If-Match {} does not match the pet", value),
+            ))
+        })
+}
+
+/// Whether the `If-None-Match` header matches the given [Pet] version, i.e. whether the
+/// client's cached copy is still current. Uses the weak comparison RFC 9110 prescribes for
+/// `If-None-Match`, so `W/"3"` matches version 3.
+pub fn if_none_match(headers: &HeaderMap, version: i64) -> bool {
+    let current = format!("\"{}\"", version);
+    headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(if_match: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_MATCH, if_match.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_pet_etag_round_trip() {
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_MATCH, pet_etag(3));
+
+        assert_eq!(pet_etag(3), "\"3\"");
+        assert_eq!(if_match_version(&headers), Ok(Some(3)));
+    }
+
+    #[test]
+    fn test_if_match_version() {
+        assert_eq!(if_match_version(&headers("*")), Ok(None));
+        assert!(matches!(if_match_version(&HeaderMap::new()), Err(ApiError::PreconditionRequired(_))));
+        for invalid in ["3", "W/\"3\"", "\"3\", \"4\"", "\"three\""] {
+            assert!(
+                matches!(if_match_version(&headers(invalid)), Err(ApiError::PreconditionFailed(_))),
+                "expected PreconditionFailed for {}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let if_none_match_headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(IF_NONE_MATCH, value.parse().unwrap());
+            headers
+        };
+
+        assert!(if_none_match(&if_none_match_headers("\"3\""), 3));
+        assert!(if_none_match(&if_none_match_headers("W/\"3\""), 3));
+        assert!(if_none_match(&if_none_match_headers("\"1\", \"3\""), 3));
+        assert!(if_none_match(&if_none_match_headers("*"), 3));
+        assert!(!if_none_match(&if_none_match_headers("\"2\""), 3));
+        assert!(!if_none_match(&HeaderMap::new(), 3));
+    }
+}
@@ -0,0 +1,164 @@
+/*
+   Module `extract` wraps axum's `Json`, `Path` and `Query` extractors so that their rejections
+   are returned as problem documents like every other error, instead of axum's plain text.
+*/
+
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::extract::{FromRequest, FromRequestParts, Request};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+
+use crate::inbound::http::handlers::problem::{ApiError, Problem};
+
+/// A JSON request body. Rejections are [ApiError]s.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json<T>(pub T);
+
+/// Path parameters. Rejections are [ApiError]s.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Path<T>(pub T);
+
+/// The query string. Rejections are [ApiError]s.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Query<T>(pub T);
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        let detail = rejection.body_text();
+        match rejection {
+            JsonRejection::JsonDataError(_) => Self::UnprocessableEntity(Problem::new("invalid-body", detail)),
+            JsonRejection::JsonSyntaxError(_) => Self::BadRequest(Problem::new("malformed-body", detail)),
+            JsonRejection::MissingJsonContentType(_) => {
+                Self::UnsupportedMediaType(Problem::new("unsupported-media-type", detail))
+            }
+            _ if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE => {
+                Self::PayloadTooLarge(Problem::new("payload-too-large", detail))
+            }
+            _ => Self::BadRequest(Problem::new("invalid-body", detail)),
+        }
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        match rejection {
+            PathRejection::FailedToDeserializePathParams(_) => {
+                Self::BadRequest(Problem::new("invalid-path-parameter", rejection.body_text()))
+            }
+            // A route whose handler expects parameters its path does not declare
+            _ => Self::internal(rejection.body_text()),
+        }
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        Self::BadRequest(Problem::new("invalid-query", rejection.body_text()))
+    }
+}
+
+impl<T, S> FromRequest<S> for Json<T>
+where
+    axum::Json<T>: FromRequest<S, Rejection = JsonRejection>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::Json(value) = axum::Json::<T>::from_request(req, state).await?;
+        Ok(Self(value))
+    }
+}
+
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    axum::extract::Path<T>: FromRequestParts<S, Rejection = PathRejection>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::extract::Path(value) = axum::extract::Path::<T>::from_request_parts(parts, state).await?;
+        Ok(Self(value))
+    }
+}
+
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    axum::extract::Query<T>: FromRequestParts<S, Rejection = QueryRejection>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::extract::Query(value) = axum::extract::Query::<T>::from_request_parts(parts, state).await?;
+        Ok(Self(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+    use axum::routing::post;
+    use axum::Router;
+    use serde::Deserialize;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::inbound::http::handlers::problem::PROBLEM_JSON;
+
+    #[derive(Deserialize)]
+    struct Body_ {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    #[derive(Deserialize)]
+    struct Page {
+        #[allow(dead_code)]
+        limit: Option<u32>,
+    }
+
+    fn router() -> Router {
+        Router::new().route(
+            "/pet/{petId}",
+            post(|Path(_): Path<i64>, Query(_): Query<Page>, Json(_): Json<Body_>| async { "ok" }),
+        )
+    }
+
+    async fn send(uri: &str, content_type: Option<&str>, body: &str) -> (StatusCode, serde_json::Value) {
+        let mut request = Request::builder().method("POST").uri(uri);
+        if let Some(content_type) = content_type {
+            request = request.header(CONTENT_TYPE, content_type);
+        }
+        let response = router()
+            .oneshot(request.body(Body::from(body.to_string())).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), PROBLEM_JSON);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_rejections_are_problems() {
+        let json = Some("application/json");
+        let cases = [
+            ("/pet/1", json, "{", StatusCode::BAD_REQUEST, "/problems/malformed-body"),
+            ("/pet/1", json, "{}", StatusCode::UNPROCESSABLE_ENTITY, "/problems/invalid-body"),
+            ("/pet/1", None, "{\"name\":\"Rex\"}", StatusCode::UNSUPPORTED_MEDIA_TYPE, "/problems/unsupported-media-type"),
+            ("/pet/rex", json, "{\"name\":\"Rex\"}", StatusCode::BAD_REQUEST, "/problems/invalid-path-parameter"),
+            ("/pet/1?limit=many", json, "{\"name\":\"Rex\"}", StatusCode::BAD_REQUEST, "/problems/invalid-query"),
+        ];
+
+        for (uri, content_type, body, expected_status, expected_type) in cases {
+            let (status, problem) = send(uri, content_type, body).await;
+
+            assert_eq!(status, expected_status, "{} {}", uri, body);
+            assert_eq!(problem["type"], expected_type, "{} {}", uri, body);
+            assert_eq!(problem["status"], expected_status.as_u16());
+        }
+    }
+}
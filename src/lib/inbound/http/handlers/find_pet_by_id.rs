@@ -2,95 +2,82 @@
    Module `find_pet_by_id` specifies an HTTP handler for finding a [Pet] by its ID.
 */
 
-use axum::extract::{State, Path};
-use axum::http::StatusCode;
-use axum::Json;
-use serde::Serialize;
+use axum::extract::State;
+use axum::http::header::{ETAG, IF_NONE_MATCH};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
 
-use crate::domain::petstore::models::pet::Pet;
 use crate::domain::petstore::ports::PetService;
+use crate::inbound::http::handlers::add_pet::{ApiSuccess, CreatePetResponseData};
+use crate::inbound::http::handlers::etag::{if_none_match, pet_etag};
+use crate::inbound::http::handlers::extract::Path;
+use crate::inbound::http::handlers::problem::{ApiError, Problem};
 use crate::inbound::http::AppState;
 
-
-
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
-pub enum FindPetResponseData {
-    Success {
-        id: Option<i64>,
-        name: String,
-        category: Option<Box<crate::domain::petstore::models::category::Category>>,
-        photo_urls: Vec<String>,
-        tags: Option<Vec<crate::domain::petstore::models::tag::Tag>>,
-        status: Option<String>,
-    },
-    Error {
-        message: String,
-    },
-}
-
-impl From<&Pet> for FindPetResponseData {
-    fn from(pet: &Pet) -> Self {
-        Self::Success {
-            id: pet.id,
-            name: pet.name.clone(),
-            category: pet.category.clone(),
-            photo_urls: pet.photo_urls.clone(),
-            tags: Some(pet.tags.clone()),
-            status: pet.status.as_ref().map(|s| s.to_string()),
-        }
-    }
-}
+/// The problem code of a request for a [Pet] that does not exist.
+pub const PET_NOT_FOUND: &str = "pet-not-found";
 
 /// Find a [Pet] by its ID.
 ///
+/// A client holding a cached copy can revalidate it by sending its `ETag` in `If-None-Match`.
+/// The answer, and the `ETag` and body of a 200, all come from the same read of the pet, so
+/// that a cached pet is never served under the `ETag` of a newer version.
+///
 /// # Responses
 ///
-/// - 200 OK: the [Pet] was found.
+/// - 200 OK: the [Pet] was found. Its version is returned as `ETag`.
+/// - 304 Not Modified: the `If-None-Match` header matches the current version.
 /// - 404 Not Found: no [Pet] exists with the given ID.
 /// - 500 Internal Server Error: an unexpected error occurred.
 pub async fn find_pet_by_id<BS: PetService>(
     State(state): State<AppState<BS>>,
     Path(pet_id): Path<i64>,
-) -> Result<(StatusCode, Json<crate::inbound::http::handlers::add_pet::ApiResponseBody<FindPetResponseData>>), crate::inbound::http::handlers::add_pet::ApiError> {
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
     let pet = state
         .pet_service
         .find_pet_by_id(pet_id)
         .await
-        .map_err(crate::inbound::http::handlers::add_pet::ApiError::from)?;
-
-    match pet {
-        Some(pet) => Ok((
-            StatusCode::OK,
-            Json(crate::inbound::http::handlers::add_pet::ApiResponseBody::new(
-                StatusCode::OK,
-                FindPetResponseData::from(&pet),
-            )),
-        )),
-        None => Ok((
-            StatusCode::NOT_FOUND,
-            Json(crate::inbound::http::handlers::add_pet::ApiResponseBody::new(
-                StatusCode::NOT_FOUND,
-                FindPetResponseData::Error {
-                    message: format!("pet with id {} not found", pet_id),
-                },
-            )),
-        )),
+        .map_err(ApiError::from)?;
+
+    let pet = pet.ok_or_else(|| {
+        ApiError::NotFound(Problem::new(
+            PET_NOT_FOUND,
+            format!("pet with id {} not found", pet_id),
+        ))
+    })?;
+    if headers.contains_key(IF_NONE_MATCH) && if_none_match(&headers, pet.version) {
+        return Ok((StatusCode::NOT_MODIFIED, [(ETAG, pet_etag(pet.version))]).into_response());
     }
+    Ok(ApiSuccess::new(StatusCode::OK, CreatePetResponseData::from(&pet))
+        .with_header(ETAG, pet_etag(pet.version))
+        .into_response())
 }
 
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
     use axum::http::StatusCode;
-    use crate::domain::petstore::models::pet::{Pet, CreatePetError, Status};
+    use crate::domain::petstore::models::pet::{Pet, ChangePetStatusError, ChangePetStatusRequest, CreatePetError, DeletePetError, ListPetsError, ListPetsRequest, PetHistoryError, PetPage, Status, StatusChange};
+    use crate::domain::petstore::models::search::{PetSearchHit, SearchPetsError, SearchPetsRequest};
     use crate::domain::petstore::models::category::Category;
     use crate::domain::petstore::models::tag::Tag;
     use crate::domain::petstore::ports::PetService;
     use super::*;
 
-    #[derive(Clone)]
+    type FindPetResult = Result<Option<Pet>, CreatePetError>;
+
+    #[derive(Clone, Default)]
     struct MockPetService {
-        find_pet_result: Arc<std::sync::Mutex<Option<Result<Option<Pet>, CreatePetError>>>>,
+        find_pet_result: Arc<std::sync::Mutex<Option<FindPetResult>>>,
+    }
+
+    impl MockPetService {
+        fn with_pet(result: FindPetResult) -> Self {
+            Self {
+                find_pet_result: Arc::new(std::sync::Mutex::new(Some(result))),
+            }
+        }
     }
 
     impl PetService for MockPetService {
@@ -108,6 +95,36 @@ mod tests {
             let mut guard = self.find_pet_result.lock().unwrap();
             guard.take().unwrap_or_else(|| Err(CreatePetError::Unknown(anyhow::anyhow!("Mock find_pet_by_id result not set"))))
         }
+
+        async fn find_pet_version(&self, _: i64) -> Result<Option<i64>, CreatePetError> {
+            Err(CreatePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn list_pets(
+            &self,
+            _: &ListPetsRequest,
+        ) -> Result<PetPage, ListPetsError> {
+            Err(ListPetsError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn search_pets(&self, _: &SearchPetsRequest) -> Result<Vec<PetSearchHit>, SearchPetsError> {
+            Err(SearchPetsError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn change_pet_status(
+            &self,
+            _: &ChangePetStatusRequest,
+        ) -> Result<Pet, ChangePetStatusError> {
+            Err(ChangePetStatusError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn delete_pet(&self, _: i64) -> Result<(), DeletePetError> {
+            Err(DeletePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn pet_status_history(&self, _: i64) -> Result<Vec<StatusChange>, PetHistoryError> {
+            Err(PetHistoryError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
     }
 
     fn create_mock_pet() -> Pet {
@@ -120,83 +137,116 @@ mod tests {
         pet
     }
 
+    fn state(service: MockPetService) -> axum::extract::State<crate::inbound::http::AppState<MockPetService>> {
+        axum::extract::State(crate::inbound::http::AppState {
+            pet_service: Arc::new(service),
+        })
+    }
+
+    fn if_none_match(etag: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, etag.parse().unwrap());
+        headers
+    }
+
+    async fn body_json(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
     #[tokio::test]
     async fn test_find_pet_by_id_success() {
         // Arrange
         let pet = create_mock_pet();
-        let service = MockPetService {
-            find_pet_result: Arc::new(std::sync::Mutex::new(Some(Ok(Some(pet.clone()))))),
-        };
-        
-        let state = axum::extract::State(crate::inbound::http::AppState {
-            pet_service: Arc::new(service),
-        });
+        let service = MockPetService::with_pet(Ok(Some(pet.clone())));
 
-        let expected = (
+        let expected = crate::inbound::http::handlers::add_pet::ApiResponseBody::new(
             StatusCode::OK,
-            Json(crate::inbound::http::handlers::add_pet::ApiResponseBody::new(
-                StatusCode::OK,
-                FindPetResponseData::Success {
-                    id: pet.id,
-                    name: pet.name,
-                    category: pet.category,
-                    photo_urls: pet.photo_urls,
-                    tags: Some(pet.tags),
-                    status: pet.status.map(|s| s.to_string()),
-                },
-            )),
+            CreatePetResponseData::from(&pet),
         );
 
         // Act
-        let actual = find_pet_by_id(state, axum::extract::Path(10)).await;
+        let actual = find_pet_by_id(state(service), Path(10), HeaderMap::new()).await;
 
         // Assert
-        assert!(actual.is_ok());
         let actual = actual.unwrap();
-        assert_eq!(actual.0, expected.0);
-        assert_eq!(actual.1 .0, expected.1 .0);
+        assert_eq!(actual.status(), StatusCode::OK);
+        assert_eq!(actual.headers().get(ETAG), Some(&pet_etag(pet.version)));
+        assert_eq!(body_json(actual).await, serde_json::to_value(expected).unwrap());
     }
 
     #[tokio::test]
-    async fn test_find_pet_by_id_not_found() {
+    async fn test_find_pet_by_id_not_modified() {
         // Arrange
-        let service = MockPetService {
-            find_pet_result: Arc::new(std::sync::Mutex::new(Some(Ok(None)))),
-        };
-        
-        let state = axum::extract::State(crate::inbound::http::AppState {
-            pet_service: Arc::new(service),
-        });
+        let mut pet = create_mock_pet();
+        pet.version = 3;
+        let service = MockPetService::with_pet(Ok(Some(pet)));
 
         // Act
-        let actual = find_pet_by_id(state, axum::extract::Path(999)).await;
+        let actual = find_pet_by_id(state(service), Path(10), if_none_match("\"3\"")).await;
 
         // Assert
-        assert!(actual.is_ok());
         let actual = actual.unwrap();
-        assert_eq!(actual.0, StatusCode::NOT_FOUND);
-        assert!(matches!(actual.1.0, crate::inbound::http::handlers::add_pet::ApiResponseBody { .. }));
+        assert_eq!(actual.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(actual.headers().get(ETAG), Some(&pet_etag(3)));
+        let body = axum::body::to_bytes(actual.into_body(), usize::MAX).await.unwrap();
+        assert!(body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_find_pet_by_id_modified() {
+        // Arrange
+        let mut pet = create_mock_pet();
+        pet.version = 4;
+        let service = MockPetService::with_pet(Ok(Some(pet)));
+
+        // Act
+        let actual = find_pet_by_id(state(service), Path(10), if_none_match("\"3\"")).await;
+
+        // Assert
+        let actual = actual.unwrap();
+        assert_eq!(actual.status(), StatusCode::OK);
+        assert_eq!(actual.headers().get(ETAG), Some(&pet_etag(4)));
+    }
+
+    #[tokio::test]
+    async fn test_find_pet_by_id_not_modified_missing_pet() {
+        // Arrange
+        let service = MockPetService::with_pet(Ok(None));
+
+        // Act
+        let actual = find_pet_by_id(state(service), Path(10), if_none_match("\"3\"")).await;
+
+        // Assert
+        assert!(matches!(actual.unwrap_err(), ApiError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_find_pet_by_id_not_found() {
+        // Arrange
+        let service = MockPetService::with_pet(Ok(None));
+
+        // Act
+        let actual = find_pet_by_id(state(service), Path(999), HeaderMap::new()).await;
+
+        // Assert
+        let error = actual.unwrap_err();
+        assert!(matches!(error, ApiError::NotFound(_)));
+        assert_eq!(error.problem().code, PET_NOT_FOUND);
+        assert_eq!(error.problem().detail, "pet with id 999 not found");
     }
 
     #[tokio::test]
     async fn test_find_pet_by_id_error() {
         // Arrange
-        let service = MockPetService {
-            find_pet_result: Arc::new(std::sync::Mutex::new(Some(Err(CreatePetError::Unknown(
-                anyhow::anyhow!("database error"),
-            ))))),
-        };
-        
-        let state = axum::extract::State(crate::inbound::http::AppState {
-            pet_service: Arc::new(service),
-        });
+        let service = MockPetService::with_pet(Err(CreatePetError::Unknown(anyhow::anyhow!("database error"))));
 
         // Act
-        let actual = find_pet_by_id(state, axum::extract::Path(10)).await;
+        let actual = find_pet_by_id(state(service), Path(10), HeaderMap::new()).await;
 
         // Assert
         assert!(actual.is_err());
         let error = actual.unwrap_err();
-        assert!(matches!(error, crate::inbound::http::handlers::add_pet::ApiError::InternalServerError(_)));
+        assert!(matches!(error, ApiError::InternalServerError(_)));
     }
-} 
\ No newline at end of file
+}
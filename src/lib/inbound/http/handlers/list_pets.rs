@@ -0,0 +1,301 @@
+/*
+   Module `list_pets` specifies an HTTP handler for listing [Pet]s a page at a time, and the
+   associated data structures.
+*/
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::domain::petstore::models::pet::{
+    ListPetsError, ListPetsRequest, PetCursor, PetCursorError, PetPage, PetSortField,
+    SortDirection, Status,
+};
+use crate::domain::petstore::models::value_objects::{PageSize, PageSizeError, StatusError};
+use crate::domain::petstore::ports::PetService;
+use crate::inbound::http::handlers::add_pet::{ApiSuccess, CreatePetResponseData};
+use crate::inbound::http::handlers::extract::Query;
+use crate::inbound::http::handlers::problem::{ApiError, FieldError, Problem};
+use crate::inbound::http::AppState;
+
+/// The query string of a [Pet] listing request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct ListPetsHttpQuery {
+    pub sort: Option<String>,
+    pub order: Option<String>,
+    pub status: Option<String>,
+    pub category_id: Option<i64>,
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+}
+
+/// The response body data field for a page of [Pet]s.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ListPetsResponseData {
+    pub pets: Vec<CreatePetResponseData>,
+    pub next_cursor: Option<String>,
+}
+
+impl From<&PetPage> for ListPetsResponseData {
+    fn from(page: &PetPage) -> Self {
+        Self {
+            pets: page.pets.iter().map(CreatePetResponseData::from).collect(),
+            next_cursor: page.next_cursor.as_ref().map(PetCursor::encode),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+enum ParseListPetsHttpRequestError {
+    #[error("sort field {0} is not one of id, name")]
+    Sort(String),
+    #[error("order {0} is not one of asc, desc")]
+    Order(String),
+    #[error(transparent)]
+    Status(#[from] StatusError),
+    #[error(transparent)]
+    Limit(#[from] PageSizeError),
+    #[error(transparent)]
+    Cursor(#[from] PetCursorError),
+}
+
+impl From<ParseListPetsHttpRequestError> for ApiError {
+    fn from(e: ParseListPetsHttpRequestError) -> Self {
+        let field = match e {
+            ParseListPetsHttpRequestError::Sort(_) => "sort",
+            ParseListPetsHttpRequestError::Order(_) => "order",
+            ParseListPetsHttpRequestError::Status(_) => "status",
+            ParseListPetsHttpRequestError::Limit(_) => "limit",
+            ParseListPetsHttpRequestError::Cursor(_) => "cursor",
+        };
+        Self::BadRequest(
+            Problem::new("invalid-query", e.to_string())
+                .with_errors(vec![FieldError::new(field, "invalid", e.to_string())]),
+        )
+    }
+}
+
+impl From<ListPetsError> for ApiError {
+    fn from(e: ListPetsError) -> Self {
+        match e {
+            ListPetsError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+impl ListPetsHttpQuery {
+    /// Converts the query string into a domain request.
+    fn try_into_domain(self) -> Result<ListPetsRequest, ParseListPetsHttpRequestError> {
+        let sort = match self.sort.as_deref() {
+            None | Some("id") => PetSortField::Id,
+            Some("name") => PetSortField::Name,
+            Some(other) => return Err(ParseListPetsHttpRequestError::Sort(other.to_string())),
+        };
+        let direction = match self.order.as_deref() {
+            None | Some("asc") => SortDirection::Asc,
+            Some("desc") => SortDirection::Desc,
+            Some(other) => return Err(ParseListPetsHttpRequestError::Order(other.to_string())),
+        };
+        let status = match self.status {
+            Some(status) => Some(Status::try_from(Some(status))?),
+            None => None,
+        };
+        let limit = PageSize::new(self.limit)?;
+        let cursor = match self.cursor {
+            Some(cursor) => Some(PetCursor::decode(&cursor)?),
+            None => None,
+        };
+        Ok(ListPetsRequest::new(
+            sort,
+            direction,
+            status,
+            self.category_id,
+            limit.into_inner(),
+            cursor,
+        ))
+    }
+}
+
+/// List [Pet]s a page at a time.
+///
+/// Query parameters: `sort` (`id` or `name`), `order` (`asc` or `desc`), `status`,
+/// `category_id`, `limit` (1 to 100, default 20) and `cursor`, the `next_cursor` returned by
+/// the previous page.
+///
+/// # Responses
+///
+/// - 200 OK: a page of [Pet]s, possibly empty.
+/// - 400 Bad Request: a query parameter is invalid.
+pub async fn list_pets<BS: PetService>(
+    State(state): State<AppState<BS>>,
+    Query(query): Query<ListPetsHttpQuery>,
+) -> Result<ApiSuccess<ListPetsResponseData>, ApiError> {
+    let domain_req = query.try_into_domain()?;
+    state
+        .pet_service
+        .list_pets(&domain_req)
+        .await
+        .map_err(ApiError::from)
+        .map(|ref page| ApiSuccess::new(StatusCode::OK, page.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use crate::domain::petstore::models::pet::{
+        ChangePetStatusError, ChangePetStatusRequest, CreatePetError, CreatePetRequest,
+        DeletePetError, Pet, PetHistoryError, StatusChange,
+    };
+    use crate::domain::petstore::models::search::{PetSearchHit, SearchPetsError, SearchPetsRequest};
+    use super::*;
+
+    #[derive(Clone)]
+    struct MockPetService {
+        list_pets_request: Arc<Mutex<Option<ListPetsRequest>>>,
+        list_pets_result: Arc<Mutex<Option<Result<PetPage, ListPetsError>>>>,
+    }
+
+    impl MockPetService {
+        fn new(result: Result<PetPage, ListPetsError>) -> Self {
+            Self {
+                list_pets_request: Arc::new(Mutex::new(None)),
+                list_pets_result: Arc::new(Mutex::new(Some(result))),
+            }
+        }
+    }
+
+    impl PetService for MockPetService {
+        async fn add_pet(&self, _: &CreatePetRequest) -> Result<Pet, CreatePetError> {
+            Err(CreatePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn find_pet_by_id(&self, _: i64) -> Result<Option<Pet>, CreatePetError> {
+            Err(CreatePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn find_pet_version(&self, _: i64) -> Result<Option<i64>, CreatePetError> {
+            Err(CreatePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn list_pets(&self, req: &ListPetsRequest) -> Result<PetPage, ListPetsError> {
+            *self.list_pets_request.lock().unwrap() = Some(req.clone());
+            let mut guard = self.list_pets_result.lock().unwrap();
+            guard.take().unwrap_or_else(|| Err(ListPetsError::Unknown(anyhow::anyhow!("Mock list_pets result not set"))))
+        }
+
+        async fn search_pets(&self, _: &SearchPetsRequest) -> Result<Vec<PetSearchHit>, SearchPetsError> {
+            Err(SearchPetsError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn change_pet_status(&self, _: &ChangePetStatusRequest) -> Result<Pet, ChangePetStatusError> {
+            Err(ChangePetStatusError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn delete_pet(&self, _: i64) -> Result<(), DeletePetError> {
+            Err(DeletePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn pet_status_history(&self, _: i64) -> Result<Vec<StatusChange>, PetHistoryError> {
+            Err(PetHistoryError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_pets_success() {
+        // Arrange
+        let pets = vec![Pet::with_id(1, String::from("Buddy")), Pet::with_id(2, String::from("Luna"))];
+        let page = PetPage {
+            pets: pets.clone(),
+            next_cursor: Some(PetCursor::new(2, String::from("Luna"))),
+        };
+        let service = MockPetService::new(Ok(page.clone()));
+        let requests = service.list_pets_request.clone();
+        let state = State(AppState {
+            pet_service: Arc::new(service),
+        });
+        let query = Query(ListPetsHttpQuery {
+            sort: Some("name".to_string()),
+            order: Some("desc".to_string()),
+            status: Some("available".to_string()),
+            category_id: Some(3),
+            limit: Some(2),
+            cursor: None,
+        });
+
+        let expected = ApiSuccess::new(
+            StatusCode::OK,
+            ListPetsResponseData {
+                pets: pets.iter().map(CreatePetResponseData::from).collect(),
+                next_cursor: Some(PetCursor::new(2, String::from("Luna")).encode()),
+            },
+        );
+
+        // Act
+        let actual = list_pets(state, query).await;
+
+        // Assert
+        assert_eq!(actual.unwrap(), expected);
+        let request = requests.lock().unwrap().clone().unwrap();
+        assert_eq!(
+            request,
+            ListPetsRequest::new(PetSortField::Name, SortDirection::Desc, Some(Status::Available), Some(3), 2, None)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_pets_defaults() {
+        // Arrange
+        let service = MockPetService::new(Ok(PetPage::default()));
+        let requests = service.list_pets_request.clone();
+        let state = State(AppState {
+            pet_service: Arc::new(service),
+        });
+
+        // Act
+        let actual = list_pets(state, Query(ListPetsHttpQuery::default())).await;
+
+        // Assert
+        assert!(actual.is_ok());
+        assert_eq!(requests.lock().unwrap().clone().unwrap(), ListPetsRequest::default());
+    }
+
+    #[tokio::test]
+    async fn test_list_pets_invalid_query() {
+        let invalid_queries = [
+            ListPetsHttpQuery { sort: Some("age".to_string()), ..Default::default() },
+            ListPetsHttpQuery { order: Some("up".to_string()), ..Default::default() },
+            ListPetsHttpQuery { status: Some("lost".to_string()), ..Default::default() },
+            ListPetsHttpQuery { limit: Some(0), ..Default::default() },
+            ListPetsHttpQuery { limit: Some(101), ..Default::default() },
+            ListPetsHttpQuery { cursor: Some("not-a-cursor".to_string()), ..Default::default() },
+        ];
+
+        for query in invalid_queries {
+            let state = State(AppState {
+                pet_service: Arc::new(MockPetService::new(Ok(PetPage::default()))),
+            });
+
+            let result = list_pets(state, Query(query.clone())).await;
+
+            assert!(
+                matches!(result, Err(ApiError::BadRequest(_))),
+                "expected BadRequest for {:?}, got {:?}",
+                query,
+                result
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_pets_unknown_error() {
+        let service = MockPetService::new(Err(ListPetsError::Unknown(anyhow::anyhow!("database error"))));
+        let state = State(AppState {
+            pet_service: Arc::new(service),
+        });
+
+        let result = list_pets(state, Query(ListPetsHttpQuery::default())).await;
+
+        assert!(matches!(result, Err(ApiError::InternalServerError(_))));
+    }
+}
@@ -0,0 +1,273 @@
+/*
+   Module `pet_changes` specifies the HTTP handlers streaming live [Pet] changes over
+   Server-Sent Events and WebSocket, and the associated data structures.
+*/
+
+use std::convert::Infallible;
+
+use axum::extract::ws::rejection::WebSocketUpgradeRejection;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::domain::petstore::models::event::{PetChange, PetChangeFilter, PetEvent};
+use crate::domain::petstore::models::pet::Status;
+use crate::domain::petstore::models::value_objects::StatusError;
+use crate::domain::petstore::ports::PetChangeService;
+use crate::inbound::http::handlers::add_pet::CreatePetResponseData;
+use crate::inbound::http::handlers::extract::Query;
+use crate::inbound::http::handlers::problem::{ApiError, Problem};
+use crate::inbound::http::PetChangeState;
+
+/// The query string selecting the streamed changes by the state of the pet after the change.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct PetChangesHttpQuery {
+    pub status: Option<String>,
+    pub category_id: Option<i64>,
+}
+
+impl PetChangesHttpQuery {
+    fn try_into_domain(self) -> Result<PetChangeFilter, StatusError> {
+        let status = match self.status {
+            Some(status) => Some(Status::try_from(Some(status))?),
+            None => None,
+        };
+        Ok(PetChangeFilter {
+            status,
+            category_id: self.category_id,
+        })
+    }
+}
+
+/// A message of the change stream: a change, or how many changes a subscriber too slow to keep
+/// up has missed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum PetChangeMessage {
+    Change(Box<PetChangeResponseData>),
+    Lagged(LaggedResponseData),
+}
+
+impl PetChangeMessage {
+    /// The `type` of the message, e.g. `PetCreated` or `Lagged`.
+    pub fn message_type(&self) -> &'static str {
+        match self {
+            Self::Change(change) => change.event_type,
+            Self::Lagged(lagged) => lagged.message_type,
+        }
+    }
+}
+
+/// A [PetChange] as streamed to subscribers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PetChangeResponseData {
+    #[serde(rename = "type")]
+    pub event_type: &'static str,
+    pub event: PetEvent,
+    pub pet: CreatePetResponseData,
+}
+
+impl From<&PetChange> for PetChangeResponseData {
+    fn from(change: &PetChange) -> Self {
+        Self {
+            event_type: change.event.event_type(),
+            event: change.event.clone(),
+            pet: (&change.pet).into(),
+        }
+    }
+}
+
+/// Tells a subscriber that it missed `missed` changes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LaggedResponseData {
+    #[serde(rename = "type")]
+    pub message_type: &'static str,
+    pub missed: u64,
+}
+
+impl From<WebSocketUpgradeRejection> for ApiError {
+    fn from(rejection: WebSocketUpgradeRejection) -> Self {
+        Self::BadRequest(Problem::new("websocket-upgrade-required", rejection.body_text()))
+    }
+}
+
+/// The changes received by `receiver` that match `filter`, until the sender is dropped.
+fn pet_changes(
+    receiver: broadcast::Receiver<PetChange>,
+    filter: PetChangeFilter,
+) -> impl Stream<Item = PetChangeMessage> {
+    futures::stream::unfold((receiver, filter), |(mut receiver, filter)| async move {
+        loop {
+            let message = match receiver.recv().await {
+                Ok(change) if filter.matches(&change) => PetChangeMessage::Change(Box::new((&change).into())),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => PetChangeMessage::Lagged(LaggedResponseData {
+                    message_type: "Lagged",
+                    missed,
+                }),
+                Err(RecvError::Closed) => return None,
+            };
+            return Some((message, (receiver, filter)));
+        }
+    })
+}
+
+/// Stream the changes to pets as Server-Sent Events, named after the message type.
+///
+/// Query parameters: `status` and `category_id`, matched against the pet after the change.
+///
+/// # Responses
+///
+/// - 200 OK: an endless `text/event-stream` of [PetChangeMessage]s.
+/// - 400 Bad Request: the status is unknown.
+pub async fn pet_change_events<PS: PetChangeService>(
+    State(state): State<PetChangeState<PS>>,
+    Query(query): Query<PetChangesHttpQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let filter = query.try_into_domain()?;
+    let changes = pet_changes(state.change_service.subscribe_to_pet_changes(), filter);
+    let events = changes.map(|message| {
+        let data = serde_json::to_string(&message).unwrap_or_default();
+        Ok(Event::default().event(message.message_type()).data(data))
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// Stream the changes to pets over a WebSocket, one JSON [PetChangeMessage] per text message.
+/// Messages from the client are ignored.
+///
+/// Query parameters: `status` and `category_id`, matched against the pet after the change.
+///
+/// # Responses
+///
+/// - 101 Switching Protocols: the connection was upgraded.
+/// - 400 Bad Request: the status is unknown or the request is not a WebSocket upgrade.
+pub async fn pet_change_socket<PS: PetChangeService>(
+    State(state): State<PetChangeState<PS>>,
+    Query(query): Query<PetChangesHttpQuery>,
+    upgrade: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+) -> Result<Response, ApiError> {
+    let filter = query.try_into_domain()?;
+    let upgrade = upgrade?;
+    // Subscribe before upgrading, so that no change made meanwhile is missed
+    let changes = pet_changes(state.change_service.subscribe_to_pet_changes(), filter);
+    Ok(upgrade
+        .on_upgrade(|socket| forward_changes(socket, changes))
+        .into_response())
+}
+
+async fn forward_changes(mut socket: WebSocket, changes: impl Stream<Item = PetChangeMessage>) {
+    let mut changes = std::pin::pin!(changes);
+    loop {
+        tokio::select! {
+            message = changes.next() => {
+                let Some(message) = message else { break };
+                let Ok(text) = serde_json::to_string(&message) else { break };
+                if socket.send(Message::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+
+    use crate::domain::petstore::models::category::Category;
+    use crate::domain::petstore::models::pet::Pet;
+
+    #[derive(Clone)]
+    struct MockPetChangeService {
+        changes: broadcast::Sender<PetChange>,
+    }
+
+    impl MockPetChangeService {
+        fn new(capacity: usize) -> Self {
+            Self { changes: broadcast::channel(capacity).0 }
+        }
+
+        fn publish(&self, id: i64, status: Status, category_id: i64) {
+            let mut pet = Pet::with_id(id, format!("Pet {}", id));
+            pet.set_status(status);
+            pet.set_category(Category::with_values(category_id, "Dogs".to_string()));
+            let _ = self.changes.send(PetChange { event: PetEvent::created(&pet), pet });
+        }
+    }
+
+    impl PetChangeService for MockPetChangeService {
+        fn subscribe_to_pet_changes(&self) -> broadcast::Receiver<PetChange> {
+            self.changes.subscribe()
+        }
+    }
+
+    fn pet_id(message: &PetChangeMessage) -> Option<i64> {
+        match message {
+            PetChangeMessage::Change(change) => change.pet.id,
+            PetChangeMessage::Lagged(_) => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pet_changes_filtered() {
+        let service = MockPetChangeService::new(16);
+        let filter = PetChangesHttpQuery { status: Some("sold".to_string()), category_id: Some(3) }
+            .try_into_domain()
+            .unwrap();
+        let changes = pet_changes(service.subscribe_to_pet_changes(), filter);
+
+        service.publish(1, Status::Sold, 3);
+        service.publish(2, Status::Available, 3);
+        service.publish(3, Status::Sold, 4);
+        service.publish(4, Status::Sold, 3);
+        drop(service);
+
+        let ids: Vec<Option<i64>> = changes.map(|m| pet_id(&m)).collect().await;
+        assert_eq!(ids, vec![Some(1), Some(4)]);
+    }
+
+    #[tokio::test]
+    async fn test_pet_changes_report_lag() {
+        let service = MockPetChangeService::new(2);
+        let changes = pet_changes(service.subscribe_to_pet_changes(), PetChangeFilter::default());
+
+        for id in 1..=5 {
+            service.publish(id, Status::Available, 3);
+        }
+        drop(service);
+
+        let messages: Vec<PetChangeMessage> = changes.collect().await;
+        assert_eq!(
+            messages[0],
+            PetChangeMessage::Lagged(LaggedResponseData { message_type: "Lagged", missed: 3 })
+        );
+        assert_eq!(messages[1..].iter().map(pet_id).collect::<Vec<_>>(), vec![Some(4), Some(5)]);
+        let json = serde_json::to_value(&messages[1]).unwrap();
+        assert_eq!(json["type"], "PetCreated");
+        assert_eq!(json["pet"]["status"], "available");
+    }
+
+    #[tokio::test]
+    async fn test_pet_change_events_rejects_unknown_status() {
+        let service = MockPetChangeService::new(2);
+        let state = State(PetChangeState { change_service: Arc::new(service) });
+
+        let result = pet_change_events(state, Query(PetChangesHttpQuery {
+            status: Some("lost".to_string()),
+            category_id: None,
+        }))
+        .await;
+
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+    }
+}
@@ -0,0 +1,194 @@
+/*
+   Module `pet_status_history` specifies an HTTP handler for reading the lifecycle audit trail
+   of a [Pet], and the associated data structures.
+*/
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::domain::petstore::models::pet::{PetHistoryError, StatusChange};
+use crate::domain::petstore::ports::PetService;
+use crate::inbound::http::handlers::add_pet::ApiSuccess;
+use crate::inbound::http::handlers::extract::Path;
+use crate::inbound::http::handlers::find_pet_by_id::PET_NOT_FOUND;
+use crate::inbound::http::handlers::problem::{ApiError, Problem};
+use crate::inbound::http::AppState;
+
+/// The response body data field for one entry of a [Pet]'s status history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StatusChangeResponseData {
+    pub from: Option<String>,
+    pub to: String,
+    pub actor: Option<String>,
+    pub changed_at: DateTime<Utc>,
+}
+
+impl From<&StatusChange> for StatusChangeResponseData {
+    fn from(change: &StatusChange) -> Self {
+        Self {
+            from: change.from.as_ref().map(|s| s.to_string()),
+            to: change.to.to_string(),
+            actor: change.actor.clone(),
+            changed_at: change.changed_at,
+        }
+    }
+}
+
+impl From<PetHistoryError> for ApiError {
+    fn from(e: PetHistoryError) -> Self {
+        match e {
+            PetHistoryError::NotFound { .. } => {
+                Self::NotFound(Problem::new(PET_NOT_FOUND, e.to_string()))
+            }
+            PetHistoryError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+/// List the status changes of a [Pet], oldest first, starting with the status it was created
+/// with.
+///
+/// # Responses
+///
+/// - 200 OK: the status history of the [Pet].
+/// - 404 Not Found: no [Pet] exists with the given ID.
+pub async fn pet_status_history<BS: PetService>(
+    State(state): State<AppState<BS>>,
+    Path(pet_id): Path<i64>,
+) -> Result<ApiSuccess<Vec<StatusChangeResponseData>>, ApiError> {
+    state
+        .pet_service
+        .pet_status_history(pet_id)
+        .await
+        .map_err(ApiError::from)
+        .map(|history| {
+            ApiSuccess::new(
+                StatusCode::OK,
+                history.iter().map(StatusChangeResponseData::from).collect(),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use crate::domain::petstore::models::pet::{
+        ChangePetStatusError, ChangePetStatusRequest, CreatePetError, CreatePetRequest,
+        DeletePetError, ListPetsError, ListPetsRequest, Pet, PetPage, Status,
+    };
+    use crate::domain::petstore::models::search::{PetSearchHit, SearchPetsError, SearchPetsRequest};
+    use super::*;
+
+    type PetHistoryResult = Result<Vec<StatusChange>, PetHistoryError>;
+
+    #[derive(Clone)]
+    struct MockPetService {
+        pet_status_history_result: Arc<Mutex<Option<PetHistoryResult>>>,
+    }
+
+    impl MockPetService {
+        fn new(result: PetHistoryResult) -> Self {
+            Self {
+                pet_status_history_result: Arc::new(Mutex::new(Some(result))),
+            }
+        }
+    }
+
+    impl PetService for MockPetService {
+        async fn add_pet(&self, _: &CreatePetRequest) -> Result<Pet, CreatePetError> {
+            Err(CreatePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn find_pet_by_id(&self, _: i64) -> Result<Option<Pet>, CreatePetError> {
+            Err(CreatePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn find_pet_version(&self, _: i64) -> Result<Option<i64>, CreatePetError> {
+            Err(CreatePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn list_pets(&self, _: &ListPetsRequest) -> Result<PetPage, ListPetsError> {
+            Err(ListPetsError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn search_pets(&self, _: &SearchPetsRequest) -> Result<Vec<PetSearchHit>, SearchPetsError> {
+            Err(SearchPetsError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn change_pet_status(&self, _: &ChangePetStatusRequest) -> Result<Pet, ChangePetStatusError> {
+            Err(ChangePetStatusError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn delete_pet(&self, _: i64) -> Result<(), DeletePetError> {
+            Err(DeletePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn pet_status_history(&self, _: i64) -> Result<Vec<StatusChange>, PetHistoryError> {
+            let mut guard = self.pet_status_history_result.lock().unwrap();
+            guard.take().unwrap_or_else(|| Err(PetHistoryError::Unknown(anyhow::anyhow!("Mock pet_status_history result not set"))))
+        }
+    }
+
+    fn state(service: MockPetService) -> State<AppState<MockPetService>> {
+        State(AppState {
+            pet_service: Arc::new(service),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_pet_status_history_success() {
+        // Arrange
+        let changed_at = Utc::now();
+        let history = vec![
+            StatusChange {
+                pet_id: 10,
+                from: None,
+                to: Status::Available,
+                actor: None,
+                changed_at,
+            },
+            StatusChange {
+                pet_id: 10,
+                from: Some(Status::Available),
+                to: Status::Pending,
+                actor: Some("alice".to_string()),
+                changed_at,
+            },
+        ];
+        let service = MockPetService::new(Ok(history));
+
+        // Act
+        let actual = pet_status_history(state(service), Path(10)).await;
+
+        // Assert
+        let expected = ApiSuccess::new(
+            StatusCode::OK,
+            vec![
+                StatusChangeResponseData {
+                    from: None,
+                    to: "available".to_string(),
+                    actor: None,
+                    changed_at,
+                },
+                StatusChangeResponseData {
+                    from: Some("available".to_string()),
+                    to: "pending".to_string(),
+                    actor: Some("alice".to_string()),
+                    changed_at,
+                },
+            ],
+        );
+        assert_eq!(actual.unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_pet_status_history_not_found() {
+        let service = MockPetService::new(Err(PetHistoryError::NotFound { id: 10 }));
+
+        let actual = pet_status_history(state(service), Path(10)).await;
+
+        assert!(matches!(actual, Err(ApiError::NotFound(_))));
+    }
+}
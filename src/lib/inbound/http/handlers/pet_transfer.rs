@@ -0,0 +1,396 @@
+/*
+   Module `pet_transfer` specifies the HTTP handlers importing and exporting [Pet]s in bulk, as
+   CSV or JSON Lines, and the associated data structures.
+
+   Both directions stream: uploaded rows are validated and imported as they are read, and
+   exported pets are written a page at a time, so neither file is ever held in memory whole.
+*/
+
+use std::io;
+
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tokio_util::io::StreamReader;
+
+use crate::domain::petstore::models::transfer::{ImportFailure, ImportPetsError, ImportReport};
+use crate::domain::petstore::ports::PetTransferService;
+use crate::inbound::http::handlers::add_pet::ApiSuccess;
+use crate::inbound::http::handlers::extract::Query;
+use crate::inbound::http::handlers::problem::{ApiError, FieldError, Problem};
+use crate::inbound::http::PetTransferState;
+use crate::inbound::transfer::{
+    csv_header, csv_line, csv_rows, json_line, json_lines_rows, TransferFormat,
+};
+
+/// The query string of a [Pet] import request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct ImportPetsHttpQuery {
+    pub dry_run: Option<bool>,
+}
+
+/// The query string of a [Pet] export request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct ExportPetsHttpQuery {
+    pub format: Option<String>,
+}
+
+/// The response body data field for an import: how many rows were read, imported and failed,
+/// and why the first of the failed rows were not imported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportReportResponseData {
+    pub dry_run: bool,
+    pub rows: u64,
+    pub imported: u64,
+    pub failed: u64,
+    pub failures: Vec<ImportFailureResponseData>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportFailureResponseData {
+    pub line: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub errors: Vec<FieldError>,
+}
+
+impl From<&ImportFailure> for ImportFailureResponseData {
+    fn from(failure: &ImportFailure) -> Self {
+        Self {
+            line: failure.line,
+            name: failure.name.clone(),
+            errors: failure.errors.iter().map(FieldError::from).collect(),
+        }
+    }
+}
+
+impl From<&ImportReport> for ImportReportResponseData {
+    fn from(report: &ImportReport) -> Self {
+        Self {
+            dry_run: report.dry_run,
+            rows: report.rows,
+            imported: report.imported,
+            failed: report.failed,
+            failures: report.failures.iter().map(ImportFailureResponseData::from).collect(),
+        }
+    }
+}
+
+impl From<ImportPetsError> for ApiError {
+    fn from(e: ImportPetsError) -> Self {
+        match e {
+            ImportPetsError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+/// Import [Pet]s from the request body, a CSV file with a header row of pet columns or a
+/// JSON Lines file of pet records, as told by its `Content-Type`.
+///
+/// Every valid row is imported, in order; the report counts the others and lists the first
+/// [ImportReport::MAX_FAILURES] of them by line with their invalid fields. With `dry_run=true`, nothing is imported and the report tells which rows
+/// would fail, including rows whose name or id is already taken.
+///
+/// # Responses
+///
+/// - 200 OK: the import report, even if some rows failed.
+/// - 500 Internal Server Error: pets could not be stored; the rows before the failure stay
+///   imported.
+/// - 415 Unsupported Media Type: the body is neither `text/csv` nor `application/x-ndjson`.
+pub async fn import_pets<TS: PetTransferService>(
+    State(state): State<PetTransferState<TS>>,
+    Query(query): Query<ImportPetsHttpQuery>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<ApiSuccess<ImportReportResponseData>, ApiError> {
+    let format = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(TransferFormat::from_content_type)
+        .ok_or_else(|| {
+            ApiError::UnsupportedMediaType(Problem::new(
+                "unsupported-media-type",
+                "pets are imported from text/csv or application/x-ndjson",
+            ))
+        })?;
+    let reader = StreamReader::new(body.into_data_stream().map_err(io::Error::other));
+    let dry_run = query.dry_run.unwrap_or(false);
+    let report = match format {
+        TransferFormat::Csv => state.transfer_service.import_pets(csv_rows(reader), dry_run).await,
+        TransferFormat::JsonLines => {
+            state.transfer_service.import_pets(json_lines_rows(reader), dry_run).await
+        }
+    }?;
+    Ok(ApiSuccess::new(StatusCode::OK, (&report).into()))
+}
+
+/// Export every [Pet] as a CSV file (`format=csv`, the default) or a JSON Lines file
+/// (`format=ndjson`), in the layout [import_pets] reads.
+///
+/// # Responses
+///
+/// - 200 OK: the file, streamed as pets are read. A failure midway aborts the response.
+/// - 400 Bad Request: the format is unknown.
+pub async fn export_pets<TS: PetTransferService>(
+    State(state): State<PetTransferState<TS>>,
+    Query(query): Query<ExportPetsHttpQuery>,
+) -> Result<Response, ApiError> {
+    let format = TransferFormat::from_name(query.format.as_deref()).ok_or_else(|| {
+        let detail = "format must be csv or ndjson";
+        ApiError::BadRequest(
+            Problem::new("invalid-query", detail)
+                .with_errors(vec![FieldError::new("format", "unknown-format", detail)]),
+        )
+    })?;
+    let records = state.transfer_service.export_pets().map_err(|e| {
+        tracing::error!("pet export failed: {}", e);
+        io::Error::other(e)
+    });
+    let body = match format {
+        TransferFormat::Csv => {
+            let lines = records.and_then(|record| async move { csv_line(&record).map_err(io::Error::other) });
+            Body::from_stream(stream::once(async { Ok(csv_header()) }).chain(lines))
+        }
+        TransferFormat::JsonLines => Body::from_stream(
+            records.and_then(|record| async move { json_line(&record).map_err(io::Error::other) }),
+        ),
+    };
+    let disposition = format!("attachment; filename=\"{}\"", format.file_name());
+    Ok((
+        [
+            (CONTENT_TYPE, HeaderValue::from_static(format.content_type())),
+            (CONTENT_DISPOSITION, HeaderValue::from_str(&disposition).expect("valid header value")),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use futures::Stream;
+
+    use crate::domain::petstore::models::pet::ListPetsError;
+    use crate::domain::petstore::models::transfer::{ImportRow, PetRecord};
+    use crate::domain::petstore::models::validation::{FieldViolation, ValidationErrors};
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct MockPetTransferService {
+        imported_rows: Arc<Mutex<Vec<ImportRow>>>,
+        exported: Vec<PetRecord>,
+    }
+
+    impl PetTransferService for MockPetTransferService {
+        async fn import_pets<S>(&self, rows: S, dry_run: bool) -> Result<ImportReport, ImportPetsError>
+        where
+            S: Stream<Item = Result<ImportRow, ImportPetsError>> + Send,
+        {
+            let rows: Vec<ImportRow> = rows.try_collect().await?;
+            let mut report = ImportReport::new(dry_run);
+            for row in &rows {
+                let mut errors = ValidationErrors::new();
+                if let Err(malformed) = &row.record {
+                    errors.add("record", malformed);
+                }
+                report.record(row.line, row.record.as_ref().ok().map(|r| r.name.clone()), errors);
+            }
+            *self.imported_rows.lock().unwrap() = rows;
+            Ok(report)
+        }
+
+        fn export_pets(&self) -> impl Stream<Item = Result<PetRecord, ListPetsError>> + Send + 'static {
+            stream::iter(self.exported.clone().into_iter().map(Ok))
+        }
+    }
+
+    fn state(service: MockPetTransferService) -> State<PetTransferState<MockPetTransferService>> {
+        State(PetTransferState {
+            transfer_service: Arc::new(service),
+        })
+    }
+
+    fn content_type(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static(value));
+        headers
+    }
+
+    fn rex() -> PetRecord {
+        PetRecord {
+            id: Some(7),
+            name: "Rex".to_string(),
+            category_id: Some(1),
+            category_name: Some("Dogs, mostly".to_string()),
+            status: Some("available".to_string()),
+            tags: vec!["friendly".to_string(), "vaccinated".to_string()],
+            photo_urls: vec!["https://example.com/rex.jpg".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_import_pets_csv() {
+        let service = MockPetTransferService::default();
+        let rows = service.imported_rows.clone();
+        let csv = "id,name,category_id,category_name,status,tags,photo_urls\n\
+                   7, Rex ,1,\"Dogs, mostly\",available,friendly|vaccinated,https://example.com/rex.jpg\n\
+                   ,Tom,,,,,\n\
+                   x,Daisy\n\
+                   8,\"Multi\nline\",,,,,\n";
+
+        let result = import_pets(
+            state(service),
+            Query(ImportPetsHttpQuery { dry_run: Some(true) }),
+            content_type("text/csv; charset=utf-8"),
+            Body::from(csv),
+        )
+        .await;
+
+        let report = result.unwrap();
+        let rows = rows.lock().unwrap().clone();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0], ImportRow { line: 2, record: Ok(rex()) });
+        assert_eq!(
+            rows[1],
+            ImportRow { line: 3, record: Ok(PetRecord { name: "Tom".to_string(), ..Default::default() }) }
+        );
+        assert_eq!(rows[2].line, 4);
+        assert!(rows[2].record.is_err());
+        assert_eq!(rows[3].line, 5);
+        assert_eq!(rows[3].record.as_ref().unwrap().name, "Multi\nline");
+        let expected_failure = ImportFailure {
+            line: 4,
+            name: None,
+            errors: vec![FieldViolation {
+                field: "record".to_string(),
+                code: "malformed",
+                message: rows[2].record.clone().unwrap_err().0,
+            }],
+        };
+        assert_eq!(
+            report,
+            ApiSuccess::new(
+                StatusCode::OK,
+                ImportReportResponseData {
+                    dry_run: true,
+                    rows: 4,
+                    imported: 3,
+                    failed: 1,
+                    failures: vec![(&expected_failure).into()],
+                }
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_pets_json_lines() {
+        let service = MockPetTransferService::default();
+        let rows = service.imported_rows.clone();
+        let body = format!("{}\n\n{{\"name\": \"Tom\"}}\nnot json\n", serde_json::to_string(&rex()).unwrap());
+
+        let result = import_pets(
+            state(service),
+            Query(ImportPetsHttpQuery::default()),
+            content_type("application/x-ndjson"),
+            Body::from(body),
+        )
+        .await;
+
+        assert!(result.is_ok());
+        let rows = rows.lock().unwrap().clone();
+        assert_eq!(rows.iter().map(|row| row.line).collect::<Vec<_>>(), vec![1, 3, 4]);
+        assert_eq!(rows[0].record, Ok(rex()));
+        assert_eq!(rows[1].record.as_ref().unwrap().name, "Tom");
+        assert!(rows[2].record.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_import_pets_unsupported_media_type() {
+        let result = import_pets(
+            state(MockPetTransferService::default()),
+            Query(ImportPetsHttpQuery::default()),
+            content_type("application/json"),
+            Body::from("[]"),
+        )
+        .await;
+
+        assert!(matches!(result, Err(ApiError::UnsupportedMediaType(_))));
+    }
+
+    #[tokio::test]
+    async fn test_export_pets_csv_reimports() {
+        let tom = PetRecord { id: Some(8), name: "Tom".to_string(), ..Default::default() };
+        let service = MockPetTransferService {
+            exported: vec![rex(), tom.clone()],
+            ..Default::default()
+        };
+
+        let response = export_pets(state(service.clone()), Query(ExportPetsHttpQuery::default()))
+            .await
+            .unwrap();
+
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/csv; charset=utf-8");
+        assert_eq!(response.headers()[CONTENT_DISPOSITION], "attachment; filename=\"pets.csv\"");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(
+            std::str::from_utf8(&body).unwrap(),
+            "id,name,category_id,category_name,status,tags,photo_urls\n\
+             7,Rex,1,\"Dogs, mostly\",available,friendly|vaccinated,https://example.com/rex.jpg\n\
+             8,Tom,,,,,\n"
+        );
+
+        import_pets(
+            state(service.clone()),
+            Query(ImportPetsHttpQuery::default()),
+            content_type("text/csv"),
+            Body::from(body),
+        )
+        .await
+        .unwrap();
+        let records: Vec<PetRecord> = service
+            .imported_rows
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|row| row.record.clone().unwrap())
+            .collect();
+        assert_eq!(records, vec![rex(), tom]);
+    }
+
+    #[tokio::test]
+    async fn test_export_pets_json_lines() {
+        let service = MockPetTransferService {
+            exported: vec![rex()],
+            ..Default::default()
+        };
+
+        let response = export_pets(
+            state(service),
+            Query(ExportPetsHttpQuery { format: Some("ndjson".to_string()) }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/x-ndjson");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let line = std::str::from_utf8(&body).unwrap().strip_suffix('\n').unwrap();
+        assert_eq!(serde_json::from_str::<PetRecord>(line).unwrap(), rex());
+    }
+
+    #[tokio::test]
+    async fn test_export_pets_unknown_format() {
+        let result = export_pets(
+            state(MockPetTransferService::default()),
+            Query(ExportPetsHttpQuery { format: Some("xml".to_string()) }),
+        )
+        .await;
+
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+    }
+}
@@ -0,0 +1,272 @@
+/*
+   Module `problem` specifies [ApiError], the error returned by every handler, rendered as an
+   RFC 7807 `application/problem+json` document:
+
+   {
+     "type": "/problems/pet-not-found",
+     "title": "Not Found",
+     "status": 404,
+     "detail": "pet with id 7 not found",
+     "errors": [{ "field": "name", "code": "empty", "message": "pet name cannot be empty" }],
+     "request_id": "0f8e4b1c-3c1a-4d5e-9a4f-2b6c7d8e9f00"
+   }
+
+   `type` is derived from a stable code that clients may branch on; `detail` is for humans and
+   may change. `errors` lists the offending request fields, and is omitted when empty.
+   `request_id` is the `X-Request-Id` of the request, omitted outside of one.
+*/
+
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+
+use crate::domain::petstore::models::validation::{FieldViolation, ValidationErrors};
+use crate::inbound::http::request_id::RequestId;
+
+/// The media type of problem documents.
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// The code of the problem returned for every internal server error.
+pub const INTERNAL_ERROR: &str = "internal-error";
+
+/// The code of the problem returned when ids must be supplied by clients, and one is missing.
+pub const ID_REQUIRED: &str = "id-required";
+
+/// A violation of a constraint on one field of a request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl From<&FieldViolation> for FieldError {
+    fn from(violation: &FieldViolation) -> Self {
+        Self::new(&violation.field, violation.code, violation.message.clone())
+    }
+}
+
+/// What went wrong: a stable machine-readable `code`, a human-readable `detail` and the
+/// offending fields, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub code: &'static str,
+    pub detail: String,
+    pub errors: Vec<FieldError>,
+}
+
+impl Problem {
+    pub fn new(code: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            code,
+            detail: detail.into(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn with_errors(mut self, errors: Vec<FieldError>) -> Self {
+        self.errors = errors;
+        self
+    }
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.detail)
+    }
+}
+
+/// The error returned by every handler. The variant selects the HTTP status.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiError {
+    InternalServerError(Problem),
+    UnprocessableEntity(Problem),
+    BadRequest(Problem),
+    NotFound(Problem),
+    Conflict(Problem),
+    PreconditionFailed(Problem),
+    PreconditionRequired(Problem),
+    PayloadTooLarge(Problem),
+    UnsupportedMediaType(Problem),
+    TooManyRequests(Problem),
+    RequestTimeout(Problem),
+    ServiceUnavailable(Problem),
+}
+
+impl ApiError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::InternalServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::UnprocessableEntity(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            ApiError::PreconditionRequired(_) => StatusCode::PRECONDITION_REQUIRED,
+            ApiError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ApiError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::RequestTimeout(_) => StatusCode::REQUEST_TIMEOUT,
+            ApiError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    pub fn problem(&self) -> &Problem {
+        match self {
+            ApiError::InternalServerError(problem)
+            | ApiError::UnprocessableEntity(problem)
+            | ApiError::BadRequest(problem)
+            | ApiError::NotFound(problem)
+            | ApiError::Conflict(problem)
+            | ApiError::PreconditionFailed(problem)
+            | ApiError::PreconditionRequired(problem)
+            | ApiError::PayloadTooLarge(problem)
+            | ApiError::UnsupportedMediaType(problem)
+            | ApiError::TooManyRequests(problem)
+            | ApiError::RequestTimeout(problem)
+            | ApiError::ServiceUnavailable(problem) => problem,
+        }
+    }
+
+    /// A bad request listing every invalid field of the request.
+    pub fn invalid(code: &'static str, errors: ValidationErrors) -> Self {
+        let field_errors = errors.violations().iter().map(FieldError::from).collect();
+        Self::BadRequest(Problem::new(code, errors.to_string()).with_errors(field_errors))
+    }
+
+    /// An internal server error. The `detail`, e.g. the `{:?}` of the cause with its chain and
+    /// backtrace, is logged once the error is rendered, but never sent to the client.
+    pub fn internal(detail: impl Into<String>) -> Self {
+        Self::InternalServerError(Problem::new(INTERNAL_ERROR, detail))
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = self.status_code();
+        write!(f, "{}: {}", status.canonical_reason().unwrap_or_default(), self.problem())
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::internal(format!("{:?}", e))
+    }
+}
+
+/// The RFC 7807 representation of an [ApiError].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProblemDocument {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    /// The `X-Request-Id` of the failed request, to quote when reporting the problem.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl From<&ApiError> for ProblemDocument {
+    fn from(e: &ApiError) -> Self {
+        let status = e.status_code();
+        let problem = e.problem();
+        let detail = match e {
+            ApiError::InternalServerError(_) => "Internal server error".to_string(),
+            _ => problem.detail.clone(),
+        };
+        Self {
+            problem_type: format!("/problems/{}", problem.code),
+            title: status.canonical_reason().unwrap_or_default().to_string(),
+            status: status.as_u16(),
+            detail,
+            errors: problem.errors.clone(),
+            request_id: RequestId::current().map(|id| id.to_string()),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if let ApiError::InternalServerError(problem) = &self {
+            tracing::error!("{}", problem.detail);
+        }
+        (
+            self.status_code(),
+            [(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON))],
+            Json(ProblemDocument::from(&self)),
+        )
+            .into_response()
+    }
+}
+
+/// Answers requests to paths no route matches.
+pub async fn route_not_found() -> ApiError {
+    ApiError::NotFound(Problem::new("route-not-found", "no resource exists at this path"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn render(error: ApiError) -> (StatusCode, Option<HeaderValue>, serde_json::Value) {
+        let response = error.into_response();
+        let status = response.status();
+        let content_type = response.headers().get(CONTENT_TYPE).cloned();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, content_type, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_problem_document() {
+        let error = ApiError::BadRequest(
+            Problem::new("invalid-pet", "the pet is invalid")
+                .with_errors(vec![FieldError::new("name", "empty", "pet name cannot be empty")]),
+        );
+
+        let (status, content_type, body) = render(error).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(content_type, Some(HeaderValue::from_static(PROBLEM_JSON)));
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "type": "/problems/invalid-pet",
+                "title": "Bad Request",
+                "status": 400,
+                "detail": "the pet is invalid",
+                "errors": [{ "field": "name", "code": "empty", "message": "pet name cannot be empty" }],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_problem_document_hides_internal_details() {
+        let (status, _, body) = render(ApiError::internal("connection refused")).await;
+
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "type": "/problems/internal-error",
+                "title": "Internal Server Error",
+                "status": 500,
+                "detail": "Internal server error",
+            })
+        );
+    }
+}
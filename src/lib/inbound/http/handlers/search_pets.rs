@@ -0,0 +1,280 @@
+/*
+   Module `search_pets` specifies an HTTP handler for searching [Pet]s by partial name,
+   category name or tag name, and the associated data structures.
+*/
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::domain::petstore::models::search::{
+    Highlight, MatchSpan, PetSearchHit, SearchField, SearchPetsError, SearchPetsRequest,
+    SearchQuery, SearchQueryError,
+};
+use crate::domain::petstore::models::validation::Violation;
+use crate::domain::petstore::models::value_objects::{PageSize, PageSizeError};
+use crate::domain::petstore::ports::PetService;
+use crate::inbound::http::handlers::add_pet::{ApiSuccess, CreatePetResponseData};
+use crate::inbound::http::handlers::extract::Query;
+use crate::inbound::http::handlers::problem::{ApiError, FieldError, Problem};
+use crate::inbound::http::AppState;
+
+/// The query string of a [Pet] search request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct SearchPetsHttpQuery {
+    pub q: Option<String>,
+    pub limit: Option<u32>,
+}
+
+/// The response body data field for the found [Pet]s, best matches first.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchPetsResponseData {
+    pub hits: Vec<PetSearchHitResponseData>,
+}
+
+/// A found [Pet], its rank and the spans of its fields matching the query.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PetSearchHitResponseData {
+    pub pet: CreatePetResponseData,
+    pub rank: f32,
+    pub highlights: Vec<HighlightResponseData>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HighlightResponseData {
+    pub field: SearchField,
+    pub value: String,
+    pub spans: Vec<MatchSpan>,
+}
+
+impl From<&Highlight> for HighlightResponseData {
+    fn from(highlight: &Highlight) -> Self {
+        Self {
+            field: highlight.field,
+            value: highlight.value.clone(),
+            spans: highlight.spans.clone(),
+        }
+    }
+}
+
+impl From<&PetSearchHit> for PetSearchHitResponseData {
+    fn from(hit: &PetSearchHit) -> Self {
+        Self {
+            pet: (&hit.pet).into(),
+            rank: hit.rank,
+            highlights: hit.highlights.iter().map(HighlightResponseData::from).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+enum ParseSearchPetsHttpRequestError {
+    #[error(transparent)]
+    Query(#[from] SearchQueryError),
+    #[error(transparent)]
+    Limit(#[from] PageSizeError),
+}
+
+impl From<ParseSearchPetsHttpRequestError> for ApiError {
+    fn from(e: ParseSearchPetsHttpRequestError) -> Self {
+        let (field, code) = match &e {
+            ParseSearchPetsHttpRequestError::Query(e) => ("q", e.code()),
+            ParseSearchPetsHttpRequestError::Limit(_) => ("limit", "invalid"),
+        };
+        Self::BadRequest(
+            Problem::new("invalid-query", e.to_string())
+                .with_errors(vec![FieldError::new(field, code, e.to_string())]),
+        )
+    }
+}
+
+impl From<SearchPetsError> for ApiError {
+    fn from(e: SearchPetsError) -> Self {
+        match e {
+            SearchPetsError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+impl SearchPetsHttpQuery {
+    /// Converts the query string into a domain request.
+    fn try_into_domain(self) -> Result<SearchPetsRequest, ParseSearchPetsHttpRequestError> {
+        let query = SearchQuery::new(self.q.as_deref().unwrap_or_default())?;
+        let limit = PageSize::new(self.limit)?;
+        Ok(SearchPetsRequest::new(query, limit.into_inner()))
+    }
+}
+
+/// Search [Pet]s whose name, category name or tag names contain every term of the query,
+/// best matches first.
+///
+/// Query parameters: `q`, the terms separated by spaces, and `limit` (1 to 100, default 20).
+///
+/// # Responses
+///
+/// - 200 OK: the found [Pet]s with their rank and highlighted matches, possibly none.
+/// - 400 Bad Request: the query is missing or a query parameter is invalid.
+pub async fn search_pets<BS: PetService>(
+    State(state): State<AppState<BS>>,
+    Query(query): Query<SearchPetsHttpQuery>,
+) -> Result<ApiSuccess<SearchPetsResponseData>, ApiError> {
+    let domain_req = query.try_into_domain()?;
+    state
+        .pet_service
+        .search_pets(&domain_req)
+        .await
+        .map_err(ApiError::from)
+        .map(|hits| {
+            ApiSuccess::new(
+                StatusCode::OK,
+                SearchPetsResponseData {
+                    hits: hits.iter().map(PetSearchHitResponseData::from).collect(),
+                },
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use crate::domain::petstore::models::pet::{
+        ChangePetStatusError, ChangePetStatusRequest, CreatePetError, CreatePetRequest,
+        DeletePetError, ListPetsError, ListPetsRequest, Pet, PetHistoryError, PetPage, StatusChange,
+    };
+    use crate::domain::petstore::models::search::PetMatch;
+    use super::*;
+
+    type SearchPetsResult = Result<Vec<PetSearchHit>, SearchPetsError>;
+
+    #[derive(Clone)]
+    struct MockPetService {
+        search_pets_request: Arc<Mutex<Option<SearchPetsRequest>>>,
+        search_pets_result: Arc<Mutex<Option<SearchPetsResult>>>,
+    }
+
+    impl MockPetService {
+        fn new(result: SearchPetsResult) -> Self {
+            Self {
+                search_pets_request: Arc::new(Mutex::new(None)),
+                search_pets_result: Arc::new(Mutex::new(Some(result))),
+            }
+        }
+    }
+
+    impl PetService for MockPetService {
+        async fn add_pet(&self, _: &CreatePetRequest) -> Result<Pet, CreatePetError> {
+            Err(CreatePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn find_pet_by_id(&self, _: i64) -> Result<Option<Pet>, CreatePetError> {
+            Err(CreatePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn find_pet_version(&self, _: i64) -> Result<Option<i64>, CreatePetError> {
+            Err(CreatePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn list_pets(&self, _: &ListPetsRequest) -> Result<PetPage, ListPetsError> {
+            Err(ListPetsError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn search_pets(&self, req: &SearchPetsRequest) -> Result<Vec<PetSearchHit>, SearchPetsError> {
+            *self.search_pets_request.lock().unwrap() = Some(req.clone());
+            let mut guard = self.search_pets_result.lock().unwrap();
+            guard.take().unwrap_or_else(|| Err(SearchPetsError::Unknown(anyhow::anyhow!("Mock search_pets result not set"))))
+        }
+
+        async fn change_pet_status(&self, _: &ChangePetStatusRequest) -> Result<Pet, ChangePetStatusError> {
+            Err(ChangePetStatusError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn delete_pet(&self, _: i64) -> Result<(), DeletePetError> {
+            Err(DeletePetError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+
+        async fn pet_status_history(&self, _: i64) -> Result<Vec<StatusChange>, PetHistoryError> {
+            Err(PetHistoryError::Unknown(anyhow::anyhow!("Not implemented")))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_pets_success() {
+        // Arrange
+        let query = SearchQuery::new("bud").unwrap();
+        let hit = PetSearchHit::new(
+            PetMatch { pet: Pet::with_id(1, String::from("Buddy")), rank: 0.5 },
+            &query,
+        );
+        let service = MockPetService::new(Ok(vec![hit.clone()]));
+        let requests = service.search_pets_request.clone();
+        let state = State(AppState {
+            pet_service: Arc::new(service),
+        });
+        let expected = ApiSuccess::new(
+            StatusCode::OK,
+            SearchPetsResponseData {
+                hits: vec![PetSearchHitResponseData::from(&hit)],
+            },
+        );
+
+        // Act
+        let actual = search_pets(state, Query(SearchPetsHttpQuery {
+            q: Some("Bud".to_string()),
+            limit: Some(5),
+        }))
+        .await;
+
+        // Assert
+        assert_eq!(actual.unwrap(), expected);
+        let json = serde_json::to_value(PetSearchHitResponseData::from(&hit)).unwrap();
+        assert_eq!(json["pet"]["name"], "Buddy");
+        assert_eq!(json["rank"], 0.5);
+        assert_eq!(
+            json["highlights"],
+            serde_json::json!([{"field": "name", "value": "Buddy", "spans": [{"start": 0, "end": 3}]}])
+        );
+        assert_eq!(requests.lock().unwrap().clone().unwrap(), SearchPetsRequest::new(query, 5));
+    }
+
+    #[tokio::test]
+    async fn test_search_pets_invalid_query() {
+        let invalid_queries = [
+            SearchPetsHttpQuery::default(),
+            SearchPetsHttpQuery { q: Some("  ".to_string()), limit: None },
+            SearchPetsHttpQuery { q: Some("x".repeat(SearchQuery::MAX_LEN + 1)), limit: None },
+            SearchPetsHttpQuery { q: Some("rex".to_string()), limit: Some(0) },
+        ];
+
+        for query in invalid_queries {
+            let state = State(AppState {
+                pet_service: Arc::new(MockPetService::new(Ok(Vec::new()))),
+            });
+
+            let result = search_pets(state, Query(query.clone())).await;
+
+            assert!(
+                matches!(result, Err(ApiError::BadRequest(_))),
+                "expected BadRequest for {:?}, got {:?}",
+                query,
+                result
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_pets_unknown_error() {
+        let service = MockPetService::new(Err(SearchPetsError::Unknown(anyhow::anyhow!("database error"))));
+        let state = State(AppState {
+            pet_service: Arc::new(service),
+        });
+
+        let result = search_pets(state, Query(SearchPetsHttpQuery {
+            q: Some("rex".to_string()),
+            limit: None,
+        }))
+        .await;
+
+        assert!(matches!(result, Err(ApiError::InternalServerError(_))));
+    }
+}
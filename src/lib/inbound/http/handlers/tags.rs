@@ -0,0 +1,389 @@
+/*
+   Module `tags` specifies the HTTP handlers for administering [Tag]s, and the associated data
+   structures.
+*/
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::petstore::models::tag::{
+    DeleteTagError, ListTagsError, MergeTagsError, MergeTagsRequest, RenameTagError,
+    RenameTagRequest, Tag, TagUsage,
+};
+use crate::domain::petstore::models::value_objects::{TagName, TagNameError};
+use crate::domain::petstore::models::validation::Violation;
+use crate::domain::petstore::ports::TagService;
+use crate::inbound::http::handlers::add_pet::ApiSuccess;
+use crate::inbound::http::handlers::extract::{Json, Path};
+use crate::inbound::http::handlers::problem::{ApiError, FieldError, Problem};
+use crate::inbound::http::TagState;
+
+/// The body of a [Tag] rename request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RenameTagHttpRequestBody {
+    pub name: String,
+}
+
+/// The body of a [Tag] merge request. The tag in the path is merged into `target_id`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct MergeTagsHttpRequestBody {
+    pub target_id: i64,
+}
+
+/// The response body data field for a single [Tag].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagResponseData {
+    pub id: Option<i64>,
+    pub name: Option<String>,
+}
+
+impl From<&Tag> for TagResponseData {
+    fn from(tag: &Tag) -> Self {
+        Self {
+            id: tag.id,
+            name: tag.name.clone(),
+        }
+    }
+}
+
+/// The response body data field for a [Tag] and the number of pets it is attached to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagUsageResponseData {
+    pub id: Option<i64>,
+    pub name: Option<String>,
+    pub pet_count: i64,
+}
+
+impl From<&TagUsage> for TagUsageResponseData {
+    fn from(usage: &TagUsage) -> Self {
+        Self {
+            id: usage.tag.id,
+            name: usage.tag.name.clone(),
+            pet_count: usage.pet_count,
+        }
+    }
+}
+
+impl From<TagNameError> for ApiError {
+    fn from(e: TagNameError) -> Self {
+        let message = format!("tag name {} is invalid", e);
+        Self::BadRequest(
+            Problem::new("invalid-tag-name", message.clone())
+                .with_errors(vec![FieldError::new("name", e.code(), message)]),
+        )
+    }
+}
+
+impl From<ListTagsError> for ApiError {
+    fn from(e: ListTagsError) -> Self {
+        match e {
+            ListTagsError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+impl From<RenameTagError> for ApiError {
+    fn from(e: RenameTagError) -> Self {
+        match e {
+            RenameTagError::NotFound { .. } => {
+                Self::NotFound(Problem::new("tag-not-found", e.to_string()))
+            },
+            RenameTagError::Duplicate { .. } => {
+                Self::UnprocessableEntity(Problem::new("duplicate-tag", e.to_string()))
+            },
+            RenameTagError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+impl From<MergeTagsError> for ApiError {
+    fn from(e: MergeTagsError) -> Self {
+        match e {
+            MergeTagsError::NotFound { .. } => {
+                Self::NotFound(Problem::new("tag-not-found", e.to_string()))
+            },
+            MergeTagsError::SameTag { .. } => {
+                Self::UnprocessableEntity(Problem::new("same-tag", e.to_string()))
+            },
+            MergeTagsError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+impl From<DeleteTagError> for ApiError {
+    fn from(e: DeleteTagError) -> Self {
+        match e {
+            DeleteTagError::NotFound { .. } => {
+                Self::NotFound(Problem::new("tag-not-found", e.to_string()))
+            },
+            DeleteTagError::InUse { .. } => {
+                Self::Conflict(Problem::new("tag-in-use", e.to_string()))
+            },
+            DeleteTagError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+/// List all [Tag]s with the number of pets each is attached to.
+///
+/// # Responses
+///
+/// - 200 OK: the [Tag]s ordered by id.
+pub async fn list_tags<TS: TagService>(
+    State(state): State<TagState<TS>>,
+) -> Result<ApiSuccess<Vec<TagUsageResponseData>>, ApiError> {
+    state
+        .tag_service
+        .list_tags()
+        .await
+        .map_err(ApiError::from)
+        .map(|tags| ApiSuccess::new(StatusCode::OK, tags.iter().map(TagUsageResponseData::from).collect()))
+}
+
+/// Rename a [Tag].
+///
+/// # Responses
+///
+/// - 200 OK: the [Tag] was renamed.
+/// - 400 Bad Request: the name is empty or too long.
+/// - 404 Not Found: no [Tag] exists with the given ID.
+/// - 422 Unprocessable entity: another [Tag] already has the name.
+pub async fn rename_tag<TS: TagService>(
+    State(state): State<TagState<TS>>,
+    Path(tag_id): Path<i64>,
+    Json(body): Json<RenameTagHttpRequestBody>,
+) -> Result<ApiSuccess<TagResponseData>, ApiError> {
+    let name = TagName::new(&body.name)?;
+    let domain_req = RenameTagRequest::new(tag_id, name.into_inner());
+    state
+        .tag_service
+        .rename_tag(&domain_req)
+        .await
+        .map_err(ApiError::from)
+        .map(|ref tag| ApiSuccess::new(StatusCode::OK, tag.into()))
+}
+
+/// Merge the [Tag] in the path into the target [Tag] of the body.
+///
+/// # Responses
+///
+/// - 200 OK: the target [Tag] with its updated usage count.
+/// - 404 Not Found: either [Tag] does not exist.
+/// - 422 Unprocessable entity: a [Tag] cannot be merged into itself.
+pub async fn merge_tags<TS: TagService>(
+    State(state): State<TagState<TS>>,
+    Path(tag_id): Path<i64>,
+    Json(body): Json<MergeTagsHttpRequestBody>,
+) -> Result<ApiSuccess<TagUsageResponseData>, ApiError> {
+    let domain_req = MergeTagsRequest::new(tag_id, body.target_id);
+    state
+        .tag_service
+        .merge_tags(&domain_req)
+        .await
+        .map_err(ApiError::from)
+        .map(|ref usage| ApiSuccess::new(StatusCode::OK, usage.into()))
+}
+
+/// Delete a [Tag] that no pet is tagged with.
+///
+/// # Responses
+///
+/// - 204 No Content: the [Tag] was deleted.
+/// - 404 Not Found: no [Tag] exists with the given ID.
+/// - 409 Conflict: pets are still tagged with the [Tag].
+pub async fn delete_tag<TS: TagService>(
+    State(state): State<TagState<TS>>,
+    Path(tag_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .tag_service
+        .delete_tag(tag_id)
+        .await
+        .map_err(ApiError::from)
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+/// Delete every [Tag] that no pet is tagged with.
+///
+/// # Responses
+///
+/// - 200 OK: the deleted [Tag]s.
+pub async fn delete_unused_tags<TS: TagService>(
+    State(state): State<TagState<TS>>,
+) -> Result<ApiSuccess<Vec<TagResponseData>>, ApiError> {
+    state
+        .tag_service
+        .delete_unused_tags()
+        .await
+        .map_err(ApiError::from)
+        .map(|tags| ApiSuccess::new(StatusCode::OK, tags.iter().map(TagResponseData::from).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct MockTagService {
+        tags: Arc<Mutex<BTreeMap<i64, TagUsage>>>,
+    }
+
+    impl MockTagService {
+        fn with_tags(tags: &[(i64, &str, i64)]) -> Self {
+            let service = Self::default();
+            for (id, name, pet_count) in tags {
+                service
+                    .tags
+                    .lock()
+                    .unwrap()
+                    .insert(*id, TagUsage::new(Tag::with_values(*id, name.to_string()), *pet_count));
+            }
+            service
+        }
+    }
+
+    impl TagService for MockTagService {
+        async fn list_tags(&self) -> Result<Vec<TagUsage>, ListTagsError> {
+            Ok(self.tags.lock().unwrap().values().cloned().collect())
+        }
+
+        async fn rename_tag(&self, req: &RenameTagRequest) -> Result<Tag, RenameTagError> {
+            let mut tags = self.tags.lock().unwrap();
+            let usage = tags.get_mut(&req.id).ok_or(RenameTagError::NotFound { id: req.id })?;
+            usage.tag.name = Some(req.name.clone());
+            Ok(usage.tag.clone())
+        }
+
+        async fn merge_tags(&self, req: &MergeTagsRequest) -> Result<TagUsage, MergeTagsError> {
+            if req.source_id == req.target_id {
+                return Err(MergeTagsError::SameTag { id: req.source_id });
+            }
+            let mut tags = self.tags.lock().unwrap();
+            let source = tags
+                .remove(&req.source_id)
+                .ok_or(MergeTagsError::NotFound { id: req.source_id })?;
+            let target = tags
+                .get_mut(&req.target_id)
+                .ok_or(MergeTagsError::NotFound { id: req.target_id })?;
+            target.pet_count += source.pet_count;
+            Ok(target.clone())
+        }
+
+        async fn delete_tag(&self, tag_id: i64) -> Result<(), DeleteTagError> {
+            let mut tags = self.tags.lock().unwrap();
+            match tags.get(&tag_id) {
+                None => Err(DeleteTagError::NotFound { id: tag_id }),
+                Some(usage) if usage.pet_count > 0 => Err(DeleteTagError::InUse {
+                    id: tag_id,
+                    pet_count: usage.pet_count,
+                }),
+                Some(_) => {
+                    tags.remove(&tag_id);
+                    Ok(())
+                }
+            }
+        }
+
+        async fn delete_unused_tags(&self) -> Result<Vec<Tag>, DeleteTagError> {
+            let mut tags = self.tags.lock().unwrap();
+            let unused: Vec<Tag> = tags.values().filter(|u| u.pet_count == 0).map(|u| u.tag.clone()).collect();
+            tags.retain(|_, u| u.pet_count > 0);
+            Ok(unused)
+        }
+    }
+
+    fn state(service: &MockTagService) -> State<TagState<MockTagService>> {
+        State(TagState {
+            tag_service: Arc::new(service.clone()),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_list_tags() {
+        let service = MockTagService::with_tags(&[(1, "friendly", 2), (2, "old", 0)]);
+
+        let actual = list_tags(state(&service)).await.unwrap();
+
+        let expected = ApiSuccess::new(
+            StatusCode::OK,
+            vec![
+                TagUsageResponseData { id: Some(1), name: Some("friendly".to_string()), pet_count: 2 },
+                TagUsageResponseData { id: Some(2), name: Some("old".to_string()), pet_count: 0 },
+            ],
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_rename_tag() {
+        let service = MockTagService::with_tags(&[(1, "friendly", 2)]);
+
+        let renamed = rename_tag(
+            state(&service),
+            Path(1),
+            Json(RenameTagHttpRequestBody { name: "Friendly ".to_string() }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            renamed,
+            ApiSuccess::new(StatusCode::OK, TagResponseData { id: Some(1), name: Some("Friendly".to_string()) })
+        );
+
+        let invalid = rename_tag(
+            state(&service),
+            Path(1),
+            Json(RenameTagHttpRequestBody { name: "".to_string() }),
+        )
+        .await;
+        assert!(matches!(invalid, Err(ApiError::BadRequest(_))));
+
+        let missing = rename_tag(
+            state(&service),
+            Path(2),
+            Json(RenameTagHttpRequestBody { name: "kind".to_string() }),
+        )
+        .await;
+        assert!(matches!(missing, Err(ApiError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_merge_tags() {
+        let service = MockTagService::with_tags(&[(1, "friendly", 2), (2, "kind", 3)]);
+
+        let merged = merge_tags(state(&service), Path(2), Json(MergeTagsHttpRequestBody { target_id: 1 }))
+            .await
+            .unwrap();
+        assert_eq!(
+            merged,
+            ApiSuccess::new(
+                StatusCode::OK,
+                TagUsageResponseData { id: Some(1), name: Some("friendly".to_string()), pet_count: 5 }
+            )
+        );
+
+        let itself = merge_tags(state(&service), Path(1), Json(MergeTagsHttpRequestBody { target_id: 1 })).await;
+        assert!(matches!(itself, Err(ApiError::UnprocessableEntity(_))));
+
+        let missing = merge_tags(state(&service), Path(2), Json(MergeTagsHttpRequestBody { target_id: 1 })).await;
+        assert!(matches!(missing, Err(ApiError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_delete_tags() {
+        let service = MockTagService::with_tags(&[(1, "friendly", 2), (2, "old", 0), (3, "stale", 0)]);
+
+        assert!(matches!(delete_tag(state(&service), Path(1)).await, Err(ApiError::Conflict(_))));
+        assert_eq!(delete_tag(state(&service), Path(2)).await.unwrap(), StatusCode::NO_CONTENT);
+        assert!(matches!(delete_tag(state(&service), Path(2)).await, Err(ApiError::NotFound(_))));
+
+        let pruned = delete_unused_tags(state(&service)).await.unwrap();
+        assert_eq!(
+            pruned,
+            ApiSuccess::new(StatusCode::OK, vec![TagResponseData { id: Some(3), name: Some("stale".to_string()) }])
+        );
+    }
+}
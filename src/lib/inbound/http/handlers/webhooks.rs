@@ -0,0 +1,407 @@
+/*
+   Module `webhooks` specifies the HTTP handlers for managing partner [Webhook]s and reading
+   their delivery logs, and the associated data structures.
+*/
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::petstore::models::validation::ValidationErrors;
+use crate::domain::petstore::models::webhook::{
+    CreateWebhookError, CreateWebhookRequest, DeleteWebhookError, DeliveryStatus, EventTypes,
+    FindWebhookError, Webhook, WebhookDelivery, WebhookDeliveryError, WebhookSecret, WebhookUrl,
+};
+use crate::domain::petstore::ports::WebhookService;
+use crate::inbound::http::handlers::add_pet::ApiSuccess;
+use crate::inbound::http::handlers::extract::{Json, Path};
+use crate::inbound::http::handlers::problem::{ApiError, Problem};
+use crate::inbound::http::WebhookState;
+
+pub const WEBHOOK_NOT_FOUND: &str = "webhook-not-found";
+
+/// The body of a [Webhook] registration request. Without `event_types`, every event is
+/// delivered.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CreateWebhookHttpRequestBody {
+    pub url: String,
+    #[serde(default)]
+    pub event_types: Vec<String>,
+    pub secret: String,
+}
+
+impl CreateWebhookHttpRequestBody {
+    fn try_into_domain(self) -> Result<CreateWebhookRequest, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let url = errors.check("url", WebhookUrl::new(&self.url));
+        let event_types = errors.check("event_types", EventTypes::new(&self.event_types));
+        let secret = errors.check("secret", WebhookSecret::new(&self.secret));
+        match (url, event_types, secret) {
+            (Some(url), Some(event_types), Some(secret)) if errors.is_empty() => {
+                Ok(CreateWebhookRequest::new(url, event_types, secret))
+            }
+            _ => Err(errors),
+        }
+    }
+}
+
+/// The response body data field for a single [Webhook]. The secret is never returned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WebhookResponseData {
+    pub id: i64,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<&Webhook> for WebhookResponseData {
+    fn from(webhook: &Webhook) -> Self {
+        Self {
+            id: webhook.id,
+            url: webhook.url.clone(),
+            event_types: webhook.event_types.clone(),
+            created_at: webhook.created_at,
+        }
+    }
+}
+
+/// The response body data field for one entry of a [Webhook]'s delivery log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WebhookDeliveryResponseData {
+    pub id: i64,
+    pub event_id: i64,
+    pub event_type: String,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    /// When the delivery is next attempted, while it is pending.
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub last_response_status: Option<u16>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl From<&WebhookDelivery> for WebhookDeliveryResponseData {
+    fn from(delivery: &WebhookDelivery) -> Self {
+        Self {
+            id: delivery.id,
+            event_id: delivery.event_id,
+            event_type: delivery.event_type.clone(),
+            status: delivery.status,
+            attempts: delivery.attempts,
+            next_attempt_at: (delivery.status == DeliveryStatus::Pending).then_some(delivery.next_attempt_at),
+            last_response_status: delivery.last_response_status,
+            last_error: delivery.last_error.clone(),
+            created_at: delivery.created_at,
+            delivered_at: delivery.delivered_at,
+        }
+    }
+}
+
+impl From<CreateWebhookError> for ApiError {
+    fn from(e: CreateWebhookError) -> Self {
+        match e {
+            CreateWebhookError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+impl From<FindWebhookError> for ApiError {
+    fn from(e: FindWebhookError) -> Self {
+        match e {
+            FindWebhookError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+impl From<DeleteWebhookError> for ApiError {
+    fn from(e: DeleteWebhookError) -> Self {
+        match e {
+            DeleteWebhookError::NotFound { .. } => {
+                Self::NotFound(Problem::new(WEBHOOK_NOT_FOUND, e.to_string()))
+            }
+            DeleteWebhookError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+impl From<WebhookDeliveryError> for ApiError {
+    fn from(e: WebhookDeliveryError) -> Self {
+        match e {
+            WebhookDeliveryError::NotFound { .. } => {
+                Self::NotFound(Problem::new(WEBHOOK_NOT_FOUND, e.to_string()))
+            }
+            WebhookDeliveryError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+/// Register a new [Webhook].
+///
+/// # Responses
+///
+/// - 201 Created: the [Webhook] was registered.
+/// - 400 Bad Request: the url, event types or secret are invalid.
+pub async fn create_webhook<WS: WebhookService>(
+    State(state): State<WebhookState<WS>>,
+    Json(body): Json<CreateWebhookHttpRequestBody>,
+) -> Result<ApiSuccess<WebhookResponseData>, ApiError> {
+    let domain_req = body
+        .try_into_domain()
+        .map_err(|e| ApiError::invalid("invalid-webhook", e))?;
+    state
+        .webhook_service
+        .create_webhook(&domain_req)
+        .await
+        .map_err(ApiError::from)
+        .map(|ref webhook| ApiSuccess::new(StatusCode::CREATED, webhook.into()))
+}
+
+/// List all [Webhook]s.
+///
+/// # Responses
+///
+/// - 200 OK: the [Webhook]s ordered by id.
+pub async fn list_webhooks<WS: WebhookService>(
+    State(state): State<WebhookState<WS>>,
+) -> Result<ApiSuccess<Vec<WebhookResponseData>>, ApiError> {
+    state
+        .webhook_service
+        .list_webhooks()
+        .await
+        .map_err(ApiError::from)
+        .map(|webhooks| {
+            ApiSuccess::new(
+                StatusCode::OK,
+                webhooks.iter().map(WebhookResponseData::from).collect(),
+            )
+        })
+}
+
+/// Find a [Webhook] by its ID.
+///
+/// # Responses
+///
+/// - 200 OK: the [Webhook] was found.
+/// - 404 Not Found: no [Webhook] exists with the given ID.
+pub async fn find_webhook<WS: WebhookService>(
+    State(state): State<WebhookState<WS>>,
+    Path(webhook_id): Path<i64>,
+) -> Result<ApiSuccess<WebhookResponseData>, ApiError> {
+    state
+        .webhook_service
+        .find_webhook(webhook_id)
+        .await?
+        .map(|ref webhook| ApiSuccess::new(StatusCode::OK, webhook.into()))
+        .ok_or_else(|| {
+            ApiError::NotFound(Problem::new(
+                WEBHOOK_NOT_FOUND,
+                format!("webhook with id {} not found", webhook_id),
+            ))
+        })
+}
+
+/// Delete a [Webhook]. Its pending deliveries are dropped.
+///
+/// # Responses
+///
+/// - 204 No Content: the [Webhook] was deleted.
+/// - 404 Not Found: no [Webhook] exists with the given ID.
+pub async fn delete_webhook<WS: WebhookService>(
+    State(state): State<WebhookState<WS>>,
+    Path(webhook_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .webhook_service
+        .delete_webhook(webhook_id)
+        .await
+        .map_err(ApiError::from)
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+/// List the deliveries of a [Webhook], most recent first, with the outcome of their latest
+/// attempt.
+///
+/// # Responses
+///
+/// - 200 OK: the delivery log of the [Webhook].
+/// - 404 Not Found: no [Webhook] exists with the given ID.
+pub async fn webhook_deliveries<WS: WebhookService>(
+    State(state): State<WebhookState<WS>>,
+    Path(webhook_id): Path<i64>,
+) -> Result<ApiSuccess<Vec<WebhookDeliveryResponseData>>, ApiError> {
+    state
+        .webhook_service
+        .webhook_deliveries(webhook_id)
+        .await
+        .map_err(ApiError::from)
+        .map(|deliveries| {
+            ApiSuccess::new(
+                StatusCode::OK,
+                deliveries.iter().map(WebhookDeliveryResponseData::from).collect(),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct MockWebhookService {
+        webhooks: Arc<Mutex<Vec<Webhook>>>,
+        deliveries: Arc<Mutex<Vec<WebhookDelivery>>>,
+    }
+
+    impl WebhookService for MockWebhookService {
+        async fn create_webhook(&self, req: &CreateWebhookRequest) -> Result<Webhook, CreateWebhookError> {
+            let mut webhooks = self.webhooks.lock().unwrap();
+            let webhook = Webhook {
+                id: webhooks.len() as i64 + 1,
+                url: req.url.clone(),
+                event_types: req.event_types.clone(),
+                secret: req.secret.clone(),
+                created_at: Utc::now(),
+            };
+            webhooks.push(webhook.clone());
+            Ok(webhook)
+        }
+
+        async fn list_webhooks(&self) -> Result<Vec<Webhook>, FindWebhookError> {
+            Ok(self.webhooks.lock().unwrap().clone())
+        }
+
+        async fn find_webhook(&self, webhook_id: i64) -> Result<Option<Webhook>, FindWebhookError> {
+            Ok(self.webhooks.lock().unwrap().iter().find(|w| w.id == webhook_id).cloned())
+        }
+
+        async fn delete_webhook(&self, webhook_id: i64) -> Result<(), DeleteWebhookError> {
+            let mut webhooks = self.webhooks.lock().unwrap();
+            let before = webhooks.len();
+            webhooks.retain(|w| w.id != webhook_id);
+            if webhooks.len() == before {
+                return Err(DeleteWebhookError::NotFound { id: webhook_id });
+            }
+            Ok(())
+        }
+
+        async fn webhook_deliveries(&self, webhook_id: i64) -> Result<Vec<WebhookDelivery>, WebhookDeliveryError> {
+            if !self.webhooks.lock().unwrap().iter().any(|w| w.id == webhook_id) {
+                return Err(WebhookDeliveryError::NotFound { id: webhook_id });
+            }
+            Ok(self.deliveries.lock().unwrap().iter().filter(|d| d.webhook_id == webhook_id).cloned().collect())
+        }
+    }
+
+    fn state(service: &MockWebhookService) -> State<WebhookState<MockWebhookService>> {
+        State(WebhookState {
+            webhook_service: Arc::new(service.clone()),
+        })
+    }
+
+    fn body(url: &str, event_types: &[&str], secret: &str) -> Json<CreateWebhookHttpRequestBody> {
+        Json(CreateWebhookHttpRequestBody {
+            url: url.to_string(),
+            event_types: event_types.iter().map(|t| t.to_string()).collect(),
+            secret: secret.to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_create_webhook_success() {
+        let service = MockWebhookService::default();
+
+        let created = create_webhook(
+            state(&service),
+            body("https://partner.example/hooks", &["PetCreated"], "0123456789abcdef"),
+        )
+        .await
+        .unwrap();
+
+        let webhook = service.webhooks.lock().unwrap()[0].clone();
+        assert_eq!(webhook.secret, "0123456789abcdef");
+        assert_eq!(created, ApiSuccess::new(StatusCode::CREATED, (&webhook).into()));
+        let json = serde_json::to_value(WebhookResponseData::from(&webhook)).unwrap();
+        assert!(json.get("secret").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_create_webhook_collects_invalid_fields() {
+        let service = MockWebhookService::default();
+
+        let result = create_webhook(state(&service), body("ftp://partner.example", &["PetSold"], "short")).await;
+
+        let Err(ApiError::BadRequest(problem)) = result else {
+            panic!("expected a bad request, got {:?}", result);
+        };
+        let fields: Vec<(&str, &str)> = problem.errors.iter().map(|e| (e.field.as_str(), e.code.as_str())).collect();
+        assert_eq!(fields, vec![
+            ("url", "unsupported-scheme"),
+            ("event_types", "unknown-event-type"),
+            ("secret", "too-short"),
+        ]);
+        assert!(service.webhooks.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_find_list_and_delete_webhooks() {
+        let service = MockWebhookService::default();
+        create_webhook(state(&service), body("https://a.example", &[], "0123456789abcdef")).await.unwrap();
+        create_webhook(state(&service), body("https://b.example", &[], "0123456789abcdef")).await.unwrap();
+
+        let found = find_webhook(state(&service), Path(2)).await.unwrap();
+        let webhook = service.webhooks.lock().unwrap()[1].clone();
+        assert_eq!(found, ApiSuccess::new(StatusCode::OK, (&webhook).into()));
+        assert!(matches!(find_webhook(state(&service), Path(3)).await, Err(ApiError::NotFound(_))));
+
+        assert_eq!(delete_webhook(state(&service), Path(1)).await.unwrap(), StatusCode::NO_CONTENT);
+        assert!(matches!(delete_webhook(state(&service), Path(1)).await, Err(ApiError::NotFound(_))));
+
+        let listed = list_webhooks(state(&service)).await.unwrap();
+        assert_eq!(listed, ApiSuccess::new(StatusCode::OK, vec![(&webhook).into()]));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_deliveries() {
+        let service = MockWebhookService::default();
+        create_webhook(state(&service), body("https://a.example", &[], "0123456789abcdef")).await.unwrap();
+        let now = Utc::now();
+        let delivery = WebhookDelivery {
+            id: 1,
+            webhook_id: 1,
+            event_id: 9,
+            event_type: "PetCreated".to_string(),
+            payload: "{}".to_string(),
+            status: DeliveryStatus::DeadLetter,
+            attempts: 8,
+            next_attempt_at: now,
+            last_response_status: Some(500),
+            last_error: Some("receiver responded with status 500".to_string()),
+            created_at: now,
+            delivered_at: None,
+        };
+        service.deliveries.lock().unwrap().push(delivery.clone());
+
+        let log = webhook_deliveries(state(&service), Path(1)).await.unwrap();
+        let expected = WebhookDeliveryResponseData {
+            id: 1,
+            event_id: 9,
+            event_type: "PetCreated".to_string(),
+            status: DeliveryStatus::DeadLetter,
+            attempts: 8,
+            next_attempt_at: None,
+            last_response_status: Some(500),
+            last_error: delivery.last_error.clone(),
+            created_at: now,
+            delivered_at: None,
+        };
+        assert_eq!(log, ApiSuccess::new(StatusCode::OK, vec![expected]));
+
+        assert!(matches!(
+            webhook_deliveries(state(&service), Path(2)).await,
+            Err(ApiError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,438 @@
+/*!
+    Module `idempotency` makes requests carrying an `Idempotency-Key` header safe to retry. The
+    first request under a key is processed and its response stored; retries of the same request
+    within the configured window get the stored response replayed, and reusing the key for a
+    different request is rejected.
+*/
+
+use std::time::Duration;
+
+use axum::body::{to_bytes, Body};
+use axum::extract::{Request, State};
+use axum::http::header::{CONTENT_TYPE, ETAG, LAST_MODIFIED, LOCATION};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use sha2::{Digest, Sha256};
+
+use crate::domain::petstore::models::idempotency::{
+    ClaimIdempotencyKeyRequest, IdempotencyClaim, IdempotencyError, IdempotencyKey,
+    IdempotentResponse,
+};
+use crate::domain::petstore::ports::IdempotencyService;
+use crate::inbound::http::handlers::problem::{ApiError, Problem};
+use crate::inbound::http::IdempotencyState;
+
+/// The request header carrying the client-chosen idempotency key.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// The response header marking a replayed response.
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// How long a stored response is replayed unless configured otherwise.
+pub const DEFAULT_IDEMPOTENCY_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long a key stays claimed by a request being processed unless configured otherwise;
+/// twice the default request timeout.
+pub const DEFAULT_IDEMPOTENCY_LEASE: Duration = Duration::from_secs(60);
+
+/// The response headers stored with a response and replayed with it.
+const REPLAYED_HEADERS: [HeaderName; 4] = [CONTENT_TYPE, ETAG, LAST_MODIFIED, LOCATION];
+
+impl From<IdempotencyError> for ApiError {
+    fn from(e: IdempotencyError) -> Self {
+        match e {
+            IdempotencyError::ClaimLost { .. } => {
+                Self::Conflict(Problem::new("idempotency-key-in-progress", e.to_string()))
+            }
+            IdempotencyError::Unknown(cause) => Self::internal(format!("{:?}", cause)),
+        }
+    }
+}
+
+/// Middleware replaying the stored response of a request with the same `Idempotency-Key`.
+/// Requests without the header are passed through untouched.
+///
+/// Server errors are not stored, so that a retry is processed again. Neither are the outcomes
+/// of requests abandoned before their response, e.g. on timeout or disconnection of the client;
+/// as the request may have taken effect regardless, their keys stay claimed until their lease
+/// expires, and retries get 409 until then.
+///
+/// # Responses
+///
+/// - 400 Bad Request: the key is empty or too long.
+/// - 409 Conflict: a request with the same key is still being processed.
+/// - 413 Payload Too Large: the body is too large to fingerprint.
+/// - 422 Unprocessable Entity: the key was used for a different request.
+pub(super) async fn idempotent<IS: IdempotencyService>(
+    State(state): State<IdempotencyState<IS>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return next.run(request).await;
+    };
+    let key = match key.to_str().map(IdempotencyKey::new) {
+        Ok(Ok(key)) => key,
+        Ok(Err(e)) => return invalid_key(e.to_string()),
+        Err(_) => return invalid_key("idempotency key must be visible ASCII".to_string()),
+    };
+
+    let (parts, body) = request.into_parts();
+    let Ok(body) = to_bytes(body, state.max_body_bytes).await else {
+        let detail = format!("request body is larger than {} bytes", state.max_body_bytes);
+        return ApiError::PayloadTooLarge(Problem::new("payload-too-large", detail)).into_response();
+    };
+    let fingerprint = fingerprint(parts.method.as_str(), parts.uri.path(), &body);
+
+    let claim_req = ClaimIdempotencyKeyRequest::new(key.clone(), fingerprint, state.lease);
+    let claim = match state.idempotency_service.claim_idempotency_key(&claim_req).await {
+        Ok(claim) => claim,
+        Err(e) => return ApiError::from(e).into_response(),
+    };
+    match claim {
+        IdempotencyClaim::Claimed => {}
+        IdempotencyClaim::Completed(response) => return replay(response),
+        IdempotencyClaim::InProgress => {
+            return ApiError::Conflict(Problem::new(
+                "idempotency-key-in-progress",
+                format!("a request with idempotency key {} is still being processed", key),
+            ))
+            .into_response()
+        }
+        IdempotencyClaim::Mismatch => {
+            return ApiError::UnprocessableEntity(Problem::new(
+                "idempotency-key-reused",
+                format!("idempotency key {} was already used for a different request", key),
+            ))
+            .into_response()
+        }
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if response.status().is_server_error() {
+        release(&state, &claim_req).await;
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            release(&state, &claim_req).await;
+            return ApiError::from(anyhow::anyhow!(e)).into_response();
+        }
+    };
+    let stored = IdempotentResponse {
+        status_code: parts.status.as_u16(),
+        headers: replayed_headers(&parts.headers),
+        body: String::from_utf8_lossy(&body).into_owned(),
+    };
+    match state.idempotency_service.complete_idempotency_key(&claim_req, &stored, state.window).await {
+        Ok(()) => {}
+        // The lease expired while the request was processed; the key belongs to another request
+        Err(e @ IdempotencyError::ClaimLost { .. }) => {
+            tracing::warn!("response not stored: {}", e);
+        }
+        // The request succeeded; a retry will get 409 until the lease expires, then be
+        // processed again
+        Err(IdempotencyError::Unknown(cause)) => {
+            tracing::error!("failed to store response for idempotency key {}: {:?}", key, cause);
+        }
+    }
+    Response::from_parts(parts, Body::from(body))
+}
+
+fn invalid_key(detail: String) -> Response {
+    ApiError::BadRequest(Problem::new("invalid-idempotency-key", detail)).into_response()
+}
+
+async fn release<IS: IdempotencyService>(state: &IdempotencyState<IS>, claim: &ClaimIdempotencyKeyRequest) {
+    if let Err(IdempotencyError::Unknown(cause)) =
+        state.idempotency_service.release_idempotency_key(claim).await
+    {
+        tracing::error!("failed to release idempotency key {}: {:?}", claim.key, cause);
+    }
+}
+
+/// The names and values of the [REPLAYED_HEADERS] of a response, skipping values that are not
+/// visible ASCII.
+fn replayed_headers(headers: &HeaderMap) -> Vec<(String, String)> {
+    REPLAYED_HEADERS
+        .iter()
+        .flat_map(|name| headers.get_all(name).iter().map(move |value| (name, value)))
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect()
+}
+
+fn replay(response: IdempotentResponse) -> Response {
+    let status = StatusCode::from_u16(response.status_code).unwrap_or(StatusCode::OK);
+    let mut replayed = (status, response.body).into_response();
+    let headers = replayed.headers_mut();
+    // Responses stored without headers are JSON, as every stored response once was
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    for (name, value) in response.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+            headers.insert(name, value);
+        }
+    }
+    headers.insert(
+        HeaderName::from_static(IDEMPOTENT_REPLAYED_HEADER),
+        HeaderValue::from_static("true"),
+    );
+    replayed
+}
+
+/// The hex-encoded SHA-256 of the method, path and body, identifying a request across retries.
+fn fingerprint(method: &str, path: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_bytes());
+    hasher.update(b" ");
+    hasher.update(path.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use axum::routing::{post, MethodRouter};
+    use axum::Router;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::inbound::http::DEFAULT_MAX_BODY_BYTES;
+
+    /// A claimed key: the request fingerprint and the stored response.
+    type Claim = (String, Option<IdempotentResponse>);
+
+    /// Keeps claims in memory, ignoring expiry.
+    #[derive(Clone, Default)]
+    struct MockIdempotencyService {
+        keys: Arc<Mutex<HashMap<IdempotencyKey, Claim>>>,
+    }
+
+    impl IdempotencyService for MockIdempotencyService {
+        async fn claim_idempotency_key(&self, req: &ClaimIdempotencyKeyRequest) -> Result<IdempotencyClaim, IdempotencyError> {
+            let mut keys = self.keys.lock().unwrap();
+            Ok(match keys.get(&req.key) {
+                None => {
+                    keys.insert(req.key.clone(), (req.fingerprint.clone(), None));
+                    IdempotencyClaim::Claimed
+                }
+                Some((fingerprint, _)) if fingerprint != &req.fingerprint => IdempotencyClaim::Mismatch,
+                Some((_, Some(response))) => IdempotencyClaim::Completed(response.clone()),
+                Some((_, None)) => IdempotencyClaim::InProgress,
+            })
+        }
+
+        async fn complete_idempotency_key(&self, claim: &ClaimIdempotencyKeyRequest, response: &IdempotentResponse, _: Duration) -> Result<(), IdempotencyError> {
+            if let Some(record) = self.keys.lock().unwrap().get_mut(&claim.key) {
+                record.1 = Some(response.clone());
+            }
+            Ok(())
+        }
+
+        async fn release_idempotency_key(&self, claim: &ClaimIdempotencyKeyRequest) -> Result<(), IdempotencyError> {
+            let mut keys = self.keys.lock().unwrap();
+            if keys.get(&claim.key).is_some_and(|(_, response)| response.is_none()) {
+                keys.remove(&claim.key);
+            }
+            Ok(())
+        }
+
+        async fn purge_expired_idempotency_keys(&self) -> Result<u64, IdempotencyError> {
+            Ok(0)
+        }
+    }
+
+    /// A router whose handler counts its calls and answers with the call number, failing the
+    /// first call if `fail_first` is set.
+    fn router(service: MockIdempotencyService, calls: Arc<AtomicUsize>, fail_first: bool) -> Router {
+        let handler = move |body: String| async move {
+            let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+            if fail_first && call == 1 {
+                return (StatusCode::INTERNAL_SERVER_ERROR, String::new());
+            }
+            (StatusCode::CREATED, format!("{{\"call\":{},\"body\":{:?}}}", call, body))
+        };
+        idempotent_router(service, post(handler))
+    }
+
+    /// A router serving `/pet` with `route` behind the idempotency middleware.
+    fn idempotent_router(service: MockIdempotencyService, route: MethodRouter) -> Router {
+        limited_router(service, route, DEFAULT_MAX_BODY_BYTES)
+    }
+
+    /// A router serving `/pet` with `route` behind the idempotency middleware, fingerprinting
+    /// bodies of up to `max_body_bytes`.
+    fn limited_router(service: MockIdempotencyService, route: MethodRouter, max_body_bytes: usize) -> Router {
+        let state = IdempotencyState {
+            idempotency_service: Arc::new(service),
+            window: DEFAULT_IDEMPOTENCY_WINDOW,
+            lease: DEFAULT_IDEMPOTENCY_LEASE,
+            max_body_bytes,
+        };
+        Router::new().route(
+            "/pet",
+            route.layer(axum::middleware::from_fn_with_state(state, idempotent::<MockIdempotencyService>)),
+        )
+    }
+
+    async fn send(router: &Router, key: Option<&str>, body: &str) -> (StatusCode, Option<HeaderValue>, String) {
+        let mut request = Request::builder().method("POST").uri("/pet");
+        if let Some(key) = key {
+            request = request.header(IDEMPOTENCY_KEY_HEADER, key);
+        }
+        let response = router
+            .clone()
+            .oneshot(request.body(Body::from(body.to_string())).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let replayed = response.headers().get(IDEMPOTENT_REPLAYED_HEADER).cloned();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, replayed, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_replays_first_response() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let router = router(MockIdempotencyService::default(), calls.clone(), false);
+
+        let first = send(&router, Some("k1"), "rex").await;
+        let retry = send(&router, Some("k1"), "rex").await;
+
+        assert_eq!(first.0, StatusCode::CREATED);
+        assert_eq!(first.1, None);
+        assert_eq!(retry.0, StatusCode::CREATED);
+        assert_eq!(retry.1, Some(HeaderValue::from_static("true")));
+        assert_eq!(retry.2, first.2);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_rejects_key_reuse_with_different_body() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let router = router(MockIdempotencyService::default(), calls.clone(), false);
+
+        send(&router, Some("k1"), "rex").await;
+        let reuse = send(&router, Some("k1"), "luna").await;
+
+        assert_eq!(reuse.0, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_retries_server_errors() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let router = router(MockIdempotencyService::default(), calls.clone(), true);
+
+        let first = send(&router, Some("k1"), "rex").await;
+        let retry = send(&router, Some("k1"), "rex").await;
+
+        assert_eq!(first.0, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(retry.0, StatusCode::CREATED);
+        assert_eq!(retry.1, None);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_without_key_or_with_invalid_key() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let router = router(MockIdempotencyService::default(), calls.clone(), false);
+
+        assert_eq!(send(&router, None, "rex").await.0, StatusCode::CREATED);
+        assert_eq!(send(&router, None, "rex").await.0, StatusCode::CREATED);
+        assert_eq!(send(&router, Some(" "), "rex").await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_replays_headers() {
+        let handler = || async {
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                [
+                    (CONTENT_TYPE, "application/problem+json"),
+                    (ETAG, "\"1\""),
+                    (HeaderName::from_static("x-unrelated"), "yes"),
+                ],
+                "{}",
+            )
+        };
+        let router = idempotent_router(MockIdempotencyService::default(), post(handler));
+        let request = || {
+            Request::builder()
+                .method("POST")
+                .uri("/pet")
+                .header(IDEMPOTENCY_KEY_HEADER, "k1")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        router.clone().oneshot(request()).await.unwrap();
+        let retry = router.oneshot(request()).await.unwrap();
+
+        assert_eq!(retry.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(retry.headers()[CONTENT_TYPE], "application/problem+json");
+        assert_eq!(retry.headers()[ETAG], "\"1\"");
+        assert_eq!(retry.headers()[IDEMPOTENT_REPLAYED_HEADER], "true");
+        assert!(retry.headers().get("x-unrelated").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_keeps_abandoned_requests_claimed() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let handler = {
+            let calls = calls.clone();
+            move || async move {
+                // The first call never completes, as if the client gave up on it
+                if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                    std::future::pending::<()>().await;
+                }
+                StatusCode::CREATED
+            }
+        };
+        let router = idempotent_router(MockIdempotencyService::default(), post(handler));
+
+        let abandoned = tokio::time::timeout(Duration::from_millis(50), send(&router, Some("k1"), "rex")).await;
+        assert!(abandoned.is_err());
+        tokio::task::yield_now().await;
+        let retry = send(&router, Some("k1"), "rex").await;
+
+        // The abandoned request may have taken effect, so the retry waits for its lease
+        assert_eq!(retry.0, StatusCode::CONFLICT);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_rejects_bodies_over_the_configured_limit() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let handler = {
+            let calls = calls.clone();
+            move || async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                StatusCode::CREATED
+            }
+        };
+        let router = limited_router(MockIdempotencyService::default(), post(handler), 3);
+
+        assert_eq!(send(&router, Some("k1"), "rex").await.0, StatusCode::CREATED);
+        assert_eq!(send(&router, Some("k2"), "luna").await.0, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let fingerprint = fingerprint("POST", "/api/pet", b"{}");
+
+        assert_eq!(fingerprint.len(), 64);
+        assert_ne!(fingerprint, super::fingerprint("POST", "/api/pet", b"{ }"));
+        assert_ne!(fingerprint, super::fingerprint("POST", "/api/category", b"{}"));
+    }
+}
@@ -0,0 +1,283 @@
+/*!
+    Module `limits` bounds what a single request may cost the server: the size of its body and
+    the time taken to handle it. Bodies declared too large are rejected before they are read,
+    and bodies turning out too large are cut off once the limit is reached. Requests taking too
+    long are answered with `408 Request Timeout` if the client was still sending the body, and
+    with `503 Service Unavailable` if the server was too slow. Imports, which may upload and
+    store large files row by row, have limits of their own.
+*/
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::Duration;
+
+use axum::body::{Body, Bytes, HttpBody};
+use axum::extract::{Request, State};
+use axum::http::header::{CONNECTION, CONTENT_LENGTH};
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use futures::stream::{self, StreamExt};
+use http_body_util::Limited;
+
+use crate::inbound::http::handlers::problem::{ApiError, Problem};
+
+/// The largest request body accepted unless configured otherwise.
+pub const DEFAULT_MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// The largest import accepted unless configured otherwise.
+pub const DEFAULT_MAX_IMPORT_BODY_BYTES: usize = 64 * 1024 * 1024;
+
+/// How long a request may take unless configured otherwise.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long an import may take unless configured otherwise.
+pub const DEFAULT_IMPORT_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// The limits on the requests handled by the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestLimits {
+    /// The largest request body accepted, except by imports.
+    pub max_body_bytes: usize,
+    /// The largest body accepted by `POST /api/pet/import`, which streams it.
+    pub max_import_body_bytes: usize,
+    /// How long the server may take to receive a request and start its response, except for
+    /// imports. Streamed responses, such as exports and change events, may take longer to
+    /// complete.
+    pub timeout: Duration,
+    /// How long `POST /api/pet/import` may take to receive and import its body. Imports cut
+    /// short keep the rows already imported, without reporting them.
+    pub import_timeout: Duration,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            max_import_body_bytes: DEFAULT_MAX_IMPORT_BODY_BYTES,
+            timeout: DEFAULT_REQUEST_TIMEOUT,
+            import_timeout: DEFAULT_IMPORT_TIMEOUT,
+        }
+    }
+}
+
+impl RequestLimits {
+    /// Applies the limits of every request but imports to the routes of `router`.
+    pub(super) fn apply<S: Clone + Send + Sync + 'static>(&self, router: Router<S>) -> Router<S> {
+        router
+            .route_layer(axum::middleware::from_fn_with_state(self.max_body_bytes, body_limit))
+            .route_layer(axum::middleware::from_fn_with_state(self.timeout, timeout))
+    }
+
+    /// Applies the limits of imports to the routes of `router`.
+    pub(super) fn apply_to_imports<S: Clone + Send + Sync + 'static>(&self, router: Router<S>) -> Router<S> {
+        router
+            .route_layer(axum::middleware::from_fn_with_state(self.max_import_body_bytes, body_limit))
+            .route_layer(axum::middleware::from_fn_with_state(self.import_timeout, timeout))
+    }
+}
+
+/// Middleware rejecting requests whose body is larger than `max` bytes.
+///
+/// # Responses
+///
+/// - 413 Payload Too Large: the `Content-Length` of the request is larger. Bodies without one
+///   fail to be read past the limit, which handlers report as they see fit.
+pub(super) async fn body_limit(State(max): State<usize>, request: Request, next: Next) -> Response {
+    let declared = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if declared.is_some_and(|length| length > max as u64) {
+        let detail = format!("request body is larger than {} bytes", max);
+        return ApiError::PayloadTooLarge(Problem::new("payload-too-large", detail)).into_response();
+    }
+    next.run(request.map(|body| Body::new(Limited::new(body, max)))).await
+}
+
+/// Middleware answering requests not handled within `timeout`.
+///
+/// # Responses
+///
+/// - 408 Request Timeout: the body of the request was not received in time.
+/// - 503 Service Unavailable: the request was received, but not handled in time.
+pub(super) async fn timeout(State(timeout): State<Duration>, request: Request, next: Next) -> Response {
+    let received = Arc::new(AtomicBool::new(request.body().is_end_stream()));
+    let request = if received.load(Ordering::Relaxed) {
+        request
+    } else {
+        let received = received.clone();
+        request.map(|body| {
+            // Polled past the last chunk once the whole body is received
+            let end = stream::poll_fn(move |_| {
+                received.store(true, Ordering::Relaxed);
+                Poll::Ready(None::<Result<Bytes, axum::Error>>)
+            });
+            Body::from_stream(body.into_data_stream().chain(end))
+        })
+    };
+
+    match tokio::time::timeout(timeout, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) if !received.load(Ordering::Relaxed) => {
+            let detail = format!("request body was not received within {:?}", timeout);
+            let mut response =
+                ApiError::RequestTimeout(Problem::new("request-timeout", detail)).into_response();
+            response.headers_mut().insert(CONNECTION, HeaderValue::from_static("close"));
+            response
+        }
+        Err(_) => {
+            let detail = format!("request was not handled within {:?}", timeout);
+            ApiError::ServiceUnavailable(Problem::new("response-timeout", detail)).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use axum::routing::{get, post};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::inbound::http::handlers::problem::PROBLEM_JSON;
+
+    fn router() -> Router {
+        Router::new()
+            .route("/echo", post(|body: Bytes| async move { body }))
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                    "late"
+                }),
+            )
+            .route(
+                "/slow-upload",
+                post(|body: Body| async move {
+                    let _ = axum::body::to_bytes(body, usize::MAX).await;
+                    "uploaded"
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(8, body_limit))
+            .layer(axum::middleware::from_fn_with_state(Duration::from_secs(5), timeout))
+    }
+
+    async fn problem_code(response: Response) -> String {
+        assert_eq!(response.headers()[axum::http::header::CONTENT_TYPE], PROBLEM_JSON);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        body["type"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_body_within_limit() {
+        let request = Request::post("/echo").body(Body::from("12345678")).unwrap();
+
+        let response = router().oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, "12345678");
+    }
+
+    #[tokio::test]
+    async fn test_declared_body_too_large() {
+        let request = Request::post("/echo")
+            .header(CONTENT_LENGTH, "9")
+            .body(Body::from("123456789"))
+            .unwrap();
+
+        let response = router().oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(problem_code(response).await, "/problems/payload-too-large");
+    }
+
+    #[tokio::test]
+    async fn test_streamed_body_too_large() {
+        let chunks = stream::iter(["12345", "6789"].map(Ok::<_, std::io::Error>));
+        let request = Request::post("/echo").body(Body::from_stream(chunks)).unwrap();
+
+        let response = router().oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_slow_handler() {
+        let request = Request::get("/slow").body(Body::empty()).unwrap();
+
+        let response = router().oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(problem_code(response).await, "/problems/response-timeout");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_slow_upload() {
+        let (sender, body) = tokio::sync::mpsc::channel::<Result<Bytes, std::io::Error>>(1);
+        sender.send(Ok(Bytes::from_static(b"1234"))).await.unwrap();
+        let body = Body::from_stream(tokio_stream_of(body));
+        let request = Request::post("/slow-upload").body(body).unwrap();
+
+        let response = router().oneshot(request).await.unwrap();
+        drop(sender);
+
+        assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+        assert_eq!(response.headers()[CONNECTION], "close");
+        assert_eq!(problem_code(response).await, "/problems/request-timeout");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_received_upload_handled_slowly() {
+        let request = Request::post("/slow-upload").body(Body::from("1234")).unwrap();
+        let router = Router::new()
+            .route(
+                "/slow-upload",
+                post(|body: Bytes| async move {
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                    body
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(Duration::from_secs(5), timeout));
+
+        let response = router.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_imports_have_limits_of_their_own() {
+        let limits = RequestLimits {
+            max_body_bytes: 4,
+            max_import_body_bytes: 8,
+            timeout: Duration::from_secs(5),
+            import_timeout: Duration::from_secs(600),
+        };
+        let slow = |body: Bytes| async move {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            body
+        };
+        let router = limits
+            .apply(Router::new().route("/pet", post(slow)))
+            .merge(limits.apply_to_imports(Router::new().route("/pet/import", post(slow))));
+        let send = |uri: &str, body: &'static str| {
+            router.clone().oneshot(Request::post(uri).body(Body::from(body)).unwrap())
+        };
+
+        assert_eq!(send("/pet", "1234").await.unwrap().status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(send("/pet", "12345678").await.unwrap().status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(send("/pet/import", "12345678").await.unwrap().status(), StatusCode::OK);
+    }
+
+    /// The chunks received from `receiver`, as a stream.
+    fn tokio_stream_of<T: Send + 'static>(
+        mut receiver: tokio::sync::mpsc::Receiver<T>,
+    ) -> impl futures::Stream<Item = T> + Send {
+        stream::poll_fn(move |cx| receiver.poll_recv(cx))
+    }
+}
@@ -0,0 +1,727 @@
+/*!
+    Module `rate_limit` protects the store from clients sending too many requests. Every client
+    has a token bucket per configured route, and one shared by the other routes, refilled at
+    the configured rate; a request without a token left is rejected with `429 Too Many
+    Requests`. Clients may also be held to a daily quota of requests, reset at midnight UTC.
+
+    Clients are identified by their `X-API-Key` header if it is one of the configured keys,
+    otherwise by their IP address, so that made-up keys do not earn fresh limits. Limits are
+    enforced per server process, which tracks a bounded number of clients.
+*/
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::{ConnectInfo, Extension, MatchedPath, Request, State};
+use axum::http::header::RETRY_AFTER;
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::time::Instant;
+
+use crate::inbound::http::handlers::add_pet::ApiSuccess;
+use crate::inbound::http::handlers::problem::{ApiError, Problem};
+
+/// The request header identifying a client by its API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// The requests a client may send in a window, and with them the size of its bucket.
+pub const RATE_LIMIT_LIMIT_HEADER: HeaderName = HeaderName::from_static("x-ratelimit-limit");
+/// The requests a client may send right now.
+pub const RATE_LIMIT_REMAINING_HEADER: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
+/// The seconds until the bucket of a client is full again.
+pub const RATE_LIMIT_RESET_HEADER: HeaderName = HeaderName::from_static("x-ratelimit-reset");
+/// The requests a client may send in a day.
+pub const QUOTA_LIMIT_HEADER: HeaderName = HeaderName::from_static("x-quota-limit");
+/// The requests a client may still send today.
+pub const QUOTA_REMAINING_HEADER: HeaderName = HeaderName::from_static("x-quota-remaining");
+/// The seconds until the quota of a client is reset.
+pub const QUOTA_RESET_HEADER: HeaderName = HeaderName::from_static("x-quota-reset");
+
+/// How many requests are admitted between sweeps of the buckets and quotas no longer needed.
+const SWEEP_INTERVAL: u64 = 1024;
+
+/// How many clients are tracked a day at most; beyond them, new clients share the limits of
+/// [ClientId::Unknown] until the next day.
+const MAX_CLIENTS: usize = 100_000;
+
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum RateLimitConfigError {
+    #[error("rate limit {0} is not of the form <requests>/<s|m|h>, e.g. 100/m")]
+    InvalidRateLimit(String),
+    #[error("route limit {0} is not of the form [<method> ]<path>=<requests>/<s|m|h>")]
+    InvalidRouteLimit(String),
+}
+
+/// A client may send `requests` requests per `per`, all at once or spread out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub per: Duration,
+}
+
+impl RateLimit {
+    pub fn new(requests: u32, per: Duration) -> Self {
+        Self { requests, per }
+    }
+
+    /// The tokens added to a bucket per second.
+    fn refill_rate(&self) -> f64 {
+        self.requests as f64 / self.per.as_secs_f64()
+    }
+}
+
+impl FromStr for RateLimit {
+    type Err = RateLimitConfigError;
+
+    /// Parses `<requests>/<s|m|h>`, e.g. `100/m` for 100 requests a minute.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || RateLimitConfigError::InvalidRateLimit(s.to_string());
+        let (requests, unit) = s.trim().split_once('/').ok_or_else(invalid)?;
+        let requests: u32 = requests.trim().parse().map_err(|_| invalid())?;
+        let per = match unit.trim() {
+            "s" => Duration::from_secs(1),
+            "m" => Duration::from_secs(60),
+            "h" => Duration::from_secs(60 * 60),
+            _ => return Err(invalid()),
+        };
+        if requests == 0 {
+            return Err(invalid());
+        }
+        Ok(Self::new(requests, per))
+    }
+}
+
+/// A limit of its own for the requests to the route `path`, e.g. `/api/pet/{petId}`, with
+/// `method` or any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteLimit {
+    pub method: Option<Method>,
+    pub path: String,
+    pub limit: RateLimit,
+}
+
+impl RouteLimit {
+    fn matches(&self, method: &Method, path: &str) -> bool {
+        self.path == path && self.method.as_ref().is_none_or(|m| m == method)
+    }
+}
+
+impl FromStr for RouteLimit {
+    type Err = RateLimitConfigError;
+
+    /// Parses `[<method> ]<path>=<limit>`, e.g. `POST /api/pet/import=5/m`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || RateLimitConfigError::InvalidRouteLimit(s.to_string());
+        let (route, limit) = s.trim().rsplit_once('=').ok_or_else(invalid)?;
+        let (method, path) = match route.trim().split_once(' ') {
+            Some((method, path)) => {
+                let method = Method::from_str(&method.to_ascii_uppercase()).map_err(|_| invalid())?;
+                (Some(method), path.trim())
+            }
+            None => (None, route.trim()),
+        };
+        if !path.starts_with('/') {
+            return Err(invalid());
+        }
+        Ok(Self {
+            method,
+            path: path.to_string(),
+            limit: limit.parse()?,
+        })
+    }
+}
+
+/// How the requests of each client are limited.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    /// The limit of the requests to routes without a [RouteLimit], or none.
+    pub default: Option<RateLimit>,
+    /// The routes limited on their own; the first matching a request applies.
+    pub routes: Vec<RouteLimit>,
+    /// The requests a client may send per day, or unlimited.
+    pub daily_quota: Option<u64>,
+    /// Whether to identify clients without an API key by the last address of their
+    /// `X-Forwarded-For` header, the one appended by the trusted proxy in front of the server.
+    /// The addresses before it are sent by the client, and may be forged.
+    pub trust_forwarded_for: bool,
+    /// The API keys identifying clients; other keys are ignored.
+    pub api_keys: Vec<String>,
+}
+
+/// Who sent a request, as far as limits are concerned.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ClientId {
+    /// The start of the SHA-256 digest of the API key, so that keys are not kept in memory.
+    ApiKey(String),
+    Ip(IpAddr),
+    /// Clients whose address is unknown, and new clients once too many are tracked.
+    Unknown,
+}
+
+impl ClientId {
+    fn api_key(key: &[u8]) -> Self {
+        Self::ApiKey(hex::encode(&Sha256::digest(key)[..8]))
+    }
+}
+
+impl fmt::Display for ClientId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ApiKey(digest) => write!(f, "key:{}", digest),
+            Self::Ip(ip) => write!(f, "ip:{}", ip),
+            Self::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(limit: &RateLimit, now: Instant) -> Self {
+        Self { tokens: limit.requests as f64, updated: now }
+    }
+
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.refill_rate()).min(limit.requests as f64);
+        self.updated = now;
+    }
+
+    /// How long until `tokens` tokens are available.
+    fn time_until(&self, limit: &RateLimit, tokens: f64) -> Duration {
+        Duration::from_secs_f64(((tokens - self.tokens).max(0.0)) / limit.refill_rate())
+    }
+}
+
+#[derive(Debug)]
+struct DailyUsage {
+    day: NaiveDate,
+    requests: u64,
+}
+
+#[derive(Debug, Default)]
+struct LimiterState {
+    /// The buckets by client and index of the [RouteLimit], `None` for the default limit.
+    buckets: HashMap<(ClientId, Option<usize>), Bucket>,
+    usage: HashMap<ClientId, DailyUsage>,
+    admitted_since_sweep: u64,
+}
+
+/// Where a client stands with its rate limit, after a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct RateStatus {
+    limit: u32,
+    remaining: u32,
+    reset: Duration,
+}
+
+/// Where a client stands with its daily quota, after a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct QuotaStatus {
+    limit: u64,
+    remaining: u64,
+    reset: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Rejection {
+    RateLimited { retry_after: Duration },
+    QuotaExceeded { retry_after: Duration },
+}
+
+/// The verdict on a request, with the status reported to the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Admission {
+    rate: Option<RateStatus>,
+    quota: Option<QuotaStatus>,
+    rejection: Option<Rejection>,
+}
+
+/// The requests of one client today.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClientUsageResponseData {
+    pub client: String,
+    pub day: NaiveDate,
+    pub requests: u64,
+    pub quota: Option<u64>,
+    pub remaining: Option<u64>,
+}
+
+/// The rate limits, quotas and usage of every client, shared by the server's handlers.
+#[derive(Debug, Clone)]
+pub(super) struct RateLimiter {
+    config: Arc<RateLimitConfig>,
+    /// The clients identified by the configured API keys.
+    api_keys: Arc<HashSet<ClientId>>,
+    max_clients: usize,
+    state: Arc<Mutex<LimiterState>>,
+}
+
+impl RateLimiter {
+    pub(super) fn new(config: RateLimitConfig) -> Self {
+        let api_keys = config.api_keys.iter().map(|key| ClientId::api_key(key.as_bytes())).collect();
+        Self {
+            config: Arc::new(config),
+            api_keys: Arc::new(api_keys),
+            max_clients: MAX_CLIENTS,
+            state: Arc::new(Mutex::new(LimiterState::default())),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LimiterState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Identifies the client sending `request`.
+    fn identify(&self, request: &Request) -> ClientId {
+        if let Some(key) = request.headers().get(API_KEY_HEADER).filter(|key| !key.is_empty()) {
+            let client = ClientId::api_key(key.as_bytes());
+            if self.api_keys.contains(&client) {
+                return client;
+            }
+        }
+        if self.config.trust_forwarded_for {
+            let forwarded = request
+                .headers()
+                .get_all("x-forwarded-for")
+                .iter()
+                .next_back()
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.rsplit(',').next())
+                .and_then(|ip| ip.trim().parse().ok());
+            if let Some(ip) = forwarded {
+                return ClientId::Ip(ip);
+            }
+        }
+        request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map_or(ClientId::Unknown, |ConnectInfo(addr)| ClientId::Ip(addr.ip()))
+    }
+
+    /// Takes a token for a request of `client` to the route `path` and counts it against the
+    /// quota of the client, unless either is exhausted.
+    pub(super) fn admit(
+        &self,
+        client: &ClientId,
+        method: &Method,
+        path: &str,
+        now: Instant,
+        utc_now: DateTime<Utc>,
+    ) -> Admission {
+        let config = &self.config;
+        let mut state = self.lock();
+        let today = utc_now.date_naive();
+        let until_tomorrow = until_next_day(utc_now);
+        let client = if state.usage.contains_key(client) || state.usage.len() < self.max_clients {
+            client
+        } else {
+            &ClientId::Unknown
+        };
+
+        let used = state
+            .usage
+            .get(client)
+            .filter(|usage| usage.day == today)
+            .map_or(0, |usage| usage.requests);
+        let quota_status = |used: u64| {
+            config.daily_quota.map(|limit| QuotaStatus {
+                limit,
+                remaining: limit.saturating_sub(used),
+                reset: until_tomorrow,
+            })
+        };
+        if config.daily_quota.is_some_and(|limit| used >= limit) {
+            return Admission {
+                rate: None,
+                quota: quota_status(used),
+                rejection: Some(Rejection::QuotaExceeded { retry_after: until_tomorrow }),
+            };
+        }
+
+        let route = config.routes.iter().position(|route| route.matches(method, path));
+        let limit = route.map_or(config.default, |i| Some(config.routes[i].limit));
+        let mut rate = None;
+        if let Some(limit) = limit {
+            let bucket = state
+                .buckets
+                .entry((client.clone(), route))
+                .or_insert_with(|| Bucket::full(&limit, now));
+            bucket.refill(&limit, now);
+            if bucket.tokens < 1.0 {
+                return Admission {
+                    rate: Some(RateStatus {
+                        limit: limit.requests,
+                        remaining: 0,
+                        reset: bucket.time_until(&limit, limit.requests as f64),
+                    }),
+                    quota: quota_status(used),
+                    rejection: Some(Rejection::RateLimited { retry_after: bucket.time_until(&limit, 1.0) }),
+                };
+            }
+            bucket.tokens -= 1.0;
+            rate = Some(RateStatus {
+                limit: limit.requests,
+                remaining: bucket.tokens.floor() as u32,
+                reset: bucket.time_until(&limit, limit.requests as f64),
+            });
+        }
+
+        state.usage.insert(client.clone(), DailyUsage { day: today, requests: used + 1 });
+        state.admitted_since_sweep += 1;
+        if state.admitted_since_sweep >= SWEEP_INTERVAL {
+            self.sweep(&mut state, now, today);
+        }
+        Admission { rate, quota: quota_status(used + 1), rejection: None }
+    }
+
+    /// Forgets the buckets that are full again and the usage of past days, which behave as if
+    /// they were new.
+    fn sweep(&self, state: &mut LimiterState, now: Instant, today: NaiveDate) {
+        let config = &self.config;
+        state.buckets.retain(|(_, route), bucket| {
+            let Some(limit) = route.map_or(config.default, |i| Some(config.routes[i].limit)) else {
+                return false;
+            };
+            bucket.refill(&limit, now);
+            bucket.tokens < limit.requests as f64
+        });
+        state.usage.retain(|_, usage| usage.day == today);
+        state.admitted_since_sweep = 0;
+    }
+
+    /// The requests of `client` on the day of `utc_now`.
+    pub(super) fn usage(&self, client: &ClientId, utc_now: DateTime<Utc>) -> ClientUsageResponseData {
+        let today = utc_now.date_naive();
+        let requests = self
+            .lock()
+            .usage
+            .get(client)
+            .filter(|usage| usage.day == today)
+            .map_or(0, |usage| usage.requests);
+        ClientUsageResponseData {
+            client: client.to_string(),
+            day: today,
+            requests,
+            quota: self.config.daily_quota,
+            remaining: self.config.daily_quota.map(|quota| quota.saturating_sub(requests)),
+        }
+    }
+}
+
+fn until_next_day(utc_now: DateTime<Utc>) -> Duration {
+    let tomorrow = utc_now.date_naive().succ_opt().unwrap_or(utc_now.date_naive());
+    let midnight = tomorrow.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    (midnight - utc_now).to_std().unwrap_or_default()
+}
+
+/// Whole seconds, rounded up so that clients waiting as told are not rejected again.
+fn seconds(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+impl Admission {
+    fn set_headers(&self, headers: &mut HeaderMap) {
+        if let Some(rate) = &self.rate {
+            headers.insert(RATE_LIMIT_LIMIT_HEADER, rate.limit.into());
+            headers.insert(RATE_LIMIT_REMAINING_HEADER, rate.remaining.into());
+            headers.insert(RATE_LIMIT_RESET_HEADER, seconds(rate.reset).into());
+        }
+        if let Some(quota) = &self.quota {
+            headers.insert(QUOTA_LIMIT_HEADER, quota.limit.into());
+            headers.insert(QUOTA_REMAINING_HEADER, quota.remaining.into());
+            headers.insert(QUOTA_RESET_HEADER, seconds(quota.reset).into());
+        }
+    }
+}
+
+impl From<Rejection> for ApiError {
+    fn from(rejection: Rejection) -> Self {
+        match rejection {
+            Rejection::RateLimited { .. } => ApiError::TooManyRequests(Problem::new(
+                "rate-limited",
+                "too many requests, retry after the time in Retry-After",
+            )),
+            Rejection::QuotaExceeded { .. } => ApiError::TooManyRequests(Problem::new(
+                "quota-exceeded",
+                "the daily request quota is used up, retry after the time in Retry-After",
+            )),
+        }
+    }
+}
+
+/// Middleware admitting requests within the limits of their client and route, and reporting
+/// those limits in `X-RateLimit-*` and `X-Quota-*` headers. Must be added as a route layer, so
+/// that routes are known by their path template.
+///
+/// # Responses
+///
+/// - 429 Too Many Requests: the client has no token left for the route, or has used up its
+///   daily quota. `Retry-After` tells when to retry.
+pub(super) async fn rate_limit(State(limiter): State<RateLimiter>, mut request: Request, next: Next) -> Response {
+    let client = limiter.identify(&request);
+    let path = match request.extensions().get::<MatchedPath>() {
+        Some(path) => path.as_str().to_string(),
+        None => request.uri().path().to_string(),
+    };
+    let admission = limiter.admit(&client, request.method(), &path, Instant::now(), Utc::now());
+
+    let mut response = match admission.rejection {
+        Some(rejection) => {
+            let (Rejection::RateLimited { retry_after } | Rejection::QuotaExceeded { retry_after }) = rejection;
+            tracing::debug!(%client, path, ?rejection, "request rejected");
+            let mut response = ApiError::from(rejection).into_response();
+            response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(seconds(retry_after)));
+            response
+        }
+        None => {
+            request.extensions_mut().insert(client);
+            next.run(request).await
+        }
+    };
+    admission.set_headers(response.headers_mut());
+    response
+}
+
+/// Reports the requests the calling client has sent today, and its quota.
+///
+/// # Responses
+///
+/// - 200 OK: the usage of the client.
+pub(super) async fn client_usage(
+    State(limiter): State<RateLimiter>,
+    Extension(client): Extension<ClientId>,
+) -> ApiSuccess<ClientUsageResponseData> {
+    ApiSuccess::new(StatusCode::OK, limiter.usage(&client, Utc::now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::routing::get;
+    use axum::Router;
+    use chrono::TimeZone;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn limiter(default: Option<&str>, routes: &[&str], daily_quota: Option<u64>) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            default: default.map(|limit| limit.parse().unwrap()),
+            routes: routes.iter().map(|route| route.parse().unwrap()).collect(),
+            daily_quota,
+            trust_forwarded_for: false,
+            api_keys: vec!["secret".to_string(), "other".to_string()],
+        })
+    }
+
+    fn noon() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap()
+    }
+
+    fn client(n: u8) -> ClientId {
+        ClientId::Ip(IpAddr::from([10, 0, 0, n]))
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        assert_eq!("100/m".parse::<RateLimit>(), Ok(RateLimit::new(100, Duration::from_secs(60))));
+        assert_eq!(" 5 / s".parse::<RateLimit>(), Ok(RateLimit::new(5, Duration::from_secs(1))));
+        for invalid in ["100", "0/s", "-1/s", "10/d", "ten/s"] {
+            assert!(invalid.parse::<RateLimit>().is_err(), "{}", invalid);
+        }
+
+        let route: RouteLimit = "post /api/pet/import=5/h".parse().unwrap();
+        assert_eq!(route.method, Some(Method::POST));
+        assert_eq!(route.path, "/api/pet/import");
+        assert_eq!(route.limit, RateLimit::new(5, Duration::from_secs(3600)));
+        assert_eq!("/api/pet/{petId}=1/s".parse::<RouteLimit>().unwrap().method, None);
+        assert!("api/pet=1/s".parse::<RouteLimit>().is_err());
+        assert!("/api/pet".parse::<RouteLimit>().is_err());
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let limiter = limiter(Some("2/s"), &[], None);
+        let now = Instant::now();
+        let admit = |at: Duration| limiter.admit(&client(1), &Method::GET, "/api/pet", now + at, noon());
+
+        let first = admit(Duration::ZERO);
+        assert_eq!(first.rate, Some(RateStatus { limit: 2, remaining: 1, reset: Duration::from_millis(500) }));
+        assert!(admit(Duration::ZERO).rejection.is_none());
+        let limited = admit(Duration::from_millis(100));
+        assert_eq!(limited.rejection, Some(Rejection::RateLimited { retry_after: Duration::from_millis(400) }));
+        assert_eq!(limited.rate.unwrap().remaining, 0);
+
+        assert!(admit(Duration::from_millis(500)).rejection.is_none());
+        assert!(admit(Duration::from_millis(500)).rejection.is_some());
+        // Other clients have buckets of their own
+        assert!(limiter.admit(&client(2), &Method::GET, "/api/pet", now, noon()).rejection.is_none());
+    }
+
+    #[test]
+    fn test_route_limits() {
+        let limiter = limiter(Some("100/s"), &["POST /api/pet/import=1/m"], None);
+        let now = Instant::now();
+        let admit = |method: Method, path: &str| limiter.admit(&client(1), &method, path, now, noon());
+
+        assert!(admit(Method::POST, "/api/pet/import").rejection.is_none());
+        assert!(admit(Method::POST, "/api/pet/import").rejection.is_some());
+        assert_eq!(admit(Method::GET, "/api/pet/import").rate.unwrap().limit, 100);
+        assert_eq!(admit(Method::GET, "/api/pet/{petId}").rate.unwrap().remaining, 98);
+    }
+
+    #[test]
+    fn test_daily_quota() {
+        let limiter = limiter(None, &[], Some(2));
+        let now = Instant::now();
+        let admit = |at: DateTime<Utc>| limiter.admit(&client(1), &Method::GET, "/api/pet", now, at);
+
+        assert_eq!(admit(noon()).quota.unwrap().remaining, 1);
+        let last = admit(noon());
+        assert_eq!(last.quota, Some(QuotaStatus { limit: 2, remaining: 0, reset: Duration::from_secs(12 * 3600) }));
+        assert!(last.rate.is_none());
+        assert_eq!(
+            admit(noon()).rejection,
+            Some(Rejection::QuotaExceeded { retry_after: Duration::from_secs(12 * 3600) })
+        );
+        assert_eq!(limiter.usage(&client(1), noon()).requests, 2, "rejected requests are not counted");
+
+        let tomorrow = noon() + chrono::Duration::days(1);
+        assert!(admit(tomorrow).rejection.is_none());
+        assert_eq!(
+            limiter.usage(&client(1), tomorrow),
+            ClientUsageResponseData {
+                client: "ip:10.0.0.1".to_string(),
+                day: tomorrow.date_naive(),
+                requests: 1,
+                quota: Some(2),
+                remaining: Some(1),
+            }
+        );
+    }
+
+    #[test]
+    fn test_sweep_forgets_idle_clients() {
+        let limiter = limiter(Some("2000/s"), &[], None);
+        let now = Instant::now();
+        limiter.admit(&client(1), &Method::GET, "/api/pet", now, noon());
+        for _ in 1..SWEEP_INTERVAL {
+            limiter.admit(&client(2), &Method::GET, "/api/pet", now + Duration::from_secs(1), noon());
+        }
+
+        let state = limiter.lock();
+        assert_eq!(state.buckets.len(), 1, "the bucket of client 1 is full again");
+        assert_eq!(state.admitted_since_sweep, 0);
+    }
+
+    #[test]
+    fn test_max_clients() {
+        let mut limiter = limiter(None, &[], Some(10));
+        limiter.max_clients = 2;
+        let now = Instant::now();
+        for n in [1, 2, 3, 4, 1] {
+            limiter.admit(&client(n), &Method::GET, "/api/pet", now, noon());
+        }
+
+        assert_eq!(limiter.usage(&client(1), noon()).requests, 2);
+        assert_eq!(limiter.usage(&client(3), noon()).requests, 0);
+        assert_eq!(limiter.usage(&ClientId::Unknown, noon()).requests, 2, "clients 3 and 4 share limits");
+        assert_eq!(limiter.lock().usage.len(), 3);
+    }
+
+    fn router(limiter: RateLimiter) -> Router {
+        let api = Router::new()
+            .route("/pet/{petId}", get(|| async { "pet" }))
+            .route("/usage", get(client_usage).with_state(limiter.clone()))
+            .route_layer(axum::middleware::from_fn_with_state(limiter, rate_limit));
+        Router::new().nest("/api", api)
+    }
+
+    async fn send(router: &Router, uri: &str, api_key: Option<&str>) -> Response {
+        let mut request = Request::builder().uri(uri);
+        if let Some(key) = api_key {
+            request = request.header(API_KEY_HEADER, key);
+        }
+        router.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_middleware() {
+        let router = router(limiter(Some("10/s"), &["GET /api/pet/{petId}=1/m"], Some(100)));
+
+        let response = send(&router, "/api/pet/1", Some("secret")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[&RATE_LIMIT_LIMIT_HEADER], "1");
+        assert_eq!(response.headers()[&RATE_LIMIT_REMAINING_HEADER], "0");
+        assert_eq!(response.headers()[&RATE_LIMIT_RESET_HEADER], "60");
+        assert_eq!(response.headers()[&QUOTA_REMAINING_HEADER], "99");
+
+        let response = send(&router, "/api/pet/2", Some("secret")).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[RETRY_AFTER], "60");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["type"], "/problems/rate-limited");
+
+        assert_eq!(send(&router, "/api/pet/2", Some("other")).await.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_api_keys_are_ignored() {
+        let router = router(limiter(Some("1/m"), &[], None));
+
+        assert_eq!(send(&router, "/api/pet/1", Some("made-up-1")).await.status(), StatusCode::OK);
+        // Identified by address like any client without a key, rather than given a fresh bucket
+        assert_eq!(send(&router, "/api/pet/1", Some("made-up-2")).await.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(send(&router, "/api/pet/1", None).await.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(send(&router, "/api/pet/1", Some("secret")).await.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_identify_by_last_forwarded_address() {
+        let limiter = RateLimiter::new(RateLimitConfig { trust_forwarded_for: true, ..Default::default() });
+        let identify = |values: &[&str]| {
+            let mut request = Request::builder();
+            for value in values {
+                request = request.header("x-forwarded-for", *value);
+            }
+            limiter.identify(&request.body(Body::empty()).unwrap())
+        };
+
+        // The client may prepend any address, but not the one appended by the proxy
+        assert_eq!(identify(&["10.0.0.9, 10.0.0.1"]), client(1));
+        assert_eq!(identify(&["10.0.0.9", "10.0.0.2"]), client(2));
+        assert_eq!(identify(&["10.0.0.9, unknown"]), ClientId::Unknown);
+        assert_eq!(identify(&[]), ClientId::Unknown);
+    }
+
+    #[tokio::test]
+    async fn test_client_usage() {
+        let router = router(limiter(None, &[], Some(100)));
+        send(&router, "/api/pet/1", Some("secret")).await;
+
+        let response = send(&router, "/api/usage", Some("secret")).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let digest = hex::encode(&Sha256::digest(b"secret")[..8]);
+        assert_eq!(body["data"]["client"], format!("key:{}", digest));
+        assert_eq!(body["data"]["requests"], 2);
+        assert_eq!(body["data"]["remaining"], 98);
+    }
+}
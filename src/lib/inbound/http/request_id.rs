@@ -0,0 +1,149 @@
+/*!
+    Module `request_id` gives every request an id, so that its logs, its response and any
+    problem reported to the client can be matched up. A well-formed `X-Request-Id` sent by the
+    client or a proxy in front of the server is kept, otherwise a new one is generated. The id
+    is echoed in the response header of the same name.
+*/
+
+use axum::extract::Request;
+use axum::http::{HeaderName, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+
+/// The header carrying the id of a request, and of its response.
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// The longest request id kept; longer ones are replaced.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: RequestId;
+}
+
+/// The id of a request, made of at most 128 ASCII letters, digits and `-_.:`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId(String);
+
+impl RequestId {
+    /// Returns the id in `value`, or `None` if it is empty, too long or has other characters,
+    /// which could forge log lines.
+    pub fn parse(value: &HeaderValue) -> Option<Self> {
+        let id = value.to_str().ok()?;
+        let valid = !id.is_empty()
+            && id.len() <= MAX_REQUEST_ID_LEN
+            && id.bytes().all(|b| b.is_ascii_alphanumeric() || b"-_.:".contains(&b));
+        valid.then(|| Self(id.to_string()))
+    }
+
+    /// Returns a new random id.
+    pub fn generate() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The id of the request being handled by the current task, if any.
+    pub fn current() -> Option<Self> {
+        CURRENT_REQUEST_ID.try_with(Clone::clone).ok()
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Middleware assigning the [RequestId] of a request. The id is added to the request
+/// extensions before the inner layers run, is the [current](RequestId::current) one while
+/// they do and is set as `X-Request-Id` on the response.
+pub(super) async fn request_id(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(RequestId::parse)
+        .unwrap_or_else(RequestId::generate);
+    request.extensions_mut().insert(id.clone());
+
+    let header = HeaderValue::from_str(id.as_str()).expect("request ids are valid header values");
+    let mut response = CURRENT_REQUEST_ID.scope(id, next.run(request)).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, header);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::inbound::http::handlers::problem::{ApiError, Problem};
+
+    fn router() -> Router {
+        Router::new()
+            .route(
+                "/id",
+                get(|| async { RequestId::current().map(|id| id.to_string()).unwrap_or_default() }),
+            )
+            .route(
+                "/problem",
+                get(|| async { ApiError::NotFound(Problem::new("pet-not-found", "pet with id 7 not found")) }),
+            )
+            .layer(axum::middleware::from_fn(request_id))
+    }
+
+    async fn send(id: Option<&str>) -> (HeaderValue, String) {
+        let mut request = Request::builder().uri("/id");
+        if let Some(id) = id {
+            request = request.header(REQUEST_ID_HEADER, id);
+        }
+        let response = router().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        let header = response.headers()[REQUEST_ID_HEADER].clone();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (header, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_request_id_is_kept() {
+        let (header, current) = send(Some("edge-42:a.b_c")).await;
+
+        assert_eq!(header, "edge-42:a.b_c");
+        assert_eq!(current, "edge-42:a.b_c");
+    }
+
+    #[tokio::test]
+    async fn test_request_id_is_generated() {
+        let long = "a".repeat(MAX_REQUEST_ID_LEN + 1);
+        for id in [None, Some(""), Some("two words"), Some("x\"}"), Some(long.as_str())] {
+            let (header, current) = send(id).await;
+
+            assert_eq!(header.len(), 36, "{:?}", id);
+            assert_eq!(header.to_str().unwrap(), current);
+        }
+        assert_ne!(send(None).await.0, send(None).await.0);
+    }
+
+    #[tokio::test]
+    async fn test_problem_quotes_request_id() {
+        let request = Request::builder()
+            .uri("/problem")
+            .header(REQUEST_ID_HEADER, "req-1")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = router().oneshot(request).await.unwrap();
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["request_id"], "req-1");
+    }
+
+    #[test]
+    fn test_no_current_request_id_outside_requests() {
+        assert_eq!(RequestId::current(), None);
+    }
+}
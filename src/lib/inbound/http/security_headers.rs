@@ -0,0 +1,114 @@
+/*!
+    Module `security_headers` adds the headers hardening browsers against misuse of the API:
+    responses are not sniffed as another media type, not framed, not allowed to load anything
+    and leak no referrer. `Strict-Transport-Security` is only sent when configured, since the
+    server itself speaks plain HTTP and only a proxy in front of it may serve HTTPS.
+*/
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{Request, State};
+use axum::http::header::{
+    CONTENT_SECURITY_POLICY, REFERRER_POLICY, STRICT_TRANSPORT_SECURITY, X_CONTENT_TYPE_OPTIONS,
+    X_FRAME_OPTIONS,
+};
+use axum::http::{HeaderName, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+
+/// Which optional security headers are sent.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SecurityHeadersConfig {
+    /// How long browsers must only reach the server over HTTPS, or `None` not to tell them.
+    pub hsts_max_age: Option<Duration>,
+}
+
+/// The security headers added to every response.
+#[derive(Debug, Clone)]
+pub(super) struct SecurityHeaders(Arc<[(HeaderName, HeaderValue)]>);
+
+impl SecurityHeaders {
+    pub(super) fn new(config: &SecurityHeadersConfig) -> Self {
+        let mut headers = vec![
+            (X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff")),
+            (X_FRAME_OPTIONS, HeaderValue::from_static("DENY")),
+            (REFERRER_POLICY, HeaderValue::from_static("no-referrer")),
+            (
+                CONTENT_SECURITY_POLICY,
+                HeaderValue::from_static("default-src 'none'; frame-ancestors 'none'"),
+            ),
+        ];
+        if let Some(max_age) = config.hsts_max_age {
+            let hsts = format!("max-age={}; includeSubDomains", max_age.as_secs());
+            headers.push((STRICT_TRANSPORT_SECURITY, HeaderValue::from_str(&hsts).unwrap()));
+        }
+        Self(headers.into())
+    }
+}
+
+/// Middleware adding the [SecurityHeaders] to every response, unless the handler already set
+/// one of them.
+pub(super) async fn security_headers(
+    State(headers): State<SecurityHeaders>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    for (name, value) in headers.0.iter() {
+        response.headers_mut().entry(name).or_insert_with(|| value.clone());
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::HeaderMap;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn headers_of(config: SecurityHeadersConfig, uri: &str) -> HeaderMap {
+        let router = Router::new()
+            .route("/ok", get(|| async { "ok" }))
+            .route("/framed", get(|| async { ([(X_FRAME_OPTIONS, "SAMEORIGIN")], "ok") }))
+            .layer(axum::middleware::from_fn_with_state(
+                SecurityHeaders::new(&config),
+                security_headers,
+            ));
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        router.oneshot(request).await.unwrap().headers().clone()
+    }
+
+    #[tokio::test]
+    async fn test_security_headers() {
+        let headers = headers_of(SecurityHeadersConfig::default(), "/ok").await;
+
+        assert_eq!(headers[X_CONTENT_TYPE_OPTIONS], "nosniff");
+        assert_eq!(headers[X_FRAME_OPTIONS], "DENY");
+        assert_eq!(headers[REFERRER_POLICY], "no-referrer");
+        assert_eq!(headers[CONTENT_SECURITY_POLICY], "default-src 'none'; frame-ancestors 'none'");
+        assert!(!headers.contains_key(STRICT_TRANSPORT_SECURITY));
+    }
+
+    #[tokio::test]
+    async fn test_handler_headers_are_kept() {
+        let headers = headers_of(SecurityHeadersConfig::default(), "/framed").await;
+
+        assert_eq!(headers[X_FRAME_OPTIONS], "SAMEORIGIN");
+    }
+
+    #[tokio::test]
+    async fn test_hsts() {
+        let config = SecurityHeadersConfig {
+            hsts_max_age: Some(Duration::from_secs(31_536_000)),
+        };
+
+        let headers = headers_of(config, "/ok").await;
+
+        assert_eq!(headers[STRICT_TRANSPORT_SECURITY], "max-age=31536000; includeSubDomains");
+    }
+}
@@ -0,0 +1,264 @@
+/*
+   Module `transfer` specifies the file formats [PetRecord]s are imported from and exported to
+   in bulk, CSV and JSON Lines, for the inbound adapters that move files: the HTTP API and the
+   admin command line.
+
+   A CSV file has a header row naming its columns, any of [CSV_HEADERS] in any order; a JSON
+   Lines file has one JSON record per line. Rows are numbered by the line they start on.
+*/
+
+use std::io;
+use std::path::Path;
+
+use bytes::Bytes;
+use futures::{stream, Stream};
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, AsyncRead};
+use tokio::sync::mpsc;
+use tokio_util::io::SyncIoBridge;
+
+use crate::domain::petstore::models::transfer::{ImportPetsError, ImportRow, MalformedRecord, PetRecord};
+
+/// The columns of pet CSV files, in the order they are exported.
+pub const CSV_HEADERS: [&str; 7] = [
+    "id",
+    "name",
+    "category_id",
+    "category_name",
+    "status",
+    "tags",
+    "photo_urls",
+];
+
+/// Separates the tags, and the photo URLs, of a pet within a CSV field.
+const CSV_LIST_SEPARATOR: char = '|';
+
+/// How many parsed rows may wait for the import to catch up before the parser stops reading.
+const IMPORT_BUFFER: usize = 64;
+
+/// The file formats pets are imported from and exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferFormat {
+    Csv,
+    JsonLines,
+}
+
+impl TransferFormat {
+    /// The format of a request body of media type `content_type`, parameters ignored.
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let media_type = content_type.split(';').next().unwrap_or_default().trim();
+        match media_type.to_ascii_lowercase().as_str() {
+            "text/csv" => Some(Self::Csv),
+            "application/x-ndjson" | "application/jsonl" => Some(Self::JsonLines),
+            _ => None,
+        }
+    }
+
+    /// The format named `format`, `csv` or `ndjson` (alias `jsonl`), CSV by default.
+    pub fn from_name(format: Option<&str>) -> Option<Self> {
+        match format {
+            None | Some("csv") => Some(Self::Csv),
+            Some("ndjson") | Some("jsonl") => Some(Self::JsonLines),
+            _ => None,
+        }
+    }
+
+    /// The format of the file at `path`, as told by its extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "ndjson" | "jsonl" => Some(Self::JsonLines),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::JsonLines => "application/x-ndjson",
+        }
+    }
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Self::Csv => "pets.csv",
+            Self::JsonLines => "pets.ndjson",
+        }
+    }
+}
+
+/// A row of a pet CSV file. Lists are [CSV_LIST_SEPARATOR]-separated, and empty fields absent.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+struct CsvPetRow {
+    id: Option<i64>,
+    #[serde(default)]
+    name: String,
+    category_id: Option<i64>,
+    category_name: Option<String>,
+    status: Option<String>,
+    #[serde(default)]
+    tags: String,
+    #[serde(default)]
+    photo_urls: String,
+}
+
+impl From<CsvPetRow> for PetRecord {
+    fn from(row: CsvPetRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            category_id: row.category_id,
+            category_name: row.category_name,
+            status: row.status,
+            tags: split_list(&row.tags),
+            photo_urls: split_list(&row.photo_urls),
+        }
+    }
+}
+
+fn split_list(field: &str) -> Vec<String> {
+    if field.is_empty() {
+        return Vec::new();
+    }
+    field.split(CSV_LIST_SEPARATOR).map(|item| item.trim().to_string()).collect()
+}
+
+/// The CSV line of `record`, its lists joined by [CSV_LIST_SEPARATOR].
+pub fn csv_line(record: &PetRecord) -> Result<Bytes, csv::Error> {
+    let separator = CSV_LIST_SEPARATOR.to_string();
+    let fields = [
+        record.id.map(|id| id.to_string()).unwrap_or_default(),
+        record.name.clone(),
+        record.category_id.map(|id| id.to_string()).unwrap_or_default(),
+        record.category_name.clone().unwrap_or_default(),
+        record.status.clone().unwrap_or_default(),
+        record.tags.join(&separator),
+        record.photo_urls.join(&separator),
+    ];
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(&fields)?;
+    writer.into_inner().map(Bytes::from).map_err(|e| e.into_error().into())
+}
+
+/// The header line of pet CSV files.
+pub fn csv_header() -> Bytes {
+    Bytes::from(format!("{}\n", CSV_HEADERS.join(",")))
+}
+
+/// The JSON Lines line of `record`.
+pub fn json_line(record: &PetRecord) -> Result<Bytes, serde_json::Error> {
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+    Ok(Bytes::from(line))
+}
+
+/// Reads the rows of a CSV file. The parser runs on a blocking thread, and stops reading when
+/// the import falls [IMPORT_BUFFER] rows behind.
+pub fn csv_rows<R>(reader: R) -> impl Stream<Item = Result<ImportRow, ImportPetsError>> + Send
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(IMPORT_BUFFER);
+    let reader = SyncIoBridge::new(reader);
+    tokio::task::spawn_blocking(move || parse_csv(reader, tx));
+    stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|row| (row, rx)) })
+}
+
+fn parse_csv(reader: impl io::Read, tx: mpsc::Sender<Result<ImportRow, ImportPetsError>>) {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(reader);
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => {
+            let _ = tx.blocking_send(Err(csv_read_error(e)));
+            return;
+        }
+    };
+    let mut record = csv::StringRecord::new();
+    loop {
+        let line = reader.position().line();
+        let row = match reader.read_record(&mut record) {
+            Ok(false) => return,
+            Ok(true) => {
+                let line = record.position().map_or(line, |position| position.line());
+                let record = record
+                    .deserialize::<CsvPetRow>(Some(&headers))
+                    .map(PetRecord::from)
+                    .map_err(|e| MalformedRecord(e.to_string()));
+                Ok(ImportRow { line, record })
+            }
+            Err(e) if e.is_io_error() => Err(csv_read_error(e)),
+            Err(e) => {
+                let line = e.position().map_or(line, |position| position.line());
+                Ok(ImportRow { line, record: Err(MalformedRecord(e.to_string())) })
+            }
+        };
+        let failed = row.is_err();
+        if tx.blocking_send(row).is_err() || failed {
+            return;
+        }
+    }
+}
+
+fn csv_read_error(e: csv::Error) -> ImportPetsError {
+    match e.into_kind() {
+        csv::ErrorKind::Io(e) => anyhow::Error::new(e).context("failed to read uploaded file").into(),
+        kind => anyhow::anyhow!("failed to read uploaded file: {:?}", kind).into(),
+    }
+}
+
+/// Reads the rows of a JSON Lines file, one [PetRecord] per line. Blank lines are skipped.
+pub fn json_lines_rows<R>(reader: R) -> impl Stream<Item = Result<ImportRow, ImportPetsError>> + Send
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    let lines = tokio::io::BufReader::new(reader).lines();
+    stream::unfold(Some((lines, 0u64)), |state| async move {
+        let (mut lines, mut line) = state?;
+        loop {
+            line += 1;
+            match lines.next_line().await {
+                Ok(None) => return None,
+                Ok(Some(text)) if text.trim().is_empty() => continue,
+                Ok(Some(text)) => {
+                    let record = serde_json::from_str::<PetRecord>(&text)
+                        .map_err(|e| MalformedRecord(e.to_string()));
+                    return Some((Ok(ImportRow { line, record }), Some((lines, line))));
+                }
+                Err(e) => {
+                    let e = anyhow::Error::new(e).context("failed to read uploaded file");
+                    return Some((Err(e.into()), None));
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+
+    #[test]
+    fn test_transfer_format() {
+        assert_eq!(TransferFormat::from_content_type("Text/CSV; charset=utf-8"), Some(TransferFormat::Csv));
+        assert_eq!(TransferFormat::from_content_type("application/jsonl"), Some(TransferFormat::JsonLines));
+        assert_eq!(TransferFormat::from_content_type("application/json"), None);
+        assert_eq!(TransferFormat::from_name(None), Some(TransferFormat::Csv));
+        assert_eq!(TransferFormat::from_name(Some("xml")), None);
+        assert_eq!(TransferFormat::from_path(Path::new("out/pets.JSONL")), Some(TransferFormat::JsonLines));
+        assert_eq!(TransferFormat::from_path(Path::new("pets")), None);
+    }
+
+    #[tokio::test]
+    async fn test_csv_columns_in_any_order() {
+        let csv = "name,tags,id\nRex,a||b,7\n";
+
+        let rows: Vec<ImportRow> = csv_rows(csv.as_bytes()).try_collect().await.unwrap();
+
+        let record = rows[0].record.clone().unwrap();
+        assert_eq!((rows[0].line, record.id, record.name.as_str()), (2, Some(7), "Rex"));
+        // An empty item is kept, for validation to reject
+        assert_eq!(record.tags, vec!["a", "", "b"]);
+    }
+}
@@ -1,3 +1,4 @@
 pub mod domain;
 pub mod inbound;
 pub mod outbound;
+pub mod telemetry;
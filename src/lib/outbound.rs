@@ -1,3 +1,13 @@
+pub mod category_repository;
 pub mod connect;
+pub mod idempotency_repository;
+pub mod log_publisher;
+pub mod migrations;
+pub mod outbox_repository;
+pub mod pet_cache;
 pub mod params;
-pub mod repository;
\ No newline at end of file
+pub mod repository;
+pub mod tag_repository;
+pub mod traced;
+pub mod webhook_repository;
+pub mod webhook_sender;
\ No newline at end of file
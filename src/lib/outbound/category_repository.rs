@@ -0,0 +1,89 @@
+use crate::domain::petstore::models::category::{
+    Category, CreateCategoryError, CreateCategoryRequest, DeleteCategoryError, FindCategoryError,
+    RenameCategoryError, RenameCategoryRequest,
+};
+use crate::domain::petstore::ports::CategoryRepository;
+use crate::outbound::connect::PostgresClient;
+use crate::outbound::repository::{assign_id, unique_violation};
+use sqlx::Row;
+
+impl CategoryRepository for PostgresClient {
+    #[tracing::instrument(skip_all)]
+    async fn create_category(&self, req: &CreateCategoryRequest) -> Result<Category, CreateCategoryError> {
+        let category_id = assign_id(self.pool(), self.id_strategy(), "categories_id_seq", req.id)
+            .await
+            .map_err(|e| CreateCategoryError::Unknown(anyhow::anyhow!(e)))?;
+        let row = sqlx::query("INSERT INTO categories (id, name) VALUES ($1, $2) RETURNING id, name")
+            .bind(category_id)
+            .bind(&req.name)
+            .fetch_one(self.pool())
+            .await
+            .map_err(|e| match unique_violation(&e).as_deref() {
+                Some("categories_pkey") => CreateCategoryError::DuplicateId { id: category_id },
+                Some(_) => CreateCategoryError::Duplicate { name: req.name.clone() },
+                None => CreateCategoryError::Unknown(anyhow::anyhow!(e)),
+            })?;
+
+        Ok(Category::with_values(row.get("id"), row.get("name")))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_categories(&self) -> Result<Vec<Category>, FindCategoryError> {
+        let rows = sqlx::query("SELECT id, name FROM categories ORDER BY id")
+            .fetch_all(self.pool())
+            .await
+            .map_err(|e| FindCategoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(rows
+            .iter()
+            .map(|row| Category::with_values(row.get("id"), row.get("name")))
+            .collect())
+    }
+
+    #[tracing::instrument(skip_all, fields(category_id = category_id))]
+    async fn find_category_by_id(&self, category_id: i64) -> Result<Option<Category>, FindCategoryError> {
+        let row = sqlx::query("SELECT id, name FROM categories WHERE id = $1")
+            .bind(category_id)
+            .fetch_optional(self.pool())
+            .await
+            .map_err(|e| FindCategoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(row.map(|row| Category::with_values(row.get("id"), row.get("name"))))
+    }
+
+    #[tracing::instrument(skip_all, fields(category_id = req.id))]
+    async fn rename_category(&self, req: &RenameCategoryRequest) -> Result<Category, RenameCategoryError> {
+        let row = sqlx::query("UPDATE categories SET name = $2 WHERE id = $1 RETURNING id, name")
+            .bind(req.id)
+            .bind(&req.name)
+            .fetch_optional(self.pool())
+            .await
+            .map_err(|e| match unique_violation(&e) {
+                Some(_) => RenameCategoryError::Duplicate { name: req.name.clone() },
+                None => RenameCategoryError::Unknown(anyhow::anyhow!(e)),
+            })?;
+
+        row.map(|row| Category::with_values(row.get("id"), row.get("name")))
+            .ok_or(RenameCategoryError::NotFound { id: req.id })
+    }
+
+    #[tracing::instrument(skip_all, fields(category_id = category_id))]
+    async fn delete_category(&self, category_id: i64) -> Result<(), DeleteCategoryError> {
+        let result = sqlx::query("DELETE FROM categories WHERE id = $1")
+            .bind(category_id)
+            .execute(self.pool())
+            .await
+            .map_err(|e| {
+                // 23503: foreign_key_violation, the category is still referenced by pets
+                match e.as_database_error().and_then(|d| d.code()).as_deref() {
+                    Some("23503") => DeleteCategoryError::InUse { id: category_id },
+                    _ => DeleteCategoryError::Unknown(anyhow::anyhow!(e)),
+                }
+            })?;
+
+        if result.rows_affected() == 0 {
+            return Err(DeleteCategoryError::NotFound { id: category_id });
+        }
+        Ok(())
+    }
+}
@@ -1,11 +1,13 @@
 use anyhow::Result;
 use sqlx::{postgres::PgPoolOptions, PgPool};
 
+use crate::domain::petstore::models::id::IdStrategy;
 use crate::outbound::params::ConnectionParams;
 
 #[derive(Debug, Clone)]
 pub struct PostgresClient {
     pool: PgPool,
+    id_strategy: IdStrategy,
 }
 
 impl PostgresClient {
@@ -14,10 +16,27 @@ impl PostgresClient {
             .max_connections(5)
             .connect(params.connect_string().as_str())
             .await?;
-        Ok(Self { pool })
+        Ok(Self { pool, id_strategy: IdStrategy::default() })
+    }
+
+    /// Sets the [IdStrategy] the ids of new rows are chosen by, so that the id sequences are
+    /// only kept ahead of the ids they may collide with.
+    pub fn with_id_strategy(mut self, id_strategy: IdStrategy) -> Self {
+        self.id_strategy = id_strategy;
+        self
     }
 
     pub fn pool(&self) -> &PgPool {
         &self.pool
     }
+
+    pub fn id_strategy(&self) -> IdStrategy {
+        self.id_strategy
+    }
+
+    /// Checks that the database answers queries.
+    pub async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
 }
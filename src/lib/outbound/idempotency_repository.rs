@@ -0,0 +1,123 @@
+use crate::domain::petstore::models::idempotency::{
+    ClaimIdempotencyKeyRequest, IdempotencyClaim, IdempotencyError, IdempotentResponse,
+};
+use crate::domain::petstore::ports::IdempotencyRepository;
+use crate::outbound::connect::PostgresClient;
+use sqlx::Row;
+use std::time::Duration;
+
+impl IdempotencyRepository for PostgresClient {
+    #[tracing::instrument(skip_all)]
+    async fn claim_idempotency_key(&self, req: &ClaimIdempotencyKeyRequest) -> Result<IdempotencyClaim, IdempotencyError> {
+        let unknown = |e: sqlx::Error| IdempotencyError::Unknown(anyhow::anyhow!(e));
+
+        // A claim released between the insert and the select is simply attempted again
+        loop {
+            // Take the key if it is free, or if its previous claim has expired
+            let claimed = sqlx::query(
+                r#"
+                INSERT INTO idempotency_keys (key, request_fingerprint, claim_token, expires_at)
+                VALUES ($1, $2, $4, now() + make_interval(secs => $3))
+                ON CONFLICT (key) DO UPDATE SET
+                    request_fingerprint = EXCLUDED.request_fingerprint,
+                    claim_token = EXCLUDED.claim_token,
+                    response_status = NULL,
+                    response_headers = '{}',
+                    response_body = NULL,
+                    created_at = now(),
+                    expires_at = EXCLUDED.expires_at
+                WHERE idempotency_keys.expires_at <= now()
+                "#
+            )
+            .bind(req.key.as_str())
+            .bind(&req.fingerprint)
+            .bind(req.lease.as_secs_f64())
+            .bind(&req.token)
+            .execute(self.pool())
+            .await
+            .map_err(unknown)?;
+
+            if claimed.rows_affected() == 1 {
+                return Ok(IdempotencyClaim::Claimed);
+            }
+
+            let row = sqlx::query(
+                "SELECT request_fingerprint, response_status, response_headers, response_body FROM idempotency_keys WHERE key = $1"
+            )
+            .bind(req.key.as_str())
+            .fetch_optional(self.pool())
+            .await
+            .map_err(unknown)?;
+
+            let Some(row) = row else {
+                continue;
+            };
+
+            if row.get::<String, _>("request_fingerprint") != req.fingerprint {
+                return Ok(IdempotencyClaim::Mismatch);
+            }
+            let status: Option<i32> = row.get("response_status");
+            let body: Option<String> = row.get("response_body");
+            return Ok(match (status, body) {
+                (Some(status), Some(body)) => IdempotencyClaim::Completed(IdempotentResponse {
+                    status_code: u16::try_from(status).map_err(|e| IdempotencyError::Unknown(anyhow::anyhow!(e)))?,
+                    headers: row
+                        .get::<Vec<String>, _>("response_headers")
+                        .iter()
+                        .filter_map(|line| line.split_once(": "))
+                        .map(|(name, value)| (name.to_string(), value.to_string()))
+                        .collect(),
+                    body,
+                }),
+                _ => IdempotencyClaim::InProgress,
+            });
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn complete_idempotency_key(&self, claim: &ClaimIdempotencyKeyRequest, response: &IdempotentResponse, window: Duration) -> Result<(), IdempotencyError> {
+        // Only this claim, unless it expired and was taken over or completed
+        let completed = sqlx::query(
+            r#"
+            UPDATE idempotency_keys
+            SET response_status = $2, response_headers = $3, response_body = $4,
+                expires_at = now() + make_interval(secs => $5)
+            WHERE key = $1 AND claim_token = $6 AND response_status IS NULL
+            "#
+        )
+            .bind(claim.key.as_str())
+            .bind(i32::from(response.status_code))
+            .bind(response.headers.iter().map(|(name, value)| format!("{}: {}", name, value)).collect::<Vec<_>>())
+            .bind(&response.body)
+            .bind(window.as_secs_f64())
+            .bind(&claim.token)
+            .execute(self.pool())
+            .await
+            .map_err(|e| IdempotencyError::Unknown(anyhow::anyhow!(e)))?;
+        if completed.rows_affected() == 0 {
+            return Err(IdempotencyError::ClaimLost { key: claim.key.clone() });
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn release_idempotency_key(&self, claim: &ClaimIdempotencyKeyRequest) -> Result<(), IdempotencyError> {
+        // A claim taken over, or completed, by another request is left alone
+        sqlx::query("DELETE FROM idempotency_keys WHERE key = $1 AND claim_token = $2 AND response_status IS NULL")
+            .bind(claim.key.as_str())
+            .bind(&claim.token)
+            .execute(self.pool())
+            .await
+            .map_err(|e| IdempotencyError::Unknown(anyhow::anyhow!(e)))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn purge_expired_idempotency_keys(&self) -> Result<u64, IdempotencyError> {
+        let purged = sqlx::query("DELETE FROM idempotency_keys WHERE expires_at <= now()")
+            .execute(self.pool())
+            .await
+            .map_err(|e| IdempotencyError::Unknown(anyhow::anyhow!(e)))?;
+        Ok(purged.rows_affected())
+    }
+}
@@ -0,0 +1,22 @@
+use crate::domain::petstore::models::event::{OutboxEvent, PublishEventError};
+use crate::domain::petstore::ports::EventPublisher;
+
+/// An [EventPublisher] that writes every event to the log, for deployments without a message
+/// broker.
+#[derive(Debug, Clone, Default)]
+pub struct LogEventPublisher;
+
+impl EventPublisher for LogEventPublisher {
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), PublishEventError> {
+        let payload = serde_json::to_string(&event.event).map_err(|e| PublishEventError::Unknown(anyhow::anyhow!(e)))?;
+        tracing::info!(
+            event_id = event.id,
+            event_type = event.event.event_type(),
+            pet_id = event.event.pet_id(),
+            occurred_at = %event.occurred_at,
+            payload,
+            "pet event"
+        );
+        Ok(())
+    }
+}
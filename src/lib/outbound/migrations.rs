@@ -0,0 +1,269 @@
+/*
+   Module `migrations` applies, reverts and reports the versioned migrations of the database
+   schema in `./migrations`. Every migration is a pair of `<version>_<name>.up.sql` and
+   `<version>_<name>.down.sql` scripts, the second undoing the first.
+*/
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::migrate::Migrator;
+use sqlx::Row;
+use thiserror::Error;
+
+use crate::outbound::connect::PostgresClient;
+
+/// The migrations of the schema, embedded at build time.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// What the server does with a database whose schema is behind at startup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MigrationMode {
+    /// Apply the pending migrations.
+    #[default]
+    Auto,
+    /// Refuse to start; migrations are applied separately, e.g. by `petstore-admin migrate`.
+    Verify,
+}
+
+#[derive(Debug, Clone, Error)]
+#[error("migration mode {0} is not one of auto, verify")]
+pub struct MigrationModeError(String);
+
+impl FromStr for MigrationMode {
+    type Err = MigrationModeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "verify" => Ok(Self::Verify),
+            other => Err(MigrationModeError(other.to_string())),
+        }
+    }
+}
+
+/// Where the database stands with a migration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but from a script that has since been edited.
+    Modified,
+    /// Started and failed; the schema may be partly migrated.
+    Failed,
+    /// Applied, but unknown to this build, e.g. by a newer release.
+    Unknown,
+}
+
+impl fmt::Display for MigrationState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self {
+            Self::Applied => "applied",
+            Self::Pending => "pending",
+            Self::Modified => "modified",
+            Self::Failed => "failed",
+            Self::Unknown => "unknown",
+        };
+        f.pad(state)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+    pub installed_on: Option<DateTime<Utc>>,
+}
+
+/// A migration recorded as applied in the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+    pub success: bool,
+    pub checksum: Vec<u8>,
+    pub installed_on: DateTime<Utc>,
+}
+
+/// Every migration of this build and of the database, by version.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaStatus {
+    pub migrations: Vec<MigrationStatus>,
+}
+
+impl SchemaStatus {
+    /// Compares the up migrations of `migrator` with those `applied` to the database.
+    pub fn new(migrator: &Migrator, applied: Vec<AppliedMigration>) -> Self {
+        let mut migrations: Vec<MigrationStatus> = migrator
+            .iter()
+            .filter(|m| !m.migration_type.is_down_migration())
+            .map(|m| {
+                let applied = applied.iter().find(|a| a.version == m.version);
+                let state = match applied {
+                    None => MigrationState::Pending,
+                    Some(a) if !a.success => MigrationState::Failed,
+                    Some(a) if a.checksum != *m.checksum => MigrationState::Modified,
+                    Some(_) => MigrationState::Applied,
+                };
+                MigrationStatus {
+                    version: m.version,
+                    description: m.description.to_string(),
+                    state,
+                    installed_on: applied.map(|a| a.installed_on),
+                }
+            })
+            .collect();
+        let unknown = applied
+            .into_iter()
+            .filter(|a| migrator.iter().all(|m| m.version != a.version))
+            .map(|a| MigrationStatus {
+                version: a.version,
+                description: a.description,
+                state: MigrationState::Unknown,
+                installed_on: Some(a.installed_on),
+            });
+        migrations.extend(unknown);
+        migrations.sort_by_key(|m| m.version);
+        Self { migrations }
+    }
+
+    /// Whether every migration of this build, and no other, is applied as is.
+    pub fn is_current(&self) -> bool {
+        self.migrations.iter().all(|m| m.state == MigrationState::Applied)
+    }
+
+    /// Fails, listing the offending migrations, unless the schema [is current](Self::is_current).
+    pub fn ensure_current(&self) -> Result<()> {
+        if self.is_current() {
+            return Ok(());
+        }
+        let offending: Vec<String> = self
+            .migrations
+            .iter()
+            .filter(|m| m.state != MigrationState::Applied)
+            .map(|m| format!("{} {} ({})", m.version, m.description, m.state))
+            .collect();
+        anyhow::bail!(
+            "database schema is not current, run `petstore-admin migrate`: {}",
+            offending.join(", ")
+        )
+    }
+}
+
+impl PostgresClient {
+    /// Applies the migrations not yet applied to the database.
+    pub async fn migrate(&self) -> Result<()> {
+        MIGRATOR.run(self.pool()).await.context("failed to apply migrations")
+    }
+
+    /// Reverts the applied migrations with a version above `target`, latest first; `0`
+    /// reverts them all.
+    pub async fn revert_migrations(&self, target: i64) -> Result<()> {
+        MIGRATOR
+            .undo(self.pool(), target)
+            .await
+            .with_context(|| format!("failed to revert migrations to version {}", target))
+    }
+
+    /// Reports which migrations are applied to the database, without changing it.
+    pub async fn migration_status(&self) -> Result<SchemaStatus> {
+        let table: Option<String> = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations')::TEXT")
+            .fetch_one(self.pool())
+            .await?;
+        if table.is_none() {
+            return Ok(SchemaStatus::new(&MIGRATOR, Vec::new()));
+        }
+        let applied = sqlx::query(
+            "SELECT version, description, success, checksum, installed_on FROM _sqlx_migrations ORDER BY version",
+        )
+        .fetch_all(self.pool())
+        .await?
+        .iter()
+        .map(|row| AppliedMigration {
+            version: row.get("version"),
+            description: row.get("description"),
+            success: row.get("success"),
+            checksum: row.get("checksum"),
+            installed_on: row.get("installed_on"),
+        })
+        .collect();
+        Ok(SchemaStatus::new(&MIGRATOR, applied))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::migrate::MigrationType;
+
+    use super::*;
+
+    fn applied(version: i64, checksum: &[u8], success: bool) -> AppliedMigration {
+        AppliedMigration {
+            version,
+            description: format!("migration {}", version),
+            success,
+            checksum: checksum.to_vec(),
+            installed_on: DateTime::UNIX_EPOCH,
+        }
+    }
+
+    #[test]
+    fn test_every_migration_is_reversible() {
+        let ups: Vec<i64> = MIGRATOR.iter().filter(|m| m.migration_type == MigrationType::ReversibleUp).map(|m| m.version).collect();
+        let downs: Vec<i64> = MIGRATOR.iter().filter(|m| m.migration_type == MigrationType::ReversibleDown).map(|m| m.version).collect();
+
+        assert!(!ups.is_empty());
+        assert_eq!(ups, downs);
+        assert_eq!(ups.len() * 2, MIGRATOR.iter().count(), "simple migrations cannot be reverted");
+    }
+
+    #[test]
+    fn test_schema_status() {
+        let mut ups = MIGRATOR.iter().filter(|m| !m.migration_type.is_down_migration());
+        let (first, second, third) = (ups.next().unwrap(), ups.next().unwrap(), ups.next().unwrap());
+
+        let status = SchemaStatus::new(
+            &MIGRATOR,
+            vec![
+                applied(first.version, &first.checksum, true),
+                applied(second.version, b"edited", true),
+                applied(third.version, &third.checksum, false),
+                applied(9999, b"", true),
+            ],
+        );
+
+        let states: Vec<(i64, MigrationState)> = status.migrations.iter().map(|m| (m.version, m.state)).collect();
+        assert_eq!(states[..3], [
+            (first.version, MigrationState::Applied),
+            (second.version, MigrationState::Modified),
+            (third.version, MigrationState::Failed),
+        ]);
+        assert_eq!(states[3].1, MigrationState::Pending);
+        assert_eq!(states.last(), Some(&(9999, MigrationState::Unknown)));
+        assert!(!status.is_current());
+        let error = status.ensure_current().unwrap_err().to_string();
+        assert!(error.contains(&format!("{} {} (modified)", second.version, second.description)), "{}", error);
+    }
+
+    #[test]
+    fn test_schema_status_current() {
+        let applied_all = MIGRATOR
+            .iter()
+            .filter(|m| !m.migration_type.is_down_migration())
+            .map(|m| applied(m.version, &m.checksum, true))
+            .collect();
+
+        assert!(SchemaStatus::new(&MIGRATOR, applied_all).ensure_current().is_ok());
+        assert!(!SchemaStatus::new(&MIGRATOR, Vec::new()).is_current());
+    }
+
+    #[test]
+    fn test_migration_mode() {
+        assert_eq!(" Verify".parse::<MigrationMode>().unwrap(), MigrationMode::Verify);
+        assert_eq!("auto".parse::<MigrationMode>().unwrap(), MigrationMode::Auto);
+        assert!("never".parse::<MigrationMode>().is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use crate::domain::petstore::models::event::{OutboxError, OutboxEvent, PetEvent};
+use crate::domain::petstore::ports::OutboxRepository;
+use crate::outbound::connect::PostgresClient;
+use crate::outbound::traced::traced;
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, Row};
+use std::time::Duration;
+
+/// Appends `events` to the outbox on `conn`, typically inside the transaction of the change
+/// they describe, which should commit right after.
+///
+/// Appends are serialized until the transaction ends, so that events get their ids in the order
+/// they commit: a relay never sees an event before an earlier one still being committed.
+pub(crate) async fn append_events(conn: &mut PgConnection, events: &[PetEvent]) -> anyhow::Result<()> {
+    if events.is_empty() {
+        return Ok(());
+    }
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('outbox_events_append'))")
+        .execute(traced(&mut *conn))
+        .await?;
+    for event in events {
+        sqlx::query(
+            "INSERT INTO outbox_events (aggregate_id, event_type, payload) VALUES ($1, $2, $3::jsonb)"
+        )
+        .bind(event.pet_id())
+        .bind(event.event_type())
+        .bind(serde_json::to_string(event)?)
+        .execute(traced(&mut *conn))
+        .await?;
+    }
+    Ok(())
+}
+
+impl OutboxRepository for PostgresClient {
+    async fn claim_pending_events(&self, limit: usize, lease: Duration) -> Result<Vec<OutboxEvent>, OutboxError> {
+        let unknown = |e: sqlx::Error| OutboxError::Unknown(anyhow::anyhow!(e));
+        let mut tx = self.pool().begin().await.map_err(unknown)?;
+
+        // Claims are taken one at a time, so that each sees the leases of the previous ones
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext('outbox_events'))")
+            .execute(&mut *tx)
+            .await
+            .map_err(unknown)?;
+        let mut rows = sqlx::query(
+            r#"
+            UPDATE outbox_events SET claimed_until = now() + make_interval(secs => $2)
+            WHERE id IN (SELECT id FROM outbox_events WHERE dispatched_at IS NULL ORDER BY id LIMIT $1)
+            AND NOT EXISTS (SELECT 1 FROM outbox_events WHERE dispatched_at IS NULL AND claimed_until > now())
+            RETURNING id, payload::text AS payload, occurred_at
+            "#
+        )
+        .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+        .bind(lease.as_secs_f64())
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(unknown)?;
+        tx.commit().await.map_err(unknown)?;
+        rows.sort_by_key(|row| row.get::<i64, _>("id"));
+
+        rows.into_iter()
+            .map(|row| {
+                let payload: String = row.get("payload");
+                Ok(OutboxEvent {
+                    id: row.get("id"),
+                    occurred_at: row.get::<DateTime<Utc>, _>("occurred_at"),
+                    event: serde_json::from_str(&payload).map_err(|e| OutboxError::Unknown(anyhow::anyhow!(e)))?,
+                })
+            })
+            .collect()
+    }
+
+    async fn mark_dispatched(&self, event_id: i64) -> Result<(), OutboxError> {
+        sqlx::query("UPDATE outbox_events SET dispatched_at = now() WHERE id = $1 AND dispatched_at IS NULL")
+            .bind(event_id)
+            .execute(self.pool())
+            .await
+            .map_err(|e| OutboxError::Unknown(anyhow::anyhow!(e)))?;
+        Ok(())
+    }
+
+    async fn release_claims(&self, event_ids: &[i64]) -> Result<(), OutboxError> {
+        sqlx::query("UPDATE outbox_events SET claimed_until = NULL WHERE id = ANY($1) AND dispatched_at IS NULL")
+            .bind(event_ids)
+            .execute(self.pool())
+            .await
+            .map_err(|e| OutboxError::Unknown(anyhow::anyhow!(e)))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,656 @@
+/*
+   Module `pet_cache` provides a read-through cache of pets by id in front of any store.
+
+   [CachedPetRepository] implements the repository ports by delegating to the store it wraps,
+   answering [PetRepository::find_pet_by_id] from a bounded LRU cache where it can. Unknown ids
+   are cached too, for a shorter time. Writes through the wrapper invalidate what they may
+   change: the pet itself, or every pet when a category or tag is renamed, merged or deleted.
+   Writes by other processes sharing the store are only seen once the entries expire.
+*/
+
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use lru::LruCache;
+use tokio::time::Instant;
+
+use crate::domain::petstore::models::category::{
+    Category, CreateCategoryError, CreateCategoryRequest, DeleteCategoryError, FindCategoryError,
+    RenameCategoryError, RenameCategoryRequest,
+};
+use crate::domain::petstore::models::event::PetEvent;
+use crate::domain::petstore::models::idempotency::{
+    ClaimIdempotencyKeyRequest, IdempotencyClaim, IdempotencyError, IdempotentResponse,
+};
+use crate::domain::petstore::models::pet::{
+    ChangePetStatusError, ChangePetStatusRequest, CreatePetError, CreatePetRequest, DeletePetError,
+    ListPetsError, ListPetsRequest, Pet, PetHistoryError, PetPage, Status, StatusChange,
+};
+use crate::domain::petstore::models::search::{PetMatch, SearchPetsError, SearchPetsRequest};
+use crate::domain::petstore::models::tag::{
+    DeleteTagError, ListTagsError, MergeTagsError, MergeTagsRequest, RenameTagError,
+    RenameTagRequest, Tag, TagUsage,
+};
+use crate::domain::petstore::models::webhook::{
+    CreateWebhookError, CreateWebhookRequest, DeleteWebhookError, DeliveryOutcome,
+    FindWebhookError, Webhook, WebhookDelivery, WebhookDeliveryError,
+};
+use crate::domain::petstore::ports::{
+    CategoryRepository, IdempotencyRepository, PetRepository, TagRepository, WebhookRepository,
+};
+
+/// How many pets a [CachedPetRepository] keeps and for how long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PetCacheConfig {
+    /// The most ids cached, found or not; the least recently used are evicted first.
+    pub capacity: NonZeroUsize,
+    /// How long a found pet is served from the cache.
+    pub ttl: Duration,
+    /// How long an id without a pet is served from the cache.
+    pub negative_ttl: Duration,
+}
+
+impl PetCacheConfig {
+    pub const DEFAULT_CAPACITY: NonZeroUsize = NonZeroUsize::new(10_000).unwrap();
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(30);
+    pub const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(5);
+}
+
+impl Default for PetCacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: Self::DEFAULT_CAPACITY,
+            ttl: Self::DEFAULT_TTL,
+            negative_ttl: Self::DEFAULT_NEGATIVE_TTL,
+        }
+    }
+}
+
+/// Counters of a [CachedPetRepository] since it was created.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PetCacheStats {
+    /// Lookups answered with a cached pet.
+    pub hits: u64,
+    /// Lookups answered with a cached unknown id.
+    pub negative_hits: u64,
+    /// Lookups passed on to the store, including those of expired entries.
+    pub misses: u64,
+    /// Entries dropped because they had expired.
+    pub expirations: u64,
+    /// Entries dropped to make room for others.
+    pub evictions: u64,
+    /// Entries dropped because of a write.
+    pub invalidations: u64,
+    /// Entries currently cached.
+    pub entries: usize,
+}
+
+impl PetCacheStats {
+    /// The share of lookups answered from the cache, or `0` before the first lookup.
+    pub fn hit_ratio(&self) -> f64 {
+        let lookups = self.hits + self.negative_hits + self.misses;
+        if lookups == 0 {
+            return 0.0;
+        }
+        (self.hits + self.negative_hits) as f64 / lookups as f64
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    pet: Option<Pet>,
+    expires_at: Instant,
+}
+
+#[derive(Debug)]
+struct PetCache {
+    entries: LruCache<i64, CacheEntry>,
+    /// Incremented by every invalidation, so that a lookup racing a write does not cache what
+    /// it read before the write.
+    generation: u64,
+    stats: PetCacheStats,
+}
+
+impl PetCache {
+    fn invalidate(&mut self, pet_id: i64) {
+        if self.entries.pop(&pet_id).is_some() {
+            self.stats.invalidations += 1;
+        }
+        self.generation += 1;
+    }
+
+    fn invalidate_all(&mut self) {
+        self.stats.invalidations += self.entries.len() as u64;
+        self.entries.clear();
+        self.generation += 1;
+    }
+}
+
+/// A [PetRepository] caching the pets found by id in the store `R` it wraps. The other ports
+/// of `R` are passed through, so that the wrapper can stand in for the store.
+#[derive(Debug, Clone)]
+pub struct CachedPetRepository<R> {
+    inner: R,
+    config: PetCacheConfig,
+    cache: Arc<Mutex<PetCache>>,
+}
+
+impl<R> CachedPetRepository<R>
+where
+    R: PetRepository,
+{
+    pub fn new(inner: R, config: PetCacheConfig) -> Self {
+        let cache = PetCache {
+            entries: LruCache::new(config.capacity),
+            generation: 0,
+            stats: PetCacheStats::default(),
+        };
+        Self { inner, config, cache: Arc::new(Mutex::new(cache)) }
+    }
+
+    /// The store this cache wraps.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn config(&self) -> &PetCacheConfig {
+        &self.config
+    }
+
+    /// A snapshot of the counters of this cache, shared by its clones.
+    pub fn stats(&self) -> PetCacheStats {
+        let cache = self.lock();
+        PetCacheStats { entries: cache.entries.len(), ..cache.stats }
+    }
+
+    /// Drops the cached pet with `pet_id`, e.g. after it was changed by another process.
+    pub fn invalidate(&self, pet_id: i64) {
+        self.lock().invalidate(pet_id);
+    }
+
+    /// Drops every cached pet.
+    pub fn invalidate_all(&self) {
+        self.lock().invalidate_all();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PetCache> {
+        // The cache is consistent between statements, so a panic elsewhere cannot corrupt it
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<R> PetRepository for CachedPetRepository<R>
+where
+    R: PetRepository,
+{
+    /// Persists the pet in the wrapped store, dropping the unknown id it may have been cached
+    /// as.
+    async fn add_pet(
+        &self,
+        req: &CreatePetRequest,
+        events: impl FnOnce(&Pet) -> Vec<PetEvent> + Send,
+    ) -> Result<Pet, CreatePetError> {
+        let pet = self.inner.add_pet(req, events).await?;
+        if let Some(id) = pet.id {
+            self.invalidate(id);
+        }
+        Ok(pet)
+    }
+
+    /// Finds the pet in the cache, or in the wrapped store if it is not cached or expired.
+    ///
+    /// # Errors:
+    ///
+    /// - Propagates any [CreatePetError] returned by the wrapped store. Errors are not cached.
+    async fn find_pet_by_id(&self, pet_id: i64) -> Result<Option<Pet>, CreatePetError> {
+        let generation = {
+            let mut cache = self.lock();
+            let now = Instant::now();
+            match cache.entries.get(&pet_id) {
+                Some(entry) if entry.expires_at > now => {
+                    let pet = entry.pet.clone();
+                    match pet {
+                        Some(_) => cache.stats.hits += 1,
+                        None => cache.stats.negative_hits += 1,
+                    }
+                    return Ok(pet);
+                }
+                Some(_) => {
+                    cache.entries.pop(&pet_id);
+                    cache.stats.expirations += 1;
+                }
+                None => {}
+            }
+            cache.stats.misses += 1;
+            cache.generation
+        };
+
+        let pet = self.inner.find_pet_by_id(pet_id).await?;
+
+        let mut cache = self.lock();
+        if cache.generation == generation {
+            let ttl = if pet.is_some() { self.config.ttl } else { self.config.negative_ttl };
+            let entry = CacheEntry { pet: pet.clone(), expires_at: Instant::now() + ttl };
+            if let Some((evicted, _)) = cache.entries.push(pet_id, entry) {
+                if evicted != pet_id {
+                    cache.stats.evictions += 1;
+                }
+            }
+        }
+        Ok(pet)
+    }
+
+    /// Reads the version from the wrapped store, since it guards concurrent changes.
+    async fn find_pet_version(&self, pet_id: i64) -> Result<Option<i64>, CreatePetError> {
+        self.inner.find_pet_version(pet_id).await
+    }
+
+    async fn find_pet_id_by_name(&self, name: &str) -> Result<Option<i64>, CreatePetError> {
+        self.inner.find_pet_id_by_name(name).await
+    }
+
+    async fn list_pets(&self, req: &ListPetsRequest) -> Result<PetPage, ListPetsError> {
+        self.inner.list_pets(req).await
+    }
+
+    async fn search_pets(&self, req: &SearchPetsRequest) -> Result<Vec<PetMatch>, SearchPetsError> {
+        self.inner.search_pets(req).await
+    }
+
+    /// Persists the change in the wrapped store and drops the cached pet, whether or not the
+    /// change was applied: a conflict means the cached pet is stale.
+    async fn update_pet_status(
+        &self,
+        req: &ChangePetStatusRequest,
+        from: &Status,
+        events: &[PetEvent],
+    ) -> Result<Pet, ChangePetStatusError> {
+        let result = self.inner.update_pet_status(req, from, events).await;
+        self.invalidate(req.pet_id);
+        result
+    }
+
+    /// Deletes the pet from the wrapped store and drops the cached pet.
+    async fn delete_pet(&self, pet_id: i64, events: &[PetEvent]) -> Result<(), DeletePetError> {
+        let result = self.inner.delete_pet(pet_id, events).await;
+        self.invalidate(pet_id);
+        result
+    }
+
+    async fn pet_status_history(&self, pet_id: i64) -> Result<Vec<StatusChange>, PetHistoryError> {
+        self.inner.pet_status_history(pet_id).await
+    }
+}
+
+/// Renaming or deleting a category drops every cached pet, since pets embed their category.
+impl<R> CategoryRepository for CachedPetRepository<R>
+where
+    R: PetRepository + CategoryRepository,
+{
+    async fn create_category(&self, req: &CreateCategoryRequest) -> Result<Category, CreateCategoryError> {
+        self.inner.create_category(req).await
+    }
+
+    async fn list_categories(&self) -> Result<Vec<Category>, FindCategoryError> {
+        self.inner.list_categories().await
+    }
+
+    async fn find_category_by_id(&self, category_id: i64) -> Result<Option<Category>, FindCategoryError> {
+        self.inner.find_category_by_id(category_id).await
+    }
+
+    async fn rename_category(&self, req: &RenameCategoryRequest) -> Result<Category, RenameCategoryError> {
+        let category = self.inner.rename_category(req).await?;
+        self.invalidate_all();
+        Ok(category)
+    }
+
+    async fn delete_category(&self, category_id: i64) -> Result<(), DeleteCategoryError> {
+        self.inner.delete_category(category_id).await?;
+        self.invalidate_all();
+        Ok(())
+    }
+}
+
+/// Changing tags drops every cached pet, since pets embed their tags.
+impl<R> TagRepository for CachedPetRepository<R>
+where
+    R: PetRepository + TagRepository,
+{
+    async fn list_tags(&self) -> Result<Vec<TagUsage>, ListTagsError> {
+        self.inner.list_tags().await
+    }
+
+    async fn rename_tag(&self, req: &RenameTagRequest) -> Result<Tag, RenameTagError> {
+        let tag = self.inner.rename_tag(req).await?;
+        self.invalidate_all();
+        Ok(tag)
+    }
+
+    async fn merge_tags(&self, req: &MergeTagsRequest) -> Result<TagUsage, MergeTagsError> {
+        let usage = self.inner.merge_tags(req).await?;
+        self.invalidate_all();
+        Ok(usage)
+    }
+
+    async fn delete_tag(&self, tag_id: i64) -> Result<(), DeleteTagError> {
+        self.inner.delete_tag(tag_id).await?;
+        self.invalidate_all();
+        Ok(())
+    }
+
+    async fn delete_unused_tags(&self) -> Result<Vec<Tag>, DeleteTagError> {
+        let tags = self.inner.delete_unused_tags().await?;
+        if !tags.is_empty() {
+            self.invalidate_all();
+        }
+        Ok(tags)
+    }
+}
+
+impl<R> IdempotencyRepository for CachedPetRepository<R>
+where
+    R: PetRepository + IdempotencyRepository,
+{
+    async fn claim_idempotency_key(&self, req: &ClaimIdempotencyKeyRequest) -> Result<IdempotencyClaim, IdempotencyError> {
+        self.inner.claim_idempotency_key(req).await
+    }
+
+    async fn complete_idempotency_key(&self, claim: &ClaimIdempotencyKeyRequest, response: &IdempotentResponse, window: Duration) -> Result<(), IdempotencyError> {
+        self.inner.complete_idempotency_key(claim, response, window).await
+    }
+
+    async fn release_idempotency_key(&self, claim: &ClaimIdempotencyKeyRequest) -> Result<(), IdempotencyError> {
+        self.inner.release_idempotency_key(claim).await
+    }
+
+    async fn purge_expired_idempotency_keys(&self) -> Result<u64, IdempotencyError> {
+        self.inner.purge_expired_idempotency_keys().await
+    }
+}
+
+impl<R> WebhookRepository for CachedPetRepository<R>
+where
+    R: PetRepository + WebhookRepository,
+{
+    async fn create_webhook(&self, req: &CreateWebhookRequest) -> Result<Webhook, CreateWebhookError> {
+        self.inner.create_webhook(req).await
+    }
+
+    async fn list_webhooks(&self) -> Result<Vec<Webhook>, FindWebhookError> {
+        self.inner.list_webhooks().await
+    }
+
+    async fn find_webhook(&self, webhook_id: i64) -> Result<Option<Webhook>, FindWebhookError> {
+        self.inner.find_webhook(webhook_id).await
+    }
+
+    async fn delete_webhook(&self, webhook_id: i64) -> Result<(), DeleteWebhookError> {
+        self.inner.delete_webhook(webhook_id).await
+    }
+
+    async fn enqueue_deliveries(&self, event_id: i64, event_type: &str, payload: &str) -> Result<usize, WebhookDeliveryError> {
+        self.inner.enqueue_deliveries(event_id, event_type, payload).await
+    }
+
+    async fn claim_due_deliveries(&self, limit: usize, lease: Duration) -> Result<Vec<(Webhook, WebhookDelivery)>, WebhookDeliveryError> {
+        self.inner.claim_due_deliveries(limit, lease).await
+    }
+
+    async fn record_delivery_attempt(&self, delivery_id: i64, outcome: &DeliveryOutcome) -> Result<(), WebhookDeliveryError> {
+        self.inner.record_delivery_attempt(delivery_id, outcome).await
+    }
+
+    async fn list_deliveries(&self, webhook_id: i64) -> Result<Vec<WebhookDelivery>, WebhookDeliveryError> {
+        self.inner.list_deliveries(webhook_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    // A store of pets counting the lookups that reach it
+    #[derive(Debug, Clone, Default)]
+    struct MockStore {
+        pets: Arc<Mutex<HashMap<i64, Pet>>>,
+        categories: Arc<Mutex<HashMap<i64, Category>>>,
+        lookups: Arc<AtomicUsize>,
+    }
+
+    impl MockStore {
+        fn with_pets(ids: &[i64]) -> Self {
+            let store = Self::default();
+            for &id in ids {
+                let mut pet = Pet::new(format!("pet {}", id));
+                pet.id = Some(id);
+                store.pets.lock().unwrap().insert(id, pet);
+            }
+            store
+        }
+
+        fn lookups(&self) -> usize {
+            self.lookups.load(Ordering::SeqCst)
+        }
+
+        fn rename(&self, pet_id: i64, name: &str) {
+            self.pets.lock().unwrap().get_mut(&pet_id).unwrap().name = name.to_string();
+        }
+    }
+
+    impl PetRepository for MockStore {
+        async fn add_pet(
+            &self,
+            req: &CreatePetRequest,
+            events: impl FnOnce(&Pet) -> Vec<PetEvent> + Send,
+        ) -> Result<Pet, CreatePetError> {
+            let mut pet = Pet::new(req.name.clone());
+            pet.id = req.id;
+            events(&pet);
+            self.pets.lock().unwrap().insert(pet.id.unwrap(), pet.clone());
+            Ok(pet)
+        }
+
+        async fn find_pet_by_id(&self, pet_id: i64) -> Result<Option<Pet>, CreatePetError> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            Ok(self.pets.lock().unwrap().get(&pet_id).cloned())
+        }
+
+        async fn find_pet_version(&self, pet_id: i64) -> Result<Option<i64>, CreatePetError> {
+            Ok(self.pets.lock().unwrap().get(&pet_id).map(|p| p.version))
+        }
+
+        async fn find_pet_id_by_name(&self, _name: &str) -> Result<Option<i64>, CreatePetError> {
+            Ok(None)
+        }
+
+        async fn list_pets(&self, _req: &ListPetsRequest) -> Result<PetPage, ListPetsError> {
+            Err(ListPetsError::Unknown(anyhow::anyhow!("not supported")))
+        }
+
+        async fn search_pets(&self, _req: &SearchPetsRequest) -> Result<Vec<PetMatch>, SearchPetsError> {
+            Ok(Vec::new())
+        }
+
+        async fn update_pet_status(
+            &self,
+            req: &ChangePetStatusRequest,
+            _from: &Status,
+            _events: &[PetEvent],
+        ) -> Result<Pet, ChangePetStatusError> {
+            let mut pets = self.pets.lock().unwrap();
+            let pet = pets.get_mut(&req.pet_id).ok_or(ChangePetStatusError::NotFound { id: req.pet_id })?;
+            pet.status = Some(req.status.clone());
+            pet.version += 1;
+            Ok(pet.clone())
+        }
+
+        async fn delete_pet(&self, pet_id: i64, _events: &[PetEvent]) -> Result<(), DeletePetError> {
+            self.pets.lock().unwrap().remove(&pet_id).ok_or(DeletePetError::NotFound { id: pet_id })?;
+            Ok(())
+        }
+
+        async fn pet_status_history(&self, pet_id: i64) -> Result<Vec<StatusChange>, PetHistoryError> {
+            Err(PetHistoryError::NotFound { id: pet_id })
+        }
+    }
+
+    impl CategoryRepository for MockStore {
+        async fn create_category(&self, req: &CreateCategoryRequest) -> Result<Category, CreateCategoryError> {
+            let category = Category::with_values(req.id.unwrap_or(1), req.name.clone());
+            self.categories.lock().unwrap().insert(category.id.unwrap(), category.clone());
+            Ok(category)
+        }
+
+        async fn list_categories(&self) -> Result<Vec<Category>, FindCategoryError> {
+            Ok(self.categories.lock().unwrap().values().cloned().collect())
+        }
+
+        async fn find_category_by_id(&self, category_id: i64) -> Result<Option<Category>, FindCategoryError> {
+            Ok(self.categories.lock().unwrap().get(&category_id).cloned())
+        }
+
+        async fn rename_category(&self, req: &RenameCategoryRequest) -> Result<Category, RenameCategoryError> {
+            let category = Category::with_values(req.id, req.name.clone());
+            self.categories.lock().unwrap().insert(req.id, category.clone());
+            Ok(category)
+        }
+
+        async fn delete_category(&self, category_id: i64) -> Result<(), DeleteCategoryError> {
+            self.categories.lock().unwrap().remove(&category_id);
+            Ok(())
+        }
+    }
+
+    fn config(capacity: usize) -> PetCacheConfig {
+        PetCacheConfig {
+            capacity: NonZeroUsize::new(capacity).unwrap(),
+            ttl: Duration::from_secs(60),
+            negative_ttl: Duration::from_secs(10),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_pet_by_id_read_through() {
+        let store = MockStore::with_pets(&[1]);
+        let cache = CachedPetRepository::new(store.clone(), config(10));
+
+        let first = cache.find_pet_by_id(1).await.unwrap().unwrap();
+        let second = cache.find_pet_by_id(1).await.unwrap().unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(store.lookups(), 1);
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+        assert_eq!(stats.hit_ratio(), 0.5);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_entries_expire() {
+        let store = MockStore::with_pets(&[1]);
+        let cache = CachedPetRepository::new(store.clone(), config(10));
+
+        cache.find_pet_by_id(1).await.unwrap();
+        store.rename(1, "renamed elsewhere");
+        tokio::time::advance(Duration::from_secs(59)).await;
+        assert_eq!(cache.find_pet_by_id(1).await.unwrap().unwrap().name, "pet 1");
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(cache.find_pet_by_id(1).await.unwrap().unwrap().name, "renamed elsewhere");
+        assert_eq!(store.lookups(), 2);
+        assert_eq!(cache.stats().expirations, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_unknown_ids_are_cached_briefly() {
+        let store = MockStore::with_pets(&[]);
+        let cache = CachedPetRepository::new(store.clone(), config(10));
+
+        assert!(cache.find_pet_by_id(7).await.unwrap().is_none());
+        assert!(cache.find_pet_by_id(7).await.unwrap().is_none());
+        assert_eq!(store.lookups(), 1);
+        assert_eq!(cache.stats().negative_hits, 1);
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert!(cache.find_pet_by_id(7).await.unwrap().is_none());
+        assert_eq!(store.lookups(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_add_pet_invalidates_unknown_id() {
+        let store = MockStore::with_pets(&[]);
+        let cache = CachedPetRepository::new(store.clone(), config(10));
+        assert!(cache.find_pet_by_id(7).await.unwrap().is_none());
+
+        let req = CreatePetRequest::new(Some(7), "Rex".to_string(), None, Vec::new(), Vec::new(), None);
+        cache.add_pet(&req, |_| Vec::new()).await.unwrap();
+
+        assert_eq!(cache.find_pet_by_id(7).await.unwrap().unwrap().name, "Rex");
+        assert_eq!(cache.stats().invalidations, 1);
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_are_evicted() {
+        let store = MockStore::with_pets(&[1, 2, 3]);
+        let cache = CachedPetRepository::new(store.clone(), config(2));
+
+        for id in [1, 2, 1, 3] {
+            cache.find_pet_by_id(id).await.unwrap();
+        }
+        assert_eq!(store.lookups(), 3);
+        assert_eq!(cache.stats().evictions, 1);
+
+        cache.find_pet_by_id(1).await.unwrap();
+        assert_eq!(store.lookups(), 3, "1 was used more recently than 2");
+        cache.find_pet_by_id(2).await.unwrap();
+        assert_eq!(store.lookups(), 4);
+        assert_eq!(cache.stats().entries, 2);
+    }
+
+    #[tokio::test]
+    async fn test_update_pet_status_invalidates_pet() {
+        let store = MockStore::with_pets(&[1, 2]);
+        let cache = CachedPetRepository::new(store.clone(), config(10));
+        cache.find_pet_by_id(1).await.unwrap();
+        cache.find_pet_by_id(2).await.unwrap();
+
+        let req = ChangePetStatusRequest::new(1, Status::Sold);
+        cache.update_pet_status(&req, &Status::Available, &[]).await.unwrap();
+
+        let pet = cache.find_pet_by_id(1).await.unwrap().unwrap();
+        assert_eq!((pet.status, pet.version), (Some(Status::Sold), Pet::INITIAL_VERSION + 1));
+        cache.find_pet_by_id(2).await.unwrap();
+        assert_eq!(store.lookups(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_delete_pet_invalidates_pet() {
+        let store = MockStore::with_pets(&[1]);
+        let cache = CachedPetRepository::new(store.clone(), config(10));
+        cache.find_pet_by_id(1).await.unwrap();
+
+        cache.delete_pet(1, &[]).await.unwrap();
+
+        assert!(cache.find_pet_by_id(1).await.unwrap().is_none());
+        assert_eq!(store.lookups(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_rename_category_invalidates_all() {
+        let store = MockStore::with_pets(&[1, 2]);
+        let cache = CachedPetRepository::new(store.clone(), config(10));
+        cache.find_pet_by_id(1).await.unwrap();
+        cache.find_pet_by_id(2).await.unwrap();
+
+        cache.rename_category(&RenameCategoryRequest::new(1, "Dogs".to_string())).await.unwrap();
+
+        assert_eq!(cache.stats().entries, 0);
+        assert_eq!(cache.stats().invalidations, 2);
+        cache.find_pet_by_id(1).await.unwrap();
+        assert_eq!(store.lookups(), 3);
+    }
+}
@@ -1,9 +1,21 @@
 use crate::domain::petstore::ports::PetRepository;
-use crate::domain::petstore::models::pet::{Pet, CreatePetRequest, CreatePetError, Status};
+use std::collections::HashMap;
+
+use crate::domain::petstore::models::pet::{
+    ChangePetStatusError, ChangePetStatusRequest, Pet, CreatePetRequest, CreatePetError,
+    DeletePetError, InvalidTransition, ListPetsRequest, ListPetsError, PetHistoryError, PetPage, PetSortField,
+    SortDirection, Status, StatusChange,
+};
 use crate::domain::petstore::models::category::Category;
+use crate::domain::petstore::models::search::{PetMatch, SearchPetsError, SearchPetsRequest};
+use crate::domain::petstore::models::event::PetEvent;
+use crate::domain::petstore::models::id::{IdKind, IdStrategy};
 use crate::domain::petstore::models::tag::Tag;
 use crate::outbound::connect::PostgresClient;
-use sqlx::Row;
+use crate::outbound::outbox_repository::append_events;
+use crate::outbound::traced::traced;
+use sqlx::postgres::PgRow;
+use sqlx::{Postgres, QueryBuilder, Row};
 
 #[derive(serde::Deserialize)]
 struct TagData {
@@ -12,11 +24,22 @@ struct TagData {
 }
 
 impl PetRepository for PostgresClient {
-    async fn add_pet(&self, req: &CreatePetRequest) -> Result<Pet, CreatePetError> {
+    #[tracing::instrument(skip_all)]
+    async fn add_pet(
+        &self,
+        req: &CreatePetRequest,
+        events: impl FnOnce(&Pet) -> Vec<PetEvent> + Send,
+    ) -> Result<Pet, CreatePetError> {
+        let mut tx = self
+            .pool()
+            .begin()
+            .await
+            .map_err(|e| CreatePetError::Unknown(anyhow::anyhow!(e)))?;
+
         // Check for duplicate pet name
         let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM pets WHERE name = $1)")
             .bind(&req.name)
-            .fetch_one(self.pool())
+            .fetch_one(traced(&mut *tx))
             .await
             .map_err(|e| CreatePetError::Unknown(anyhow::anyhow!(e)))?;
 
@@ -24,30 +47,47 @@ impl PetRepository for PostgresClient {
             return Err(CreatePetError::Duplicate { name: req.name.clone() });
         }
 
-        // Insert category if provided
-        let category_id = if let Some(category) = &req.category {
-            let cat_id: i64 = sqlx::query_scalar(
-                "INSERT INTO categories (id, name) VALUES ($1, $2) ON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name RETURNING id"
-            )
-            .bind(category.id)
-            .bind(&category.name)
-            .fetch_one(self.pool())
-            .await
-            .map_err(|e| CreatePetError::Unknown(anyhow::anyhow!(e)))?;
-            Some(cat_id)
+        // Resolve the referenced category; categories are managed on their own
+        let category = if let Some(category) = &req.category {
+            let category_id = category.id.ok_or(CreatePetError::CategoryIdRequired)?;
+            let name: Option<String> = sqlx::query_scalar("SELECT name FROM categories WHERE id = $1")
+                .bind(category_id)
+                .fetch_optional(traced(&mut *tx))
+                .await
+                .map_err(|e| CreatePetError::Unknown(anyhow::anyhow!(e)))?;
+            match name {
+                Some(name) => Some(Category::with_values(category_id, name)),
+                None => return Err(CreatePetError::UnknownCategory { id: category_id }),
+            }
         } else {
             None
         };
+        let category_id = category.as_ref().and_then(|c| c.id);
 
         // Insert the pet
-        let pet_id: i64 = sqlx::query_scalar(
-            "INSERT INTO pets (id, name, category_id, status) VALUES ($1, $2, $3, $4) RETURNING id"
+        let pet_id = assign_id(traced(&mut *tx), self.id_strategy(), "pets_id_seq", req.id)
+            .await
+            .map_err(|e| CreatePetError::Unknown(anyhow::anyhow!(e)))?;
+        sqlx::query("INSERT INTO pets (id, name, category_id, status) VALUES ($1, $2, $3, $4)")
+            .bind(pet_id)
+            .bind(&req.name)
+            .bind(category_id)
+            .bind(req.status.as_ref().map(|s| s.to_str()))
+            .execute(traced(&mut *tx))
+            .await
+            .map_err(|e| match unique_violation(&e).as_deref() {
+                Some("pets_pkey") => CreatePetError::DuplicateId { id: pet_id },
+                // A pet of the same name created concurrently
+                Some(_) => CreatePetError::Duplicate { name: req.name.clone() },
+                None => CreatePetError::Unknown(anyhow::anyhow!(e)),
+            })?;
+
+        // Record the initial status as the first history entry
+        sqlx::query(
+            "INSERT INTO pet_status_history (pet_id, to_status) SELECT id, status FROM pets WHERE id = $1"
         )
-        .bind(req.id)
-        .bind(&req.name)
-        .bind(category_id)
-        .bind(req.status.as_ref().map(|s| s.to_str()))
-        .fetch_one(self.pool())
+        .bind(pet_id)
+        .execute(traced(&mut *tx))
         .await
         .map_err(|e| CreatePetError::Unknown(anyhow::anyhow!(e)))?;
 
@@ -57,66 +97,95 @@ impl PetRepository for PostgresClient {
                 sqlx::query("INSERT INTO pet_photos (pet_id, url) VALUES ($1, $2)")
                     .bind(pet_id)
                     .bind(url)
-                    .execute(self.pool())
+                    .execute(traced(&mut *tx))
                     .await
                     .map_err(|e| CreatePetError::Unknown(anyhow::anyhow!(e)))?;
             }
         }
 
-        // Insert tags if any
+        // Insert tags if any, keeping the ids they are stored under
+        let mut tags = Vec::with_capacity(req.tags.len());
         if !req.tags.is_empty() {
             for tag in &req.tags {
-                // First ensure tag exists
-                let tag_id: i64 = sqlx::query_scalar(
-                    "INSERT INTO tags (id, name) VALUES ($1, $2) ON CONFLICT (name) DO UPDATE SET name = EXCLUDED.name RETURNING id"
-                )
-                .bind(tag.id)
-                .bind(&tag.name)
-                .fetch_one(self.pool())
-                .await
-                .map_err(|e| CreatePetError::Unknown(anyhow::anyhow!(e)))?;
+                // First ensure tag exists, taking an id only for a new one
+                let existing: Option<i64> = sqlx::query_scalar("SELECT id FROM tags WHERE name = $1")
+                    .bind(&tag.name)
+                    .fetch_optional(traced(&mut *tx))
+                    .await
+                    .map_err(|e| CreatePetError::Unknown(anyhow::anyhow!(e)))?;
+                let tag_id = match existing {
+                    Some(tag_id) => tag_id,
+                    None if tag.id.is_none() && self.id_strategy() == IdStrategy::ClientSupplied => {
+                        return Err(CreatePetError::IdRequired { kind: IdKind::Tag });
+                    }
+                    None => {
+                        let tag_id = assign_id(traced(&mut *tx), self.id_strategy(), "tags_id_seq", tag.id)
+                            .await
+                            .map_err(|e| CreatePetError::Unknown(anyhow::anyhow!(e)))?;
+                        // A tag of the same name created concurrently is reused
+                        sqlx::query_scalar(
+                            "INSERT INTO tags (id, name) VALUES ($1, $2) ON CONFLICT (name) DO UPDATE SET name = EXCLUDED.name RETURNING id"
+                        )
+                        .bind(tag_id)
+                        .bind(&tag.name)
+                        .fetch_one(traced(&mut *tx))
+                        .await
+                        .map_err(|e| match unique_violation(&e).as_deref() {
+                            Some("tags_pkey") => CreatePetError::DuplicateTagId { id: tag_id },
+                            _ => CreatePetError::Unknown(anyhow::anyhow!(e)),
+                        })?
+                    }
+                };
 
                 // Then link tag to pet
                 sqlx::query("INSERT INTO pet_tags (pet_id, tag_id) VALUES ($1, $2)")
                     .bind(pet_id)
                     .bind(tag_id)
-                    .execute(self.pool())
+                    .execute(traced(&mut *tx))
                     .await
                     .map_err(|e| CreatePetError::Unknown(anyhow::anyhow!(e)))?;
+                tags.push(Tag { id: Some(tag_id), name: tag.name.clone() });
             }
         }
 
         // Create and return the pet
         let mut pet = Pet::new(req.name.clone());
         pet.id = Some(pet_id);
-        if let Some(category) = &req.category {
-            pet.set_category(category.clone());
+        if let Some(category) = category {
+            pet.set_category(category);
         }
         for url in &req.photo_urls {
             pet.add_photo(url.clone());
         }
-        for tag in &req.tags {
-            pet.add_tag(tag.clone());
+        for tag in tags {
+            pet.add_tag(tag);
         }
         if let Some(status) = &req.status {
             pet.set_status(status.clone());
         }
 
+        // The events commit or roll back together with the pet
+        append_events(&mut tx, &events(&pet)).await?;
+        tx.commit()
+            .await
+            .map_err(|e| CreatePetError::Unknown(anyhow::anyhow!(e)))?;
+
         Ok(pet)
     }
 
+    #[tracing::instrument(skip_all, fields(pet_id = pet_id))]
     async fn find_pet_by_id(&self, pet_id: i64) -> Result<Option<Pet>, CreatePetError> {
         // Get pet details
         let rows = sqlx::query(
             r#"
-            SELECT p.id, p.name, p.status, c.id as category_id, c.name as category_name
+            SELECT p.id, p.name, p.status, p.version, c.id as category_id, c.name as category_name
             FROM pets p
             LEFT JOIN categories c ON p.category_id = c.id
             WHERE p.id = $1
             "#
         )
         .bind(pet_id)
-        .fetch_optional(self.pool())
+        .fetch_optional(traced(self.pool()))
         .await
         .map_err(|e| CreatePetError::Unknown(anyhow::anyhow!(e)))?;
 
@@ -127,13 +196,8 @@ impl PetRepository for PostgresClient {
         // Build the pet
         let mut pet = Pet::new(row.get::<String, _>("name"));
         pet.id = Some(row.get::<i64, _>("id"));
-        let status = match row.get::<String, _>("status").as_str() {
-            "available" => Status::Available,
-            "pending" => Status::Pending,
-            "sold" => Status::Sold,
-            _ => Status::Available,
-        };
-        pet.set_status(status);
+        pet.set_status(status_from_db(&row.get::<String, _>("status")));
+        pet.version = row.get("version");
 
         // Set category if available
         if let (Ok(category_id), Ok(category_name)) = (
@@ -148,7 +212,7 @@ impl PetRepository for PostgresClient {
             "SELECT url FROM pet_photos WHERE pet_id = $1"
         )
         .bind(pet_id)
-        .fetch_all(self.pool())
+        .fetch_all(traced(self.pool()))
         .await
         .map_err(|e| CreatePetError::Unknown(anyhow::anyhow!(e)))?;
 
@@ -162,7 +226,7 @@ impl PetRepository for PostgresClient {
             "#
         )
         .bind(pet_id)
-        .fetch_all(self.pool())
+        .fetch_all(traced(self.pool()))
         .await
         .map_err(|e| CreatePetError::Unknown(anyhow::anyhow!(e)))?;
 
@@ -183,4 +247,393 @@ impl PetRepository for PostgresClient {
 
         Ok(Some(pet))
     }
+
+    #[tracing::instrument(skip_all, fields(pet_id = pet_id))]
+    async fn find_pet_version(&self, pet_id: i64) -> Result<Option<i64>, CreatePetError> {
+        sqlx::query_scalar("SELECT version FROM pets WHERE id = $1")
+            .bind(pet_id)
+            .fetch_optional(traced(self.pool()))
+            .await
+            .map_err(|e| CreatePetError::Unknown(anyhow::anyhow!(e)))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn find_pet_id_by_name(&self, name: &str) -> Result<Option<i64>, CreatePetError> {
+        sqlx::query_scalar("SELECT id FROM pets WHERE name = $1")
+            .bind(name)
+            .fetch_optional(traced(self.pool()))
+            .await
+            .map_err(|e| CreatePetError::Unknown(anyhow::anyhow!(e)))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_pets(&self, req: &ListPetsRequest) -> Result<PetPage, ListPetsError> {
+        let mut query: QueryBuilder<Postgres> = QueryBuilder::new(
+            r#"
+            SELECT p.id, p.name, p.status, p.version, c.id as category_id, c.name as category_name
+            FROM pets p
+            LEFT JOIN categories c ON p.category_id = c.id
+            WHERE TRUE
+            "#,
+        );
+
+        if let Some(status) = &req.status {
+            query.push(" AND p.status = ").push_bind(status.to_str().to_string());
+        }
+        if let Some(category_id) = req.category_id {
+            query.push(" AND p.category_id = ").push_bind(category_id);
+        }
+
+        // Keyset condition: continue strictly after the cursor in the requested order.
+        let (cmp, dir) = match req.direction {
+            SortDirection::Asc => (">", "ASC"),
+            SortDirection::Desc => ("<", "DESC"),
+        };
+        if let Some(cursor) = &req.cursor {
+            match req.sort {
+                PetSortField::Id => {
+                    query.push(format!(" AND p.id {} ", cmp)).push_bind(cursor.id);
+                }
+                PetSortField::Name => {
+                    query
+                        .push(format!(" AND (p.name, p.id) {} (", cmp))
+                        .push_bind(cursor.name.clone())
+                        .push(", ")
+                        .push_bind(cursor.id)
+                        .push(")");
+                }
+            }
+        }
+
+        match req.sort {
+            PetSortField::Id => query.push(format!(" ORDER BY p.id {}", dir)),
+            PetSortField::Name => query.push(format!(" ORDER BY p.name {dir}, p.id {dir}")),
+        };
+        // Fetch one extra row to find out whether another page follows.
+        query.push(" LIMIT ").push_bind(i64::from(req.limit) + 1);
+
+        let rows = query
+            .build()
+            .fetch_all(traced(self.pool()))
+            .await
+            .map_err(|e| ListPetsError::Unknown(anyhow::anyhow!(e)))?;
+
+        // Drop the extra row before loading photos and tags, which only the page needs
+        let pets: Vec<Pet> = rows.iter().map(pet_from_row).collect();
+        let mut page = PetPage::from_overfetched(pets, req.limit);
+        self.attach_photos_and_tags(&mut page.pets)
+            .await
+            .map_err(|e| ListPetsError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(page)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn search_pets(&self, req: &SearchPetsRequest) -> Result<Vec<PetMatch>, SearchPetsError> {
+        // Terms match as substrings through the trigram index on the search text, and rank by
+        // the weighted prefix matches in the search vector plus the similarity of the words.
+        let patterns: Vec<String> = req
+            .query
+            .terms()
+            .iter()
+            .map(|term| format!("%{}%", escape_like(term)))
+            .collect();
+        let prefixes = req
+            .query
+            .terms()
+            .iter()
+            .map(|term| format!("'{}':*", term.replace('\\', "\\\\").replace('\'', "''")))
+            .collect::<Vec<_>>()
+            .join(" | ");
+
+        let rows = sqlx::query(
+            r#"
+            SELECT p.id, p.name, p.status, p.version, c.id as category_id, c.name as category_name,
+                (ts_rank(p.search_vector, to_tsquery('simple', $2)) + word_similarity($3, p.search_text))::REAL AS rank
+            FROM pets p
+            LEFT JOIN categories c ON p.category_id = c.id
+            WHERE p.search_text LIKE ALL($1)
+            ORDER BY rank DESC, p.id
+            LIMIT $4
+            "#
+        )
+        .bind(&patterns)
+        .bind(prefixes)
+        .bind(req.query.text())
+        .bind(i64::from(req.limit))
+        .fetch_all(traced(self.pool()))
+        .await
+        .map_err(|e| SearchPetsError::Unknown(anyhow::anyhow!(e)))?;
+
+        let mut pets: Vec<Pet> = rows.iter().map(pet_from_row).collect();
+        self.attach_photos_and_tags(&mut pets)
+            .await
+            .map_err(|e| SearchPetsError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(pets
+            .into_iter()
+            .zip(&rows)
+            .map(|(pet, row)| PetMatch { pet, rank: row.get("rank") })
+            .collect())
+    }
+
+    #[tracing::instrument(skip_all, fields(pet_id = req.pet_id))]
+    async fn update_pet_status(
+        &self,
+        req: &ChangePetStatusRequest,
+        from: &Status,
+        events: &[PetEvent],
+    ) -> Result<Pet, ChangePetStatusError> {
+        let unknown = |e: sqlx::Error| ChangePetStatusError::Unknown(anyhow::anyhow!(e));
+
+        let mut tx = self.pool().begin().await.map_err(unknown)?;
+
+        // Compare-and-set: only move the pet if nobody changed it in the meantime
+        let updated = sqlx::query(
+            r#"
+            UPDATE pets SET status = $3, version = version + 1
+            WHERE id = $1 AND status = $2 AND ($4::BIGINT IS NULL OR version = $4)
+            "#
+        )
+        .bind(req.pet_id)
+        .bind(from.to_str())
+        .bind(req.status.to_str())
+        .bind(req.expected_version)
+        .execute(traced(&mut *tx))
+        .await
+        .map_err(unknown)?;
+
+        if updated.rows_affected() == 0 {
+            let current = sqlx::query("SELECT status, version FROM pets WHERE id = $1")
+                .bind(req.pet_id)
+                .fetch_optional(traced(&mut *tx))
+                .await
+                .map_err(unknown)?;
+            let Some(current) = current else {
+                return Err(ChangePetStatusError::NotFound { id: req.pet_id });
+            };
+            let version: i64 = current.get("version");
+            return match req.expected_version {
+                Some(expected) if expected != version => Err(ChangePetStatusError::Conflict {
+                    id: req.pet_id,
+                    expected,
+                    actual: version,
+                }),
+                _ => Err(InvalidTransition {
+                    from: status_from_db(&current.get::<String, _>("status")),
+                    to: req.status.clone(),
+                }
+                .into()),
+            };
+        }
+
+        // The history entry commits or rolls back together with the status change
+        sqlx::query(
+            "INSERT INTO pet_status_history (pet_id, from_status, to_status, actor) VALUES ($1, $2, $3, $4)"
+        )
+        .bind(req.pet_id)
+        .bind(from.to_str())
+        .bind(req.status.to_str())
+        .bind(&req.actor)
+        .execute(traced(&mut *tx))
+        .await
+        .map_err(unknown)?;
+        append_events(&mut tx, events).await?;
+
+        tx.commit().await.map_err(unknown)?;
+
+        self.find_pet_by_id(req.pet_id)
+            .await
+            .map_err(|e| ChangePetStatusError::Unknown(anyhow::anyhow!(e)))?
+            .ok_or(ChangePetStatusError::NotFound { id: req.pet_id })
+    }
+
+    #[tracing::instrument(skip_all, fields(pet_id = pet_id))]
+    async fn delete_pet(&self, pet_id: i64, events: &[PetEvent]) -> Result<(), DeletePetError> {
+        let unknown = |e: sqlx::Error| DeletePetError::Unknown(anyhow::anyhow!(e));
+
+        let mut tx = self.pool().begin().await.map_err(unknown)?;
+
+        // Photos, tag links and history entries go with the pet
+        let deleted = sqlx::query("DELETE FROM pets WHERE id = $1")
+            .bind(pet_id)
+            .execute(traced(&mut *tx))
+            .await
+            .map_err(unknown)?;
+        if deleted.rows_affected() == 0 {
+            return Err(DeletePetError::NotFound { id: pet_id });
+        }
+        append_events(&mut tx, events).await?;
+
+        tx.commit().await.map_err(unknown)
+    }
+
+    #[tracing::instrument(skip_all, fields(pet_id = pet_id))]
+    async fn pet_status_history(&self, pet_id: i64) -> Result<Vec<StatusChange>, PetHistoryError> {
+        let unknown = |e: sqlx::Error| PetHistoryError::Unknown(anyhow::anyhow!(e));
+
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM pets WHERE id = $1)")
+            .bind(pet_id)
+            .fetch_one(traced(self.pool()))
+            .await
+            .map_err(unknown)?;
+        if !exists {
+            return Err(PetHistoryError::NotFound { id: pet_id });
+        }
+
+        let rows = sqlx::query(
+            r#"
+            SELECT from_status, to_status, actor, changed_at
+            FROM pet_status_history
+            WHERE pet_id = $1
+            ORDER BY id
+            "#
+        )
+        .bind(pet_id)
+        .fetch_all(traced(self.pool()))
+        .await
+        .map_err(unknown)?;
+
+        Ok(rows
+            .iter()
+            .map(|row| StatusChange {
+                pet_id,
+                from: row.get::<Option<String>, _>("from_status").as_deref().map(status_from_db),
+                to: status_from_db(&row.get::<String, _>("to_status")),
+                actor: row.get("actor"),
+                changed_at: row.get("changed_at"),
+            })
+            .collect())
+    }
+}
+
+impl PostgresClient {
+    /// Load the photo urls and tags of `pets` in one query each.
+    async fn attach_photos_and_tags(&self, pets: &mut [Pet]) -> Result<(), sqlx::Error> {
+        let pet_ids: Vec<i64> = pets.iter().filter_map(|p| p.id).collect();
+
+        let photo_rows = sqlx::query(
+            "SELECT pet_id, url FROM pet_photos WHERE pet_id = ANY($1) ORDER BY id"
+        )
+        .bind(&pet_ids)
+        .fetch_all(traced(self.pool()))
+        .await?;
+
+        let tag_rows = sqlx::query(
+            r#"
+            SELECT pt.pet_id, t.id, t.name
+            FROM pet_tags pt
+            JOIN tags t ON t.id = pt.tag_id
+            WHERE pt.pet_id = ANY($1)
+            ORDER BY t.id
+            "#
+        )
+        .bind(&pet_ids)
+        .fetch_all(traced(self.pool()))
+        .await?;
+
+        let mut photos: HashMap<i64, Vec<String>> = HashMap::new();
+        for row in photo_rows {
+            photos.entry(row.get("pet_id")).or_default().push(row.get("url"));
+        }
+        let mut tags: HashMap<i64, Vec<Tag>> = HashMap::new();
+        for row in tag_rows {
+            tags.entry(row.get("pet_id"))
+                .or_default()
+                .push(Tag::with_values(row.get("id"), row.get("name")));
+        }
+
+        for pet in pets.iter_mut() {
+            let id = pet.id.unwrap_or_default();
+            for url in photos.remove(&id).unwrap_or_default() {
+                pet.add_photo(url);
+            }
+            for tag in tags.remove(&id).unwrap_or_default() {
+                pet.add_tag(tag);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A pet without photos and tags from a row of `id`, `name`, `status`, `version`,
+/// `category_id` and `category_name`.
+fn pet_from_row(row: &PgRow) -> Pet {
+    let mut pet = Pet::with_id(row.get::<i64, _>("id"), row.get::<String, _>("name"));
+    pet.set_status(status_from_db(&row.get::<String, _>("status")));
+    pet.version = row.get("version");
+    if let (Ok(category_id), Ok(category_name)) = (
+        row.try_get::<i64, _>("category_id"),
+        row.try_get::<String, _>("category_name")
+    ) {
+        pet.set_category(Category::with_values(category_id, category_name));
+    }
+    pet
+}
+
+/// Escapes the wildcards of a `LIKE` pattern, so that `text` only matches itself.
+fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn status_from_db(status: &str) -> Status {
+    match status {
+        "available" => Status::Available,
+        "pending" => Status::Pending,
+        "sold" => Status::Sold,
+        _ => Status::Available,
+    }
+}
+
+/// Returns the id to insert a row under: the next value of `sequence` when `id` is omitted, or
+/// `id` itself. Unless ids are [snowflakes](IdStrategy::Snowflake), `sequence` is first
+/// advanced past a supplied id, so rows created later without an id do not collide with it.
+///
+/// The sequence only ever moves forward: it is set to a supplied id only if that id is at least
+/// the value `nextval` hands out, which accounts for a sequence that was never called and for
+/// values taken concurrently. A supplied id below it costs one unused sequence value.
+pub(crate) async fn assign_id<'c, E>(
+    executor: E,
+    strategy: IdStrategy,
+    sequence: &str,
+    id: Option<i64>,
+) -> Result<i64, sqlx::Error>
+where
+    E: sqlx::Executor<'c, Database = Postgres>,
+{
+    match id {
+        // Sequences only hand out positive ids, so others can never collide, and snowflakes
+        // are far beyond any value the sequence reaches
+        Some(id) if id <= 0 || strategy == IdStrategy::Snowflake => Ok(id),
+        Some(id) => {
+            sqlx::query(
+                "SELECT CASE WHEN nextval($1::regclass) <= $2 THEN setval($1::regclass, $2) END"
+            )
+                .bind(sequence)
+                .bind(id)
+                .execute(executor)
+                .await?;
+            Ok(id)
+        }
+        None => sqlx::query_scalar("SELECT nextval($1::regclass)")
+            .bind(sequence)
+            .fetch_one(executor)
+            .await,
+    }
+}
+
+/// Returns the name of the violated constraint if `e` is a unique violation.
+pub(crate) fn unique_violation(e: &sqlx::Error) -> Option<String> {
+    let db_error = e.as_database_error()?;
+    if db_error.code().as_deref() != Some("23505") {
+        return None;
+    }
+    Some(db_error.constraint().unwrap_or_default().to_string())
 }
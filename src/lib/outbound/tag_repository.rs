@@ -0,0 +1,156 @@
+use crate::domain::petstore::models::tag::{
+    DeleteTagError, ListTagsError, MergeTagsError, MergeTagsRequest, RenameTagError,
+    RenameTagRequest, Tag, TagUsage,
+};
+use crate::domain::petstore::ports::TagRepository;
+use crate::outbound::connect::PostgresClient;
+use crate::outbound::repository::unique_violation;
+use sqlx::Row;
+
+impl TagRepository for PostgresClient {
+    #[tracing::instrument(skip_all)]
+    async fn list_tags(&self) -> Result<Vec<TagUsage>, ListTagsError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT t.id, t.name, COUNT(pt.pet_id) AS pet_count
+            FROM tags t
+            LEFT JOIN pet_tags pt ON pt.tag_id = t.id
+            GROUP BY t.id, t.name
+            ORDER BY t.id
+            "#
+        )
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| ListTagsError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(rows
+            .iter()
+            .map(|row| TagUsage::new(Tag::with_values(row.get("id"), row.get("name")), row.get("pet_count")))
+            .collect())
+    }
+
+    #[tracing::instrument(skip_all, fields(tag_id = req.id))]
+    async fn rename_tag(&self, req: &RenameTagRequest) -> Result<Tag, RenameTagError> {
+        let row = sqlx::query("UPDATE tags SET name = $2 WHERE id = $1 RETURNING id, name")
+            .bind(req.id)
+            .bind(&req.name)
+            .fetch_optional(self.pool())
+            .await
+            .map_err(|e| match unique_violation(&e) {
+                Some(_) => RenameTagError::Duplicate { name: req.name.clone() },
+                None => RenameTagError::Unknown(anyhow::anyhow!(e)),
+            })?;
+
+        row.map(|row| Tag::with_values(row.get("id"), row.get("name")))
+            .ok_or(RenameTagError::NotFound { id: req.id })
+    }
+
+    #[tracing::instrument(skip_all, fields(source_id = req.source_id, target_id = req.target_id))]
+    async fn merge_tags(&self, req: &MergeTagsRequest) -> Result<TagUsage, MergeTagsError> {
+        let unknown = |e: sqlx::Error| MergeTagsError::Unknown(anyhow::anyhow!(e));
+        let mut tx = self.pool().begin().await.map_err(unknown)?;
+
+        // Lock both tags so concurrent merges or renames cannot interleave
+        let rows = sqlx::query("SELECT id, name FROM tags WHERE id = ANY($1) FOR UPDATE")
+            .bind(vec![req.source_id, req.target_id])
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(unknown)?;
+        for id in [req.source_id, req.target_id] {
+            if !rows.iter().any(|row| row.get::<i64, _>("id") == id) {
+                return Err(MergeTagsError::NotFound { id });
+            }
+        }
+        let target = rows
+            .iter()
+            .find(|row| row.get::<i64, _>("id") == req.target_id)
+            .map(|row| Tag::with_values(row.get("id"), row.get("name")))
+            .unwrap_or_default();
+
+        // The pets of the source change representation, so their ETags must stop matching
+        sqlx::query("UPDATE pets SET version = version + 1 WHERE id IN (SELECT pet_id FROM pet_tags WHERE tag_id = $1)")
+            .bind(req.source_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(unknown)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO pet_tags (pet_id, tag_id)
+            SELECT pet_id, $2 FROM pet_tags WHERE tag_id = $1
+            ON CONFLICT DO NOTHING
+            "#
+        )
+        .bind(req.source_id)
+        .bind(req.target_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(unknown)?;
+
+        // Associations of the source go with it through ON DELETE CASCADE
+        sqlx::query("DELETE FROM tags WHERE id = $1")
+            .bind(req.source_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(unknown)?;
+
+        let pet_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pet_tags WHERE tag_id = $1")
+            .bind(req.target_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(unknown)?;
+
+        tx.commit().await.map_err(unknown)?;
+        Ok(TagUsage::new(target, pet_count))
+    }
+
+    #[tracing::instrument(skip_all, fields(tag_id = tag_id))]
+    async fn delete_tag(&self, tag_id: i64) -> Result<(), DeleteTagError> {
+        let unknown = |e: sqlx::Error| DeleteTagError::Unknown(anyhow::anyhow!(e));
+        let deleted = sqlx::query(
+            "DELETE FROM tags WHERE id = $1 AND NOT EXISTS (SELECT 1 FROM pet_tags WHERE tag_id = $1)"
+        )
+        .bind(tag_id)
+        .execute(self.pool())
+        .await
+        .map_err(unknown)?;
+        if deleted.rows_affected() > 0 {
+            return Ok(());
+        }
+
+        // Nothing was deleted: tell a missing tag apart from one that is in use
+        let pet_count: Option<i64> = sqlx::query_scalar(
+            "SELECT (SELECT COUNT(*) FROM pet_tags WHERE tag_id = t.id) FROM tags t WHERE t.id = $1"
+        )
+        .bind(tag_id)
+        .fetch_optional(self.pool())
+        .await
+        .map_err(unknown)?;
+
+        match pet_count {
+            Some(pet_count) => Err(DeleteTagError::InUse { id: tag_id, pet_count }),
+            None => Err(DeleteTagError::NotFound { id: tag_id }),
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn delete_unused_tags(&self) -> Result<Vec<Tag>, DeleteTagError> {
+        let rows = sqlx::query(
+            r#"
+            DELETE FROM tags t
+            WHERE NOT EXISTS (SELECT 1 FROM pet_tags pt WHERE pt.tag_id = t.id)
+            RETURNING t.id, t.name
+            "#
+        )
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| DeleteTagError::Unknown(anyhow::anyhow!(e)))?;
+
+        let mut tags: Vec<Tag> = rows
+            .iter()
+            .map(|row| Tag::with_values(row.get("id"), row.get("name")))
+            .collect();
+        tags.sort();
+        Ok(tags)
+    }
+}
@@ -0,0 +1,135 @@
+/*
+   Module `traced` runs SQL statements in spans of their own, so that traces show which
+   statements a request issued and how long each took. Statements are run through a [Traced]
+   executor in place of the pool or connection it wraps:
+
+   sqlx::query("SELECT version FROM pets WHERE id = $1")
+       .bind(pet_id)
+       .fetch_optional(traced(self.pool()))
+*/
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::future::BoxFuture;
+use futures::stream::{BoxStream, Stream, StreamExt};
+use futures::FutureExt;
+use sqlx::database::HasStatement;
+use sqlx::postgres::{PgQueryResult, PgRow};
+use sqlx::{Describe, Either, Execute, Executor, Postgres};
+use tracing::{Instrument, Span};
+
+/// An executor running every statement in a `db.query` span, a child of the current span.
+#[derive(Debug)]
+pub struct Traced<E>(E);
+
+/// Wraps `executor`, e.g. a pool or a transaction, to trace the statements it runs.
+pub fn traced<'c, E>(executor: E) -> Traced<E>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    Traced(executor)
+}
+
+/// The span of `statement`, named after its operation, e.g. `SELECT`, and described with the
+/// OpenTelemetry database conventions. Statements are parameterised, so their text holds no
+/// values.
+pub fn statement_span(statement: &str) -> Span {
+    let (operation, text) = summarize(statement);
+    tracing::info_span!(
+        "db.query",
+        otel.name = %operation,
+        otel.kind = "client",
+        db.system.name = "postgresql",
+        db.operation.name = %operation,
+        db.query.text = %text,
+    )
+}
+
+/// The operation of `statement` and its text on one line.
+fn summarize(statement: &str) -> (String, String) {
+    let text = statement.split_whitespace().collect::<Vec<_>>().join(" ");
+    let operation = text.split(' ').next().unwrap_or_default().to_ascii_uppercase();
+    (operation, text)
+}
+
+impl<'c, E> Executor<'c> for Traced<E>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    type Database = Postgres;
+
+    fn fetch_many<'e, 'q: 'e, Q>(
+        self,
+        query: Q,
+    ) -> BoxStream<'e, Result<Either<PgQueryResult, PgRow>, sqlx::Error>>
+    where
+        'c: 'e,
+        Q: Execute<'q, Postgres> + 'q,
+    {
+        let span = statement_span(query.sql());
+        let stream = span.in_scope(|| self.0.fetch_many(query));
+        InSpan { stream, span }.boxed()
+    }
+
+    fn fetch_optional<'e, 'q: 'e, Q>(self, query: Q) -> BoxFuture<'e, Result<Option<PgRow>, sqlx::Error>>
+    where
+        'c: 'e,
+        Q: Execute<'q, Postgres> + 'q,
+    {
+        let span = statement_span(query.sql());
+        let future = span.in_scope(|| self.0.fetch_optional(query));
+        future.instrument(span).boxed()
+    }
+
+    fn prepare_with<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+        parameters: &'e [sqlx::postgres::PgTypeInfo],
+    ) -> BoxFuture<'e, Result<<Postgres as HasStatement<'q>>::Statement, sqlx::Error>>
+    where
+        'c: 'e,
+    {
+        self.0.prepare_with(sql, parameters)
+    }
+
+    fn describe<'e, 'q: 'e>(self, sql: &'q str) -> BoxFuture<'e, Result<Describe<Postgres>, sqlx::Error>>
+    where
+        'c: 'e,
+    {
+        self.0.describe(sql)
+    }
+}
+
+/// A stream polled inside a span, which ends when the stream is dropped.
+struct InSpan<S> {
+    stream: S,
+    span: Span,
+}
+
+impl<S> Stream for InSpan<S>
+where
+    S: Stream + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let _entered = this.span.enter();
+        this.stream.poll_next_unpin(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        assert_eq!(
+            summarize("\n    select id\n    FROM pets WHERE id = $1\n    "),
+            ("SELECT".to_string(), "select id FROM pets WHERE id = $1".to_string())
+        );
+        assert_eq!(summarize(""), (String::new(), String::new()));
+    }
+}
@@ -0,0 +1,218 @@
+use crate::domain::petstore::models::webhook::{
+    CreateWebhookError, CreateWebhookRequest, DeleteWebhookError, DeliveryOutcome, DeliveryStatus,
+    FindWebhookError, Webhook, WebhookDelivery, WebhookDeliveryError,
+};
+use crate::domain::petstore::ports::WebhookRepository;
+use crate::outbound::connect::PostgresClient;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::time::Duration;
+
+const WEBHOOK_COLUMNS: &str = "id, url, event_types, secret, created_at";
+const DELIVERY_COLUMNS: &str = "d.id, d.webhook_id, d.event_id, d.event_type, d.payload, d.status, d.attempts, \
+    d.next_attempt_at, d.last_response_status, d.last_error, d.created_at, d.delivered_at";
+
+fn webhook_from_row(row: &PgRow) -> Webhook {
+    Webhook {
+        id: row.get("id"),
+        url: row.get("url"),
+        event_types: row.get("event_types"),
+        secret: row.get("secret"),
+        created_at: row.get("created_at"),
+    }
+}
+
+fn delivery_from_row(row: &PgRow) -> anyhow::Result<WebhookDelivery> {
+    Ok(WebhookDelivery {
+        id: row.get("id"),
+        webhook_id: row.get("webhook_id"),
+        event_id: row.get("event_id"),
+        event_type: row.get("event_type"),
+        payload: row.get("payload"),
+        status: row.get::<String, _>("status").parse()?,
+        attempts: u32::try_from(row.get::<i32, _>("attempts"))?,
+        next_attempt_at: row.get("next_attempt_at"),
+        last_response_status: row
+            .get::<Option<i32>, _>("last_response_status")
+            .map(u16::try_from)
+            .transpose()?,
+        last_error: row.get("last_error"),
+        created_at: row.get("created_at"),
+        delivered_at: row.get("delivered_at"),
+    })
+}
+
+impl WebhookRepository for PostgresClient {
+    #[tracing::instrument(skip_all)]
+    async fn create_webhook(&self, req: &CreateWebhookRequest) -> Result<Webhook, CreateWebhookError> {
+        let row = sqlx::query(&format!(
+            "INSERT INTO webhooks (url, event_types, secret) VALUES ($1, $2, $3) RETURNING {}",
+            WEBHOOK_COLUMNS
+        ))
+        .bind(&req.url)
+        .bind(&req.event_types)
+        .bind(&req.secret)
+        .fetch_one(self.pool())
+        .await
+        .map_err(|e| CreateWebhookError::Unknown(anyhow::anyhow!(e)))?;
+        Ok(webhook_from_row(&row))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_webhooks(&self) -> Result<Vec<Webhook>, FindWebhookError> {
+        let rows = sqlx::query(&format!("SELECT {} FROM webhooks ORDER BY id", WEBHOOK_COLUMNS))
+            .fetch_all(self.pool())
+            .await
+            .map_err(|e| FindWebhookError::Unknown(anyhow::anyhow!(e)))?;
+        Ok(rows.iter().map(webhook_from_row).collect())
+    }
+
+    #[tracing::instrument(skip_all, fields(webhook_id = webhook_id))]
+    async fn find_webhook(&self, webhook_id: i64) -> Result<Option<Webhook>, FindWebhookError> {
+        let row = sqlx::query(&format!("SELECT {} FROM webhooks WHERE id = $1", WEBHOOK_COLUMNS))
+            .bind(webhook_id)
+            .fetch_optional(self.pool())
+            .await
+            .map_err(|e| FindWebhookError::Unknown(anyhow::anyhow!(e)))?;
+        Ok(row.as_ref().map(webhook_from_row))
+    }
+
+    #[tracing::instrument(skip_all, fields(webhook_id = webhook_id))]
+    async fn delete_webhook(&self, webhook_id: i64) -> Result<(), DeleteWebhookError> {
+        // Deliveries go with the webhook, by the cascading foreign key
+        let deleted = sqlx::query("DELETE FROM webhooks WHERE id = $1")
+            .bind(webhook_id)
+            .execute(self.pool())
+            .await
+            .map_err(|e| DeleteWebhookError::Unknown(anyhow::anyhow!(e)))?;
+        if deleted.rows_affected() == 0 {
+            return Err(DeleteWebhookError::NotFound { id: webhook_id });
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(event_id = event_id))]
+    async fn enqueue_deliveries(&self, event_id: i64, event_type: &str, payload: &str) -> Result<usize, WebhookDeliveryError> {
+        let enqueued = sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries (webhook_id, event_id, event_type, payload)
+            SELECT id, $1, $2, $3 FROM webhooks
+            WHERE cardinality(event_types) = 0 OR $2 = ANY(event_types)
+            ON CONFLICT (webhook_id, event_id) DO NOTHING
+            "#
+        )
+        .bind(event_id)
+        .bind(event_type)
+        .bind(payload)
+        .execute(self.pool())
+        .await
+        .map_err(|e| WebhookDeliveryError::Unknown(anyhow::anyhow!(e)))?;
+        Ok(enqueued.rows_affected() as usize)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn claim_due_deliveries(&self, limit: usize, lease: Duration) -> Result<Vec<(Webhook, WebhookDelivery)>, WebhookDeliveryError> {
+        // Rows claimed by a concurrent caller are skipped rather than waited for
+        let rows = sqlx::query(&format!(
+            r#"
+            WITH due AS (
+                SELECT id FROM webhook_deliveries
+                WHERE status = 'pending' AND next_attempt_at <= now()
+                ORDER BY next_attempt_at, id
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            ), claimed AS (
+                UPDATE webhook_deliveries SET next_attempt_at = now() + make_interval(secs => $2)
+                FROM due WHERE webhook_deliveries.id = due.id
+                RETURNING webhook_deliveries.*
+            )
+            SELECT {}, w.url, w.event_types, w.secret, w.created_at AS webhook_created_at
+            FROM claimed d
+            JOIN webhooks w ON w.id = d.webhook_id
+            ORDER BY d.id
+            "#,
+            DELIVERY_COLUMNS
+        ))
+        .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+        .bind(lease.as_secs_f64())
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| WebhookDeliveryError::Unknown(anyhow::anyhow!(e)))?;
+
+        rows.iter()
+            .map(|row| {
+                let delivery = delivery_from_row(row)?;
+                let webhook = Webhook {
+                    id: delivery.webhook_id,
+                    url: row.get("url"),
+                    event_types: row.get("event_types"),
+                    secret: row.get("secret"),
+                    created_at: row.get("webhook_created_at"),
+                };
+                Ok((webhook, delivery))
+            })
+            .collect()
+    }
+
+    #[tracing::instrument(skip_all, fields(delivery_id = delivery_id))]
+    async fn record_delivery_attempt(&self, delivery_id: i64, outcome: &DeliveryOutcome) -> Result<(), WebhookDeliveryError> {
+        let (status, next_attempt_at, response_status, error) = match outcome {
+            DeliveryOutcome::Delivered { response_status } => {
+                (DeliveryStatus::Delivered, None, Some(*response_status), None)
+            }
+            DeliveryOutcome::Retry { next_attempt_at, response_status, error } => {
+                (DeliveryStatus::Pending, Some(*next_attempt_at), *response_status, Some(error))
+            }
+            DeliveryOutcome::DeadLetter { response_status, error } => {
+                (DeliveryStatus::DeadLetter, None, *response_status, Some(error))
+            }
+        };
+        sqlx::query(
+            r#"
+            UPDATE webhook_deliveries SET
+                status = $2,
+                attempts = attempts + 1,
+                next_attempt_at = COALESCE($3, next_attempt_at),
+                last_response_status = $4,
+                last_error = $5,
+                delivered_at = CASE WHEN $2 = 'delivered' THEN now() END
+            WHERE id = $1
+            "#
+        )
+        .bind(delivery_id)
+        .bind(status.to_str())
+        .bind(next_attempt_at)
+        .bind(response_status.map(i32::from))
+        .bind(error)
+        .execute(self.pool())
+        .await
+        .map_err(|e| WebhookDeliveryError::Unknown(anyhow::anyhow!(e)))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(webhook_id = webhook_id))]
+    async fn list_deliveries(&self, webhook_id: i64) -> Result<Vec<WebhookDelivery>, WebhookDeliveryError> {
+        let unknown = |e: sqlx::Error| WebhookDeliveryError::Unknown(anyhow::anyhow!(e));
+
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM webhooks WHERE id = $1)")
+            .bind(webhook_id)
+            .fetch_one(self.pool())
+            .await
+            .map_err(unknown)?;
+        if !exists {
+            return Err(WebhookDeliveryError::NotFound { id: webhook_id });
+        }
+
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM webhook_deliveries d WHERE d.webhook_id = $1 ORDER BY d.id DESC",
+            DELIVERY_COLUMNS
+        ))
+        .bind(webhook_id)
+        .fetch_all(self.pool())
+        .await
+        .map_err(unknown)?;
+        rows.iter()
+            .map(|row| delivery_from_row(row).map_err(WebhookDeliveryError::Unknown))
+            .collect()
+    }
+}
@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use crate::domain::petstore::models::webhook::{SendWebhookError, WebhookMessage};
+use crate::domain::petstore::ports::WebhookSender;
+
+/// The header carrying the signature of a delivery.
+pub const SIGNATURE_HEADER: &str = "x-petstore-signature";
+/// The header carrying the signing time of a delivery, in seconds since the Unix epoch.
+pub const TIMESTAMP_HEADER: &str = "x-petstore-timestamp";
+/// The header carrying the event type of a delivery.
+pub const EVENT_HEADER: &str = "x-petstore-event";
+/// The header carrying the id of a delivery, identical across its attempts.
+pub const DELIVERY_HEADER: &str = "x-petstore-delivery";
+
+/// A [WebhookSender] posting messages as JSON over HTTP.
+#[derive(Debug, Clone)]
+pub struct HttpWebhookSender {
+    client: reqwest::Client,
+}
+
+impl HttpWebhookSender {
+    /// How long a receiver may take to respond before the attempt fails.
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    pub fn new(timeout: Duration) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            // A redirect would resend the signed body to a url the partner did not register
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        Ok(Self { client })
+    }
+}
+
+impl WebhookSender for HttpWebhookSender {
+    async fn send(&self, message: &WebhookMessage) -> Result<u16, SendWebhookError> {
+        let response = self
+            .client
+            .post(&message.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, &message.signature)
+            .header(TIMESTAMP_HEADER, message.timestamp.to_string())
+            .header(EVENT_HEADER, &message.event_type)
+            .header(DELIVERY_HEADER, message.delivery_id.to_string())
+            .body(message.body.clone())
+            .send()
+            .await
+            .map_err(|e| SendWebhookError::Unknown(anyhow::anyhow!(e)))?;
+        Ok(response.status().as_u16())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+
+    use crate::domain::petstore::webhooks::sign;
+
+    /// Serves a receiver answering with `status` on a local port, and returns its url and the
+    /// requests it received.
+    async fn spawn_receiver(status: StatusCode) -> (String, Arc<Mutex<Vec<(HeaderMap, String)>>>) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let recorded = received.clone();
+        let app = axum::Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: String| async move {
+                recorded.lock().unwrap().push((headers, body));
+                status
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, received)
+    }
+
+    fn message(url: String) -> WebhookMessage {
+        let body = r#"{"id":1,"type":"PetStatusChanged"}"#.to_string();
+        WebhookMessage {
+            url,
+            delivery_id: 3,
+            event_type: "PetStatusChanged".to_string(),
+            timestamp: 1_700_000_000,
+            signature: sign("0123456789abcdef", 1_700_000_000, &body),
+            body,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_signed_message() {
+        let (url, received) = spawn_receiver(StatusCode::NO_CONTENT).await;
+        let sender = HttpWebhookSender::new(HttpWebhookSender::DEFAULT_TIMEOUT).unwrap();
+        let message = message(url);
+
+        assert_eq!(sender.send(&message).await.unwrap(), 204);
+
+        let received = received.lock().unwrap();
+        let (headers, body) = &received[0];
+        assert_eq!(body, &message.body);
+        assert_eq!(headers[SIGNATURE_HEADER], message.signature.as_str());
+        assert_eq!(headers[TIMESTAMP_HEADER], "1700000000");
+        assert_eq!(headers[EVENT_HEADER], "PetStatusChanged");
+        assert_eq!(headers[DELIVERY_HEADER], "3");
+        assert_eq!(headers["content-type"], "application/json");
+        // The receiver can authenticate the delivery with the shared secret
+        let timestamp: i64 = headers[TIMESTAMP_HEADER].to_str().unwrap().parse().unwrap();
+        assert_eq!(headers[SIGNATURE_HEADER], sign("0123456789abcdef", timestamp, body).as_str());
+    }
+
+    #[tokio::test]
+    async fn test_send_reports_failures() {
+        let (url, _) = spawn_receiver(StatusCode::SERVICE_UNAVAILABLE).await;
+        let sender = HttpWebhookSender::new(HttpWebhookSender::DEFAULT_TIMEOUT).unwrap();
+
+        assert_eq!(sender.send(&message(url)).await.unwrap(), 503);
+
+        // Nothing listens on the port of a dropped listener
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        drop(listener);
+        assert!(sender.send(&message(url)).await.is_err());
+    }
+}
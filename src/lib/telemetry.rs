@@ -0,0 +1,316 @@
+/*!
+    Module `telemetry` sets up the diagnostics emitted by the server: log events, as text for
+    people or as JSON lines for log collectors, each carrying the fields of the spans it was
+    emitted in, such as the `request_id` of the HTTP request being handled.
+
+    Built with the `otel` feature, the spans are also exported as OpenTelemetry traces over
+    OTLP/HTTP when `OTEL_EXPORTER_OTLP_ENDPOINT` (or `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`) is set,
+    and inbound requests carrying a W3C `traceparent` continue the trace of their caller.
+*/
+
+use std::str::FromStr;
+
+use axum::http::HeaderMap;
+use thiserror::Error;
+use tracing::Span;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+/// The levels logged unless `RUST_LOG` says otherwise.
+pub const DEFAULT_LOG_FILTER: &str = "info";
+
+/// How log events are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One human-readable line per event.
+    #[default]
+    Text,
+    /// One JSON object per line and event, with the fields of the event at the top level and
+    /// those of its spans in `span` and `spans`.
+    Json,
+}
+
+#[derive(Debug, Clone, Error)]
+#[error("log format {0} is not one of text, json")]
+pub struct LogFormatError(String);
+
+impl FromStr for LogFormat {
+    type Err = LogFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => Err(LogFormatError(other.to_string())),
+        }
+    }
+}
+
+/// The installed telemetry. Dropping it flushes the spans not yet exported, so it is kept
+/// until the server exits.
+#[derive(Debug, Default)]
+#[must_use = "dropping the telemetry stops the export of traces"]
+pub struct Telemetry {
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("failed to export the last traces: {e}");
+            }
+        }
+    }
+}
+
+/// Installs the global subscriber writing the events enabled by `RUST_LOG`, or
+/// [DEFAULT_LOG_FILTER], to stdout in `format`, and exporting traces if the `otel` feature is
+/// enabled and an OTLP endpoint configured.
+///
+/// # Errors:
+///
+/// - If `RUST_LOG` is invalid, or a global subscriber is already installed.
+/// - If the OTLP exporter cannot be built from the `OTEL_EXPORTER_OTLP_*` variables.
+pub fn init_telemetry(format: LogFormat) -> anyhow::Result<Telemetry> {
+    let filter = match std::env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(directives) => EnvFilter::try_new(directives)?,
+        Err(_) => EnvFilter::new(DEFAULT_LOG_FILTER),
+    };
+    let output = match format {
+        LogFormat::Text => tracing_subscriber::fmt::layer().boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(true)
+            .boxed(),
+    };
+    let (export, telemetry) = trace_export()?;
+    tracing_subscriber::registry().with(export).with(filter).with(output).try_init()?;
+    Ok(telemetry)
+}
+
+/// A layer exporting the spans of the global subscriber.
+type ExportLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// The layer exporting traces, if an OTLP endpoint is configured, and the telemetry to keep
+/// until it has exported them all.
+#[cfg(feature = "otel")]
+fn trace_export() -> anyhow::Result<(Option<ExportLayer>, Telemetry)> {
+    if !otel::endpoint_configured() {
+        return Ok((None, Telemetry::default()));
+    }
+    let provider = otel::tracer_provider(otel::otlp_exporter()?);
+    let layer = otel::layer(&provider).boxed();
+    Ok((Some(layer), Telemetry { provider: Some(provider) }))
+}
+
+#[cfg(not(feature = "otel"))]
+fn trace_export() -> anyhow::Result<(Option<ExportLayer>, Telemetry)> {
+    Ok((None, Telemetry::default()))
+}
+
+/// Makes `span` a child of the remote span named by the W3C `traceparent` and `tracestate` in
+/// `headers`, if any, so that it belongs to the trace of the caller. Without the `otel` feature
+/// spans are not exported, and this does nothing.
+#[cfg(feature = "otel")]
+pub fn set_remote_parent(span: &Span, headers: &HeaderMap) {
+    otel::set_remote_parent(span, headers)
+}
+
+/// Makes `span` a child of the remote span named by the W3C `traceparent` and `tracestate` in
+/// `headers`, if any, so that it belongs to the trace of the caller. Without the `otel` feature
+/// spans are not exported, and this does nothing.
+#[cfg(not(feature = "otel"))]
+pub fn set_remote_parent(_span: &Span, _headers: &HeaderMap) {}
+
+#[cfg(feature = "otel")]
+pub mod otel {
+    //! The OpenTelemetry pipeline: spans are batched and exported over OTLP/HTTP, with the
+    //! service name from `OTEL_SERVICE_NAME`, or [SERVICE_NAME].
+
+    use axum::http::HeaderMap;
+    use opentelemetry::propagation::{Extractor, TextMapPropagator};
+    use opentelemetry::trace::{TraceContextExt, TracerProvider};
+    use opentelemetry_otlp::{OTEL_EXPORTER_OTLP_ENDPOINT, OTEL_EXPORTER_OTLP_TRACES_ENDPOINT};
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::{SdkTracerProvider, SpanExporter};
+    use opentelemetry_sdk::Resource;
+    use tracing::Span;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Layer;
+
+    /// The service name of exported spans unless `OTEL_SERVICE_NAME` is set.
+    pub const SERVICE_NAME: &str = "petstore";
+
+    /// Whether an OTLP endpoint to export traces to is configured.
+    pub fn endpoint_configured() -> bool {
+        [OTEL_EXPORTER_OTLP_ENDPOINT, OTEL_EXPORTER_OTLP_TRACES_ENDPOINT]
+            .iter()
+            .any(|var| std::env::var_os(var).is_some_and(|value| !value.is_empty()))
+    }
+
+    /// The OTLP/HTTP exporter configured by the `OTEL_EXPORTER_OTLP_*` variables.
+    pub fn otlp_exporter() -> anyhow::Result<opentelemetry_otlp::SpanExporter> {
+        Ok(opentelemetry_otlp::SpanExporter::builder().with_http().build()?)
+    }
+
+    /// A provider exporting spans to `exporter` in batches, from a thread of its own.
+    pub fn tracer_provider(exporter: impl SpanExporter + 'static) -> SdkTracerProvider {
+        let mut resource = Resource::builder();
+        if std::env::var_os("OTEL_SERVICE_NAME").is_none() {
+            resource = resource.with_service_name(SERVICE_NAME);
+        }
+        SdkTracerProvider::builder()
+            .with_resource(resource.build())
+            .with_batch_exporter(exporter)
+            .build()
+    }
+
+    /// A layer turning spans into OpenTelemetry spans of `provider`.
+    pub fn layer<S>(provider: &SdkTracerProvider) -> impl Layer<S>
+    where
+        S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+    }
+
+    pub(super) fn set_remote_parent(span: &Span, headers: &HeaderMap) {
+        let context = TraceContextPropagator::new().extract(&HeaderExtractor(headers));
+        if context.span().span_context().is_valid() {
+            // Fails only if the span is not recorded by the layer, e.g. filtered out
+            let _ = span.set_parent(context);
+        }
+    }
+
+    struct HeaderExtractor<'a>(&'a HeaderMap);
+
+    impl Extractor for HeaderExtractor<'_> {
+        fn get(&self, key: &str) -> Option<&str> {
+            self.0.get(key).and_then(|value| value.to_str().ok())
+        }
+
+        fn keys(&self) -> Vec<&str> {
+            self.0.keys().map(|key| key.as_str()).collect()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::time::Duration;
+
+        use axum::body::Bytes;
+        use axum::http::{header, HeaderValue, Uri};
+        use axum::routing::post;
+        use opentelemetry::trace::{SpanId, TraceId};
+        use opentelemetry_otlp::WithExportConfig;
+        use opentelemetry_sdk::trace::InMemorySpanExporter;
+        use tokio::sync::mpsc;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        use super::*;
+
+        const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+        const PARENT_ID: &str = "00f067aa0ba902b7";
+
+        fn traceparent() -> HeaderMap {
+            let mut headers = HeaderMap::new();
+            let traceparent = format!("00-{TRACE_ID}-{PARENT_ID}-01");
+            headers.insert("traceparent", HeaderValue::from_str(&traceparent).unwrap());
+            headers
+        }
+
+        #[test]
+        fn test_remote_parent() {
+            let exporter = InMemorySpanExporter::default();
+            let provider = SdkTracerProvider::builder()
+                .with_simple_exporter(exporter.clone())
+                .build();
+            let subscriber = tracing_subscriber::registry().with(layer(&provider));
+
+            tracing::subscriber::with_default(subscriber, || {
+                let continued = tracing::info_span!("continued");
+                set_remote_parent(&continued, &traceparent());
+                continued.in_scope(|| tracing::info_span!("child").in_scope(|| {}));
+                drop(continued);
+
+                let started = tracing::info_span!("started");
+                set_remote_parent(&started, &HeaderMap::new());
+                drop(started);
+            });
+
+            let spans = exporter.get_finished_spans().unwrap();
+            let span = |name: &str| spans.iter().find(|span| span.name == name).unwrap();
+            let trace_id = TraceId::from_hex(TRACE_ID).unwrap();
+            assert_eq!(span("continued").span_context.trace_id(), trace_id);
+            assert_eq!(span("continued").parent_span_id, SpanId::from_hex(PARENT_ID).unwrap());
+            assert!(span("continued").parent_span_is_remote);
+            assert_eq!(span("child").span_context.trace_id(), trace_id);
+            assert_eq!(span("child").parent_span_id, span("continued").span_context.span_id());
+            assert_ne!(span("started").span_context.trace_id(), trace_id);
+            assert_eq!(span("started").parent_span_id, SpanId::INVALID);
+        }
+
+        /// Spans are exported over OTLP/HTTP, here to a collector stand-in recording the
+        /// requests it receives.
+        #[tokio::test(flavor = "multi_thread")]
+        async fn test_otlp_export() {
+            let (requests, mut received) = mpsc::unbounded_channel();
+            let collector = axum::Router::new().route(
+                "/v1/traces",
+                post(move |uri: Uri, headers: HeaderMap, body: Bytes| async move {
+                    requests.send((uri, headers, body)).unwrap();
+                }),
+            );
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let endpoint = format!("http://{}/v1/traces", listener.local_addr().unwrap());
+            tokio::spawn(async move { axum::serve(listener, collector).await });
+
+            let exporter = opentelemetry_otlp::SpanExporter::builder()
+                .with_http()
+                .with_endpoint(endpoint)
+                .build()
+                .unwrap();
+            let provider = tracer_provider(exporter);
+            let subscriber = tracing_subscriber::registry().with(layer(&provider));
+            tracing::subscriber::with_default(subscriber, || {
+                let span = tracing::info_span!("http_request");
+                set_remote_parent(&span, &traceparent());
+                span.in_scope(|| crate::outbound::traced::statement_span("SELECT 1").in_scope(|| {}));
+            });
+            tokio::task::spawn_blocking(move || provider.force_flush())
+                .await
+                .unwrap()
+                .unwrap();
+
+            let (uri, headers, body) = tokio::time::timeout(Duration::from_secs(10), received.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(uri.path(), "/v1/traces");
+            assert_eq!(headers[header::CONTENT_TYPE], "application/x-protobuf");
+            let contains = |bytes: &[u8]| body.windows(bytes.len()).any(|window| window == bytes);
+            assert!(contains(&hex::decode(TRACE_ID).unwrap()));
+            assert!(contains(SERVICE_NAME.as_bytes()));
+            assert!(contains(b"http_request"));
+            assert!(contains(b"db.query.text"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_format() {
+        assert_eq!("JSON ".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!("text".parse::<LogFormat>().unwrap(), LogFormat::Text);
+        assert!("logfmt".parse::<LogFormat>().is_err());
+    }
+}
@@ -1,12 +1,28 @@
-use testcontainers::{core::{WaitFor, IntoContainerPort}, runners::AsyncRunner, GenericImage, ImageExt};
+use testcontainers::{core::{WaitFor, IntoContainerPort}, runners::AsyncRunner, ContainerAsync, GenericImage, ImageExt};
 use sqlx::postgres::PgPoolOptions;
 use tokio::time::{sleep, Duration};
-use petstore_hexarch_rust::domain::petstore::models::pet::{CreatePetRequest, Status};
-use petstore_hexarch_rust::domain::petstore::models::category::Category;
+use petstore_hexarch_rust::domain::petstore::models::pet::{CreatePetRequest, ListPetsRequest, Pet, PetSortField, SortDirection, Status};
+use petstore_hexarch_rust::domain::petstore::models::category::{Category, CreateCategoryError, CreateCategoryRequest, DeleteCategoryError, RenameCategoryRequest};
+use petstore_hexarch_rust::domain::petstore::models::pet::{ChangePetStatusError, ChangePetStatusRequest, CreatePetError, DeletePetError, InvalidTransition, PetHistoryError};
 use petstore_hexarch_rust::domain::petstore::models::tag::Tag;
+use petstore_hexarch_rust::domain::petstore::models::id::{IdKind, IdStrategy};
+use petstore_hexarch_rust::domain::petstore::models::search::{SearchPetsRequest, SearchQuery};
+use petstore_hexarch_rust::domain::petstore::models::seed::{SeedPlan, SeedProfile};
+use petstore_hexarch_rust::domain::petstore::seed::Seeder;
+use petstore_hexarch_rust::domain::petstore::models::transfer::{ImportPetsError, ImportRow, PetRecord};
+use petstore_hexarch_rust::domain::petstore::service::Service;
 use petstore_hexarch_rust::outbound::connect::PostgresClient;
+use petstore_hexarch_rust::outbound::migrations::MigrationState;
 use petstore_hexarch_rust::outbound::params::ConnectionParams;
-use petstore_hexarch_rust::domain::petstore::ports::PetRepository;
+use petstore_hexarch_rust::domain::petstore::models::tag::{DeleteTagError, MergeTagsRequest, RenameTagRequest, TagUsage};
+use petstore_hexarch_rust::domain::petstore::models::idempotency::{ClaimIdempotencyKeyRequest, IdempotencyClaim, IdempotencyError, IdempotencyKey, IdempotentResponse};
+use petstore_hexarch_rust::domain::petstore::models::event::PetEvent;
+use petstore_hexarch_rust::domain::petstore::models::webhook::{CreateWebhookRequest, DeliveryStatus, WebhookDeliveryError};
+use petstore_hexarch_rust::domain::petstore::ports::{CategoryRepository, IdempotencyRepository, OutboxRepository, PetRepository, PetTransferService, TagRepository, WebhookRepository};
+use petstore_hexarch_rust::domain::petstore::relay::OutboxRelay;
+use petstore_hexarch_rust::domain::petstore::webhooks::{sign, RetryPolicy, WebhookDispatcher, WebhookPublisher};
+use petstore_hexarch_rust::outbound::webhook_sender::HttpWebhookSender;
+use std::sync::{Arc, Mutex};
 
 
 #[tokio::test]
@@ -109,6 +125,11 @@ async fn test_add_pet() {
     };
     let client = PostgresClient::new(&params).await.expect("Failed to create PostgresClient");
 
+    // Pets reference existing categories
+    client.create_category(&CreateCategoryRequest::new(Some(1), "Dogs".to_string()))
+        .await
+        .expect("Failed to create category");
+
     // Test adding a pet with all fields
     let category = Category::with_values(1, "Dogs".to_string());
     let tags = vec![
@@ -130,7 +151,7 @@ async fn test_add_pet() {
     );
 
     // Add the pet
-    let pet = client.add_pet(&req)
+    let pet = client.add_pet(&req, |_| Vec::new())
         .await
         .expect("Failed to add pet");
 
@@ -150,7 +171,7 @@ async fn test_add_pet() {
         None,
     );
 
-    let result = client.add_pet(&duplicate_req).await;
+    let result = client.add_pet(&duplicate_req, |_| Vec::new()).await;
     assert!(result.is_err());
 
     // Verify the database state
@@ -234,6 +255,11 @@ async fn test_find_pet_by_id() {
     };
     let client = PostgresClient::new(&params).await.expect("Failed to create PostgresClient");
 
+    // Pets reference existing categories
+    client.create_category(&CreateCategoryRequest::new(Some(1), "Dogs".to_string()))
+        .await
+        .expect("Failed to create category");
+
     // Test adding a pet with all fields
     let category = Category::with_values(1, "Dogs".to_string());
     let tags = vec![
@@ -252,7 +278,7 @@ async fn test_find_pet_by_id() {
     );
 
     // Add the pet
-    let pet = client.add_pet(&req)
+    let pet = client.add_pet(&req, |_| Vec::new())
         .await
         .expect("Failed to add pet");
 
@@ -286,4 +312,889 @@ async fn test_find_pet_by_id() {
         .await
         .expect("Failed to query non-existent pet");
     assert!(not_found.is_none());
-}
\ No newline at end of file
+}
+
+/// Starts a PostgreSQL container and returns a [PostgresClient] connected to its empty database.
+/// The container is stopped when the returned handle is dropped.
+async fn start_postgres() -> (ContainerAsync<GenericImage>, PostgresClient) {
+    let container = GenericImage::new("postgres", "latest")
+        .with_wait_for(WaitFor::message_on_stdout("database system is ready to accept connections"))
+        .with_exposed_port(5432.tcp())
+        .with_env_var("POSTGRES_PASSWORD", "postgres")
+        .with_env_var("POSTGRES_USER", "postgres")
+        .with_env_var("POSTGRES_DB", "postgres")
+        .start()
+        .await
+        .expect("Failed to start container");
+
+    let host_port = container.get_host_port_ipv4(5432)
+        .await
+        .expect("Failed to get host port");
+
+    sleep(Duration::from_secs(1)).await;
+
+    let params = ConnectionParams {
+        host: "localhost".to_string(),
+        port: host_port,
+        dbname: "postgres".to_string(),
+        user: "postgres".to_string(),
+        password: "postgres".to_string(),
+    };
+    let client = PostgresClient::new(&params).await.expect("Failed to create PostgresClient");
+
+    (container, client)
+}
+
+/// Starts a PostgreSQL container, runs the migrations and returns a connected [PostgresClient].
+async fn start_migrated_postgres() -> (ContainerAsync<GenericImage>, PostgresClient) {
+    let (container, client) = start_postgres().await;
+    client.migrate().await.expect("Failed to run migrations");
+    (container, client)
+}
+
+/// The tables, sequences, functions and extensions of the public schema.
+async fn schema_objects(client: &PostgresClient) -> Vec<String> {
+    sqlx::query_scalar(
+        "SELECT kind || ' ' || name FROM (
+            SELECT 'relation' AS kind, c.relname::TEXT AS name FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE n.nspname = 'public' AND c.relname NOT LIKE '\\_sqlx\\_migrations%'
+            UNION ALL
+            SELECT 'function', p.proname::TEXT FROM pg_proc p
+            JOIN pg_namespace n ON n.oid = p.pronamespace
+            WHERE n.nspname = 'public'
+            UNION ALL
+            SELECT 'extension', extname::TEXT FROM pg_extension WHERE extname <> 'plpgsql'
+            UNION ALL
+            SELECT 'column', table_name || '.' || column_name FROM information_schema.columns
+            WHERE table_schema = 'public' AND table_name <> '_sqlx_migrations'
+        ) objects ORDER BY 1",
+    )
+    .fetch_all(client.pool())
+    .await
+    .expect("Failed to list schema objects")
+}
+
+#[tokio::test]
+async fn test_migration_round_trips() {
+    let (_container, client) = start_postgres().await;
+
+    let status = client.migration_status().await.expect("Failed to read migration status");
+    assert!(status.migrations.iter().all(|m| m.state == MigrationState::Pending));
+    assert!(status.ensure_current().is_err());
+
+    client.migrate().await.expect("Failed to run migrations");
+    let status = client.migration_status().await.unwrap();
+    assert!(status.is_current(), "{:?}", status);
+    let migrated = schema_objects(&client).await;
+
+    // Reverting each migration and applying it again leaves the schema as it was
+    let versions: Vec<i64> = status.migrations.iter().map(|m| m.version).collect();
+    for window in versions.windows(2).rev() {
+        let (previous, version) = (window[0], window[1]);
+        client.revert_migrations(previous).await.unwrap_or_else(|e| panic!("Failed to revert {}: {:?}", version, e));
+        let status = client.migration_status().await.unwrap();
+        assert_eq!(status.migrations.last().unwrap().state, MigrationState::Pending);
+        client.migrate().await.unwrap_or_else(|e| panic!("Failed to reapply {}: {:?}", version, e));
+        assert_eq!(schema_objects(&client).await, migrated, "migration {} does not round-trip", version);
+    }
+
+    // Reverting every migration leaves an empty schema, which migrates again
+    client.revert_migrations(0).await.expect("Failed to revert every migration");
+    assert_eq!(schema_objects(&client).await, Vec::<String>::new());
+    client.migrate().await.expect("Failed to run migrations again");
+    assert!(client.migration_status().await.unwrap().is_current());
+}
+
+#[tokio::test]
+async fn test_list_pets() {
+    let (_container, client) = start_migrated_postgres().await;
+
+    let pets = [
+        (1, "Rex", 1, Status::Available),
+        (2, "Buddy", 1, Status::Sold),
+        (3, "Luna", 2, Status::Available),
+        (4, "Ace", 1, Status::Available),
+        (5, "Milo", 2, Status::Pending),
+    ];
+    for (id, name) in [(1, "Dogs"), (2, "Cats")] {
+        client.create_category(&CreateCategoryRequest::new(Some(id), name.to_string()))
+            .await
+            .expect("Failed to create category");
+    }
+    for (id, name, category_id, status) in pets {
+        let category_name = if category_id == 1 { "Dogs" } else { "Cats" };
+        let req = CreatePetRequest::new(
+            Some(id),
+            name.to_string(),
+            Some(Category::with_values(category_id, category_name.to_string())),
+            vec![format!("http://example.com/{}.jpg", name)],
+            vec![Tag::with_values(id, format!("tag-{}", id))],
+            Some(status),
+        );
+        client.add_pet(&req, |_| Vec::new()).await.expect("Failed to add pet");
+    }
+
+    // Walk all pages ordered by id
+    let mut req = ListPetsRequest {
+        limit: 2,
+        ..Default::default()
+    };
+    let mut ids = Vec::new();
+    loop {
+        let page = client.list_pets(&req).await.expect("Failed to list pets");
+        assert!(page.pets.len() <= 2);
+        for pet in &page.pets {
+            assert_eq!(pet.photo_urls, vec![format!("http://example.com/{}.jpg", pet.name)]);
+            assert_eq!(pet.tags.len(), 1);
+            assert!(pet.category.is_some());
+        }
+        ids.extend(page.pets.iter().filter_map(|p| p.id));
+        match page.next_cursor {
+            Some(cursor) => req.cursor = Some(cursor),
+            None => break,
+        }
+    }
+    assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+
+    // Sort by name descending, across a page boundary
+    let mut req = ListPetsRequest::new(PetSortField::Name, SortDirection::Desc, None, None, 3, None);
+    let first = client.list_pets(&req).await.expect("Failed to list pets");
+    let names: Vec<&str> = first.pets.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["Rex", "Milo", "Luna"]);
+    req.cursor = first.next_cursor;
+    let second = client.list_pets(&req).await.expect("Failed to list pets");
+    let names: Vec<&str> = second.pets.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["Buddy", "Ace"]);
+    assert!(second.next_cursor.is_none());
+
+    // Filter by status and category
+    let req = ListPetsRequest::new(PetSortField::Id, SortDirection::Asc, Some(Status::Available), Some(1), 10, None);
+    let page = client.list_pets(&req).await.expect("Failed to list pets");
+    let ids: Vec<Option<i64>> = page.pets.iter().map(|p| p.id).collect();
+    assert_eq!(ids, vec![Some(1), Some(4)]);
+}
+
+#[tokio::test]
+async fn test_search_pets() {
+    let (_container, client) = start_migrated_postgres().await;
+
+    for (id, name) in [(1, "Dogs"), (2, "Birds")] {
+        client.create_category(&CreateCategoryRequest::new(Some(id), name.to_string()))
+            .await
+            .expect("Failed to create category");
+    }
+    let pets = [
+        (1, "Buddy", (1, "Dogs"), "good boy"),
+        (2, "Budgie", (2, "Birds"), "talks"),
+        (3, "Rex", (1, "Dogs"), "buddy-friendly"),
+        (4, "Tweety_50%", (2, "Birds"), "yellow"),
+    ];
+    for (id, name, (category_id, category_name), tag) in pets {
+        let req = CreatePetRequest::new(
+            Some(id),
+            name.to_string(),
+            Some(Category::with_values(category_id, category_name.to_string())),
+            vec![],
+            vec![Tag::with_values(id, tag.to_string())],
+            None,
+        );
+        client.add_pet(&req, |_| Vec::new()).await.expect("Failed to add pet");
+    }
+    let search = |q: &str| {
+        let req = SearchPetsRequest::new(SearchQuery::new(q).unwrap(), 10);
+        let client = client.clone();
+        async move {
+            let found = client.search_pets(&req).await.expect("Failed to search pets");
+            found.iter().filter_map(|m| m.pet.id).collect::<Vec<i64>>()
+        }
+    };
+
+    // Partial names rank above matches in tags
+    let found = search("BUD").await;
+    assert_eq!(found.len(), 3);
+    assert_eq!(found[2], 3);
+    // Every term must match, in any field
+    assert_eq!(search("dogs good").await, vec![1]);
+    assert_eq!(search("birds").await, vec![2, 4]);
+    // Wildcards match literally
+    assert_eq!(search("_50%").await, vec![4]);
+    assert_eq!(search("%").await, vec![4]);
+
+    // Renamed categories and tags are found by their new names
+    client.rename_category(&RenameCategoryRequest::new(2, "Parrots".to_string()))
+        .await
+        .expect("Failed to rename category");
+    assert_eq!(search("parrot").await, vec![2, 4]);
+    assert!(search("birds").await.is_empty());
+    client.rename_tag(&RenameTagRequest::new(2, "chatty".to_string()))
+        .await
+        .expect("Failed to rename tag");
+    assert_eq!(search("chat").await, vec![2]);
+
+    // The found pets come with their photos and tags
+    let req = SearchPetsRequest::new(SearchQuery::new("rex").unwrap(), 10);
+    let found = client.search_pets(&req).await.expect("Failed to search pets");
+    assert_eq!(found[0].pet.tags, vec![Tag::with_values(3, "buddy-friendly".to_string())]);
+    assert!(found[0].rank > 0.0);
+}
+
+#[tokio::test]
+async fn test_categories() {
+    let (_container, client) = start_migrated_postgres().await;
+
+    // Ids are assigned when omitted
+    let dogs = client.create_category(&CreateCategoryRequest::new(None, "Dogs".to_string()))
+        .await
+        .expect("Failed to create category");
+    let cats = client.create_category(&CreateCategoryRequest::new(None, "Cats".to_string()))
+        .await
+        .expect("Failed to create category");
+    assert!(dogs.id.is_some());
+    assert_ne!(dogs.id, cats.id);
+
+    let duplicate = client.create_category(&CreateCategoryRequest::new(None, "Dogs".to_string())).await;
+    assert!(duplicate.is_err());
+
+    // Supplied ids move the sequence past them, and taken ones are reported
+    let birds = client.create_category(&CreateCategoryRequest::new(Some(cats.id.unwrap() + 1), "Birds".to_string()))
+        .await
+        .expect("Failed to create category with an id");
+    let fish = client.create_category(&CreateCategoryRequest::new(None, "Fish".to_string()))
+        .await
+        .expect("Failed to create category after a supplied id");
+    assert!(fish.id > birds.id);
+    let duplicate = client.create_category(&CreateCategoryRequest::new(birds.id, "Reptiles".to_string())).await;
+    assert!(matches!(duplicate, Err(CreateCategoryError::DuplicateId { id }) if Some(id) == birds.id));
+
+    // A supplied id below the last one assigned never moves the sequence back
+    client.delete_category(dogs.id.unwrap()).await.expect("Failed to delete category");
+    let dogs = client.create_category(&CreateCategoryRequest::new(dogs.id, "Dogs".to_string()))
+        .await
+        .expect("Failed to create category with a lower id");
+    let frogs = client.create_category(&CreateCategoryRequest::new(None, "Frogs".to_string()))
+        .await
+        .expect("Failed to create category after a lower supplied id");
+    assert!(frogs.id > fish.id);
+
+    // Adding a pet references the category and never renames it
+    let req = CreatePetRequest::new(
+        Some(1),
+        "Buddy".to_string(),
+        Some(Category::with_values(dogs.id.unwrap(), "Not Dogs".to_string())),
+        vec![],
+        vec![],
+        None,
+    );
+    let pet = client.add_pet(&req, |_| Vec::new()).await.expect("Failed to add pet");
+    assert_eq!(pet.category.map(|c| *c), Some(dogs.clone()));
+
+    // Unknown categories are rejected
+    let req = CreatePetRequest::new(
+        Some(2),
+        "Ghost".to_string(),
+        Some(Category::with_values(999, "Birds".to_string())),
+        vec![],
+        vec![],
+        None,
+    );
+    let result = client.add_pet(&req, |_| Vec::new()).await;
+    assert!(matches!(result, Err(CreatePetError::UnknownCategory { id: 999 })));
+
+    // Category references without an id are rejected as such
+    let req = CreatePetRequest::new(
+        Some(2),
+        "Ghost".to_string(),
+        Some(Category { id: None, name: Some("Dogs".to_string()) }),
+        vec![],
+        vec![],
+        None,
+    );
+    let result = client.add_pet(&req, |_| Vec::new()).await;
+    assert!(matches!(result, Err(CreatePetError::CategoryIdRequired)));
+
+    // Rename is visible through the pet
+    let renamed = client.rename_category(&RenameCategoryRequest::new(dogs.id.unwrap(), "Canines".to_string()))
+        .await
+        .expect("Failed to rename category");
+    assert_eq!(renamed.name, Some("Canines".to_string()));
+    let found = client.find_pet_by_id(1).await.unwrap().unwrap();
+    assert_eq!(found.category.unwrap().name, Some("Canines".to_string()));
+    // and changes its version, so its ETag stops matching
+    assert_eq!(found.version, Pet::INITIAL_VERSION + 1);
+
+    // Categories in use cannot be deleted, unused ones can
+    let result = client.delete_category(dogs.id.unwrap()).await;
+    assert!(matches!(result, Err(DeleteCategoryError::InUse { .. })));
+    client.delete_category(cats.id.unwrap()).await.expect("Failed to delete category");
+    assert_eq!(client.list_categories().await.unwrap(), vec![renamed, birds, fish, frogs]);
+    assert!(client.find_category_by_id(cats.id.unwrap()).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_tag_administration() {
+    let (_container, client) = start_migrated_postgres().await;
+
+    let friendly = Tag::with_values(1, "friendly".to_string());
+    let kind = Tag::with_values(2, "kind".to_string());
+    let stale = Tag::with_values(3, "stale".to_string());
+    let pets = [
+        (1, "Rex", vec![friendly.clone()]),
+        (2, "Buddy", vec![friendly.clone(), kind.clone()]),
+        (3, "Luna", vec![kind.clone()]),
+        (4, "Milo", vec![stale.clone()]),
+    ];
+    for (id, name, tags) in pets {
+        let req = CreatePetRequest::new(Some(id), name.to_string(), None, vec![], tags, None);
+        client.add_pet(&req, |_| Vec::new()).await.expect("Failed to add pet");
+    }
+    // Detach the only pet tagged "stale"
+    sqlx::query("DELETE FROM pets WHERE id = 4")
+        .execute(client.pool())
+        .await
+        .expect("Failed to delete pet");
+
+    let usage = client.list_tags().await.expect("Failed to list tags");
+    assert_eq!(usage, vec![
+        TagUsage::new(friendly.clone(), 2),
+        TagUsage::new(kind.clone(), 2),
+        TagUsage::new(stale.clone(), 0),
+    ]);
+
+    let renamed = client.rename_tag(&RenameTagRequest::new(1, "Friendly".to_string()))
+        .await
+        .expect("Failed to rename tag");
+    assert_eq!(renamed, Tag::with_values(1, "Friendly".to_string()));
+    assert!(client.rename_tag(&RenameTagRequest::new(2, "Friendly".to_string())).await.is_err());
+    // Pets carrying a renamed or merged tag change version, so their ETags stop matching
+    assert_eq!(client.find_pet_version(1).await.unwrap(), Some(Pet::INITIAL_VERSION + 1));
+    assert_eq!(client.find_pet_version(3).await.unwrap(), Some(Pet::INITIAL_VERSION));
+
+    // Buddy has both tags and keeps a single association after the merge
+    let merged = client.merge_tags(&MergeTagsRequest::new(2, 1))
+        .await
+        .expect("Failed to merge tags");
+    assert_eq!(merged, TagUsage::new(renamed.clone(), 3));
+    let buddy = client.find_pet_by_id(2).await.unwrap().unwrap();
+    assert_eq!(buddy.tags, vec![renamed.clone()]);
+    assert_eq!(buddy.version, Pet::INITIAL_VERSION + 2);
+    assert_eq!(client.find_pet_version(3).await.unwrap(), Some(Pet::INITIAL_VERSION + 1));
+
+    let result = client.delete_tag(1).await;
+    assert!(matches!(result, Err(DeleteTagError::InUse { id: 1, pet_count: 3 })));
+    let result = client.delete_tag(2).await;
+    assert!(matches!(result, Err(DeleteTagError::NotFound { id: 2 })));
+
+    let deleted = client.delete_unused_tags().await.expect("Failed to delete unused tags");
+    assert_eq!(deleted, vec![stale]);
+    assert_eq!(client.list_tags().await.unwrap(), vec![TagUsage::new(renamed, 3)]);
+}
+
+#[tokio::test]
+async fn test_update_pet_status() {
+    let (_container, client) = start_migrated_postgres().await;
+
+    let req = CreatePetRequest::new(Some(1), "Rex".to_string(), None, vec![], vec![], Some(Status::Available));
+    client.add_pet(&req, |_| Vec::new()).await.expect("Failed to add pet");
+
+    let pet = client.update_pet_status(&ChangePetStatusRequest::new(1, Status::Pending), &Status::Available, &[])
+        .await
+        .expect("Failed to update status");
+    assert_eq!(pet.status, Some(Status::Pending));
+
+    // A transition validated against a stale status is rejected
+    let result = client.update_pet_status(&ChangePetStatusRequest::new(1, Status::Pending), &Status::Available, &[]).await;
+    assert!(matches!(
+        result,
+        Err(ChangePetStatusError::InvalidTransition(InvalidTransition { from: Status::Pending, to: Status::Pending }))
+    ));
+
+    let result = client.update_pet_status(&ChangePetStatusRequest::new(2, Status::Pending), &Status::Available, &[]).await;
+    assert!(matches!(result, Err(ChangePetStatusError::NotFound { id: 2 })));
+
+    let found = client.find_pet_by_id(1).await.unwrap().unwrap();
+    assert_eq!(found.status, Some(Status::Pending));
+}
+
+#[tokio::test]
+async fn test_pet_status_history() {
+    let (_container, client) = start_migrated_postgres().await;
+
+    let req = CreatePetRequest::new(Some(1), "Rex".to_string(), None, vec![], vec![], Some(Status::Available));
+    client.add_pet(&req, |_| Vec::new()).await.expect("Failed to add pet");
+
+    let change = ChangePetStatusRequest::new(1, Status::Pending).with_actor(Some("alice".to_string()));
+    client.update_pet_status(&change, &Status::Available, &[]).await.expect("Failed to update status");
+
+    // A rejected transition leaves no trace
+    let stale = ChangePetStatusRequest::new(1, Status::Sold);
+    assert!(client.update_pet_status(&stale, &Status::Available, &[]).await.is_err());
+
+    let history = client.pet_status_history(1).await.expect("Failed to read history");
+    let transitions: Vec<(Option<Status>, Status, Option<String>)> = history
+        .into_iter()
+        .map(|c| (c.from, c.to, c.actor))
+        .collect();
+    assert_eq!(transitions, vec![
+        (None, Status::Available, None),
+        (Some(Status::Available), Status::Pending, Some("alice".to_string())),
+    ]);
+
+    let result = client.pet_status_history(2).await;
+    assert!(matches!(result, Err(PetHistoryError::NotFound { id: 2 })));
+}
+
+#[tokio::test]
+async fn test_update_pet_status_version() {
+    let (_container, client) = start_migrated_postgres().await;
+
+    let req = CreatePetRequest::new(Some(1), "Rex".to_string(), None, vec![], vec![], Some(Status::Available));
+    let pet = client.add_pet(&req, |_| Vec::new()).await.expect("Failed to add pet");
+    assert_eq!(pet.version, Pet::INITIAL_VERSION);
+
+    let change = ChangePetStatusRequest::new(1, Status::Pending).with_expected_version(Some(pet.version));
+    let pet = client.update_pet_status(&change, &Status::Available, &[]).await.expect("Failed to update status");
+    assert_eq!(pet.version, Pet::INITIAL_VERSION + 1);
+
+    // The same change based on the old version is stale
+    let result = client.update_pet_status(&change, &Status::Pending, &[]).await;
+    assert!(matches!(result, Err(ChangePetStatusError::Conflict { id: 1, expected: 1, actual: 2 })));
+
+    let found = client.find_pet_by_id(1).await.unwrap().unwrap();
+    assert_eq!(found.version, Pet::INITIAL_VERSION + 1);
+    assert_eq!(client.find_pet_version(1).await.unwrap(), Some(found.version));
+    assert_eq!(client.find_pet_version(2).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_idempotency_keys() {
+    let (_container, client) = start_migrated_postgres().await;
+    let key = IdempotencyKey::new("retry-me").unwrap();
+    let lease = std::time::Duration::from_secs(60);
+    let claim = ClaimIdempotencyKeyRequest::new(key.clone(), "first".to_string(), lease);
+    let response = IdempotentResponse {
+        status_code: 201,
+        headers: vec![
+            ("content-type".to_string(), "application/json".to_string()),
+            ("etag".to_string(), "\"1\"".to_string()),
+        ],
+        body: "{\"id\":1}".to_string(),
+    };
+
+    assert_eq!(client.claim_idempotency_key(&claim).await.unwrap(), IdempotencyClaim::Claimed);
+    assert_eq!(client.claim_idempotency_key(&claim).await.unwrap(), IdempotencyClaim::InProgress);
+
+    client.complete_idempotency_key(&claim, &response, std::time::Duration::from_secs(3600)).await.unwrap();
+    assert_eq!(client.claim_idempotency_key(&claim).await.unwrap(), IdempotencyClaim::Completed(response.clone()));
+
+    let other = ClaimIdempotencyKeyRequest::new(key.clone(), "second".to_string(), lease);
+    assert_eq!(client.claim_idempotency_key(&other).await.unwrap(), IdempotencyClaim::Mismatch);
+
+    // Releasing a completed claim keeps its response
+    client.release_idempotency_key(&claim).await.unwrap();
+    assert_eq!(client.claim_idempotency_key(&other).await.unwrap(), IdempotencyClaim::Mismatch);
+
+    // Releasing a claim in progress frees the key for any request
+    let released = ClaimIdempotencyKeyRequest::new(IdempotencyKey::new("released").unwrap(), "first".to_string(), lease);
+    assert_eq!(client.claim_idempotency_key(&released).await.unwrap(), IdempotencyClaim::Claimed);
+    client.release_idempotency_key(&released).await.unwrap();
+    let released_other = ClaimIdempotencyKeyRequest::new(released.key.clone(), "second".to_string(), lease);
+    assert_eq!(client.claim_idempotency_key(&released_other).await.unwrap(), IdempotencyClaim::Claimed);
+
+    // An expired claim is taken over
+    let expiring = ClaimIdempotencyKeyRequest::new(IdempotencyKey::new("expiring").unwrap(), "first".to_string(), std::time::Duration::ZERO);
+    assert_eq!(client.claim_idempotency_key(&expiring).await.unwrap(), IdempotencyClaim::Claimed);
+    assert_eq!(client.claim_idempotency_key(&expiring).await.unwrap(), IdempotencyClaim::Claimed);
+
+    // Unless it was completed, which keeps it for the window rather than the lease
+    client
+        .complete_idempotency_key(&expiring, &response, std::time::Duration::from_secs(3600))
+        .await
+        .unwrap();
+    assert!(matches!(client.claim_idempotency_key(&expiring).await.unwrap(), IdempotencyClaim::Completed(_)));
+
+    // A request whose claim expired and was taken over cannot store its response
+    let slow = ClaimIdempotencyKeyRequest::new(IdempotencyKey::new("slow").unwrap(), "first".to_string(), std::time::Duration::ZERO);
+    assert_eq!(client.claim_idempotency_key(&slow).await.unwrap(), IdempotencyClaim::Claimed);
+    let taken_over = ClaimIdempotencyKeyRequest::new(slow.key.clone(), "second".to_string(), lease);
+    assert_eq!(client.claim_idempotency_key(&taken_over).await.unwrap(), IdempotencyClaim::Claimed);
+    let lost = client.complete_idempotency_key(&slow, &response, std::time::Duration::from_secs(3600)).await;
+    assert!(matches!(lost, Err(IdempotencyError::ClaimLost { .. })));
+    assert_eq!(client.claim_idempotency_key(&taken_over).await.unwrap(), IdempotencyClaim::InProgress);
+
+    // Nor overwrite a completed response
+    client.complete_idempotency_key(&taken_over, &response, std::time::Duration::from_secs(3600)).await.unwrap();
+    let lost = client.complete_idempotency_key(&taken_over, &response, std::time::Duration::from_secs(3600)).await;
+    assert!(matches!(lost, Err(IdempotencyError::ClaimLost { .. })));
+
+    // Nor release the claim of a retry that took the key over and completed it
+    let abandoned = ClaimIdempotencyKeyRequest::new(IdempotencyKey::new("abandoned").unwrap(), "first".to_string(), std::time::Duration::ZERO);
+    assert_eq!(client.claim_idempotency_key(&abandoned).await.unwrap(), IdempotencyClaim::Claimed);
+    let retry = ClaimIdempotencyKeyRequest::new(abandoned.key.clone(), "first".to_string(), lease);
+    assert_eq!(client.claim_idempotency_key(&retry).await.unwrap(), IdempotencyClaim::Claimed);
+    client.complete_idempotency_key(&retry, &response, std::time::Duration::from_secs(3600)).await.unwrap();
+    client.release_idempotency_key(&abandoned).await.unwrap();
+    assert_eq!(client.claim_idempotency_key(&retry).await.unwrap(), IdempotencyClaim::Completed(response.clone()));
+
+    // Expired claims are purged, unexpired ones kept
+    let expired = ClaimIdempotencyKeyRequest::new(IdempotencyKey::new("expired").unwrap(), "first".to_string(), std::time::Duration::ZERO);
+    client.claim_idempotency_key(&expired).await.unwrap();
+    assert_eq!(client.purge_expired_idempotency_keys().await.unwrap(), 1);
+    assert!(matches!(client.claim_idempotency_key(&released).await.unwrap(), IdempotencyClaim::Mismatch));
+}
+
+#[tokio::test]
+async fn test_store_assigned_ids() {
+    let (_container, client) = start_migrated_postgres().await;
+
+    let tags = vec![Tag::with_values(1, "kind".to_string())];
+    let supplied = CreatePetRequest::new(Some(1), "Rex".to_string(), None, vec![], tags, None);
+    client.add_pet(&supplied, |_| Vec::new()).await.expect("Failed to add pet with an id");
+
+    // Pets and tags without an id get the next value of their sequence, past the supplied ids
+    let tags = vec![Tag { id: None, name: Some("friendly".to_string()) }];
+    let req = CreatePetRequest::new(None, "Luna".to_string(), None, vec![], tags, None);
+    let pet = client.add_pet(&req, |_| Vec::new()).await.expect("Failed to add pet without an id");
+    let pet_id = pet.id.expect("persisted pet has no id");
+    assert!(pet_id > 1);
+    assert!(pet.tags.iter().all(|t| t.id.is_some_and(|id| id > 1)));
+
+    // Supplying a taken id reports that id
+    let result = client.add_pet(&supplied, |_| Vec::new()).await;
+    assert!(matches!(result, Err(CreatePetError::DuplicateId { id: 1 })));
+
+    // So is supplying the id of a tag of another name
+    let tags = vec![Tag::with_values(1, "other".to_string())];
+    let req = CreatePetRequest::new(None, "Milo".to_string(), None, vec![], tags, None);
+    let result = client.add_pet(&req, |_| Vec::new()).await;
+    assert!(matches!(result, Err(CreatePetError::DuplicateTagId { id: 1 })));
+
+    let found = client.find_pet_by_id(pet_id).await.unwrap().expect("pet not found");
+    assert_eq!(found.name, "Luna");
+    assert_eq!(found.tags, pet.tags);
+
+    // Existing tags are reused without taking an id from the sequence
+    let last_tag_id = || async {
+        sqlx::query_scalar::<_, i64>("SELECT last_value FROM tags_id_seq").fetch_one(client.pool()).await.unwrap()
+    };
+    let before = last_tag_id().await;
+    let tags = vec![Tag { id: None, name: Some("friendly".to_string()) }];
+    let req = CreatePetRequest::new(None, "Bella".to_string(), None, vec![], tags, None);
+    let bella = client.add_pet(&req, |_| Vec::new()).await.expect("Failed to add pet with an existing tag");
+    assert_eq!(bella.tags, pet.tags);
+    assert_eq!(last_tag_id().await, before);
+
+    // With ids supplied by clients, only new tags need one
+    let supplied_only = client.clone().with_id_strategy(IdStrategy::ClientSupplied);
+    let tags = vec![Tag { id: None, name: Some("friendly".to_string()) }];
+    let req = CreatePetRequest::new(Some(20), "Coco".to_string(), None, vec![], tags, None);
+    let coco = supplied_only.add_pet(&req, |_| Vec::new()).await.expect("Failed to add pet with an existing tag");
+    assert_eq!(coco.tags, pet.tags);
+    let tags = vec![Tag { id: None, name: Some("shy".to_string()) }];
+    let req = CreatePetRequest::new(Some(21), "Nala".to_string(), None, vec![], tags, None);
+    let result = supplied_only.add_pet(&req, |_| Vec::new()).await;
+    assert!(matches!(result, Err(CreatePetError::IdRequired { kind: IdKind::Tag })));
+
+    // Snowflake ids leave the sequences alone
+    let snowflakes = client.clone().with_id_strategy(IdStrategy::Snowflake);
+    let last_pet_id = || async {
+        sqlx::query_scalar::<_, i64>("SELECT last_value FROM pets_id_seq").fetch_one(client.pool()).await.unwrap()
+    };
+    let before = last_pet_id().await;
+    let snowflake = 1_i64 << 60;
+    let req = CreatePetRequest::new(Some(snowflake), "Max".to_string(), None, vec![], vec![], None);
+    snowflakes.add_pet(&req, |_| Vec::new()).await.expect("Failed to add pet with a snowflake id");
+    assert_eq!(last_pet_id().await, before);
+}
+
+#[tokio::test]
+async fn test_supplied_ids_never_rewind_sequences() {
+    let (_container, client) = start_migrated_postgres().await;
+
+    // Rows that existed before the migration leave the sequence uncalled, past their ids
+    sqlx::query("INSERT INTO pets (id, name) VALUES (50, 'Rex')").execute(client.pool()).await.unwrap();
+    sqlx::query("SELECT setval('pets_id_seq', 51, false)").execute(client.pool()).await.unwrap();
+
+    let req = CreatePetRequest::new(Some(10), "Luna".to_string(), None, vec![], vec![], None);
+    client.add_pet(&req, |_| Vec::new()).await.expect("Failed to add pet with a lower id");
+
+    let req = CreatePetRequest::new(None, "Bella".to_string(), None, vec![], vec![], None);
+    let pet = client.add_pet(&req, |_| Vec::new()).await.expect("Failed to add pet without an id");
+    assert!(pet.id.is_some_and(|id| id > 50));
+}
+
+#[tokio::test]
+async fn test_import_export_pets() {
+    let (_container, client) = start_migrated_postgres().await;
+    client.create_category(&CreateCategoryRequest::new(Some(1), "Dogs".to_string()))
+        .await
+        .expect("Failed to create category");
+    let service = Service::new(client.clone());
+    let record = |id: i64, name: &str, category_id: Option<i64>| PetRecord {
+        id: Some(id),
+        name: name.to_string(),
+        category_id,
+        tags: vec!["imported".to_string()],
+        ..Default::default()
+    };
+    let rows = |records: Vec<PetRecord>| {
+        futures::stream::iter(records.into_iter().enumerate().map(|(i, record)| {
+            Ok::<_, ImportPetsError>(ImportRow { line: i as u64 + 2, record: Ok(record) })
+        }))
+    };
+    let records = vec![record(1, "Rex", Some(1)), record(2, "Tom", Some(9)), record(1, "Luna", None)];
+
+    // A dry run reports the unknown category and the taken id, and imports nothing
+    let report = service.import_pets(rows(records.clone()), true).await.expect("Failed to import");
+    assert_eq!((report.rows, report.imported), (3, 1));
+    let failed: Vec<(u64, &str)> = report.failures.iter().map(|f| (f.line, f.errors[0].field.as_str())).collect();
+    assert_eq!(failed, vec![(3, "category_id"), (4, "id")]);
+    assert!(client.find_pet_by_id(1).await.unwrap().is_none());
+
+    // The real import fails the same rows against the store
+    let report = service.import_pets(rows(records), false).await.expect("Failed to import");
+    assert_eq!(report.imported, 1);
+    let failed: Vec<(u64, &str)> = report.failures.iter().map(|f| (f.line, f.errors[0].code)).collect();
+    assert_eq!(failed, vec![(3, "unknown-category"), (4, "duplicate")]);
+
+    let exported: Vec<PetRecord> = futures::TryStreamExt::try_collect(service.export_pets())
+        .await
+        .expect("Failed to export");
+    assert_eq!(
+        exported,
+        vec![PetRecord { category_name: Some("Dogs".to_string()), ..record(1, "Rex", Some(1)) }]
+    );
+}
+
+#[tokio::test]
+async fn test_seed_profiles() {
+    let (_container, client) = start_migrated_postgres().await;
+    let seeder = Seeder::new(client.clone());
+
+    let plan = SeedPlan::new(SeedProfile::Sample, 0);
+    let report = seeder.seed(&plan).await.expect("Failed to seed");
+    assert_eq!(report.categories_created, plan.categories.len() as u64);
+    assert_eq!(report.pets_created, plan.pets.len() as u64);
+
+    // Seeding again changes nothing
+    let report = seeder.seed(&plan).await.expect("Failed to seed again");
+    assert_eq!((report.categories_created, report.pets_created), (0, 0));
+    assert_eq!(report.pets_existing, plan.pets.len() as u64);
+
+    // Synthetic pets reuse the existing categories, and are the same for the same seed
+    let plan = SeedPlan::new(SeedProfile::Synthetic { pets: 50 }, 7);
+    let report = seeder.seed(&plan).await.expect("Failed to seed synthetic pets");
+    assert!(report.categories_existing > 0);
+    assert_eq!(report.pets_created, 50);
+    let req = ListPetsRequest { limit: 100, ..Default::default() };
+    let page = client.list_pets(&req).await.unwrap();
+    let seeded: Vec<&str> = page.pets.iter().map(|p| p.name.as_str()).filter(|name| name.contains(" the ")).collect();
+    let planned: Vec<&str> = plan.pets.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(seeded, planned);
+    let first = page.pets.iter().find(|p| p.name == plan.pets[0].name).unwrap();
+    assert_eq!(first.photo_urls, plan.pets[0].photo_urls);
+}
+
+#[tokio::test]
+async fn test_outbox_events() {
+    let (_container, client) = start_migrated_postgres().await;
+
+    let req = CreatePetRequest::new(None, "Rex".to_string(), None, vec![], vec![], None);
+    client.add_pet(&req, |pet| vec![PetEvent::created(pet)]).await.expect("Failed to add pet");
+    let changed = PetEvent::PetStatusChanged {
+        pet_id: 1,
+        from: Status::Available,
+        to: Status::Pending,
+        actor: None,
+        version: 2,
+    };
+    let change = ChangePetStatusRequest::new(1, Status::Pending);
+    client.update_pet_status(&change, &Status::Available, std::slice::from_ref(&changed))
+        .await
+        .expect("Failed to update status");
+
+    // A rejected change leaves no event behind
+    assert!(client.update_pet_status(&change, &Status::Available, std::slice::from_ref(&changed)).await.is_err());
+
+    let lease = std::time::Duration::from_secs(60);
+    let pending = client.claim_pending_events(10, lease).await.expect("Failed to read the outbox");
+    let events: Vec<&str> = pending.iter().map(|e| e.event.event_type()).collect();
+    assert_eq!(events, vec!["PetCreated", "PetStatusChanged"]);
+    assert_eq!(pending[1].event, changed);
+
+    // Nothing more is claimed while the claimed events are pending
+    assert!(client.claim_pending_events(10, lease).await.unwrap().is_empty());
+    client.mark_dispatched(pending[0].id).await.unwrap();
+    assert!(client.claim_pending_events(10, lease).await.unwrap().is_empty());
+
+    // Once the lease ends, the events left pending are claimed again
+    sqlx::query("UPDATE outbox_events SET claimed_until = now()")
+        .execute(client.pool())
+        .await
+        .unwrap();
+    let pending = client.claim_pending_events(10, lease).await.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].event, changed);
+
+    // Released events are claimed again before their lease ends
+    client.release_claims(&[pending[0].id]).await.unwrap();
+    let pending = client.claim_pending_events(10, lease).await.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].event, changed);
+}
+
+#[tokio::test]
+async fn test_outbox_events_are_ordered_by_commit() {
+    let (_container, client) = start_migrated_postgres().await;
+
+    // A change appending its event, but not committed yet
+    let mut first = client.pool().begin().await.unwrap();
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('outbox_events_append'))")
+        .execute(&mut *first)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO outbox_events (aggregate_id, event_type, payload) VALUES (7, 'PetDeleted', $1::jsonb)")
+        .bind(serde_json::to_string(&PetEvent::PetDeleted { pet_id: 7 }).unwrap())
+        .execute(&mut *first)
+        .await
+        .unwrap();
+
+    // A later change waits for it before appending its own event
+    let second = tokio::spawn({
+        let client = client.clone();
+        async move {
+            let req = CreatePetRequest::new(None, "Rex".to_string(), None, vec![], vec![], None);
+            client.add_pet(&req, |pet| vec![PetEvent::created(pet)]).await
+        }
+    });
+    sleep(Duration::from_millis(500)).await;
+    assert!(!second.is_finished());
+    let lease = std::time::Duration::from_secs(60);
+    assert!(client.claim_pending_events(10, lease).await.unwrap().is_empty());
+
+    first.commit().await.unwrap();
+    second.await.unwrap().expect("Failed to add pet");
+    let pending = client.claim_pending_events(10, lease).await.unwrap();
+    let events: Vec<&str> = pending.iter().map(|e| e.event.event_type()).collect();
+    assert_eq!(events, vec!["PetDeleted", "PetCreated"]);
+}
+
+#[tokio::test]
+async fn test_delete_pet() {
+    let (_container, client) = start_migrated_postgres().await;
+
+    let tags = vec![Tag::with_values(1, "friendly".to_string())];
+    let photos = vec!["https://example.com/rex.jpg".to_string()];
+    let req = CreatePetRequest::new(Some(1), "Rex".to_string(), None, photos, tags, None);
+    client.add_pet(&req, |_| Vec::new()).await.expect("Failed to add pet");
+
+    let deleted = PetEvent::PetDeleted { pet_id: 1 };
+    client.delete_pet(1, std::slice::from_ref(&deleted)).await.expect("Failed to delete pet");
+
+    // The pet goes with its history, and the event is recorded with the deletion
+    assert!(client.find_pet_by_id(1).await.unwrap().is_none());
+    assert!(matches!(client.pet_status_history(1).await, Err(PetHistoryError::NotFound { id: 1 })));
+    let lease = std::time::Duration::from_secs(60);
+    let pending = client.claim_pending_events(10, lease).await.expect("Failed to read the outbox");
+    assert_eq!(pending.iter().map(|e| &e.event).collect::<Vec<_>>(), vec![&deleted]);
+
+    // Deleting it again records nothing
+    let result = client.delete_pet(1, std::slice::from_ref(&deleted)).await;
+    assert!(matches!(result, Err(DeletePetError::NotFound { id: 1 })));
+    let events: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM outbox_events").fetch_one(client.pool()).await.unwrap();
+    assert_eq!(events, 1);
+}
+
+#[tokio::test]
+async fn test_webhook_deliveries() {
+    let (_container, client) = start_migrated_postgres().await;
+
+    // A local receiver failing the first delivery attempt
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let recorded = received.clone();
+    let receiver = axum::Router::new().route(
+        "/hook",
+        axum::routing::post(move |headers: axum::http::HeaderMap, body: String| async move {
+            let mut received = recorded.lock().unwrap();
+            received.push((headers, body));
+            if received.len() == 1 {
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR
+            } else {
+                axum::http::StatusCode::OK
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, receiver).await });
+
+    let secret = "0123456789abcdef".to_string();
+    let webhook = client
+        .create_webhook(&CreateWebhookRequest { url, event_types: vec!["PetCreated".to_string()], secret: secret.clone() })
+        .await
+        .expect("Failed to create webhook");
+
+    let req = CreatePetRequest::new(None, "Rex".to_string(), None, vec![], vec![], None);
+    client.add_pet(&req, |pet| vec![PetEvent::created(pet)]).await.expect("Failed to add pet");
+    let change = ChangePetStatusRequest::new(1, Status::Pending);
+    let changed = PetEvent::PetStatusChanged { pet_id: 1, from: Status::Available, to: Status::Pending, actor: None, version: 2 };
+    client.update_pet_status(&change, &Status::Available, &[changed]).await.expect("Failed to update status");
+
+    // Only the subscribed event is queued, once however often it is relayed
+    let relay = OutboxRelay::new(client.clone(), WebhookPublisher::new(client.clone()));
+    assert_eq!(relay.relay_pending().await.unwrap(), 2);
+    assert_eq!(client.enqueue_deliveries(1, "PetCreated", "{}").await.unwrap(), 0);
+
+    let dispatcher = WebhookDispatcher::new(
+        client.clone(),
+        HttpWebhookSender::new(HttpWebhookSender::DEFAULT_TIMEOUT).unwrap(),
+    )
+    .with_retry_policy(RetryPolicy {
+        max_attempts: 3,
+        initial_backoff: std::time::Duration::ZERO,
+        max_backoff: std::time::Duration::ZERO,
+    });
+    assert_eq!(dispatcher.dispatch_due().await.unwrap(), 1);
+    let log = client.list_deliveries(webhook.id).await.unwrap();
+    assert_eq!((log[0].status, log[0].attempts, log[0].last_response_status), (DeliveryStatus::Pending, 1, Some(500)));
+
+    assert_eq!(dispatcher.dispatch_due().await.unwrap(), 1);
+    assert_eq!(dispatcher.dispatch_due().await.unwrap(), 0);
+    let log = client.list_deliveries(webhook.id).await.unwrap();
+    assert_eq!((log[0].status, log[0].attempts, log[0].last_response_status), (DeliveryStatus::Delivered, 2, Some(200)));
+    assert!(log[0].delivered_at.is_some());
+
+    let (headers, body) = received.lock().unwrap()[1].clone();
+    let timestamp: i64 = headers["x-petstore-timestamp"].to_str().unwrap().parse().unwrap();
+    assert_eq!(headers["x-petstore-signature"], sign(&secret, timestamp, &body).as_str());
+    let payload: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(payload["type"], "PetCreated");
+    assert_eq!(payload["data"]["PetCreated"]["name"], "Rex");
+
+    client.delete_webhook(webhook.id).await.unwrap();
+    assert!(matches!(client.list_deliveries(webhook.id).await, Err(WebhookDeliveryError::NotFound { .. })));
+}
+
+#[tokio::test]
+async fn test_webhook_deliveries_are_claimed_once() {
+    let (_container, client) = start_migrated_postgres().await;
+    let webhook = client
+        .create_webhook(&CreateWebhookRequest { url: "http://localhost/hook".to_string(), event_types: vec![], secret: "0123456789abcdef".to_string() })
+        .await
+        .expect("Failed to create webhook");
+    for event_id in 1..=10 {
+        client.enqueue_deliveries(event_id, "PetCreated", "{}").await.unwrap();
+    }
+
+    // Concurrent dispatchers split the due deliveries between them
+    let lease = std::time::Duration::from_secs(60);
+    let (first, second) = tokio::join!(client.claim_due_deliveries(6, lease), client.claim_due_deliveries(6, lease));
+    let (first, second) = (first.unwrap(), second.unwrap());
+    assert_eq!(first.len() + second.len(), 10);
+    assert!(first.iter().all(|(_, d)| second.iter().all(|(_, other)| other.id != d.id)));
+    assert!(client.claim_due_deliveries(10, lease).await.unwrap().is_empty());
+
+    // Claimed deliveries stay pending until their attempt is recorded
+    let log = client.list_deliveries(webhook.id).await.unwrap();
+    assert!(log.iter().all(|d| d.status == DeliveryStatus::Pending && d.next_attempt_at > chrono::Utc::now()));
+}